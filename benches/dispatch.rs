@@ -0,0 +1,98 @@
+//! The cost of queueing and dispatching thousands of requests, as events and with
+//! `HttpClient::send_batch`. Responses come from fault injection, so no request leaves the process.
+//!
+//! Run it with `cargo bench --bench dispatch`.
+
+use bevy::ecs::system::Command;
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+const QUEUED: [usize; 2] = [1_000, 10_000];
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HttpClientPlugin::new(HttpClientSettings {
+            max_concurrent: 256,
+            ..default()
+        }),
+        FaultInjectionPlugin::new(1.0).with_fault(Fault::Status(204)),
+    ));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+fn pings(count: usize) -> Vec<HttpRequest> {
+    (0..count)
+        .map(|seq| {
+            HttpClient::new()
+                .post("http://localhost/ping")
+                .label("telemetry")
+                .json(&serde_json::json!({ "event": "ping", "seq": seq }))
+                .build()
+        })
+        .collect()
+}
+
+/// Queues the requests and runs the frame that dispatches the first of them.
+fn queue(c: &mut Criterion) {
+    let mut group = c.benchmark_group("queue");
+    for count in QUEUED {
+        group.bench_with_input(BenchmarkId::new("events", count), &count, |b, &count| {
+            b.iter_batched(
+                || (app(), pings(count)),
+                |(mut app, requests)| {
+                    app.world.send_event_batch(requests);
+                    app.update();
+                    app
+                },
+                BatchSize::LargeInput,
+            );
+        });
+        group.bench_with_input(
+            BenchmarkId::new("send_batch", count),
+            &count,
+            |b, &count| {
+                b.iter_batched(
+                    || (app(), pings(count)),
+                    |(mut app, requests)| {
+                        HttpClient::send_batch(requests).apply(&mut app.world);
+                        app.update();
+                        app
+                    },
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+/// A frame with a long queue waiting behind the concurrency limit.
+fn dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dispatch");
+    for count in QUEUED {
+        group.bench_with_input(BenchmarkId::new("waiting", count), &count, |b, &count| {
+            b.iter_batched(
+                || {
+                    let mut app = app();
+                    HttpClient::send_batch(pings(count)).apply(&mut app.world);
+                    app.update();
+                    app
+                },
+                |mut app| {
+                    app.update();
+                    app
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, queue, dispatch);
+criterion_main!(benches);
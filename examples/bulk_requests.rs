@@ -0,0 +1,153 @@
+//! Sends thousands of small telemetry pings per second, once as events and once with
+//! `HttpClient::send_batch`, and prints the frame times and throughput of both.
+//!
+//! Run it with `cargo run --release --example bulk_requests`. The pings are answered by fault
+//! injection, so only the cost of the request pipeline is measured. Pass `--network` to send them
+//! to a server on localhost instead.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+/// Requests sent per second.
+const RATE: usize = 5000;
+/// How long each mode runs.
+const RUN_FOR: Duration = Duration::from_secs(5);
+const FRAME: Duration = Duration::from_micros(16_667);
+
+#[derive(Resource)]
+struct Bench {
+    url: String,
+    batch: bool,
+    received: usize,
+}
+
+fn main() {
+    let network = std::env::args().any(|arg| arg == "--network");
+    let addr = serve();
+    for batch in [false, true] {
+        run(addr, batch, network);
+    }
+}
+
+fn run(addr: SocketAddr, batch: bool, network: bool) {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HttpClientPlugin::new(HttpClientSettings {
+            max_concurrent: 256,
+            ..default()
+        }),
+    ));
+    if !network {
+        app.add_plugins(FaultInjectionPlugin::new(1.0).with_fault(Fault::Status(204)));
+    }
+    app.insert_resource(Bench {
+        url: format!("http://{addr}/ping"),
+        batch,
+        received: 0,
+    })
+    .add_systems(Update, (send_pings, count_responses));
+    app.finish();
+    app.cleanup();
+
+    let mut frames = vec![];
+    let started = Instant::now();
+    while started.elapsed() < RUN_FOR {
+        let frame_started = Instant::now();
+        app.update();
+        let frame = frame_started.elapsed();
+        frames.push(frame);
+        std::thread::sleep(FRAME.saturating_sub(frame));
+    }
+
+    frames.sort();
+    let percentile = |p: f64| frames[((frames.len() - 1) as f64 * p) as usize];
+    let received = app.world.resource::<Bench>().received;
+    println!(
+        "{}: {} frames, median {:?}, p99 {:?}, max {:?}, {:.0} responses/s",
+        if batch { "send_batch" } else { "events" },
+        frames.len(),
+        percentile(0.5),
+        percentile(0.99),
+        percentile(1.0),
+        received as f64 / started.elapsed().as_secs_f64(),
+    );
+}
+
+fn send_pings(
+    mut commands: Commands,
+    time: Res<Time>,
+    bench: Res<Bench>,
+    mut ev_request: EventWriter<HttpRequest>,
+) {
+    let count = (RATE as f32 * time.delta_seconds()).ceil() as usize;
+    let pings = (0..count).map(|i| {
+        HttpClient::new()
+            .post(&bench.url)
+            .label("telemetry")
+            .json(&serde_json::json!({ "event": "ping", "seq": i }))
+            .build()
+    });
+    if bench.batch {
+        commands.add(HttpClient::send_batch(pings));
+    } else {
+        ev_request.send_batch(pings);
+    }
+}
+
+fn count_responses(
+    mut bench: ResMut<Bench>,
+    mut ev_response: EventReader<HttpResponse>,
+    mut ev_error: EventReader<HttpResponseError>,
+) {
+    bench.received += ev_response.read().count();
+    for error in ev_error.read() {
+        println!("failed: {}", error.message);
+    }
+}
+
+/// Answers every request with `204 No Content`, keeping connections alive.
+fn serve() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let addr = listener.local_addr().expect("no address");
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || answer(stream));
+        }
+    });
+    addr
+}
+
+fn answer(stream: TcpStream) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            if line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+        let response = b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n";
+        if reader.get_mut().write_all(response).is_err() {
+            return;
+        }
+    }
+}
@@ -3,7 +3,7 @@ use bevy_http_client::prelude::*;
 
 fn main() {
     App::new()
-        .add_plugins((MinimalPlugins, HttpClientPlugin))
+        .add_plugins((MinimalPlugins, HttpClientPlugin::default()))
         .add_systems(Update, handle_response)
         .add_systems(
             Update,
@@ -9,7 +9,7 @@ pub struct IpInfo {
 
 fn main() {
     let mut app = App::new();
-    app.add_plugins((MinimalPlugins, HttpClientPlugin))
+    app.add_plugins((MinimalPlugins, HttpClientPlugin::default()))
         .add_systems(Update, handle_response)
         .add_systems(
             Update,
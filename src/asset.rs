@@ -0,0 +1,317 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_channel::{Receiver, Sender};
+use bevy::app::{App, Plugin, Update};
+use bevy::asset::io::{
+    AssetReader, AssetReaderError, AssetSource, AssetSourceId, PathStream, Reader, VecReader,
+};
+use bevy::asset::{
+    Asset, AssetApp, AssetServer, Handle, RecursiveDependencyLoadState, UntypedAssetId,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::{BoxedFuture, HashMap};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::DownloadCache;
+use crate::{HttpClient, HttpRequest, HttpSet, OnComplete};
+
+/// Name of the asset source the downloads are read from.
+const SOURCE: &str = "http-client";
+
+/// Lets [`HttpAssets`] load downloaded bytes as assets. Only available with the `asset` feature.
+///
+/// Downloads go through the usual request queue, so concurrency limits, stats and timeouts apply.
+/// The asset is then read by the loader of its asset type, or by extension if the type has several
+/// loaders. Files the asset refers to, e.g. the buffers and textures of a glTF file, are resolved
+/// relative to its url and downloaded the same way. With a `DownloadCache` the files are kept on
+/// disk and only downloaded again when they changed. Asset sources cannot be added once the asset
+/// server exists, so add it before `DefaultPlugins`, or `AssetPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// App::new()
+///     .add_plugins(HttpAssetPlugin)
+///     .add_plugins(DefaultPlugins)
+///     .add_plugins(HttpClientPlugin::default());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpAssetPlugin;
+
+impl Plugin for HttpAssetPlugin {
+    fn build(&self, app: &mut App) {
+        let downloads = Downloads::default();
+        let reader_downloads = downloads.clone();
+        app.register_asset_source(
+            AssetSourceId::Name(SOURCE.into()),
+            AssetSource::build()
+                .with_reader(move || Box::new(DownloadReader(reader_downloads.clone()))),
+        );
+        app.insert_resource(downloads);
+        // Added before `HttpClientPlugin`, so its schedule is not known yet.
+        app.add_systems(
+            Update,
+            (
+                send_dependency_requests.before(HttpSet::Queue),
+                forget_loaded,
+            ),
+        );
+    }
+}
+
+/// Downloads assets, see [`HttpAssetPlugin`].
+///
+/// The handle is returned right away, the asset is added to its `Assets` once it was downloaded
+/// and loaded. Failed downloads, including non-2xx responses, end in `LoadState::Failed`. The
+/// fragment of the url is the label of a sub-asset, e.g. `#Scene0` for the first scene of a glTF
+/// file.
+///
+/// # Examples
+///
+/// ```
+/// fn load_banner(mut commands: Commands, mut http_assets: HttpAssets) {
+///     let banner: Handle<Image> = http_assets.load_as("https://cdn.example.com/banner.png");
+///     commands.spawn(SpriteBundle {
+///         texture: banner,
+///         ..default()
+///     });
+/// }
+///
+/// // A user-generated model, its buffers and textures are downloaded from next to it.
+/// fn spawn_model(mut commands: Commands, mut http_assets: HttpAssets) {
+///     commands.spawn(SceneBundle {
+///         scene: http_assets.load_as("https://ugc.example.com/models/42/robot.gltf#Scene0"),
+///         ..default()
+///     });
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct HttpAssets<'w> {
+    asset_server: Res<'w, AssetServer>,
+    downloads: Res<'w, Downloads>,
+    #[cfg(not(target_arch = "wasm32"))]
+    cache: Option<Res<'w, DownloadCache>>,
+    requests: EventWriter<'w, HttpRequest>,
+}
+
+impl HttpAssets<'_> {
+    /// Downloads `url` with a GET request and loads it as an `A`.
+    pub fn load_as<A: Asset>(&mut self, url: impl ToString) -> Handle<A> {
+        self.load_request_as(HttpClient::new().get(url).build())
+    }
+
+    /// Sends `request`, e.g. one with auth headers, and loads its response body as an `A`.
+    ///
+    /// The `on_complete` and `respond_to` of the request are replaced, the result only goes to the
+    /// asset server. Files the asset refers to are requested without the headers of `request`.
+    pub fn load_request_as<A: Asset>(&mut self, mut request: HttpRequest) -> Handle<A> {
+        let key = request.id.to_string();
+        let Ok(mut url) = url::Url::parse(&request.request.url) else {
+            // Fails with the error of the backend.
+            let path = PathBuf::from(format!("{key}/asset"));
+            let handle = self.asset_server.load(asset_path(&path, None));
+            let request = self.downloads.request(
+                path,
+                request,
+                #[cfg(not(target_arch = "wasm32"))]
+                self.cache.as_deref(),
+            );
+            self.requests.send(request);
+            return handle;
+        };
+        let label = url.fragment().map(str::to_owned);
+        url.set_fragment(None);
+        request.request.url = url.to_string();
+
+        // The url path is kept, so relative paths resolve like they would on the server.
+        let path = match url.path() {
+            "/" => PathBuf::from(format!("{key}/asset")),
+            url_path => PathBuf::from(format!("{key}{url_path}")),
+        };
+        let origin = url.join("/").ok().filter(|_| url.has_host());
+        let request = self.downloads.request(
+            path.clone(),
+            request,
+            #[cfg(not(target_arch = "wasm32"))]
+            self.cache.as_deref(),
+        );
+        self.requests.send(request);
+        let handle = self.asset_server.load(asset_path(&path, label.as_deref()));
+        if let Some(origin) = origin {
+            self.downloads
+                .add_origin(key, origin, handle.id().untyped());
+        }
+        handle
+    }
+}
+
+fn asset_path(path: &Path, label: Option<&str>) -> String {
+    let path = path.to_string_lossy();
+    match label {
+        Some(label) => format!("{SOURCE}://{path}#{label}"),
+        None => format!("{SOURCE}://{path}"),
+    }
+}
+
+/// Makes `request` send its body to `tx`, revalidating the cached file if there is one.
+fn deliver_to(
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))] mut request: HttpRequest,
+    tx: Sender<Body>,
+    #[cfg(not(target_arch = "wasm32"))] cache: Option<&DownloadCache>,
+) -> HttpRequest {
+    #[cfg(not(target_arch = "wasm32"))]
+    let cached = cache.and_then(|cache| cache.revalidate(&mut request.request));
+    HttpRequest {
+        on_complete: Some(OnComplete::new(move |_, _, result| {
+            #[cfg(not(target_arch = "wasm32"))]
+            let result = match &cached {
+                Some(cached) => cached.finish(result),
+                None => result,
+            };
+            let result = match result {
+                Ok(res) if res.ok => Ok(res.bytes),
+                Ok(res) => Err(AssetReaderError::HttpError(res.status)),
+                Err(err) => Err(std::io::Error::other(err).into()),
+            };
+            let _ = tx.try_send(result);
+        })),
+        respond_to: None,
+        ..request
+    }
+}
+
+/// The body of a download, or why there is none.
+type Body = Result<Vec<u8>, AssetReaderError>;
+
+/// The body of a download, once it finished.
+type Download = Receiver<Body>;
+
+#[derive(Resource, Clone, Default)]
+struct Downloads(Arc<Mutex<DownloadState>>);
+
+#[derive(Default)]
+struct DownloadState {
+    /// Bodies not yet read by the asset server, by asset path.
+    pending: HashMap<PathBuf, Download>,
+    /// What the paths of a download are resolved against, by the request id they start with,
+    /// until the downloaded asset and its dependencies loaded.
+    origins: HashMap<String, (url::Url, UntypedAssetId)>,
+    /// Dependencies to download, sent by `send_dependency_requests`.
+    dependencies: Vec<(url::Url, Sender<Body>)>,
+}
+
+impl Downloads {
+    /// Makes `request` deliver its body to the asset at `path`.
+    fn request(
+        &self,
+        path: PathBuf,
+        request: HttpRequest,
+        #[cfg(not(target_arch = "wasm32"))] cache: Option<&DownloadCache>,
+    ) -> HttpRequest {
+        let (tx, rx) = async_channel::bounded(1);
+        if let Ok(mut state) = self.0.lock() {
+            state.pending.insert(path, rx);
+        }
+        deliver_to(
+            request,
+            tx,
+            #[cfg(not(target_arch = "wasm32"))]
+            cache,
+        )
+    }
+
+    fn add_origin(&self, key: String, origin: url::Url, asset: UntypedAssetId) {
+        if let Ok(mut state) = self.0.lock() {
+            state.origins.insert(key, (origin, asset));
+        }
+    }
+
+    /// Takes the body of the asset at `path`, downloading it first if another download refers to
+    /// it.
+    fn take(&self, path: &Path) -> Option<Download> {
+        let mut state = self.0.lock().ok()?;
+        if let Some(download) = state.pending.remove(path) {
+            return Some(download);
+        }
+        let mut components = path.iter().map(|component| component.to_str());
+        let key = components.next()??;
+        let relative = components.collect::<Option<Vec<_>>>()?.join("/");
+        let url = state.origins.get(key)?.0.join(&relative).ok()?;
+        let (tx, rx) = async_channel::bounded(1);
+        state.dependencies.push((url, tx));
+        Some(rx)
+    }
+}
+
+/// Reads an asset once its download finished.
+struct DownloadReader(Downloads);
+
+impl AssetReader for DownloadReader {
+    fn read<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<Reader<'a>>, AssetReaderError>> {
+        Box::pin(async move {
+            let not_found = || AssetReaderError::NotFound(path.to_owned());
+            let download = self.0.take(path).ok_or_else(not_found)?;
+            let bytes = download.recv().await.map_err(|_| not_found())??;
+            let reader: Box<Reader> = Box::new(VecReader::new(bytes));
+            Ok(reader)
+        })
+    }
+
+    fn read_meta<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<Reader<'a>>, AssetReaderError>> {
+        Box::pin(async move { Err(AssetReaderError::NotFound(path.to_owned())) })
+    }
+
+    fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<Box<PathStream>, AssetReaderError>> {
+        Box::pin(async move { Err(AssetReaderError::NotFound(path.to_owned())) })
+    }
+
+    fn is_directory<'a>(
+        &'a self,
+        _path: &'a Path,
+    ) -> BoxedFuture<'a, Result<bool, AssetReaderError>> {
+        Box::pin(async move { Ok(false) })
+    }
+}
+
+/// Sends the downloads of files that downloaded assets refer to.
+fn send_dependency_requests(
+    downloads: Res<Downloads>,
+    #[cfg(not(target_arch = "wasm32"))] cache: Option<Res<DownloadCache>>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    if let Ok(mut state) = downloads.0.lock() {
+        requests.send_batch(state.dependencies.drain(..).map(|(url, tx)| {
+            deliver_to(
+                HttpClient::new().get(url).build(),
+                tx,
+                #[cfg(not(target_arch = "wasm32"))]
+                cache.as_deref(),
+            )
+        }));
+    }
+}
+
+/// Stops resolving the paths of downloads whose asset finished loading, with its dependencies.
+fn forget_loaded(downloads: Res<Downloads>, asset_server: Res<AssetServer>) {
+    if let Ok(mut state) = downloads.0.lock() {
+        state.origins.retain(|_, (_, asset)| {
+            matches!(
+                asset_server.get_recursive_dependency_load_state(*asset),
+                Some(
+                    RecursiveDependencyLoadState::NotLoaded | RecursiveDependencyLoadState::Loading
+                )
+            )
+        });
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::HttpRequest;
+
+/// Changes the ureq request of every hop before it is sent.
+#[cfg(not(target_arch = "wasm32"))]
+type ConfigureRequestFn = Arc<dyn Fn(ureq::Request) -> ureq::Request + Send + Sync>;
+
+/// Changes the browser `RequestInit` before the fetch starts.
+#[cfg(target_arch = "wasm32")]
+type ConfigureFetchFn = Arc<dyn Fn(&web_sys::RequestInit) + Send + Sync>;
+
+/// An escape hatch to the backend the request is sent with, for the options the crate does not
+/// wrap yet.
+///
+/// Native builds send requests with ureq, wasm builds with the fetch API of the browser. The hooks
+/// run after the crate configured the request, so they can override what it set. They are not
+/// covered by semver, as they depend on the backend in use. Requests to `unix://` urls ignore them.
+///
+/// Set it with `HttpClient::backend_options`, or insert it on the entity passed to
+/// `HttpClient::entity` to apply it to every request of that entity.
+///
+/// # Examples
+///
+/// ```
+/// let agent = ureq::AgentBuilder::new()
+///     .proxy(ureq::Proxy::new("socks5://localhost:9050")?)
+///     .no_delay(false)
+///     .redirects(0)
+///     .build();
+/// commands.spawn(BackendOptions::new().with_ureq_agent(agent));
+/// ```
+#[derive(Component, Clone, Default)]
+pub struct BackendOptions {
+    #[cfg(not(target_arch = "wasm32"))]
+    agent: Option<ureq::Agent>,
+    #[cfg(not(target_arch = "wasm32"))]
+    configure_request: Option<ConfigureRequestFn>,
+    #[cfg(target_arch = "wasm32")]
+    configure_fetch: Option<ConfigureFetchFn>,
+}
+
+impl BackendOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the request with `agent` instead of the agent shared by all requests, e.g. for a proxy
+    /// or socket options. Only available on native builds.
+    ///
+    /// Build it with `redirects(0)`, the crate follows redirects itself to record them. The DNS and
+    /// TLS phases of the `RequestTiming` are not measured on agents of your own, and their requests
+    /// count as neither new nor reused connections in the `HttpStats`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_ureq_agent(mut self, agent: ureq::Agent) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    /// Calls `configure` with the ureq request of every hop before it is sent, e.g. to set a
+    /// timeout for reading the body. Only available on native builds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_ureq_request(
+        mut self,
+        configure: impl Fn(ureq::Request) -> ureq::Request + Send + Sync + 'static,
+    ) -> Self {
+        self.configure_request = Some(Arc::new(configure));
+        self
+    }
+
+    /// Calls `configure` with the `RequestInit` of the fetch before it starts, e.g. to set
+    /// `keepalive` or `priority`. Only available on wasm builds.
+    #[cfg(target_arch = "wasm32")]
+    pub fn with_fetch_init(
+        mut self,
+        configure: impl Fn(&web_sys::RequestInit) + Send + Sync + 'static,
+    ) -> Self {
+        self.configure_fetch = Some(Arc::new(configure));
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn agent(&self) -> Option<&ureq::Agent> {
+        self.agent.as_ref()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn configure_request(&self, request: ureq::Request) -> ureq::Request {
+        match &self.configure_request {
+            Some(configure) => configure(request),
+            None => request,
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn configure_fetch(&self, init: &web_sys::RequestInit) {
+        if let Some(configure) = &self.configure_fetch {
+            configure(init);
+        }
+    }
+}
+
+impl std::fmt::Debug for BackendOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("BackendOptions");
+        #[cfg(not(target_arch = "wasm32"))]
+        debug
+            .field("agent", &self.agent.is_some())
+            .field("configure_request", &self.configure_request.is_some());
+        #[cfg(target_arch = "wasm32")]
+        debug.field("configure_fetch", &self.configure_fetch.is_some());
+        debug.finish()
+    }
+}
+
+/// Picks up the backend options of the request entity, unless the request brings its own.
+pub(crate) fn resolve(request: &mut HttpRequest, options: &Query<&BackendOptions>) {
+    if request.backend.is_none() {
+        request.backend = request
+            .from_entity
+            .and_then(|entity| options.get(entity).ok())
+            .cloned();
+    }
+}
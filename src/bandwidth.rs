@@ -0,0 +1,168 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bevy::utils::HashMap;
+
+use crate::RequestLabel;
+
+/// Caps how fast response bodies are downloaded, for all requests and per `RequestLabel`.
+///
+/// A request is held to every cap that applies to it, the requests under a cap share its rate.
+/// The body is read more slowly, so the server slows down too instead of filling the connection.
+/// Changed caps apply to downloads already running. Not available on wasm builds, where the
+/// browser reads the body.
+///
+/// # Examples
+///
+/// ```
+/// // During a match, content downloads get 256 KiB/s and everything else 2 MiB/s.
+/// fn on_match_start(mut settings: ResMut<HttpClientSetting>) {
+///     settings.download_limits.set_global(Some(2 * 1024 * 1024));
+///     settings
+///         .download_limits
+///         .set_label(RequestLabel::new("content"), Some(256 * 1024));
+/// }
+///
+/// fn on_match_end(mut settings: ResMut<HttpClientSetting>) {
+///     settings.download_limits = DownloadLimits::default();
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DownloadLimits {
+    global: Arc<RateLimiter>,
+    labels: HashMap<RequestLabel, Arc<RateLimiter>>,
+}
+
+impl DownloadLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// cap all downloads together at `bytes_per_second`
+    pub fn with_global(mut self, bytes_per_second: u64) -> Self {
+        self.set_global(Some(bytes_per_second));
+        self
+    }
+
+    /// cap the downloads of requests with `label` together at `bytes_per_second`
+    pub fn with_label(mut self, label: RequestLabel, bytes_per_second: u64) -> Self {
+        self.set_label(label, Some(bytes_per_second));
+        self
+    }
+
+    /// Sets or, with `None`, lifts the cap for all downloads.
+    pub fn set_global(&mut self, bytes_per_second: Option<u64>) {
+        self.global.set_rate(bytes_per_second);
+    }
+
+    /// Sets or, with `None`, lifts the cap for requests with `label`.
+    pub fn set_label(&mut self, label: RequestLabel, bytes_per_second: Option<u64>) {
+        self.labels
+            .entry(label)
+            .or_default()
+            .set_rate(bytes_per_second);
+    }
+
+    /// The cap for all downloads in bytes per second.
+    pub fn global(&self) -> Option<u64> {
+        self.global.rate()
+    }
+
+    /// The cap for requests with `label` in bytes per second.
+    pub fn label(&self, label: &RequestLabel) -> Option<u64> {
+        self.labels.get(label).and_then(|limiter| limiter.rate())
+    }
+
+    /// The caps a request with `label` is held to.
+    pub(crate) fn throttle(&self, label: Option<&RequestLabel>) -> Throttle {
+        let mut limiters = vec![self.global.clone()];
+        limiters.extend(label.and_then(|label| self.labels.get(label)).cloned());
+        Throttle(limiters)
+    }
+}
+
+/// A token bucket holding up to a second worth of bytes.
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter(Mutex<Bucket>);
+
+#[derive(Debug, Default)]
+struct Bucket {
+    rate: Option<u64>,
+    /// Bytes that may be read right away, negative while the readers are in debt.
+    available: f64,
+    refilled_at: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(rate: Option<u64>) -> Self {
+        let limiter = Self::default();
+        limiter.set_rate(rate);
+        limiter
+    }
+
+    fn rate(&self) -> Option<u64> {
+        self.0.lock().ok().and_then(|bucket| bucket.rate)
+    }
+
+    fn set_rate(&self, rate: Option<u64>) {
+        if let Ok(mut bucket) = self.0.lock() {
+            bucket.rate = rate.filter(|rate| *rate > 0);
+            bucket.available = 0.0;
+            bucket.refilled_at = None;
+        }
+    }
+
+    /// Takes `bytes` from the bucket, returning how long to wait until they were due.
+    fn take(&self, bytes: usize) -> Duration {
+        let Ok(mut bucket) = self.0.lock() else {
+            return Duration::ZERO;
+        };
+        let Some(rate) = bucket.rate.map(|rate| rate as f64) else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        let refilled = bucket
+            .refilled_at
+            .map_or(0.0, |at| (now - at).as_secs_f64() * rate);
+        bucket.available = (bucket.available + refilled).min(rate) - bytes as f64;
+        bucket.refilled_at = Some(now);
+        if bucket.available < 0.0 {
+            Duration::from_secs_f64(-bucket.available / rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// The caps a download is held to.
+pub(crate) struct Throttle(Vec<Arc<RateLimiter>>);
+
+impl Throttle {
+    /// Holds the download to `limiter` as well.
+    pub(crate) fn with(mut self, limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.0.extend(limiter);
+        self
+    }
+
+    /// How many bytes to read at once, a tenth of a second at the lowest cap.
+    pub(crate) fn chunk_size(&self, max: usize) -> usize {
+        self.0
+            .iter()
+            .filter_map(|limiter| limiter.rate())
+            .map(|rate| (rate / 10).max(1) as usize)
+            .fold(max, usize::min)
+    }
+
+    /// Blocks until reading `bytes` is within every cap.
+    pub(crate) fn wait(&self, bytes: usize) {
+        let wait = self
+            .0
+            .iter()
+            .map(|limiter| limiter.take(bytes))
+            .max()
+            .unwrap_or_default();
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::retry::PendingRetries;
+use crate::{deliver, HttpRequest, HttpResponse, HttpResponseError, RequestId, RequestQueue};
+
+/// Sends several requests at once and delivers a single [`BatchResponse`] once all of them settled.
+///
+/// The items are sent in parallel, within the usual concurrency limit. Their own `on_complete`
+/// and `respond_to` are ignored, every result ends up in the batch response.
+///
+/// # Examples
+///
+/// ```
+/// ev_batch.send(RequestBatch::new([
+///     HttpClient::new().get("http://example.com/profile").build(),
+///     HttpClient::new().get("http://example.com/inventory").build(),
+///     HttpClient::new().get("http://example.com/friends").build(),
+/// ]));
+/// ```
+#[derive(Event, Debug, Clone)]
+pub struct RequestBatch {
+    /// Identifies the batch in its [`BatchResponse`].
+    pub id: RequestId,
+    pub requests: Vec<HttpRequest>,
+    /// The batch response is inserted on this entity instead of being sent as an event.
+    pub respond_to: Option<Entity>,
+}
+
+impl RequestBatch {
+    pub fn new(requests: impl IntoIterator<Item = HttpRequest>) -> Self {
+        Self {
+            id: RequestId::new(),
+            requests: requests.into_iter().collect(),
+            respond_to: None,
+        }
+    }
+
+    /// insert the batch response on `target` instead of sending it as an event
+    pub fn respond_to(mut self, target: Entity) -> Self {
+        self.respond_to = Some(target);
+        self
+    }
+}
+
+/// The results of a [`RequestBatch`], in the order of its requests.
+#[derive(Event, Component, Debug, Clone)]
+pub struct BatchResponse {
+    pub batch_id: RequestId,
+    pub results: Vec<Result<HttpResponse, HttpResponseError>>,
+}
+
+impl BatchResponse {
+    /// whether every request got a 2xx response
+    pub fn all_ok(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.as_ref().is_ok_and(|response| response.ok))
+    }
+}
+
+/// Sends the same logical request to several urls, e.g. mirrors or regions, and delivers a single
+/// [`RaceResponse`] with the first 2xx response. The other requests are cancelled then.
+///
+/// The requests are dispatched in order, within the usual concurrency limit. They are always sent
+/// on entities of their own, their `entity`, `on_complete` and `respond_to` are ignored.
+///
+/// # Examples
+///
+/// ```
+/// ev_race.send(RequestRace::new([
+///     HttpClient::new().get("https://eu.example.com/ping").build(),
+///     HttpClient::new().get("https://us.example.com/ping").build(),
+/// ]));
+/// ```
+#[derive(Event, Debug, Clone)]
+pub struct RequestRace {
+    /// Identifies the race in its [`RaceResponse`].
+    pub id: RequestId,
+    pub requests: Vec<HttpRequest>,
+    /// The race response is inserted on this entity instead of being sent as an event.
+    pub respond_to: Option<Entity>,
+}
+
+impl RequestRace {
+    pub fn new(requests: impl IntoIterator<Item = HttpRequest>) -> Self {
+        Self {
+            id: RequestId::new(),
+            requests: requests.into_iter().collect(),
+            respond_to: None,
+        }
+    }
+
+    /// insert the race response on `target` instead of sending it as an event
+    pub fn respond_to(mut self, target: Entity) -> Self {
+        self.respond_to = Some(target);
+        self
+    }
+}
+
+/// The outcome of a [`RequestRace`].
+#[derive(Event, Component, Debug, Clone)]
+pub struct RaceResponse {
+    pub race_id: RequestId,
+    /// The index of the winning request with its response, `None` if every request failed.
+    pub winner: Option<(usize, HttpResponse)>,
+    /// The requests that failed or got a non-2xx response before the race was decided.
+    pub errors: Vec<HttpResponseError>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GroupKind {
+    Batch,
+    Race,
+}
+
+/// The results of a batch or race, filled in by its requests.
+struct GroupState {
+    kind: GroupKind,
+    id: RequestId,
+    respond_to: Option<Entity>,
+    request_ids: Vec<RequestId>,
+    results: Vec<Option<Result<HttpResponse, HttpResponseError>>>,
+}
+
+impl GroupState {
+    fn new(
+        kind: GroupKind,
+        id: RequestId,
+        respond_to: Option<Entity>,
+        requests: &[HttpRequest],
+    ) -> Self {
+        Self {
+            kind,
+            id,
+            respond_to,
+            request_ids: requests.iter().map(|request| request.id).collect(),
+            results: requests.iter().map(|_| None).collect(),
+        }
+    }
+
+    fn winner(&self) -> Option<usize> {
+        self.results
+            .iter()
+            .position(|result| matches!(result, Some(Ok(response)) if response.ok))
+    }
+
+    fn is_settled(&self) -> bool {
+        let all_settled = self.results.iter().all(Option::is_some);
+        match self.kind {
+            GroupKind::Batch => all_settled,
+            GroupKind::Race => all_settled || self.winner().is_some(),
+        }
+    }
+}
+
+/// Batches and races with requests still in flight.
+#[derive(Resource, Default)]
+pub(crate) struct PendingBatches(Vec<Arc<Mutex<GroupState>>>);
+
+/// The place of one request in its group. Dropping it unfilled, e.g. with the request's entity,
+/// settles the request with an error so the group still completes.
+struct BatchSlot {
+    state: Arc<Mutex<GroupState>>,
+    index: usize,
+    request_id: RequestId,
+}
+
+impl BatchSlot {
+    fn fill(&self, result: Result<HttpResponse, HttpResponseError>) {
+        // The results are gone once the group was delivered.
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(slot) = state.results.get_mut(self.index) {
+                slot.get_or_insert(result);
+            }
+        }
+    }
+}
+
+impl Drop for BatchSlot {
+    fn drop(&mut self) {
+        self.fill(Err(HttpResponseError::new(
+            self.request_id,
+            "Request dropped before it finished",
+        )));
+    }
+}
+
+/// Queues the requests of every batch and race, each filling its slot of the group.
+pub(crate) fn handle_batches(
+    mut queue: ResMut<RequestQueue>,
+    mut pending: ResMut<PendingBatches>,
+    mut batches: EventReader<RequestBatch>,
+    mut races: EventReader<RequestRace>,
+) {
+    for batch in batches.read() {
+        let state = GroupState::new(
+            GroupKind::Batch,
+            batch.id,
+            batch.respond_to,
+            &batch.requests,
+        );
+        pending
+            .0
+            .push(queue_group(&mut queue, state, &batch.requests));
+    }
+    for race in races.read() {
+        let state = GroupState::new(GroupKind::Race, race.id, race.respond_to, &race.requests);
+        let requests: Vec<_> = race
+            .requests
+            .iter()
+            .map(|request| HttpRequest {
+                from_entity: None,
+                ..request.clone()
+            })
+            .collect();
+        pending.0.push(queue_group(&mut queue, state, &requests));
+    }
+}
+
+fn queue_group(
+    queue: &mut RequestQueue,
+    state: GroupState,
+    requests: &[HttpRequest],
+) -> Arc<Mutex<GroupState>> {
+    let state = Arc::new(Mutex::new(state));
+    for (index, request) in requests.iter().enumerate() {
+        let mut request = request.clone();
+        request.on_complete = None;
+        let slot = BatchSlot {
+            state: state.clone(),
+            index,
+            request_id: request.id,
+        };
+        queue.push(request, move |_, request_id, response| {
+            slot.fill(
+                response
+                    .map(|res| HttpResponse::new(request_id, res))
+                    .map_err(|e| HttpResponseError::new(request_id, e)),
+            );
+        });
+    }
+    state
+}
+
+/// Delivers the batches whose requests all settled and the races that were decided.
+pub(crate) fn deliver_batches(world: &mut World) {
+    let settled: Vec<_> = {
+        let mut pending = world.resource_mut::<PendingBatches>();
+        let (settled, waiting) = std::mem::take(&mut pending.0).into_iter().partition(
+            |state: &Arc<Mutex<GroupState>>| state.lock().map_or(true, |state| state.is_settled()),
+        );
+        pending.0 = waiting;
+        settled
+    };
+
+    for state in settled {
+        let Ok(mut state) = state.lock() else {
+            continue;
+        };
+        match state.kind {
+            GroupKind::Batch => {
+                let response = BatchResponse {
+                    batch_id: state.id,
+                    results: state.results.drain(..).flatten().collect(),
+                };
+                deliver(world, state.respond_to, response);
+            }
+            GroupKind::Race => {
+                let losers: HashSet<RequestId> = state
+                    .request_ids
+                    .iter()
+                    .zip(&state.results)
+                    .filter(|(_, result)| result.is_none())
+                    .map(|(request_id, _)| *request_id)
+                    .collect();
+                // Despawning the losers drops their slots, which lock the state.
+                let race_id = state.id;
+                let respond_to = state.respond_to;
+                let winner = state.winner();
+                let results: Vec<_> = state.results.drain(..).collect();
+                drop(state);
+                cancel_requests(world, &losers);
+
+                let mut response = RaceResponse {
+                    race_id,
+                    winner: None,
+                    errors: vec![],
+                };
+                for (index, result) in results.into_iter().enumerate() {
+                    match result {
+                        Some(Ok(res)) if Some(index) == winner => {
+                            response.winner = Some((index, res));
+                        }
+                        Some(Ok(res)) => response.errors.push(HttpResponseError::new(
+                            res.request_id,
+                            format!("{} {}", res.status, res.status_text),
+                        )),
+                        Some(Err(e)) => response.errors.push(e),
+                        None => {}
+                    }
+                }
+                deliver(world, respond_to, response);
+            }
+        }
+    }
+}
+
+/// Drops the queued requests and despawns the in-flight requests with the given ids.
+pub(crate) fn cancel_requests(world: &mut World, request_ids: &HashSet<RequestId>) {
+    if request_ids.is_empty() {
+        return;
+    }
+    world
+        .resource_mut::<RequestQueue>()
+        .remove_requests(request_ids);
+    world
+        .resource_mut::<PendingRetries>()
+        .remove_requests(request_ids);
+    let entities: Vec<Entity> = world
+        .query::<(Entity, &RequestId)>()
+        .iter(world)
+        .filter(|(_, request_id)| request_ids.contains(*request_id))
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in entities {
+        world.entity_mut(entity).despawn_recursive();
+    }
+}
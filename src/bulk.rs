@@ -0,0 +1,62 @@
+use bevy::ecs::system::{Command, SystemState};
+use bevy::prelude::*;
+
+use crate::{
+    deliver, queue_request, Environments, HttpClock, HttpRequest, RequestQueue, ResponseCache,
+};
+
+/// The resources queueing a request looks up, fetched once per batch.
+type QueueParams<'w> = (
+    ResMut<'w, RequestQueue>,
+    Option<ResMut<'w, ResponseCache>>,
+    Option<Res<'w, Environments>>,
+    Res<'w, HttpClock>,
+);
+
+/// Queues many requests at once, see `HttpClient::send_batch`.
+#[derive(Debug)]
+pub struct SendBatch {
+    requests: Vec<HttpRequest>,
+}
+
+impl SendBatch {
+    pub(crate) fn new(requests: impl IntoIterator<Item = HttpRequest>) -> Self {
+        Self {
+            requests: requests.into_iter().collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+impl Command for SendBatch {
+    fn apply(self, world: &mut World) {
+        let mut state = SystemState::<QueueParams>::new(world);
+        let (mut queue, mut cache, environments, clock) = state.get_mut(world);
+        let now = clock.now();
+        queue.0.reserve(self.requests.len());
+        let mut stale = vec![];
+        for request in self.requests {
+            let respond_to = request.respond_to;
+            let response = queue_request(
+                &mut queue,
+                cache.as_deref_mut(),
+                environments.as_deref(),
+                now,
+                request,
+            );
+            if let Some(response) = response {
+                stale.push((respond_to, response));
+            }
+        }
+        for (respond_to, response) in stale {
+            deliver(world, respond_to, response);
+        }
+    }
+}
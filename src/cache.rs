@@ -0,0 +1,477 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+use ehttp::Response;
+use url::Url;
+
+use crate::{
+    parse_http_date, CacheControl, ClockSkew, Environments, HttpClock, HttpRequest,
+    ResponseHandler, TypedHeader,
+};
+
+/// Where a request keeps its response in the [`ResponseCache`], see `HttpClient::cache_key` and
+/// `HttpClient::cache_namespace`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestCaching {
+    /// The cache key, `None` for the url of GET requests. Other requests are only cached with a key.
+    pub key: Option<String>,
+    /// Keeps entries of the same key apart, e.g. the responses of different players.
+    pub namespace: Option<String>,
+    /// Answers with a stale response right away and sends the request anyway, see
+    /// `HttpClient::stale_while_revalidate`.
+    pub stale_while_revalidate: bool,
+    /// How long a response without freshness headers is kept, overrides
+    /// `ResponseCache::default_ttl`.
+    pub ttl: Option<Duration>,
+}
+
+/// Keeps successful responses in memory and answers requests for them without sending them again
+/// while they are fresh. Insert it to enable caching.
+///
+/// GET requests are cached by their url, other requests only when they have a `cache_key`. A
+/// response is fresh for the `max-age` of its `Cache-Control` header minus its `Age`, or until its
+/// `Expires` header, and for `default_ttl` without either. Responses with `no-store` or `no-cache`
+/// are not kept, and requests sending `Cache-Control: no-cache` skip the cache but update it. Once
+/// the cache is full the least recently used entries make room. A successful POST, PUT, PATCH or
+/// DELETE without a cache key invalidates the entries of its url, other changes are invalidated
+/// with the methods of the cache. Removed entries are reported as [`CacheEvicted`] events.
+///
+/// Stale responses are kept for `max_stale` to answer `stale_while_revalidate` requests right
+/// away while a fresh response is fetched, other requests only get fresh responses.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(ResponseCache::new().with_default_ttl(Duration::from_secs(30)));
+///
+/// let request = HttpClient::new()
+///     .get("https://api.example.com/inventory")
+///     .bearer_auth(&player.token)
+///     .cache_namespace(&player.id)
+///     .cache_key("inventory/items");
+///
+/// // After buying an item.
+/// cache.invalidate_prefix("inventory/");
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct ResponseCache {
+    /// At most this many responses are kept.
+    pub max_entries: usize,
+    /// How long responses without `max-age` or `Expires` stay fresh, `None` to not keep them.
+    pub default_ttl: Option<Duration>,
+    /// How long stale responses are kept for `stale_while_revalidate` requests, unless the
+    /// `stale-while-revalidate` directive of their `Cache-Control` header says otherwise.
+    pub max_stale: Duration,
+    entries: HashMap<EntryKey, CachedResponse>,
+    /// Counts lookups and stores, for finding the least recently used entry.
+    uses: u64,
+    /// Sent as [`CacheEvicted`] events at the end of `HttpSet::HandleResponses`.
+    evicted: Vec<CacheEvicted>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct EntryKey {
+    namespace: Option<String>,
+    key: String,
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    /// The url of the request, the key may be another.
+    url: String,
+    response: Response,
+    expires_at: Instant,
+    /// When the response is dropped, it can still be served stale until then.
+    stale_until: Instant,
+    last_used: u64,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self {
+            max_entries: 256,
+            default_ttl: None,
+            max_stale: Duration::from_secs(24 * 60 * 60),
+            entries: HashMap::new(),
+            uses: 0,
+            evicted: vec![],
+        }
+    }
+}
+
+impl ResponseCache {
+    /// a cache of at most 256 responses that only keeps responses with freshness headers, stale
+    /// for up to a day
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// keep at most `max_entries` responses, see `max_entries`
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// keep responses without freshness headers for `ttl`, see `default_ttl`
+    pub fn with_default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    /// keep stale responses for `max_stale`, see `max_stale`
+    pub fn with_max_stale(mut self, max_stale: Duration) -> Self {
+        self.max_stale = max_stale;
+        self
+    }
+
+    /// number of responses kept
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// check if no response is kept
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The size of the bodies of the responses kept, in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.entries
+            .values()
+            .map(|entry| entry.response.bytes.len())
+            .sum()
+    }
+
+    /// Whether a response of `key` is kept in any namespace, fresh or stale.
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.keys().any(|entry| entry.key == key)
+    }
+
+    /// Removes the entries of `key`, in every namespace. Returns how many were removed.
+    pub fn invalidate(&mut self, key: &str) -> usize {
+        self.remove_where(|entry, _| entry.key == key)
+    }
+
+    /// Removes the entries whose key starts with `prefix`, in every namespace, e.g. after a
+    /// request changed them. Returns how many were removed.
+    pub fn invalidate_prefix(&mut self, prefix: &str) -> usize {
+        self.remove_where(|entry, _| entry.key.starts_with(prefix))
+    }
+
+    /// Removes the entries of `namespace`, e.g. when the player logs out. Returns how many were
+    /// removed.
+    pub fn invalidate_namespace(&mut self, namespace: &str) -> usize {
+        self.remove_where(|entry, _| entry.namespace.as_deref() == Some(namespace))
+    }
+
+    /// Removes the entries of requests to `host`, e.g. `api.example.com`, whatever their keys.
+    /// Returns how many were removed.
+    pub fn invalidate_host(&mut self, host: &str) -> usize {
+        self.remove_where(|_, cached| {
+            Url::parse(&cached.url)
+                .is_ok_and(|url| url.host_str().is_some_and(|h| h.eq_ignore_ascii_case(host)))
+        })
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        self.remove_where(|_, _| true);
+    }
+
+    fn remove_where(&mut self, remove: impl Fn(&EntryKey, &CachedResponse) -> bool) -> usize {
+        let before = self.entries.len();
+        let evicted = &mut self.evicted;
+        self.entries.retain(|entry, cached| {
+            let removed = remove(entry, cached);
+            if removed {
+                evicted.push(CacheEvicted::new(entry, EvictionReason::Invalidated));
+            }
+            !removed
+        });
+        before - self.entries.len()
+    }
+
+    /// Evicts the least recently used entries until the bodies kept fit in `max_bytes`. Returns
+    /// how many were evicted.
+    pub(crate) fn shrink_to(&mut self, max_bytes: usize) -> usize {
+        let mut size = self.size_bytes();
+        if size <= max_bytes {
+            return 0;
+        }
+        let mut entries: Vec<(u64, usize, EntryKey)> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.last_used, entry.response.bytes.len(), key.clone()))
+            .collect();
+        entries.sort_unstable_by_key(|(last_used, _, _)| *last_used);
+        let mut evicted = 0;
+        for (_, bytes, key) in entries {
+            if size <= max_bytes {
+                break;
+            }
+            self.evict(&key, EvictionReason::MemoryBudget);
+            size -= bytes;
+            evicted += 1;
+        }
+        evicted
+    }
+
+    fn evict(&mut self, key: &EntryKey, reason: EvictionReason) {
+        if self.entries.remove(key).is_some() {
+            self.evicted.push(CacheEvicted::new(key, reason));
+        }
+    }
+
+    /// The response of `key` if it is fresh, or else if it is stale and `stale` is set, dropping
+    /// it once it is too stale.
+    fn get(&mut self, key: &EntryKey, stale: bool, now: Instant) -> Option<Response> {
+        self.uses += 1;
+        let entry = self.entries.get_mut(key)?;
+        if entry.stale_until <= now {
+            self.evict(key, EvictionReason::Expired);
+            return None;
+        }
+        if entry.expires_at <= now && !stale {
+            return None;
+        }
+        entry.last_used = self.uses;
+        Some(entry.response.clone())
+    }
+
+    fn store(
+        &mut self,
+        key: EntryKey,
+        url: String,
+        response: Response,
+        fresh_for: Duration,
+        now: Instant,
+    ) {
+        let stale_for = response
+            .headers
+            .get(CacheControl::NAME)
+            .and_then(CacheControl::parse)
+            .and_then(|directives| directives.stale_while_revalidate)
+            .unwrap_or(self.max_stale);
+        self.uses += 1;
+        self.entries.insert(
+            key,
+            CachedResponse {
+                url,
+                response,
+                expires_at: now + fresh_for,
+                stale_until: now + fresh_for + stale_for,
+                last_used: self.uses,
+            },
+        );
+        if self.entries.len() > self.max_entries {
+            let expired: Vec<EntryKey> = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.stale_until <= now)
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in expired {
+                self.evict(&key, EvictionReason::Expired);
+            }
+        }
+        while self.entries.len() > self.max_entries {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.evict(&oldest, EvictionReason::Full);
+        }
+    }
+}
+
+/// A response was removed from the [`ResponseCache`].
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct CacheEvicted {
+    pub key: String,
+    pub namespace: Option<String>,
+    pub reason: EvictionReason,
+}
+
+impl CacheEvicted {
+    fn new(key: &EntryKey, reason: EvictionReason) -> Self {
+        Self {
+            key: key.key.clone(),
+            namespace: key.namespace.clone(),
+            reason,
+        }
+    }
+}
+
+/// Why a response was removed from the [`ResponseCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    /// It was invalidated or the cache was cleared, either with the methods of the cache or by a
+    /// successful request changing its url.
+    Invalidated,
+    /// It was stale for longer than it may be served.
+    Expired,
+    /// It was the least recently used entry of a full cache.
+    Full,
+    /// It was the least recently used entry while responses were over the `MemoryBudget`.
+    MemoryBudget,
+}
+
+/// The cache entry of `request`, `None` if the request is not cached.
+fn entry_key(request: &HttpRequest) -> Option<EntryKey> {
+    let key = match &request.caching.key {
+        Some(key) => key.clone(),
+        None if request.request.method == "GET" => request.request.url.clone(),
+        None => return None,
+    };
+    Some(EntryKey {
+        namespace: request.caching.namespace.clone(),
+        key,
+    })
+}
+
+/// The `Cache-Control` directives the request sends.
+fn request_directives(request: &HttpRequest) -> CacheControl {
+    request
+        .request
+        .headers
+        .get(CacheControl::NAME)
+        .and_then(CacheControl::parse)
+        .unwrap_or_default()
+}
+
+/// The cache entry of `request` and its response if it is fresh, `None` if the request is not
+/// cached.
+pub(crate) fn lookup(
+    cache: &mut ResponseCache,
+    request: &HttpRequest,
+    now: Instant,
+) -> Option<(EntryKey, Option<Response>)> {
+    let directives = request_directives(request);
+    if directives.no_store {
+        return None;
+    }
+    let key = entry_key(request)?;
+    let response = match directives.no_cache {
+        true => None,
+        false => cache.get(&key, false, now),
+    };
+    Some((key, response))
+}
+
+/// The stale response a `stale_while_revalidate` request is answered with before it is sent, `None`
+/// if the cached response is fresh, and dispatching answers with it, or if there is none.
+pub(crate) fn stale_response(
+    cache: Option<&mut ResponseCache>,
+    environments: Option<&Environments>,
+    request: &HttpRequest,
+    now: Instant,
+) -> Option<Response> {
+    let cache = cache?;
+    let directives = request_directives(request);
+    if !request.caching.stale_while_revalidate || directives.no_store || directives.no_cache {
+        return None;
+    }
+    // The url the request is dispatched with.
+    let mut request = request.clone();
+    if let Some(environment) = environments.and_then(Environments::active) {
+        environment.apply(&mut request.request);
+    }
+    let key = entry_key(&request)?;
+    match cache.get(&key, false, now) {
+        Some(_) => None,
+        None => cache.get(&key, true, now),
+    }
+}
+
+/// Wraps `on_response` so a successful response is kept under `key`, or, for a POST, PUT, PATCH or
+/// DELETE without a cache key, invalidates the entries of its url.
+pub(crate) fn with_cache(
+    request: &HttpRequest,
+    key: Option<EntryKey>,
+    on_response: ResponseHandler,
+) -> ResponseHandler {
+    let url = request.request.url.clone();
+    let ttl = request.caching.ttl;
+    let Some(key) = key else {
+        if !matches!(
+            request.request.method.as_str(),
+            "POST" | "PUT" | "PATCH" | "DELETE"
+        ) {
+            return on_response;
+        }
+        return Box::new(move |world, request_id, response| {
+            let changed = response.as_ref().is_ok_and(|res| res.ok);
+            if let (true, Some(mut cache)) = (changed, world.get_resource_mut::<ResponseCache>()) {
+                cache.invalidate(&url);
+            }
+            on_response(world, request_id, response);
+        });
+    };
+    Box::new(move |world, request_id, response| {
+        if let Some(res) = response.as_ref().ok().filter(|res| res.ok) {
+            let default_ttl = ttl.or_else(|| {
+                world
+                    .get_resource::<ResponseCache>()
+                    .and_then(|cache| cache.default_ttl)
+            });
+            let fresh_for = freshness(res, default_ttl, world.resource::<ClockSkew>());
+            let now = world.resource::<HttpClock>().now();
+            if let (Some(fresh_for), Some(mut cache)) =
+                (fresh_for, world.get_resource_mut::<ResponseCache>())
+            {
+                cache.store(key, url, res.clone(), fresh_for, now);
+            }
+        }
+        on_response(world, request_id, response);
+    })
+}
+
+/// How long `res` may be answered from the cache.
+fn freshness(
+    res: &Response,
+    default_ttl: Option<Duration>,
+    clock_skew: &ClockSkew,
+) -> Option<Duration> {
+    let fresh_for = match res
+        .headers
+        .get(CacheControl::NAME)
+        .and_then(CacheControl::parse)
+    {
+        Some(directives) if directives.no_store || directives.no_cache => return None,
+        Some(CacheControl {
+            max_age: Some(max_age),
+            ..
+        }) => {
+            let age = res
+                .headers
+                .get("Age")
+                .and_then(|age| age.trim().parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or_default();
+            max_age.saturating_sub(age)
+        }
+        // An invalid date means the response is already stale.
+        _ => match res.headers.get("Expires") {
+            Some(expires) => parse_http_date(expires)
+                .map(|expires| clock_skew.until(expires))
+                .unwrap_or_default(),
+            None => default_ttl?,
+        },
+    };
+    (!fresh_for.is_zero()).then_some(fresh_for)
+}
+
+/// Sends the [`CacheEvicted`] events of the evictions since the last run.
+pub(crate) fn send_evictions(
+    cache: Option<ResMut<ResponseCache>>,
+    mut events: EventWriter<CacheEvicted>,
+) {
+    if let Some(mut cache) = cache.filter(|cache| !cache.evicted.is_empty()) {
+        events.send_batch(std::mem::take(&mut cache.evicted));
+    }
+}
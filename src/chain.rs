@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+
+use crate::{deliver, HttpRequest, HttpResponse, HttpResponseError, RequestId, RequestQueue};
+
+/// Builds the request of the next step from the response of the previous one.
+type ChainStep = Arc<dyn Fn(&HttpResponse) -> Result<HttpRequest, String> + Send + Sync>;
+
+/// Sends requests one after the other, each built from the response of the one before, e.g. log
+/// in, then fetch the profile with the token, then fetch the inventory of the profile, and delivers
+/// a single [`ChainResponse`] with the last response.
+///
+/// The chain stops at the first step that fails, gets a non-2xx response or whose response the
+/// next step cannot be built from, and reports that step in a [`ChainError`]. The requests are
+/// always sent on entities of their own, their `entity`, `on_complete` and `respond_to` are
+/// ignored.
+///
+/// # Examples
+///
+/// ```
+/// ev_chain.send(
+///     RequestChain::new(HttpClient::new().post("https://api.example.com/login").json(&credentials).build())
+///         .then(|login: Login| {
+///             HttpClient::new()
+///                 .get("https://api.example.com/profile")
+///                 .bearer_auth(login.token)
+///                 .build()
+///         })
+///         .then(|profile: Profile| {
+///             HttpClient::new()
+///                 .get("https://api.example.com/inventory")
+///                 .path_segment(&profile.id)
+///                 .build()
+///         }),
+/// );
+/// ```
+#[derive(Event, Clone)]
+pub struct RequestChain {
+    /// Identifies the chain in its [`ChainResponse`].
+    pub id: RequestId,
+    first: HttpRequest,
+    steps: Vec<ChainStep>,
+    /// The chain response is inserted on this entity instead of being sent as an event.
+    pub respond_to: Option<Entity>,
+}
+
+impl RequestChain {
+    /// a chain starting with `first`
+    pub fn new(first: HttpRequest) -> Self {
+        Self {
+            id: RequestId::new(),
+            first,
+            steps: vec![],
+            respond_to: None,
+        }
+    }
+
+    /// Adds a step sending the request `next` builds from the JSON body of the previous response.
+    pub fn then<T: DeserializeOwned>(
+        self,
+        next: impl Fn(T) -> HttpRequest + Send + Sync + 'static,
+    ) -> Self {
+        self.then_response(move |response| {
+            response.json::<T>().map(&next).map_err(|e| format!("{e}"))
+        })
+    }
+
+    /// Adds a step sending the request `next` builds from the previous response, e.g. from its
+    /// headers. An error stops the chain with `ChainErrorKind::Parse`.
+    pub fn then_response(
+        mut self,
+        next: impl Fn(&HttpResponse) -> Result<HttpRequest, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.steps.push(Arc::new(next));
+        self
+    }
+
+    /// insert the chain response on `target` instead of sending it as an event
+    pub fn respond_to(mut self, target: Entity) -> Self {
+        self.respond_to = Some(target);
+        self
+    }
+}
+
+impl std::fmt::Debug for RequestChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestChain")
+            .field("id", &self.id)
+            .field("first", &self.first)
+            .field("steps", &self.steps.len())
+            .field("respond_to", &self.respond_to)
+            .finish()
+    }
+}
+
+/// The outcome of a [`RequestChain`], the response of its last request or the step that broke it.
+#[derive(Event, Component, Debug, Clone)]
+pub struct ChainResponse {
+    pub chain_id: RequestId,
+    pub result: Result<HttpResponse, ChainError>,
+}
+
+/// The step a [`RequestChain`] stopped at, counted from 0 for the first request.
+#[derive(Debug, Clone)]
+pub struct ChainError {
+    pub step: usize,
+    pub kind: ChainErrorKind,
+}
+
+/// Why a step of a [`RequestChain`] broke it.
+#[derive(Debug, Clone)]
+pub enum ChainErrorKind {
+    /// The request of the step failed.
+    Failed(HttpResponseError),
+    /// The request of the step got a non-2xx response.
+    Status(HttpResponse),
+    /// The next step could not be built from the response of the step, e.g. from a JSON body
+    /// that does not match its type.
+    Parse(String),
+}
+
+impl std::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let step = self.step;
+        match &self.kind {
+            ChainErrorKind::Failed(e) => write!(f, "request chain step {step} failed: {e}"),
+            ChainErrorKind::Status(res) => write!(
+                f,
+                "request chain step {step} got {} {}",
+                res.status, res.status_text
+            ),
+            ChainErrorKind::Parse(e) => {
+                write!(
+                    f,
+                    "request chain step {step} has an unexpected response: {e}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+/// A chain waiting for the response of its current step.
+struct ChainRun {
+    id: RequestId,
+    respond_to: Option<Entity>,
+    step: usize,
+    steps: VecDeque<ChainStep>,
+}
+
+impl ChainRun {
+    fn send(self, queue: &mut RequestQueue, request: HttpRequest) {
+        let request = HttpRequest {
+            from_entity: None,
+            respond_to: None,
+            on_complete: None,
+            ..request
+        };
+        queue.push(request, move |world, request_id, result| {
+            self.advance(world, request_id, result);
+        });
+    }
+
+    fn advance(
+        mut self,
+        world: &mut World,
+        request_id: RequestId,
+        result: ehttp::Result<ehttp::Response>,
+    ) {
+        let response = match result {
+            Ok(res) if res.ok => HttpResponse::new(request_id, res),
+            Ok(res) => {
+                let response = HttpResponse::new(request_id, res);
+                return self.fail(world, ChainErrorKind::Status(response));
+            }
+            Err(e) => {
+                let error = HttpResponseError::new(request_id, e);
+                return self.fail(world, ChainErrorKind::Failed(error));
+            }
+        };
+        let Some(next) = self.steps.pop_front() else {
+            let response = ChainResponse {
+                chain_id: self.id,
+                result: Ok(response),
+            };
+            deliver(world, self.respond_to, response);
+            return;
+        };
+        match next(&response) {
+            Ok(request) => {
+                self.step += 1;
+                self.send(&mut world.resource_mut::<RequestQueue>(), request);
+            }
+            Err(e) => self.fail(world, ChainErrorKind::Parse(e)),
+        }
+    }
+
+    fn fail(self, world: &mut World, kind: ChainErrorKind) {
+        let response = ChainResponse {
+            chain_id: self.id,
+            result: Err(ChainError {
+                step: self.step,
+                kind,
+            }),
+        };
+        deliver(world, self.respond_to, response);
+    }
+}
+
+/// Queues the first request of every chain.
+pub(crate) fn handle_chains(
+    mut queue: ResMut<RequestQueue>,
+    mut chains: EventReader<RequestChain>,
+) {
+    for chain in chains.read() {
+        let run = ChainRun {
+            id: chain.id,
+            respond_to: chain.respond_to,
+            step: 0,
+            steps: chain.steps.iter().cloned().collect(),
+        };
+        run.send(&mut queue, chain.first.clone());
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::app::{App, Plugin};
+use bevy::log::warn;
+use bevy::prelude::Resource;
+use ehttp::{Headers, Response};
+
+use crate::{
+    HttpErrorKind, REQUEST_ABORTED, REQUEST_BLOCKED, REQUEST_DEADLINE_EXCEEDED, REQUEST_DUPLICATE,
+    REQUEST_QUEUE_FULL,
+};
+
+/// Fails a fraction of the requests on purpose, to soak-test retries and circuit breakers in CI.
+///
+/// A failing request is not sent, it gets one of the faults, picked at random, as its result. The
+/// random draws come from the seed, so a test run can be repeated exactly. Change the rate or the
+/// faults at runtime through the [`FaultInjection`] resource. Add it after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(
+///     FaultInjectionPlugin::new(0.1)
+///         .with_fault(Fault::Error(HttpErrorKind::Timeout))
+///         .with_fault(Fault::Status(503))
+///         .with_seed(42),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct FaultInjectionPlugin {
+    /// Fraction of requests that fail, from `0.0` to `1.0`.
+    pub rate: f32,
+    /// The faults a failing request gets, a `HttpErrorKind::Connect` error if there are none.
+    pub faults: Vec<Fault>,
+    pub seed: u64,
+}
+
+impl FaultInjectionPlugin {
+    /// fail `rate` of the requests, from `0.0` to `1.0`
+    pub fn new(rate: f32) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+            faults: vec![],
+            seed: 0,
+        }
+    }
+
+    pub fn with_fault(mut self, fault: Fault) -> Self {
+        self.faults.push(fault);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Plugin for FaultInjectionPlugin {
+    fn build(&self, app: &mut App) {
+        warn!(
+            "fault injection is active, {:.0}% of the requests fail on purpose",
+            self.rate * 100.0
+        );
+        app.insert_resource(FaultInjection {
+            enabled: true,
+            rate: self.rate,
+            faults: self.faults.clone(),
+            injected: 0,
+            rng: fastrand::Rng::with_seed(self.seed),
+        });
+    }
+}
+
+/// How an injected failure looks to the requester.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The request fails with an error of this kind.
+    Error(HttpErrorKind),
+    /// The request gets a response with this status and an empty body.
+    Status(u16),
+}
+
+impl Fault {
+    fn result(self, url: &str) -> ehttp::Result<Response> {
+        let message = match self {
+            Fault::Status(status) => {
+                return Ok(Response {
+                    url: url.to_owned(),
+                    ok: (200..300).contains(&status),
+                    status,
+                    status_text: "Injected fault".to_owned(),
+                    headers: Headers::default(),
+                    bytes: vec![],
+                })
+            }
+            Fault::Error(HttpErrorKind::Aborted) => return Err(REQUEST_ABORTED.to_owned()),
+            Fault::Error(HttpErrorKind::DeadlineExceeded) => {
+                return Err(REQUEST_DEADLINE_EXCEEDED.to_owned())
+            }
+            Fault::Error(HttpErrorKind::QueueFull) => return Err(REQUEST_QUEUE_FULL.to_owned()),
+            Fault::Error(HttpErrorKind::Blocked) => return Err(REQUEST_BLOCKED.to_owned()),
+            Fault::Error(HttpErrorKind::Duplicate) => return Err(REQUEST_DUPLICATE.to_owned()),
+            // Worded so `HttpErrorKind` classifies them as the chosen kind.
+            Fault::Error(HttpErrorKind::Dns) => "Dns Failed: injected fault",
+            Fault::Error(HttpErrorKind::Connect) => "Connection Failed: injected fault",
+            Fault::Error(HttpErrorKind::ConnectTimeout) => "Connection Failed: injected timed out",
+            Fault::Error(HttpErrorKind::Timeout) => "Injected fault: timed out",
+            Fault::Error(HttpErrorKind::Tls) => "Injected fault: TLS handshake failed",
+            Fault::Error(HttpErrorKind::InvalidRequest) => "Bad URL: injected fault",
+            Fault::Error(HttpErrorKind::TooManyRedirects) => "Injected fault: too many redirects",
+            Fault::Error(HttpErrorKind::Io) => "Network Error: injected fault",
+            Fault::Error(HttpErrorKind::Backend) => "Injected fault",
+        };
+        Err(message.to_owned())
+    }
+}
+
+/// The failures the [`FaultInjectionPlugin`] injects.
+#[derive(Resource, Debug)]
+pub struct FaultInjection {
+    /// Turns fault injection off without removing the plugin.
+    pub enabled: bool,
+    /// Fraction of requests that fail, from `0.0` to `1.0`.
+    pub rate: f32,
+    pub faults: Vec<Fault>,
+    injected: u64,
+    rng: fastrand::Rng,
+}
+
+impl FaultInjection {
+    /// Number of requests failed on purpose so far.
+    pub fn injected(&self) -> u64 {
+        self.injected
+    }
+
+    /// Draws whether the request to `url` fails, and how.
+    pub(crate) fn inject(&mut self, url: &str) -> Option<ehttp::Result<Response>> {
+        if !self.enabled || self.rate <= 0.0 || self.rng.f32() >= self.rate {
+            return None;
+        }
+        self.injected += 1;
+        let fault = match self.faults.len() {
+            0 => Fault::Error(HttpErrorKind::Connect),
+            len => self.faults[self.rng.usize(..len)],
+        };
+        Some(fault.result(url))
+    }
+}
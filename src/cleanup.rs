@@ -0,0 +1,236 @@
+use std::any::TypeId;
+use std::time::Duration;
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::{HttpClientSetting, HttpClock};
+
+/// Removes the responses the client leaves on entities once they had time to be handled, see
+/// `HttpClientSettings::response_cleanup`.
+///
+/// Applies to the results inserted on the target of `HttpClient::respond_to`, e.g. `HttpResponse`,
+/// `HttpResponseError` and `TypedResponse<T>`, and to the `ResponseMeta` and `RequestTiming` of
+/// entities passed to `HttpClient::entity`. Insert it on an entity to override the setting for
+/// that entity, only results delivered while a policy applies are cleaned up. Entities spawned for
+/// requests without `HttpClient::entity` are despawned right away as before.
+///
+/// # Examples
+///
+/// ```
+/// // Keep results for a frame, then despawn the entities so long sessions don't pile them up.
+/// app.add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///     response_cleanup: Some(ResponseCleanup::after_frames(1).despawning()),
+///     ..default()
+/// }));
+///
+/// // The profile panel keeps its response for 30 seconds instead.
+/// commands.spawn((ProfilePanel, ResponseCleanup::after(Duration::from_secs(30))));
+/// ```
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseCleanup {
+    /// How long the results stay after they were delivered.
+    pub keep: CleanupAfter,
+    /// Despawns the entity instead of removing the response components from it.
+    pub despawn: bool,
+}
+
+/// How long a [`ResponseCleanup`] keeps results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupAfter {
+    /// Removed in the given frame after the one they were delivered in, counting the frames of
+    /// the schedule the requests run in.
+    Frames(u32),
+    /// Removed once the duration passed on the [`HttpClock`].
+    Time(Duration),
+}
+
+impl ResponseCleanup {
+    /// remove the response components `frames` frames after the one they were delivered in
+    pub fn after_frames(frames: u32) -> Self {
+        Self {
+            keep: CleanupAfter::Frames(frames),
+            despawn: false,
+        }
+    }
+
+    /// remove the response components once `duration` passed on the [`HttpClock`]
+    pub fn after(duration: Duration) -> Self {
+        Self {
+            keep: CleanupAfter::Time(duration),
+            despawn: false,
+        }
+    }
+
+    /// despawn the entity instead of removing its response components, see `despawn`
+    pub fn despawning(mut self) -> Self {
+        self.despawn = true;
+        self
+    }
+}
+
+/// Removes one of the components a response left on the entity.
+type Remover = fn(&mut EntityCommands);
+
+fn remove<T: Component>(entity: &mut EntityCommands) {
+    entity.remove::<T>();
+}
+
+/// The results delivered to an entity under a [`ResponseCleanup`], since the latest delivery.
+#[derive(Component)]
+pub(crate) struct DeliveredResponses {
+    cleanup: ResponseCleanup,
+    delivered_at: Instant,
+    frames: u32,
+    removers: Vec<(TypeId, Remover)>,
+}
+
+/// Schedules the removal of the `T` just put on `entity`, if a [`ResponseCleanup`] applies to it.
+pub(crate) fn track<T: Component>(world: &mut World, entity: Entity) {
+    let now = world.resource::<HttpClock>().now();
+    let setting = world
+        .get_resource::<HttpClientSetting>()
+        .and_then(|settings| settings.response_cleanup);
+    let Some(mut entity) = world.get_entity_mut(entity) else {
+        return;
+    };
+    let Some(cleanup) = entity.get::<ResponseCleanup>().copied().or(setting) else {
+        return;
+    };
+    let remover = (TypeId::of::<T>(), remove::<T> as Remover);
+    if let Some(mut delivered) = entity.get_mut::<DeliveredResponses>() {
+        delivered.cleanup = cleanup;
+        delivered.delivered_at = now;
+        delivered.frames = 0;
+        if !delivered.removers.iter().any(|(id, _)| *id == remover.0) {
+            delivered.removers.push(remover);
+        }
+    } else {
+        entity.insert(DeliveredResponses {
+            cleanup,
+            delivered_at: now,
+            frames: 0,
+            removers: vec![remover],
+        });
+    }
+}
+
+/// Removes the results whose time is up, or despawns their entities.
+pub(crate) fn clean_up_responses(
+    mut commands: Commands,
+    mut delivered: Query<(Entity, &mut DeliveredResponses)>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    for (entity, mut responses) in delivered.iter_mut() {
+        let expired = match responses.cleanup.keep {
+            CleanupAfter::Frames(frames) => responses.frames >= frames,
+            CleanupAfter::Time(duration) => now >= responses.delivered_at + duration,
+        };
+        if !expired {
+            responses.frames += 1;
+            continue;
+        }
+        if responses.cleanup.despawn {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let mut entity = commands.entity(entity);
+        for (_, remove) in &responses.removers {
+            remove(&mut entity);
+        }
+        entity.remove::<DeliveredResponses>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpClientSettings;
+
+    #[derive(Component)]
+    struct Answer;
+
+    fn app(cleanup: Option<ResponseCleanup>) -> App {
+        let mut app = App::new();
+        app.insert_resource(HttpClock::default());
+        app.insert_resource(HttpClientSetting::from(&HttpClientSettings {
+            response_cleanup: cleanup,
+            ..default()
+        }));
+        app.add_systems(Update, clean_up_responses);
+        app
+    }
+
+    fn deliver(app: &mut App, entity: Entity) {
+        app.world.entity_mut(entity).insert(Answer);
+        track::<Answer>(&mut app.world, entity);
+    }
+
+    #[test]
+    fn removes_results_after_frames() {
+        let mut app = app(Some(ResponseCleanup::after_frames(2)));
+        let entity = app.world.spawn_empty().id();
+        deliver(&mut app, entity);
+        app.update();
+        app.update();
+        assert!(app.world.get::<Answer>(entity).is_some());
+        app.update();
+        assert!(app.world.get::<Answer>(entity).is_none());
+        assert!(app.world.get::<DeliveredResponses>(entity).is_none());
+        assert!(app.world.get_entity(entity).is_some());
+    }
+
+    #[test]
+    fn a_new_result_restarts_the_count() {
+        let mut app = app(Some(ResponseCleanup::after_frames(1)));
+        let entity = app.world.spawn_empty().id();
+        deliver(&mut app, entity);
+        app.update();
+        deliver(&mut app, entity);
+        app.update();
+        assert!(app.world.get::<Answer>(entity).is_some());
+        app.update();
+        assert!(app.world.get::<Answer>(entity).is_none());
+    }
+
+    #[test]
+    fn despawns_entities() {
+        let mut app = app(None);
+        let entity = app
+            .world
+            .spawn(ResponseCleanup::after_frames(0).despawning())
+            .id();
+        deliver(&mut app, entity);
+        app.update();
+        assert!(app.world.get_entity(entity).is_none());
+    }
+
+    #[test]
+    fn removes_results_after_a_duration() {
+        let mut app = app(Some(ResponseCleanup::after(Duration::from_secs(60))));
+        let entity = app.world.spawn_empty().id();
+        deliver(&mut app, entity);
+        app.update();
+        assert!(app.world.get::<Answer>(entity).is_some());
+        app.world
+            .get_mut::<DeliveredResponses>(entity)
+            .unwrap()
+            .delivered_at -= Duration::from_secs(61);
+        app.update();
+        assert!(app.world.get::<Answer>(entity).is_none());
+    }
+
+    #[test]
+    fn keeps_results_without_a_policy() {
+        let mut app = app(None);
+        let entity = app.world.spawn_empty().id();
+        deliver(&mut app, entity);
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(app.world.get::<Answer>(entity).is_some());
+        assert!(app.world.get::<DeliveredResponses>(entity).is_none());
+    }
+}
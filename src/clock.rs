@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+/// Where the [`HttpClock`] reads the time from, see `HttpClientSettings::clock`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ClockSource {
+    /// The wall clock, time keeps running while the game is paused.
+    #[default]
+    Real,
+    /// Bevy's `Time` of the schedule the requests run in, so pausing `Time<Virtual>` pauses
+    /// retries and polling, and a test can fast-forward them with `TimeUpdateStrategy`.
+    Virtual,
+}
+
+/// The clock retry backoffs, timeouts, deadlines, poll intervals and cache lifetimes are measured
+/// with. Latencies, timings and stats always measure the wall clock.
+///
+/// With [`ClockSource::Virtual`] it advances with Bevy's `Time` once per frame, before
+/// `HttpSet::Queue`, starting at the instant the plugin was added. Build deadlines with
+/// [`HttpClock::deadline`] rather than from `Instant::now()`, since the two drift apart while
+/// `Time<Virtual>` is paused or scaled.
+///
+/// # Examples
+///
+/// ```
+/// // A test retrying a failing request three times, without waiting for the backoff.
+/// app.add_plugins((
+///     MinimalPlugins,
+///     HttpClientPlugin::new(HttpClientSettings {
+///         clock: ClockSource::Virtual,
+///         ..default()
+///     }),
+/// ));
+/// app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(250)));
+///
+/// fn matchmaking_deadline(mut commands: Commands, clock: Res<HttpClock>) {
+///     commands.spawn((Matchmaking, clock.deadline(Duration::from_secs(10))));
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct HttpClock {
+    source: ClockSource,
+    origin: Instant,
+    elapsed: Duration,
+}
+
+impl HttpClock {
+    /// a clock reading the time from `source`, starting now
+    pub fn new(source: ClockSource) -> Self {
+        Self {
+            source,
+            origin: Instant::now(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    /// The current instant of the clock.
+    pub fn now(&self) -> Instant {
+        match self.source {
+            ClockSource::Real => Instant::now(),
+            ClockSource::Virtual => self.origin + self.elapsed,
+        }
+    }
+
+    /// A deadline `duration` from now on this clock.
+    pub fn deadline(&self, duration: Duration) -> crate::Deadline {
+        crate::Deadline(self.now() + duration)
+    }
+}
+
+impl Default for HttpClock {
+    fn default() -> Self {
+        Self::new(ClockSource::Real)
+    }
+}
+
+/// Catches a virtual clock up with the `Time` of the schedule.
+pub(crate) fn advance_clock(time: Option<Res<Time>>, mut clock: ResMut<HttpClock>) {
+    if let (ClockSource::Virtual, Some(time)) = (clock.source, time) {
+        clock.elapsed = time.elapsed();
+    }
+}
@@ -0,0 +1,355 @@
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+use bytes::Bytes;
+use ehttp::{Headers, Request, Response};
+
+use crate::{HttpClock, HttpRequest, HttpSchedule, HttpSet, OnComplete, REQUEST_ABORTED};
+
+/// Syncs save blobs with `{url}/{slot}`, with ETag based optimistic concurrency.
+///
+/// Uploads are sent as PUT with `If-Match` set to the ETag of the last download or upload of the
+/// slot, or `If-None-Match: *` when the slot is not known to exist yet. A `412 Precondition Failed`
+/// means another device saved in the meantime: the remote save is downloaded and handed to game
+/// code with a [`SaveConflict`], whose ETag is used by the next upload of the slot, so uploading
+/// the resolved save overwrites the remote one. Uploads that fail with a connection error, a
+/// timeout or a 5xx status are kept and retried every `retry_interval`, a newer upload of the slot
+/// replaces the waiting one. Add it after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(CloudSavePlugin::new("https://saves.example.com/players/42"));
+///
+/// fn save_game(mut uploads: EventWriter<UploadSave>, save: Res<SaveGame>) {
+///     uploads.send(UploadSave::new("slot1", save.to_bytes()));
+/// }
+///
+/// fn resolve(mut conflicts: EventReader<SaveConflict>, mut uploads: EventWriter<UploadSave>) {
+///     for conflict in conflicts.read() {
+///         let merged = merge(&conflict.local, conflict.remote.as_deref());
+///         uploads.send(UploadSave::new(conflict.slot.clone(), merged));
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CloudSavePlugin {
+    pub url: String,
+    /// Time between two attempts of an upload that failed while offline.
+    pub retry_interval: Duration,
+}
+
+impl CloudSavePlugin {
+    /// create the plugin syncing the saves below `url`
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            retry_interval: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+}
+
+impl Plugin for CloudSavePlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        app.insert_resource(CloudSaves {
+            url: self.url.trim_end_matches('/').to_string(),
+            retry_interval: self.retry_interval,
+            slots: HashMap::default(),
+        });
+        app.add_event::<UploadSave>();
+        app.add_event::<DownloadSave>();
+        app.add_event::<SaveUploaded>();
+        app.add_event::<SaveDownloaded>();
+        app.add_event::<SaveConflict>();
+        app.add_event::<SaveSyncFailed>();
+        app.add_systems(schedule, sync_cloud_saves.before(HttpSet::Queue));
+    }
+}
+
+/// Uploads `data` to the slot.
+#[derive(Event, Debug, Clone)]
+pub struct UploadSave {
+    pub slot: String,
+    pub data: Bytes,
+}
+
+impl UploadSave {
+    pub fn new(slot: impl ToString, data: impl Into<Bytes>) -> Self {
+        Self {
+            slot: slot.to_string(),
+            data: data.into(),
+        }
+    }
+}
+
+/// Downloads the slot, answered with a [`SaveDownloaded`].
+#[derive(Event, Debug, Clone)]
+pub struct DownloadSave {
+    pub slot: String,
+}
+
+impl DownloadSave {
+    pub fn new(slot: impl ToString) -> Self {
+        Self {
+            slot: slot.to_string(),
+        }
+    }
+}
+
+/// Sent when the server accepted an upload.
+#[derive(Event, Debug, Clone)]
+pub struct SaveUploaded {
+    pub slot: String,
+    pub etag: Option<String>,
+}
+
+/// The downloaded save, `None` if the slot does not exist on the server.
+#[derive(Event, Debug, Clone)]
+pub struct SaveDownloaded {
+    pub slot: String,
+    pub data: Option<Bytes>,
+    pub etag: Option<String>,
+}
+
+/// Sent when the remote save changed since the slot was last synced, with both versions.
+#[derive(Event, Debug, Clone)]
+pub struct SaveConflict {
+    pub slot: String,
+    /// The save that was rejected.
+    pub local: Bytes,
+    /// The save on the server, `None` if it was deleted.
+    pub remote: Option<Bytes>,
+    pub remote_etag: Option<String>,
+}
+
+/// Sent when an upload or download failed for good.
+#[derive(Event, Debug, Clone)]
+pub struct SaveSyncFailed {
+    pub slot: String,
+    pub error: String,
+}
+
+/// The sync state of every slot of the [`CloudSavePlugin`].
+#[derive(Resource, Debug)]
+pub struct CloudSaves {
+    url: String,
+    retry_interval: Duration,
+    slots: HashMap<String, SlotState>,
+}
+
+impl CloudSaves {
+    /// The ETag of the slot as of its last sync.
+    pub fn etag(&self, slot: &str) -> Option<&str> {
+        self.slots.get(slot)?.etag.as_deref()
+    }
+
+    /// Whether an upload of the slot is waiting or in flight, e.g. while offline.
+    pub fn is_pending(&self, slot: &str) -> bool {
+        self.slots
+            .get(slot)
+            .is_some_and(|state| state.upload.is_some() || state.in_flight)
+    }
+
+    fn slot_url(&self, slot: &str) -> String {
+        format!("{}/{slot}", self.url)
+    }
+}
+
+#[derive(Debug, Default)]
+struct SlotState {
+    etag: Option<String>,
+    /// The latest save waiting to be uploaded, with its generation.
+    upload: Option<(u64, Bytes)>,
+    generation: u64,
+    download: bool,
+    /// Only one request per slot is in flight, so uploads cannot overtake each other.
+    in_flight: bool,
+    retry_at: Option<Instant>,
+}
+
+fn sync_cloud_saves(
+    mut saves: ResMut<CloudSaves>,
+    mut uploads: EventReader<UploadSave>,
+    mut downloads: EventReader<DownloadSave>,
+    mut requests: EventWriter<HttpRequest>,
+    clock: Res<HttpClock>,
+) {
+    for upload in uploads.read() {
+        let state = saves.slots.entry(upload.slot.clone()).or_default();
+        state.generation += 1;
+        state.upload = Some((state.generation, upload.data.clone()));
+        state.retry_at = None;
+    }
+    for download in downloads.read() {
+        saves
+            .slots
+            .entry(download.slot.clone())
+            .or_default()
+            .download = true;
+    }
+
+    let now = clock.now();
+    let saves = &mut *saves;
+    for (slot, state) in saves.slots.iter_mut() {
+        if state.in_flight || state.retry_at.is_some_and(|retry_at| now < retry_at) {
+            continue;
+        }
+        let url = format!("{}/{slot}", saves.url);
+        if let Some((generation, data)) = state.upload.clone() {
+            state.in_flight = true;
+            requests.send(upload_request(
+                slot.clone(),
+                url,
+                state.etag.clone(),
+                generation,
+                data,
+            ));
+        } else if state.download {
+            state.download = false;
+            state.in_flight = true;
+            requests.send(download_request(slot.clone(), url, None));
+        }
+    }
+}
+
+fn upload_request(
+    slot: String,
+    url: String,
+    etag: Option<String>,
+    generation: u64,
+    data: Bytes,
+) -> HttpRequest {
+    let mut headers = Headers::new(&[("Content-Type", "application/octet-stream")]);
+    match &etag {
+        Some(etag) => headers.insert("If-Match", etag),
+        None => headers.insert("If-None-Match", "*"),
+    }
+    let request = Request {
+        method: "PUT".to_string(),
+        url,
+        body: data.to_vec(),
+        headers,
+        #[cfg(target_arch = "wasm32")]
+        mode: ehttp::Mode::default(),
+    };
+    HttpRequest {
+        on_complete: Some(OnComplete::new(move |world, _, response| {
+            on_uploaded(world, slot, generation, data, response);
+        })),
+        ..HttpRequest::new(request)
+    }
+}
+
+/// Downloads the slot, reporting the result as a conflict with `rejected` if set.
+fn download_request(slot: String, url: String, rejected: Option<Bytes>) -> HttpRequest {
+    HttpRequest {
+        on_complete: Some(OnComplete::new(move |world, _, response| {
+            on_downloaded(world, slot, rejected, response);
+        })),
+        ..HttpRequest::new(Request::get(url))
+    }
+}
+
+fn on_uploaded(
+    world: &mut World,
+    slot: String,
+    generation: u64,
+    data: Bytes,
+    response: ehttp::Result<Response>,
+) {
+    let now = world.resource::<HttpClock>().now();
+    let mut saves = world.resource_mut::<CloudSaves>();
+    let retry_interval = saves.retry_interval;
+    let url = saves.slot_url(&slot);
+    let state = saves.slots.entry(slot.clone()).or_default();
+    state.in_flight = false;
+    let retry = match &response {
+        Ok(res) => res.status >= 500 || matches!(res.status, 408 | 429),
+        Err(e) => e != REQUEST_ABORTED,
+    };
+    if retry {
+        state.retry_at = Some(now + retry_interval);
+        return;
+    }
+    if state
+        .upload
+        .as_ref()
+        .is_some_and(|(pending, _)| *pending == generation)
+    {
+        state.upload = None;
+    }
+
+    match response {
+        Ok(res) if res.ok => {
+            state.etag = etag(&res);
+            let etag = state.etag.clone();
+            world.send_event(SaveUploaded { slot, etag });
+        }
+        Ok(res) if res.status == 412 => {
+            state.in_flight = true;
+            world.send_event(download_request(slot, url, Some(data)));
+        }
+        Ok(res) => {
+            let error = format!("{} {}", res.status, res.status_text);
+            world.send_event(SaveSyncFailed { slot, error });
+        }
+        Err(error) => {
+            world.send_event(SaveSyncFailed { slot, error });
+        }
+    }
+}
+
+fn on_downloaded(
+    world: &mut World,
+    slot: String,
+    rejected: Option<Bytes>,
+    response: ehttp::Result<Response>,
+) {
+    let (etag, data) = match response {
+        Ok(res) if res.ok => (etag(&res), Some(Bytes::from(res.bytes))),
+        Ok(res) if res.status == 404 => (None, None),
+        result => {
+            let mut saves = world.resource_mut::<CloudSaves>();
+            saves.slots.entry(slot.clone()).or_default().in_flight = false;
+            let error = match result {
+                Ok(res) => format!("{} {}", res.status, res.status_text),
+                Err(e) => e,
+            };
+            world.send_event(SaveSyncFailed { slot, error });
+            return;
+        }
+    };
+    let mut saves = world.resource_mut::<CloudSaves>();
+    let state = saves.slots.entry(slot.clone()).or_default();
+    state.in_flight = false;
+    state.etag = etag.clone();
+    match rejected {
+        Some(local) => {
+            world.send_event(SaveConflict {
+                slot,
+                local,
+                remote: data,
+                remote_etag: etag,
+            });
+        }
+        None => {
+            world.send_event(SaveDownloaded { slot, data, etag });
+        }
+    }
+}
+
+fn etag(response: &Response) -> Option<String> {
+    response.headers.get("etag").map(ToString::to_string)
+}
@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+
+/// Pauses and resumes all outbound traffic, e.g. during a latency-critical match or while the
+/// platform reports a metered connection.
+///
+/// While paused no request is dispatched, requests that are sent keep waiting in the
+/// `RequestQueue` in their usual order and requests in flight finish as usual. Timeouts and
+/// deadlines of the waiting requests keep running, so a long pause can fail them.
+///
+/// # Examples
+///
+/// ```
+/// fn pause_during_match(mut control: ResMut<HttpClientControl>, state: Res<State<GameState>>) {
+///     if state.is_changed() {
+///         match state.get() {
+///             GameState::InMatch => control.pause(),
+///             _ => control.resume(),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct HttpClientControl {
+    paused: bool,
+}
+
+impl HttpClientControl {
+    /// stop dispatching requests until `resume`
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// dispatch the waiting requests again
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// check if requests are held back
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
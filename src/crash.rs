@@ -0,0 +1,224 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{HttpClient, HttpRequest, HttpSchedule, HttpSet, OnComplete};
+
+/// Installs a panic hook that writes a [`CrashReport`] to `directory`, and uploads the reports of
+/// earlier runs on the next startup. Not available on wasm builds.
+///
+/// Nothing is uploaded until [`CrashReportConsent`] is `Granted`, so the game can ask the player
+/// first. Reports are sent one POST each with a JSON body and removed once the server accepted
+/// them, denying consent removes them without uploading. The previous panic hook still runs after
+/// the report is written. Add it after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(CrashReportPlugin::new(
+///     "https://crash.example.com/reports",
+///     "crash_reports",
+/// ));
+///
+/// fn on_consent_dialog(mut consent: ResMut<CrashReportConsent>, reports: Res<PendingCrashReports>) {
+///     if !reports.is_empty() {
+///         *consent = CrashReportConsent::Granted;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrashReportPlugin {
+    pub url: String,
+    /// Where reports are stored until they are uploaded.
+    pub directory: PathBuf,
+    /// Consent used when no `CrashReportConsent` was inserted, e.g. when the EULA covers it.
+    pub consent: CrashReportConsent,
+}
+
+impl CrashReportPlugin {
+    /// create the plugin uploading to `url`, keeping reports in `directory`
+    pub fn new(url: impl ToString, directory: impl Into<PathBuf>) -> Self {
+        Self {
+            url: url.to_string(),
+            directory: directory.into(),
+            consent: CrashReportConsent::Pending,
+        }
+    }
+
+    pub fn with_consent(mut self, consent: CrashReportConsent) -> Self {
+        self.consent = consent;
+        self
+    }
+}
+
+impl Plugin for CrashReportPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        // Reports of earlier runs, read before this run can add its own.
+        let pending = PendingCrashReports(read_reports(&self.directory));
+        install_panic_hook(self.directory.clone());
+
+        if !app.world.contains_resource::<CrashReportConsent>() {
+            app.insert_resource(self.consent);
+        }
+        app.insert_resource(pending);
+        app.insert_resource(CrashReportUploader {
+            url: self.url.clone(),
+            uploading: false,
+        });
+        app.add_systems(schedule, upload_crash_reports.before(HttpSet::Queue));
+    }
+}
+
+/// Whether crash reports may be uploaded.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CrashReportConsent {
+    /// Reports are kept until the player decides.
+    #[default]
+    Pending,
+    Granted,
+    /// Reports are removed without being uploaded.
+    Denied,
+}
+
+/// What is known about a panic, stored as JSON and uploaded as is.
+///
+/// The game build is sent with the `ClientMetadata` headers, if configured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub message: String,
+    /// `file:line:column` of the panic, if known.
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub thread: Option<String>,
+    pub os: String,
+    pub arch: String,
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+}
+
+/// The crash reports of earlier runs that were not uploaded yet.
+#[derive(Resource, Debug, Default)]
+pub struct PendingCrashReports(Vec<(PathBuf, CrashReport)>);
+
+impl PendingCrashReports {
+    pub fn reports(&self) -> impl Iterator<Item = &CrashReport> {
+        self.0.iter().map(|(_, report)| report)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Resource)]
+struct CrashReportUploader {
+    url: String,
+    /// Whether a report is being uploaded, they are sent one after the other.
+    uploading: bool,
+}
+
+fn install_panic_hook(directory: PathBuf) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let report = CrashReport {
+            message,
+            location: info.location().map(ToString::to_string),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            thread: std::thread::current().name().map(ToString::to_string),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            timestamp,
+        };
+        // Best effort, the process is going down anyway.
+        if let Ok(bytes) = serde_json::to_vec_pretty(&report) {
+            let _ = std::fs::create_dir_all(&directory);
+            let name = format!("crash-{timestamp}-{}.json", std::process::id());
+            let _ = std::fs::write(directory.join(name), bytes);
+        }
+        previous(info);
+    }));
+}
+
+fn read_reports(directory: &Path) -> Vec<(PathBuf, CrashReport)> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return vec![];
+    };
+    let mut reports: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let report = serde_json::from_slice(&std::fs::read(&path).ok()?).ok()?;
+            Some((path, report))
+        })
+        .collect();
+    reports.sort_by_key(|(_, report): &(PathBuf, CrashReport)| report.timestamp);
+    reports
+}
+
+fn upload_crash_reports(
+    consent: Res<CrashReportConsent>,
+    mut pending: ResMut<PendingCrashReports>,
+    mut uploader: ResMut<CrashReportUploader>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    match *consent {
+        CrashReportConsent::Pending => {}
+        CrashReportConsent::Denied => {
+            for (path, _) in pending.0.drain(..) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        CrashReportConsent::Granted => {
+            if uploader.uploading {
+                return;
+            }
+            let Some((path, report)) = pending.0.first() else {
+                return;
+            };
+            let path = path.clone();
+            uploader.uploading = true;
+            let on_complete = OnComplete::new(move |world, _, response| {
+                world.resource_mut::<CrashReportUploader>().uploading = false;
+                let mut pending = world.resource_mut::<PendingCrashReports>();
+                let Some(index) = pending.0.iter().position(|(pending, _)| *pending == path) else {
+                    return;
+                };
+                if response.is_ok_and(|res| res.ok) {
+                    let _ = std::fs::remove_file(&path);
+                }
+                // Failed uploads stay on disk for the next run.
+                pending.0.remove(index);
+            });
+            requests.send(HttpRequest {
+                on_complete: Some(on_complete),
+                ..HttpClient::new()
+                    .post(&uploader.url)
+                    .json(report)
+                    .label("crash-report")
+                    .build()
+            });
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::{HttpClock, HttpRequest, RequestQueue, REQUEST_DEADLINE_EXCEEDED};
+
+/// The instant a request must have finished by, counting the time it waits in the queue, every
+/// retry and every mirror, unlike `HttpClient::timeout` which limits each attempt on its own.
+///
+/// Set it with `HttpClient::deadline`, or insert it on the entity passed to `HttpClient::entity` to
+/// apply it to every request of that entity. A request still waiting, in flight or due for a retry
+/// that would start too late fails with a `HttpErrorKind::DeadlineExceeded` error. It is an instant
+/// of the [`HttpClock`], which drifts apart from `Instant::now()` when the clock is virtual.
+///
+/// # Examples
+///
+/// ```
+/// // The whole matchmaking flow gets 10 seconds, however often its requests are retried.
+/// fn start_matchmaking(mut commands: Commands, clock: Res<HttpClock>) {
+///     commands.spawn((Matchmaking, Deadline::after(Duration::from_secs(10), &clock)));
+/// }
+/// ```
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Deadline(pub Instant);
+
+impl Deadline {
+    /// a deadline `duration` from now on the `clock`, like `HttpClock::deadline`
+    pub fn after(duration: std::time::Duration, clock: &HttpClock) -> Self {
+        clock.deadline(duration)
+    }
+
+    pub fn has_passed(&self, clock: &HttpClock) -> bool {
+        clock.now() >= self.0
+    }
+}
+
+/// The deadline of the request, or else of the request entity.
+fn deadline_of(request: &HttpRequest, deadlines: &Query<&Deadline>) -> Option<Deadline> {
+    request.deadline.or_else(|| {
+        request
+            .from_entity
+            .and_then(|entity| deadlines.get(entity).ok())
+            .copied()
+    })
+}
+
+/// Picks up the deadline of the request entity, unless the request brings its own, so retries and
+/// mirrors keep it.
+pub(crate) fn resolve(request: &mut HttpRequest, deadlines: &Query<&Deadline>) {
+    request.deadline = deadline_of(request, deadlines);
+}
+
+/// Fails the queued requests whose deadline passed while they waited for a client.
+pub(crate) fn expire_queued_requests(
+    mut commands: Commands,
+    mut queue: ResMut<RequestQueue>,
+    deadlines: Query<&Deadline>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    let has_expired = |request: &HttpRequest| {
+        deadline_of(request, &deadlines).is_some_and(|deadline| now >= deadline.0)
+    };
+    // Most frames nothing expires, a long queue is only rebuilt when something did.
+    if !queue.0.iter().any(|(request, _)| has_expired(request)) {
+        return;
+    }
+    let (expired, waiting) = std::mem::take(&mut queue.0)
+        .into_iter()
+        .partition::<Vec<_>, _>(|(request, _)| has_expired(request));
+    queue.0 = waiting.into();
+    for (request, on_response) in expired {
+        let request_id = request.id;
+        commands.add(move |world: &mut World| {
+            on_response(
+                world,
+                request_id,
+                Err(REQUEST_DEADLINE_EXCEEDED.to_string()),
+            );
+        });
+    }
+}
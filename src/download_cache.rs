@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use ehttp::{Headers, Request, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::write_atomically;
+use crate::{ETag, TypedHeader};
+
+/// Keeps the files downloaded by `HttpAssets` and `HttpImages` in a directory, with their `ETag`
+/// and `Last-Modified` headers, so restarted clients revalidate them instead of downloading them
+/// again. Only available with the `asset` or `image` feature, and not on wasm builds, where the
+/// browser cache does the same.
+///
+/// A cached file is requested with `If-None-Match` or `If-Modified-Since`, a `304 Not Modified`
+/// answer is turned into the cached response, any other successful answer replaces it. Only GET
+/// requests are cached, files without validators are stored but always downloaded again.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(DownloadCache::new("cache/downloads"));
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct DownloadCache {
+    dir: PathBuf,
+}
+
+impl DownloadCache {
+    /// keep the files in `dir`, created on the first download
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Removes every cached file.
+    pub fn clear(&self) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Adds the validators of the cached copy of `request` to it, the result of the request goes
+    /// through `CachedDownload::finish`.
+    pub(crate) fn revalidate(&self, request: &mut Request) -> Option<CachedDownload> {
+        if request.method != "GET" {
+            return None;
+        }
+        // A stable hash, the file names have to outlive the build.
+        let hash = request
+            .url
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+        let download = CachedDownload {
+            body: self.dir.join(format!("{hash:016x}")),
+            meta: self.dir.join(format!("{hash:016x}.json")),
+            url: request.url.clone(),
+        };
+        if let Some(meta) = download.read_meta() {
+            let headers = Headers {
+                headers: meta.headers,
+            };
+            if let Some(etag) = headers.get(ETag::NAME).and_then(ETag::parse) {
+                request.headers.insert("If-None-Match", etag.encode());
+            } else if let Some(last_modified) = headers.get("Last-Modified") {
+                request.headers.insert("If-Modified-Since", last_modified);
+            }
+        }
+        Some(download)
+    }
+}
+
+/// The headers of a cached file.
+#[derive(Serialize, Deserialize)]
+struct CachedMeta {
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+/// The cache files of one download.
+pub(crate) struct CachedDownload {
+    body: PathBuf,
+    meta: PathBuf,
+    url: String,
+}
+
+impl CachedDownload {
+    fn read_meta(&self) -> Option<CachedMeta> {
+        let meta: CachedMeta = serde_json::from_slice(&std::fs::read(&self.meta).ok()?)
+            .map_err(|e| warn!("Failed to parse {}: {e}", self.meta.display()))
+            .ok()?;
+        // Another url with the same hash.
+        (meta.url == self.url).then_some(meta)
+    }
+
+    /// Answers a `304 Not Modified` from the cache and stores fresh files.
+    pub(crate) fn finish(&self, result: ehttp::Result<Response>) -> ehttp::Result<Response> {
+        let res = result?;
+        if res.status == 304 {
+            if let Some((meta, bytes)) = self.read_meta().zip(std::fs::read(&self.body).ok()) {
+                return Ok(Response {
+                    ok: true,
+                    status: 200,
+                    status_text: "OK".to_string(),
+                    headers: Headers {
+                        headers: meta.headers,
+                    },
+                    bytes,
+                    ..res
+                });
+            }
+        } else if res.ok {
+            self.store(&res);
+        }
+        Ok(res)
+    }
+
+    /// Best effort, the file is downloaded again next time otherwise.
+    fn store(&self, res: &Response) {
+        let meta = CachedMeta {
+            url: self.url.clone(),
+            headers: res.headers.headers.clone(),
+        };
+        let Ok(meta) = serde_json::to_vec(&meta) else {
+            return;
+        };
+        if let Some(dir) = self.body.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        // The validators of the previous file must not vouch for the new one.
+        let _ = std::fs::remove_file(&self.meta);
+        let written = write_atomically(&self.body, &res.bytes)
+            .map_err(|e| (&self.body, e))
+            .and_then(|()| write_atomically(&self.meta, &meta).map_err(|e| (&self.meta, e)));
+        if let Err((path, e)) = written {
+            warn!("Failed to write {}: {e}", path.display());
+        }
+    }
+}
@@ -0,0 +1,384 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::{Instant, SystemTime, Uuid};
+use ehttp::Request;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{Store, StoredRequest};
+use crate::{idempotency, HttpClock, HttpRequest, HttpSchedule, HttpSet, OnComplete, RequestLabel};
+
+/// Delivers [`SendDurable`] requests until the server answers with a 2xx status or their TTL runs
+/// out, for purchase confirmations and other calls that must not be lost.
+///
+/// Failed attempts are retried with exponential backoff, whatever the error, with the same
+/// `Idempotency-Key` header so the backend can drop duplicates. With a store file, or a storage key on
+/// wasm builds, the waiting requests are written to disk or `localStorage` on every change and read
+/// back at startup, so delivery carries on in the next session. Sends [`DurableDelivered`] or [`DurableExpired`] once a request is settled.
+/// Add it after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(DurableDeliveryPlugin::new().with_store_file("outbox.json"));
+///
+/// fn confirm_purchase(mut durable: EventWriter<SendDurable>, purchase: Res<Purchase>) {
+///     durable.send(
+///         HttpClient::new()
+///             .post("https://shop.example.com/confirm")
+///             .json(&*purchase)
+///             .build_durable(),
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DurableDeliveryPlugin {
+    /// How long a request is retried when its `SendDurable` has no TTL.
+    pub ttl: Duration,
+    /// Backoff after the first failed attempt, doubled for every further failure.
+    pub retry_backoff: Duration,
+    pub max_retry_backoff: Duration,
+    /// File the waiting requests are kept in across sessions. Not available on wasm builds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub store_file: Option<PathBuf>,
+    /// `localStorage` key the waiting requests are kept under across sessions. Only available on
+    /// wasm builds.
+    #[cfg(target_arch = "wasm32")]
+    pub storage_key: Option<String>,
+}
+
+impl Default for DurableDeliveryPlugin {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            retry_backoff: Duration::from_secs(1),
+            max_retry_backoff: Duration::from_secs(600),
+            #[cfg(not(target_arch = "wasm32"))]
+            store_file: None,
+            #[cfg(target_arch = "wasm32")]
+            storage_key: None,
+        }
+    }
+}
+
+impl DurableDeliveryPlugin {
+    /// create the plugin retrying requests for a week
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_retry_backoff(
+        mut self,
+        retry_backoff: Duration,
+        max_retry_backoff: Duration,
+    ) -> Self {
+        self.retry_backoff = retry_backoff;
+        self.max_retry_backoff = max_retry_backoff;
+        self
+    }
+
+    /// keep the waiting requests in `path`, see `store_file`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_store_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.store_file = Some(path.into());
+        self
+    }
+
+    /// keep the waiting requests under `key` in `localStorage`, see `storage_key`
+    #[cfg(target_arch = "wasm32")]
+    pub fn with_storage_key(mut self, key: impl ToString) -> Self {
+        self.storage_key = Some(key.to_string());
+        self
+    }
+
+    fn store(&self) -> Option<Store> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self
+            .store_file
+            .as_ref()
+            .map(|path| Store::File(path.clone()));
+
+        #[cfg(target_arch = "wasm32")]
+        return self
+            .storage_key
+            .as_ref()
+            .map(|key| Store::LocalStorage(key.clone()));
+    }
+}
+
+impl Plugin for DurableDeliveryPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        let deliveries = self
+            .store()
+            .and_then(|store| store.load::<Vec<StoredDelivery>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(StoredDelivery::into_delivery)
+            .collect();
+        app.insert_resource(DurableDeliveries {
+            config: self.clone(),
+            deliveries,
+        });
+        app.add_event::<SendDurable>();
+        app.add_event::<DurableDelivered>();
+        app.add_event::<DurableExpired>();
+        app.add_systems(schedule, deliver_durable.before(HttpSet::Queue));
+    }
+}
+
+/// Identifies a [`SendDurable`] request across its attempts and sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeliveryId(pub Uuid);
+
+impl std::fmt::Display for DeliveryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Hands a request to the [`DurableDeliveryPlugin`], see `HttpClient::build_durable`.
+#[derive(Event, Debug, Clone)]
+pub struct SendDurable {
+    pub id: DeliveryId,
+    pub request: Request,
+    /// Overrides `DurableDeliveryPlugin::ttl`.
+    pub ttl: Option<Duration>,
+}
+
+impl SendDurable {
+    /// Wraps `request`, adding an `Idempotency-Key` header to POST and PATCH requests without one.
+    pub fn new(mut request: Request) -> Self {
+        idempotency::ensure_key(&mut request);
+        Self {
+            id: DeliveryId(Uuid::new_v4()),
+            request,
+            ttl: None,
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+
+/// Sent when the server accepted a durable request.
+#[derive(Event, Debug, Clone)]
+pub struct DurableDelivered {
+    pub id: DeliveryId,
+    pub status: u16,
+    /// Attempts it took, including the successful one.
+    pub attempts: u32,
+}
+
+/// Sent when the TTL of a durable request ran out before it was delivered.
+#[derive(Event, Debug, Clone)]
+pub struct DurableExpired {
+    pub id: DeliveryId,
+    pub attempts: u32,
+    /// The error of the last attempt, `None` if it was never sent.
+    pub last_error: Option<String>,
+}
+
+/// The requests the [`DurableDeliveryPlugin`] is still delivering.
+#[derive(Resource, Debug)]
+pub struct DurableDeliveries {
+    config: DurableDeliveryPlugin,
+    deliveries: Vec<Delivery>,
+}
+
+impl DurableDeliveries {
+    /// Whether the request is still being delivered.
+    pub fn contains(&self, id: DeliveryId) -> bool {
+        self.deliveries.iter().any(|delivery| delivery.id == id)
+    }
+
+    /// number of requests waiting to be delivered
+    pub fn len(&self) -> usize {
+        self.deliveries.len()
+    }
+
+    /// check if every request was delivered
+    pub fn is_empty(&self) -> bool {
+        self.deliveries.is_empty()
+    }
+
+    /// Writes the waiting requests to the store, removing it once there are none.
+    ///
+    /// Best effort, the requests stay in memory either way.
+    fn save(&self) {
+        if let Some(store) = self.config.store() {
+            if self.deliveries.is_empty() {
+                store.clear();
+            } else {
+                let stored: Vec<_> = self.deliveries.iter().map(StoredDelivery::from).collect();
+                store.save(&stored);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Delivery {
+    id: DeliveryId,
+    request: Request,
+    expires_at: SystemTime,
+    attempts: u32,
+    last_error: Option<String>,
+    retry_at: Option<Instant>,
+    in_flight: bool,
+}
+
+/// A delivery as written to the store.
+#[derive(Serialize, Deserialize)]
+struct StoredDelivery {
+    id: String,
+    #[serde(flatten)]
+    request: StoredRequest,
+    /// Seconds since the unix epoch.
+    expires_at: u64,
+    attempts: u32,
+    last_error: Option<String>,
+}
+
+impl From<&Delivery> for StoredDelivery {
+    fn from(delivery: &Delivery) -> Self {
+        Self {
+            id: delivery.id.to_string(),
+            request: StoredRequest::from(&delivery.request),
+            expires_at: delivery
+                .expires_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            attempts: delivery.attempts,
+            last_error: delivery.last_error.clone(),
+        }
+    }
+}
+
+impl StoredDelivery {
+    fn into_delivery(self) -> Option<Delivery> {
+        Some(Delivery {
+            id: DeliveryId(Uuid::parse_str(&self.id).ok()?),
+            request: self.request.into(),
+            expires_at: SystemTime::UNIX_EPOCH + Duration::from_secs(self.expires_at),
+            attempts: self.attempts,
+            last_error: self.last_error,
+            // Retried right away in the new session.
+            retry_at: None,
+            in_flight: false,
+        })
+    }
+}
+
+fn deliver_durable(
+    mut deliveries: ResMut<DurableDeliveries>,
+    mut sends: EventReader<SendDurable>,
+    mut expired: EventWriter<DurableExpired>,
+    mut requests: EventWriter<HttpRequest>,
+    clock: Res<HttpClock>,
+) {
+    let mut changed = false;
+    for send in sends.read() {
+        let ttl = send.ttl.unwrap_or(deliveries.config.ttl);
+        deliveries.deliveries.push(Delivery {
+            id: send.id,
+            request: send.request.clone(),
+            expires_at: SystemTime::now() + ttl,
+            attempts: 0,
+            last_error: None,
+            retry_at: None,
+            in_flight: false,
+        });
+        changed = true;
+    }
+
+    let now = SystemTime::now();
+    let before = deliveries.deliveries.len();
+    deliveries.deliveries.retain(|delivery| {
+        // An attempt in flight may still succeed, it expires once it failed.
+        let keep = delivery.in_flight || now < delivery.expires_at;
+        if !keep {
+            expired.send(DurableExpired {
+                id: delivery.id,
+                attempts: delivery.attempts,
+                last_error: delivery.last_error.clone(),
+            });
+        }
+        keep
+    });
+    changed |= deliveries.deliveries.len() != before;
+    if changed {
+        deliveries.save();
+    }
+
+    let instant = clock.now();
+    for delivery in deliveries.deliveries.iter_mut() {
+        if delivery.in_flight || delivery.retry_at.is_some_and(|retry_at| instant < retry_at) {
+            continue;
+        }
+        delivery.in_flight = true;
+        let id = delivery.id;
+        let on_complete = OnComplete::new(move |world, _, response| {
+            on_attempt_finished(world, id, response);
+        });
+        requests.send(HttpRequest {
+            on_complete: Some(on_complete),
+            label: Some(RequestLabel::new("durable")),
+            ..HttpRequest::new(delivery.request.clone())
+        });
+    }
+}
+
+fn on_attempt_finished(
+    world: &mut World,
+    id: DeliveryId,
+    response: ehttp::Result<ehttp::Response>,
+) {
+    let instant = world.resource::<HttpClock>().now();
+    let mut deliveries = world.resource_mut::<DurableDeliveries>();
+    let Some(index) = deliveries.deliveries.iter().position(|d| d.id == id) else {
+        return;
+    };
+    let retry_backoff = deliveries.config.retry_backoff;
+    let max_retry_backoff = deliveries.config.max_retry_backoff;
+    let delivery = &mut deliveries.deliveries[index];
+    delivery.in_flight = false;
+    delivery.attempts += 1;
+    match response {
+        Ok(res) if res.ok => {
+            let attempts = delivery.attempts;
+            deliveries.deliveries.remove(index);
+            deliveries.save();
+            world.send_event(DurableDelivered {
+                id,
+                status: res.status,
+                attempts,
+            });
+        }
+        result => {
+            delivery.last_error = Some(match result {
+                Ok(res) => format!("{} {}", res.status, res.status_text),
+                Err(e) => e,
+            });
+            let backoff = retry_backoff
+                .saturating_mul(2u32.saturating_pow(delivery.attempts - 1))
+                .min(max_retry_backoff);
+            delivery.retry_at = Some(instant + backoff);
+            deliveries.save();
+        }
+    }
+}
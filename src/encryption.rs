@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::{HttpRequest, ResponseTransform};
+
+/// Encrypts request bodies and decrypts response bodies for [`PayloadEncryption`], e.g. AES-GCM
+/// with a session key established at login.
+///
+/// The cipher frames its own output, an AEAD cipher would prefix each ciphertext with its nonce.
+/// Requests are encrypted again when they are retried, so every attempt can use a fresh nonce.
+///
+/// # Examples
+///
+/// ```
+/// struct SessionCipher(Aes256Gcm);
+///
+/// impl PayloadCipher for SessionCipher {
+///     fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+///         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+///         let ciphertext = self.0.encrypt(&nonce, plaintext).map_err(|e| e.to_string())?;
+///         Ok([nonce.as_slice(), &ciphertext].concat())
+///     }
+///
+///     fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+///         let (nonce, ciphertext) = ciphertext.split_at_checked(12).ok_or("Payload too short")?;
+///         self.0.decrypt(nonce.into(), ciphertext).map_err(|e| e.to_string())
+///     }
+/// }
+/// ```
+pub trait PayloadCipher: Send + Sync + 'static {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String>;
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+/// Encrypts the body of every request and decrypts the body of every 2xx response while it is
+/// inserted, except for requests sent with `HttpClient::plaintext`, such as the login that
+/// establishes the key.
+///
+/// Bodies are encrypted when the request is dispatched and decrypted in the request task, before
+/// the [`ResponseTransform`]s. Requests without a body are sent as they are and empty responses are
+/// delivered as they are. A request whose body fails to encrypt fails without being sent, as does
+/// one with a `StreamingBody`, a response that fails to decrypt fails the request.
+///
+/// # Examples
+///
+/// ```
+/// fn on_login(mut commands: Commands, mut ev_login: EventReader<TypedResponse<Session>>) {
+///     for session in ev_login.read() {
+///         let cipher = SessionCipher(Aes256Gcm::new(&session.key.into()));
+///         commands.insert_resource(PayloadEncryption::new(cipher));
+///     }
+/// }
+/// ```
+#[derive(Resource, Clone)]
+pub struct PayloadEncryption {
+    cipher: Arc<dyn PayloadCipher>,
+    /// Replaces the `Content-Type` of encrypted requests, e.g. `application/octet-stream`.
+    pub content_type: Option<String>,
+}
+
+impl PayloadEncryption {
+    pub fn new(cipher: impl PayloadCipher) -> Self {
+        Self {
+            cipher: Arc::new(cipher),
+            content_type: None,
+        }
+    }
+
+    /// send encrypted requests as `content_type`, see `content_type`
+    pub fn with_content_type(mut self, content_type: impl ToString) -> Self {
+        self.content_type = Some(content_type.to_string());
+        self
+    }
+
+    /// Encrypts the body of `request` and has its response decrypted, unless it is plaintext.
+    pub(crate) fn resolve(&self, request: &mut HttpRequest) -> Result<(), String> {
+        if request.plaintext {
+            return Ok(());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if request.streaming_body.is_some() {
+            return Err(
+                "Streaming bodies can not be encrypted, send them as plaintext".to_string(),
+            );
+        }
+        if !request.request.body.is_empty() {
+            request.request.body = self
+                .cipher
+                .encrypt(&request.request.body)
+                .map_err(|e| format!("Failed to encrypt request body: {e}"))?;
+            if let Some(content_type) = &self.content_type {
+                let headers = &mut request.request.headers;
+                headers
+                    .headers
+                    .retain(|(name, _)| !name.eq_ignore_ascii_case("Content-Type"));
+                headers.insert("Content-Type", content_type);
+            }
+        }
+        let cipher = self.cipher.clone();
+        let decrypt = ResponseTransform::new(move |mut res| {
+            if !res.bytes.is_empty() {
+                res.bytes = cipher
+                    .decrypt(&res.bytes)
+                    .map_err(|e| format!("Failed to decrypt response body: {e}"))?;
+            }
+            Ok(res)
+        });
+        request.transforms.insert(0, decrypt);
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for PayloadEncryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PayloadEncryption")
+            .field("content_type", &self.content_type)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,325 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use ehttp::Request;
+
+use crate::{join_url, metadata, RetryPolicy};
+
+/// The backend a build talks to, e.g. dev, staging or prod, registered in [`Environments`].
+///
+/// Bundles the base url that relative request urls are joined onto, further named base urls for
+/// other services, API keys and default headers. The keys and headers are sent with every request
+/// to the base url or one of the services that does not set them itself. Requests to any other
+/// url, e.g. a third-party CDN, a pre-signed upload url or a webhook, are sent without them, so the
+/// keys never leave the backend. Native builds also drop them when a redirect leads to another
+/// origin.
+///
+/// The timeout, retries and redirect limit of the environment apply to the requests that do not
+/// set their own with the `HttpClient` builder, and take the place of those of the
+/// `HttpClientSettings`.
+///
+/// # Examples
+///
+/// ```
+/// let staging = Environment::new()
+///     .base_url("https://staging.example.com/api/v1")
+///     .service("cdn", "https://staging-cdn.example.com")
+///     .api_key("X-Api-Key", "staging-key")
+///     .header("X-Env", "staging")
+///     .timeout(Duration::from_secs(30));
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Environment {
+    /// The url relative request urls are joined onto.
+    pub base_url: Option<String>,
+    /// Base urls of other services by name, see `Environments::url`.
+    pub services: HashMap<String, String>,
+    /// API keys by the header they are sent in.
+    pub api_keys: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    /// Overrides `HttpClientSettings::default_timeout`.
+    pub timeout: Option<Duration>,
+    /// Overrides `HttpClientSettings::default_retry`.
+    pub retry: Option<RetryPolicy>,
+    /// Overrides `HttpClientSettings::max_redirects`.
+    pub max_redirects: Option<usize>,
+}
+
+impl Environment {
+    /// an environment without base urls, keys or headers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// join relative request urls onto `base_url`, see `base_url`
+    pub fn base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// keep the base url of the service `name`, see `services`
+    pub fn service(mut self, name: impl ToString, base_url: impl ToString) -> Self {
+        self.services.insert(name.to_string(), base_url.to_string());
+        self
+    }
+
+    /// Sends `key` in the header `header` with every request, e.g. `X-Api-Key`. Unlike headers,
+    /// keys are left out of the `Debug` output.
+    pub fn api_key(mut self, header: impl ToString, key: impl ToString) -> Self {
+        self.api_keys.push((header.to_string(), key.to_string()));
+        self
+    }
+
+    /// send the header `name` with every request, see `headers`
+    pub fn header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// time out requests after `timeout`, see `timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// retry failed requests with `policy`, see `retry`
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// follow at most `max_redirects` redirects, see `max_redirects`
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = Some(max_redirects);
+        self
+    }
+
+    /// Joins a relative request url onto the base url and adds the keys and headers the request
+    /// does not set, if it goes to the base url or a service. Returns the names of the keys and
+    /// headers, so redirects to other origins can drop them.
+    pub(crate) fn apply(&self, request: &mut Request) -> Vec<String> {
+        if let Some(base_url) = &self.base_url {
+            if matches!(
+                url::Url::parse(&request.url),
+                Err(url::ParseError::RelativeUrlWithoutBase)
+            ) {
+                if let Ok(url) = join_url(base_url, &request.url) {
+                    request.url = url;
+                }
+            }
+        }
+        if !self.serves(&request.url) {
+            return vec![];
+        }
+        let mut names = vec![];
+        for (key, value) in self.api_keys.iter().chain(&self.headers) {
+            metadata::insert_default_header(&mut request.headers, key, value);
+            names.push(key.clone());
+        }
+        names
+    }
+
+    /// Whether `url` is below the base url or the base url of a service.
+    fn serves(&self, url: &str) -> bool {
+        let Ok(url) = url::Url::parse(url) else {
+            return false;
+        };
+        self.base_url
+            .iter()
+            .chain(self.services.values())
+            .filter_map(|base| url::Url::parse(base).ok())
+            .any(|base| {
+                let path = base.path().trim_end_matches('/');
+                base.origin() == url.origin()
+                    && url
+                        .path()
+                        .strip_prefix(path)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+    }
+}
+
+impl std::fmt::Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let api_keys: Vec<_> = self
+            .api_keys
+            .iter()
+            .map(|(header, _)| (header, "<redacted>"))
+            .collect();
+        f.debug_struct("Environment")
+            .field("base_url", &self.base_url)
+            .field("services", &self.services)
+            .field("api_keys", &api_keys)
+            .field("headers", &self.headers)
+            .field("timeout", &self.timeout)
+            .field("retry", &self.retry)
+            .field("max_redirects", &self.max_redirects)
+            .finish()
+    }
+}
+
+/// The environments a build knows and the active one, applied to every request when it is
+/// dispatched, so the same build can point at staging or prod without being recompiled.
+///
+/// Switching the environment at runtime affects the requests dispatched afterwards, requests
+/// already in flight or waiting for a retry keep the environment they were sent with. Requests are
+/// sent untouched while no environment is active.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(
+///     Environments::new("prod", Environment::new().base_url("https://api.example.com/v1"))
+///         .with("staging", Environment::new().base_url("https://staging.example.com/api/v1"))
+///         .select_from_env_var("GAME_ENV"),
+/// );
+///
+/// // https://api.example.com/v1/players/42, or the staging url with GAME_ENV=staging
+/// let request = HttpClient::new().get("players/42").build();
+///
+/// fn use_staging(mut environments: ResMut<Environments>) {
+///     environments.select("staging").unwrap();
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Environments {
+    environments: HashMap<String, Environment>,
+    active: Option<String>,
+}
+
+impl Environments {
+    /// register `environment` as `name` and make it the active one
+    pub fn new(name: impl ToString, environment: Environment) -> Self {
+        let name = name.to_string();
+        Self {
+            environments: HashMap::from([(name.clone(), environment)]),
+            active: Some(name),
+        }
+    }
+
+    /// register `environment` as `name`, see `insert`
+    pub fn with(mut self, name: impl ToString, environment: Environment) -> Self {
+        self.insert(name, environment);
+        self
+    }
+
+    /// Activates the environment named by the environment variable `var`, if it is set to one.
+    /// Does nothing on wasm builds, which have no environment variables.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+    pub fn select_from_env_var(mut self, var: &str) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(name) = std::env::var(var) {
+            if let Err(e) = self.select(&name) {
+                warn!("{var}: {e}");
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = var;
+        self
+    }
+
+    /// Registers `environment` as `name`, replacing the environment registered under that name
+    /// before.
+    pub fn insert(&mut self, name: impl ToString, environment: Environment) {
+        self.environments.insert(name.to_string(), environment);
+    }
+
+    /// Makes the environment `name` the active one, for the requests dispatched from now on.
+    pub fn select(&mut self, name: &str) -> Result<(), String> {
+        if !self.environments.contains_key(name) {
+            return Err(format!("Unknown environment {name:?}"));
+        }
+        self.active = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Sends requests untouched from now on.
+    pub fn deselect(&mut self) {
+        self.active = None;
+    }
+
+    /// The name of the active environment.
+    pub fn active_name(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    pub fn active(&self) -> Option<&Environment> {
+        self.environments.get(self.active.as_deref()?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Environment> {
+        self.environments.get(name)
+    }
+
+    /// Joins `path` onto the base url of the service `service` of the active environment, see
+    /// [`join_url`]. `None` without an active environment or if it has no such service.
+    pub fn url(&self, service: &str, path: &str) -> Option<String> {
+        let base_url = self.active()?.services.get(service)?;
+        join_url(base_url, path).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(environment: &Environment, url: &str) -> Request {
+        let mut request = Request::get(url);
+        environment.apply(&mut request);
+        request
+    }
+
+    fn staging() -> Environment {
+        Environment::new()
+            .base_url("https://staging.example.com/api/v1")
+            .service("cdn", "https://staging-cdn.example.com")
+            .api_key("X-Api-Key", "staging-key")
+            .header("X-Env", "staging")
+    }
+
+    #[test]
+    fn relative_urls_are_joined_and_get_the_key() {
+        let request = apply(&staging(), "players/42");
+        assert_eq!(request.url, "https://staging.example.com/api/v1/players/42");
+        assert_eq!(request.headers.get("X-Api-Key"), Some("staging-key"));
+        assert_eq!(request.headers.get("X-Env"), Some("staging"));
+    }
+
+    #[test]
+    fn service_urls_get_the_key() {
+        let request = apply(&staging(), "https://staging-cdn.example.com/maps/1.bin");
+        assert_eq!(request.headers.get("X-Api-Key"), Some("staging-key"));
+    }
+
+    #[test]
+    fn foreign_origins_do_not_get_the_key() {
+        for url in [
+            "https://cdn.thirdparty.net/avatar.png",
+            "https://bucket.s3.amazonaws.com/upload?X-Amz-Signature=abc",
+            "http://staging.example.com/api/v1/players/42",
+            "https://staging.example.com:8443/api/v1/players/42",
+            "https://staging.example.com.evil.net/api/v1",
+        ] {
+            let request = apply(&staging(), url);
+            assert_eq!(request.headers.get("X-Api-Key"), None, "{url}");
+            assert_eq!(request.headers.get("X-Env"), None, "{url}");
+        }
+    }
+
+    #[test]
+    fn paths_outside_the_base_url_do_not_get_the_key() {
+        for url in [
+            "https://staging.example.com/admin",
+            "https://staging.example.com/api/v10/players",
+        ] {
+            assert_eq!(
+                apply(&staging(), url).headers.get("X-Api-Key"),
+                None,
+                "{url}"
+            );
+        }
+        let request = apply(&staging(), "https://staging.example.com/api/v1");
+        assert_eq!(request.headers.get("X-Api-Key"), Some("staging-key"));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    REQUEST_ABORTED, REQUEST_BLOCKED, REQUEST_DEADLINE_EXCEEDED, REQUEST_DUPLICATE,
+    REQUEST_QUEUE_FULL, REQUEST_TIMED_OUT,
+};
+
+/// What made a request fail, taken from the error kind of ureq on native builds and classified
+/// from the error message of the backend otherwise.
+///
+/// # Examples
+///
+/// ```
+/// for error in ev_error.read() {
+///     match error.kind {
+///         HttpErrorKind::Dns | HttpErrorKind::Connect => println!("offline? {}", error.message),
+///         HttpErrorKind::Aborted => {}
+///         _ => println!("request failed: {error}"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HttpErrorKind {
+    /// The host name could not be resolved.
+    Dns,
+    /// The connection was refused or reset before the request was sent.
+    Connect,
+    /// The connection could not be established in time.
+    ConnectTimeout,
+    /// The request took longer than its timeout.
+    Timeout,
+    /// The request did not finish by its `Deadline`, counting its retries.
+    DeadlineExceeded,
+    /// The TLS handshake failed, e.g. on an invalid certificate.
+    Tls,
+    /// The request could not be built, e.g. from a malformed url or an unknown scheme.
+    InvalidRequest,
+    /// The server redirected too many times.
+    TooManyRedirects,
+    /// The connection broke while the response was read.
+    Io,
+    /// The request was aborted with `AbortRequest`.
+    Aborted,
+    /// The request was dropped from the queue over its `QueueLimit` before it was sent.
+    QueueFull,
+    /// The url of the request or of one of its redirects is not allowed by the `UrlPolicy`.
+    Blocked,
+    /// Another request with the same `SingleFlight` key was in flight, the request was not sent.
+    Duplicate,
+    /// Any other failure, e.g. a browser fetch error on wasm builds.
+    Backend,
+}
+
+impl HttpErrorKind {
+    /// The kinds the native transport labels its messages with, see `label`.
+    const LABELLED: [Self; 9] = [
+        Self::Dns,
+        Self::Connect,
+        Self::ConnectTimeout,
+        Self::Timeout,
+        Self::Tls,
+        Self::InvalidRequest,
+        Self::TooManyRedirects,
+        Self::Io,
+        Self::Backend,
+    ];
+
+    /// What the native transport puts before the message of a ureq error, followed by `: `. The
+    /// label is chosen from the kind of the error, so the rest of the message, e.g. the url, does
+    /// not change the classification.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Dns => "DNS lookup failed",
+            Self::Connect => "Connection failed",
+            Self::ConnectTimeout => "Connection timed out",
+            Self::Timeout => "Response timed out",
+            Self::Tls => "TLS handshake failed",
+            Self::InvalidRequest => "Invalid request",
+            Self::TooManyRedirects => "Too many redirects",
+            Self::Io => "Network error",
+            _ => "Request failed",
+        }
+    }
+
+    pub(crate) fn classify(message: &str) -> Self {
+        let labelled = Self::LABELLED.into_iter().find(|kind| {
+            message
+                .strip_prefix(kind.label())
+                .is_some_and(|rest| rest.starts_with(": "))
+        });
+        if let Some(kind) = labelled {
+            return kind;
+        }
+        if message == REQUEST_ABORTED {
+            return Self::Aborted;
+        }
+        if message == REQUEST_DEADLINE_EXCEEDED {
+            return Self::DeadlineExceeded;
+        }
+        if message == REQUEST_QUEUE_FULL {
+            return Self::QueueFull;
+        }
+        if message == REQUEST_DUPLICATE {
+            return Self::Duplicate;
+        }
+        if message == REQUEST_TIMED_OUT {
+            return Self::Timeout;
+        }
+        // Redirects are blocked in the transport, with the reason appended.
+        if message.starts_with(REQUEST_BLOCKED) {
+            return Self::Blocked;
+        }
+        let lower = message.to_lowercase();
+        let timed_out = lower.contains("timed out") || lower.contains("timeout");
+        if lower.contains("dns failed") {
+            Self::Dns
+        } else if lower.contains("tls") || lower.contains("certificate") {
+            Self::Tls
+        } else if lower.contains("connection failed") {
+            if timed_out {
+                Self::ConnectTimeout
+            } else {
+                Self::Connect
+            }
+        } else if timed_out {
+            Self::Timeout
+        } else if [
+            "bad url",
+            "unknown scheme",
+            "malformed proxy",
+            "insecure request",
+            "not supported by browsers",
+        ]
+        .iter()
+        .any(|pattern| lower.contains(pattern))
+        {
+            Self::InvalidRequest
+        } else if lower.contains("too many redirects") {
+            Self::TooManyRedirects
+        } else if lower.contains("network error") || lower.contains("failed to read response body")
+        {
+            Self::Io
+        } else {
+            Self::Backend
+        }
+    }
+
+    /// Whether sending the request again may succeed.
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            Self::Dns | Self::Connect | Self::ConnectTimeout | Self::Timeout | Self::Io
+        )
+    }
+}
+
+/// Why `HttpClient::try_build` could not build a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpBuildError {
+    MissingMethod,
+    MissingUrl,
+    /// The method contains characters not allowed in methods.
+    InvalidMethod(String),
+    /// The backend cannot send the method, e.g. `TRACE` on wasm builds.
+    UnsupportedMethod(String),
+    /// The url could not be parsed, or has no host.
+    InvalidUrl {
+        url: String,
+        reason: String,
+    },
+    /// The url is not an http or https url.
+    UnsupportedScheme {
+        url: String,
+        scheme: String,
+    },
+}
+
+impl std::fmt::Display for HttpBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingMethod => f.write_str("request has no method"),
+            Self::MissingUrl => f.write_str("request has no url"),
+            Self::InvalidMethod(method) => write!(f, "invalid method {method:?}"),
+            Self::UnsupportedMethod(method) => write!(f, "{method} requests are not supported"),
+            Self::InvalidUrl { url, reason } => write!(f, "invalid url {url:?}: {reason}"),
+            Self::UnsupportedScheme { url, scheme } => {
+                write!(f, "unsupported scheme {scheme:?} in url {url:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HttpBuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labelled_messages_ignore_the_url() {
+        let message = format!(
+            "{}: http://tls-timeout.example.com/: Connection Failed: Connect error",
+            HttpErrorKind::Connect.label()
+        );
+        assert_eq!(HttpErrorKind::classify(&message), HttpErrorKind::Connect);
+        for kind in HttpErrorKind::LABELLED {
+            let message = format!("{}: https://example.com/dns-failed", kind.label());
+            assert_eq!(HttpErrorKind::classify(&message), kind);
+        }
+    }
+
+    #[test]
+    fn other_messages_are_classified_by_their_wording() {
+        assert_eq!(
+            HttpErrorKind::classify(REQUEST_ABORTED),
+            HttpErrorKind::Aborted
+        );
+        assert_eq!(
+            HttpErrorKind::classify("Too many redirects, gave up after 5"),
+            HttpErrorKind::TooManyRedirects
+        );
+        assert_eq!(
+            HttpErrorKind::classify("Failed to read response body: connection reset"),
+            HttpErrorKind::Io
+        );
+    }
+}
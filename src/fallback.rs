@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use ehttp::Response;
+
+use crate::{
+    idempotency, DespawnOnResponse, HttpRequest, RequestQueue, ResponseHandler, REQUEST_ABORTED,
+    REQUEST_DEADLINE_EXCEEDED, REQUEST_QUEUE_FULL,
+};
+
+/// Mirrors tried in order when a request fails with a connection error, a timeout or a 5xx status.
+///
+/// Set it with `HttpClient::fallback_urls`, or insert it on the entity passed to `HttpClient::entity`
+/// to apply it to every request of that entity. Only the last failure is reported, and the `url` of
+/// the response tells which mirror answered. POST and PATCH requests get an `Idempotency-Key`
+/// header shared by every attempt, so the backend can tell retries apart from new requests.
+///
+/// # Examples
+///
+/// ```
+/// commands.spawn(FallbackUrls(vec![
+///     "https://cdn2.example.com/patch.bin".to_string(),
+///     "https://origin.example.com/patch.bin".to_string(),
+/// ]));
+/// ```
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FallbackUrls(pub Vec<String>);
+
+/// Picks up the fallbacks of the request entity, unless the request brings its own, and keys the
+/// request for the retries.
+pub(crate) fn resolve(request: &mut HttpRequest, fallbacks: &Query<&FallbackUrls>) {
+    if request.fallback_urls.is_none() {
+        request.fallback_urls = request
+            .from_entity
+            .and_then(|entity| fallbacks.get(entity).ok())
+            .cloned();
+    }
+    if request
+        .fallback_urls
+        .as_ref()
+        .is_some_and(|urls| !urls.0.is_empty())
+    {
+        idempotency::ensure_key(&mut request.request);
+    }
+}
+
+/// Wraps `on_response` so a failed attempt queues the request again with the next mirror.
+pub(crate) fn with_fallbacks(
+    request: &HttpRequest,
+    on_response: ResponseHandler,
+) -> ResponseHandler {
+    let Some(FallbackUrls(urls)) = request
+        .fallback_urls
+        .as_ref()
+        .filter(|urls| !urls.0.is_empty())
+    else {
+        return on_response;
+    };
+    let mut next = request.clone();
+    next.request.url = urls[0].clone();
+    next.fallback_urls = Some(FallbackUrls(urls[1..].to_vec()));
+    next.on_complete = None;
+    // The retries of the request start once every mirror failed.
+    next.retry = None;
+
+    Box::new(move |world, request_id, response| {
+        // The entity of a `DespawnOnResponse` request is gone after this attempt.
+        let entity_despawned = next
+            .from_entity
+            .is_some_and(|entity| world.get::<DespawnOnResponse>(entity).is_some());
+        if !entity_despawned && should_retry(&response) {
+            world
+                .resource_mut::<RequestQueue>()
+                .0
+                .push_front((next, on_response));
+        } else {
+            on_response(world, request_id, response);
+        }
+    })
+}
+
+fn should_retry(response: &ehttp::Result<Response>) -> bool {
+    match response {
+        Ok(res) => res.status >= 500,
+        Err(e) => ![
+            REQUEST_ABORTED,
+            REQUEST_DEADLINE_EXCEEDED,
+            REQUEST_QUEUE_FULL,
+        ]
+        .contains(&e.as_str()),
+    }
+}
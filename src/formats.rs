@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use ehttp::Headers;
+use serde::de::DeserializeOwned;
+
+/// A body format typed responses can be deserialized from, e.g. MessagePack or protobuf, see
+/// `register_response_format`.
+///
+/// # Examples
+///
+/// ```
+/// struct MessagePack;
+///
+/// impl BodyFormat for MessagePack {
+///     fn media_type(&self) -> &str {
+///         "application/msgpack"
+///     }
+///
+///     fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String> {
+///         rmp_serde::from_slice(bytes).map_err(|e| e.to_string())
+///     }
+/// }
+/// ```
+pub trait BodyFormat: Send + Sync + 'static {
+    /// The `Content-Type` of bodies in the format, without parameters.
+    fn media_type(&self) -> &str;
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String>;
+}
+
+/// JSON, deserialized with serde_json. Typed responses are read as JSON unless their
+/// `Content-Type` is one of the other formats registered for the type.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+impl BodyFormat for JsonFormat {
+    fn media_type(&self) -> &str {
+        "application/json"
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String> {
+        serde_json::from_slice(bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Deserializes a body in one format.
+pub(crate) type DecodeFn<T> = Arc<dyn Fn(&[u8]) -> Result<T, String> + Send + Sync>;
+
+/// The formats registered for the responses of `T`, in the order of preference.
+#[derive(Resource)]
+pub(crate) struct ResponseFormats<T> {
+    formats: Vec<(String, DecodeFn<T>)>,
+}
+
+impl<T> Default for ResponseFormats<T> {
+    fn default() -> Self {
+        Self {
+            formats: Vec::new(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned + 'static> ResponseFormats<T> {
+    pub(crate) fn push(&mut self, format: impl BodyFormat) {
+        let media_type = format.media_type().to_ascii_lowercase();
+        self.formats
+            .retain(|(registered, _)| *registered != media_type);
+        self.formats
+            .push((media_type, Arc::new(move |bytes| format.deserialize(bytes))));
+    }
+
+    /// Asks for the registered formats in their order, with JSON last unless it was registered,
+    /// replacing an `Accept` header that accepts anything.
+    pub(crate) fn set_accept(&self, headers: &mut Headers) {
+        if self.formats.is_empty()
+            || headers
+                .get("Accept")
+                .is_some_and(|accept| accept.trim() != "*/*")
+        {
+            return;
+        }
+        let json = JsonFormat.media_type();
+        let mut media_types: Vec<&str> = self.formats.iter().map(|(m, _)| m.as_str()).collect();
+        if !media_types.contains(&json) {
+            media_types.push(json);
+        }
+        let accept = media_types
+            .iter()
+            .enumerate()
+            .map(|(index, media_type)| match index {
+                0 => media_type.to_string(),
+                // Quality values have at most three decimals.
+                _ => format!("{media_type};q={}", (1000 / (index + 1)) as f64 / 1000.0),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        headers
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("Accept"));
+        headers.insert("Accept", accept);
+    }
+
+    /// The deserializer of the registered format matching `content_type`.
+    pub(crate) fn decoder(&self, content_type: Option<&str>) -> Option<DecodeFn<T>> {
+        let media_type = content_type?.split(';').next()?.trim();
+        self.formats
+            .iter()
+            .find(|(registered, _)| registered.eq_ignore_ascii_case(media_type))
+            .map(|(_, decode)| decode.clone())
+    }
+}
+
+/// Deserializes `bytes` with `decoder`, or as JSON without one.
+pub(crate) fn decode<T: DeserializeOwned>(
+    decoder: Option<&DecodeFn<T>>,
+    bytes: &[u8],
+) -> Result<T, String> {
+    match decoder {
+        Some(decode) => decode(bytes),
+        None => JsonFormat.deserialize(bytes),
+    }
+}
@@ -0,0 +1,346 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde::de::DeserializeOwned;
+
+use crate::batch::cancel_requests;
+use crate::{deliver, HttpRequest, HttpResponse, HttpResponseError, RequestId, RequestQueue};
+
+/// Builds the request of a node from the responses of its dependencies.
+type NodeBuild = Arc<dyn Fn(&GraphResponses) -> Result<HttpRequest, String> + Send + Sync>;
+
+/// A small graph of requests where some requests are built from the responses of others, e.g. a
+/// session bootstrap where the profile and the settings need the login and the inventory needs the
+/// profile and the settings, delivered as a single [`GraphResponse`].
+///
+/// Every request is sent as soon as the requests it depends on have their responses, so
+/// independent requests run in parallel within the usual concurrency limit. The graph stops at the
+/// first request that fails, gets a non-2xx response or cannot be built, its requests still
+/// waiting or in flight are cancelled then. A node can only depend on nodes added before it. The
+/// requests are always sent on entities of their own, their `entity`, `on_complete` and
+/// `respond_to` are ignored.
+///
+/// # Examples
+///
+/// ```
+/// ev_graph.send(
+///     RequestGraph::new()
+///         .node("login", HttpClient::new().post("https://api.example.com/login").json(&credentials).build())
+///         .node_after("profile", ["login"], |responses| {
+///             let login: Login = responses.json("login")?;
+///             Ok(HttpClient::new().get("https://api.example.com/profile").bearer_auth(login.token).build())
+///         })
+///         .node_after("settings", ["login"], |responses| {
+///             let login: Login = responses.json("login")?;
+///             Ok(HttpClient::new().get("https://api.example.com/settings").bearer_auth(login.token).build())
+///         })
+///         .node_after("inventory", ["profile", "settings"], |responses| {
+///             let profile: Profile = responses.json("profile")?;
+///             let settings: Settings = responses.json("settings")?;
+///             Ok(HttpClient::new().get(inventory_url(&profile, &settings)).build())
+///         }),
+/// );
+/// ```
+#[derive(Event, Clone, Default)]
+pub struct RequestGraph {
+    /// Identifies the graph in its [`GraphResponse`].
+    pub id: RequestId,
+    nodes: Vec<GraphNode>,
+    /// The graph response is inserted on this entity instead of being sent as an event.
+    pub respond_to: Option<Entity>,
+}
+
+#[derive(Clone)]
+struct GraphNode {
+    name: String,
+    dependencies: Vec<String>,
+    build: NodeBuild,
+}
+
+impl RequestGraph {
+    /// a graph without requests
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the request `name` that depends on no other request and is sent right away.
+    pub fn node(self, name: impl ToString, request: HttpRequest) -> Self {
+        self.node_after(name, [] as [&str; 0], move |_| Ok(request.clone()))
+    }
+
+    /// Adds the request `name` that `build` builds once every request of `dependencies` has its
+    /// response. An error stops the graph with `GraphErrorKind::Parse`.
+    pub fn node_after(
+        mut self,
+        name: impl ToString,
+        dependencies: impl IntoIterator<Item = impl ToString>,
+        build: impl Fn(&GraphResponses) -> Result<HttpRequest, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.nodes.push(GraphNode {
+            name: name.to_string(),
+            dependencies: dependencies.into_iter().map(|d| d.to_string()).collect(),
+            build: Arc::new(build),
+        });
+        self
+    }
+
+    /// insert the graph response on `target` instead of sending it as an event
+    pub fn respond_to(mut self, target: Entity) -> Self {
+        self.respond_to = Some(target);
+        self
+    }
+}
+
+impl std::fmt::Debug for RequestGraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nodes: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|node| (&node.name, &node.dependencies))
+            .collect();
+        f.debug_struct("RequestGraph")
+            .field("id", &self.id)
+            .field("nodes", &nodes)
+            .field("respond_to", &self.respond_to)
+            .finish()
+    }
+}
+
+/// The responses of the requests of a [`RequestGraph`] by node name.
+#[derive(Debug, Clone, Default)]
+pub struct GraphResponses(HashMap<String, HttpResponse>);
+
+impl GraphResponses {
+    pub fn get(&self, name: &str) -> Option<&HttpResponse> {
+        self.0.get(name)
+    }
+
+    /// Deserializes the JSON body of the response of `name`.
+    pub fn json<T: DeserializeOwned>(&self, name: &str) -> Result<T, String> {
+        let response = self
+            .get(name)
+            .ok_or_else(|| format!("no response of {name:?}"))?;
+        response.json().map_err(|e| format!("{name}: {e}"))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &HttpResponse)> {
+        self.0
+            .iter()
+            .map(|(name, response)| (name.as_str(), response))
+    }
+}
+
+/// The outcome of a [`RequestGraph`], the responses of all its requests or the request that broke
+/// it.
+#[derive(Event, Component, Debug, Clone)]
+pub struct GraphResponse {
+    pub graph_id: RequestId,
+    pub result: Result<GraphResponses, GraphError>,
+}
+
+/// The node a [`RequestGraph`] stopped at.
+#[derive(Debug, Clone)]
+pub struct GraphError {
+    pub node: String,
+    pub kind: GraphErrorKind,
+}
+
+/// Why a node of a [`RequestGraph`] broke it.
+#[derive(Debug, Clone)]
+pub enum GraphErrorKind {
+    /// The request of the node failed.
+    Failed(HttpResponseError),
+    /// The request of the node got a non-2xx response.
+    Status(HttpResponse),
+    /// The request of the node could not be built from the responses of its dependencies.
+    Parse(String),
+    /// The node depends on a node that was not added before it, or on itself.
+    UnknownDependency(String),
+}
+
+impl std::fmt::Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let node = &self.node;
+        match &self.kind {
+            GraphErrorKind::Failed(e) => write!(f, "request {node:?} of the graph failed: {e}"),
+            GraphErrorKind::Status(res) => write!(
+                f,
+                "request {node:?} of the graph got {} {}",
+                res.status, res.status_text
+            ),
+            GraphErrorKind::Parse(e) => write!(f, "request {node:?} could not be built: {e}"),
+            GraphErrorKind::UnknownDependency(dependency) => {
+                write!(
+                    f,
+                    "request {node:?} depends on unknown request {dependency:?}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// A graph with requests still waiting or in flight.
+struct GraphRun {
+    id: RequestId,
+    respond_to: Option<Entity>,
+    nodes: Vec<GraphNode>,
+    /// The nodes whose request was sent.
+    started: HashSet<usize>,
+    /// The ids of the requests sent and not finished yet.
+    in_flight: HashSet<RequestId>,
+    responses: GraphResponses,
+    /// Whether the graph response was delivered.
+    settled: bool,
+}
+
+/// What a node that finished leads to.
+enum Step {
+    Send(Vec<(usize, HttpRequest)>),
+    Fail(GraphError, HashSet<RequestId>),
+    Done(GraphResponses),
+}
+
+impl GraphRun {
+    /// The nodes whose dependencies all have their responses, with their requests.
+    fn ready(&mut self) -> Step {
+        let mut requests = vec![];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if self.started.contains(&index)
+                || !node
+                    .dependencies
+                    .iter()
+                    .all(|dependency| self.responses.0.contains_key(dependency))
+            {
+                continue;
+            }
+            match (node.build)(&self.responses) {
+                Ok(request) => requests.push((index, request)),
+                Err(e) => {
+                    return self.fail(GraphError {
+                        node: node.name.clone(),
+                        kind: GraphErrorKind::Parse(e),
+                    })
+                }
+            }
+        }
+        if requests.is_empty() && self.in_flight.is_empty() {
+            self.settled = true;
+            return Step::Done(std::mem::take(&mut self.responses));
+        }
+        for (index, request) in &requests {
+            self.started.insert(*index);
+            self.in_flight.insert(request.id);
+        }
+        Step::Send(requests)
+    }
+
+    fn fail(&mut self, error: GraphError) -> Step {
+        self.settled = true;
+        Step::Fail(error, std::mem::take(&mut self.in_flight))
+    }
+}
+
+fn advance(world: &mut World, run: &Arc<Mutex<GraphRun>>, step: Step) {
+    let Ok(state) = run.lock() else {
+        return;
+    };
+    let (id, respond_to) = (state.id, state.respond_to);
+    drop(state);
+    match step {
+        Step::Send(requests) => {
+            let mut queue = world.resource_mut::<RequestQueue>();
+            for (index, request) in requests {
+                send(&mut queue, run.clone(), index, request);
+            }
+        }
+        Step::Fail(error, in_flight) => {
+            cancel_requests(world, &in_flight);
+            let response = GraphResponse {
+                graph_id: id,
+                result: Err(error),
+            };
+            deliver(world, respond_to, response);
+        }
+        Step::Done(responses) => {
+            let response = GraphResponse {
+                graph_id: id,
+                result: Ok(responses),
+            };
+            deliver(world, respond_to, response);
+        }
+    }
+}
+
+fn send(queue: &mut RequestQueue, run: Arc<Mutex<GraphRun>>, index: usize, request: HttpRequest) {
+    let request = HttpRequest {
+        from_entity: None,
+        respond_to: None,
+        on_complete: None,
+        ..request
+    };
+    queue.push(request, move |world, request_id, result| {
+        let step = {
+            let Ok(mut state) = run.lock() else {
+                return;
+            };
+            if state.settled {
+                return;
+            }
+            state.in_flight.remove(&request_id);
+            let node = state.nodes[index].name.clone();
+            match result {
+                Ok(res) if res.ok => {
+                    let response = HttpResponse::new(request_id, res);
+                    state.responses.0.insert(node, response);
+                    state.ready()
+                }
+                Ok(res) => state.fail(GraphError {
+                    node,
+                    kind: GraphErrorKind::Status(HttpResponse::new(request_id, res)),
+                }),
+                Err(e) => state.fail(GraphError {
+                    node,
+                    kind: GraphErrorKind::Failed(HttpResponseError::new(request_id, e)),
+                }),
+            }
+        };
+        advance(world, &run, step);
+    });
+}
+
+/// A dependency of a node that was not added before it.
+fn unknown_dependency(nodes: &[GraphNode]) -> Option<GraphError> {
+    nodes.iter().enumerate().find_map(|(index, node)| {
+        node.dependencies
+            .iter()
+            .find(|dependency| !nodes[..index].iter().any(|node| node.name == **dependency))
+            .map(|dependency| GraphError {
+                node: node.name.clone(),
+                kind: GraphErrorKind::UnknownDependency(dependency.clone()),
+            })
+    })
+}
+
+/// Sends the requests of every graph that depend on nothing.
+pub(crate) fn handle_graphs(mut commands: Commands, mut graphs: EventReader<RequestGraph>) {
+    for graph in graphs.read() {
+        let mut run = GraphRun {
+            id: graph.id,
+            respond_to: graph.respond_to,
+            nodes: graph.nodes.clone(),
+            started: HashSet::new(),
+            in_flight: HashSet::new(),
+            responses: GraphResponses::default(),
+            settled: false,
+        };
+        let step = match unknown_dependency(&run.nodes) {
+            Some(error) => run.fail(error),
+            None => run.ready(),
+        };
+        let run = Arc::new(Mutex::new(run));
+        commands.add(move |world: &mut World| advance(world, &run, step));
+    }
+}
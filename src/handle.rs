@@ -0,0 +1,160 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_channel::{Receiver, Sender};
+use bevy::utils::Instant;
+
+use crate::{HttpErrorKind, HttpResponseError, RequestId};
+
+/// Where a request is at, see [`RequestHandle::status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestStatus {
+    /// Waiting for a free client.
+    Queued,
+    /// Sent, waiting for the response.
+    InFlight,
+    /// A response arrived, whatever its status code.
+    Done,
+    /// The request failed or its body could not be deserialized.
+    Failed,
+    /// Aborted with `AbortRequest`, or dropped with its entity.
+    Aborted,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    dispatched_at: Option<Instant>,
+    finished_at: Option<Instant>,
+    status: Option<RequestStatus>,
+    status_code: Option<u16>,
+}
+
+/// Tracks a request and resolves to its result, for code that holds on to a request instead of reading events.
+///
+/// Created with `HttpClient::build_with_handle` or `TypedRequest::with_handle`.
+/// The result is sent to the handle instead of being delivered as an event.
+///
+/// # Examples
+///
+/// ```
+/// let (request, handle) = HttpClient::new().get("http://example.com").build_with_handle();
+/// ev_request.send(request);
+///
+/// AsyncComputeTaskPool::get()
+///     .spawn(async move {
+///         let response = handle.await_response().await?;
+///         println!("status: {}", response.status);
+///     })
+///     .detach();
+/// ```
+#[derive(Debug)]
+pub struct RequestHandle<T = crate::HttpResponse> {
+    request_id: RequestId,
+    result: Receiver<Result<T, HttpResponseError>>,
+    state: Arc<Mutex<State>>,
+    created_at: Instant,
+}
+
+/// The request side of a [`RequestHandle`].
+pub(crate) struct HandleSender<T> {
+    result: Sender<Result<T, HttpResponseError>>,
+    state: Arc<Mutex<State>>,
+}
+
+impl<T> HandleSender<T> {
+    /// Marks the request as in flight. Doesn't hold on to the result channel, so a dropped request
+    /// still closes it.
+    pub(crate) fn on_dispatch(&self) -> impl Fn() + Send + Sync + 'static {
+        let state = self.state.clone();
+        move || {
+            if let Ok(mut state) = state.lock() {
+                state.dispatched_at = Some(Instant::now());
+            }
+        }
+    }
+
+    pub(crate) fn finish(&self, status_code: Option<u16>, result: Result<T, HttpResponseError>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.finished_at = Some(Instant::now());
+            state.status_code = status_code;
+            state.status = Some(match &result {
+                Ok(_) => RequestStatus::Done,
+                Err(e) if e.kind == HttpErrorKind::Aborted => RequestStatus::Aborted,
+                Err(_) => RequestStatus::Failed,
+            });
+        }
+        let _ = self.result.try_send(result);
+    }
+}
+
+impl<T> Drop for HandleSender<T> {
+    /// The request was dropped without a result, e.g. with its entity.
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.status.is_none() {
+                state.finished_at = Some(Instant::now());
+                state.status = Some(RequestStatus::Aborted);
+            }
+        }
+    }
+}
+
+impl<T> RequestHandle<T> {
+    pub(crate) fn new(request_id: RequestId) -> (HandleSender<T>, Self) {
+        let (tx, rx) = async_channel::bounded(1);
+        let state = Arc::new(Mutex::new(State::default()));
+        let sender = HandleSender {
+            result: tx,
+            state: state.clone(),
+        };
+        let handle = Self {
+            request_id,
+            result: rx,
+            state,
+            created_at: Instant::now(),
+        };
+        (sender, handle)
+    }
+
+    /// The id of the request.
+    pub fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    /// Where the request is at.
+    pub fn status(&self) -> RequestStatus {
+        let state = self.state.lock().unwrap();
+        if let Some(status) = state.status {
+            status
+        } else if state.dispatched_at.is_some() {
+            RequestStatus::InFlight
+        } else {
+            RequestStatus::Queued
+        }
+    }
+
+    /// The status code of the response, once it arrived.
+    pub fn status_code(&self) -> Option<u16> {
+        self.state.lock().unwrap().status_code
+    }
+
+    /// Time since the request was created, until it finished.
+    pub fn elapsed(&self) -> Duration {
+        let finished_at = self.state.lock().unwrap().finished_at;
+        finished_at
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.created_at)
+    }
+
+    /// Waits for the request to finish.
+    ///
+    /// Fails if the request was dropped, e.g. because its entity was despawned.
+    pub async fn await_response(self) -> Result<T, HttpResponseError> {
+        self.result.recv().await.unwrap_or_else(|_| {
+            Err(HttpResponseError::new(
+                self.request_id,
+                "Request dropped before it finished",
+            ))
+        })
+    }
+}
@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// A header with a typed value, read with `HttpResponse::typed_header` and set with
+/// `HttpClient::typed_header`.
+///
+/// # Examples
+///
+/// ```
+/// let request = HttpClient::new()
+///     .get("http://example.com/scores")
+///     .typed_header(&Authorization::Bearer(token))
+///     .build();
+///
+/// for response in ev_response.read() {
+///     if let Some(content_type) = response.typed_header::<ContentType>() {
+///         println!("{} in {:?}", content_type.mime, content_type.charset);
+///     }
+/// }
+/// ```
+pub trait TypedHeader: Sized {
+    /// The header name.
+    const NAME: &'static str;
+
+    /// Parses the header value, `None` if it is malformed.
+    fn parse(value: &str) -> Option<Self>;
+
+    /// The header value.
+    fn encode(&self) -> String;
+}
+
+/// A `Content-Type` header, e.g. `application/json; charset=utf-8`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType {
+    /// The lowercased media type without parameters, e.g. `application/json`.
+    pub mime: String,
+    pub charset: Option<String>,
+}
+
+impl ContentType {
+    pub fn new(mime: impl ToString) -> Self {
+        Self {
+            mime: mime.to_string().to_ascii_lowercase(),
+            charset: None,
+        }
+    }
+
+    /// Whether the body is JSON, including `+json` types such as `application/problem+json`.
+    pub fn is_json(&self) -> bool {
+        self.mime == "application/json" || self.mime.ends_with("+json")
+    }
+
+    /// Whether the body is text, including JSON and XML.
+    pub fn is_text(&self) -> bool {
+        self.mime.starts_with("text/")
+            || self.is_json()
+            || self.mime == "application/xml"
+            || self.mime.ends_with("+xml")
+    }
+}
+
+impl TypedHeader for ContentType {
+    const NAME: &'static str = "Content-Type";
+
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(';');
+        let mime = parts.next()?.trim().to_ascii_lowercase();
+        if !mime.contains('/') {
+            return None;
+        }
+        let charset = parts.find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            name.trim()
+                .eq_ignore_ascii_case("charset")
+                .then(|| value.trim().trim_matches('"').to_ascii_lowercase())
+        });
+        Some(Self { mime, charset })
+    }
+
+    fn encode(&self) -> String {
+        match &self.charset {
+            Some(charset) => format!("{}; charset={charset}", self.mime),
+            None => self.mime.clone(),
+        }
+    }
+}
+
+/// An `ETag` header, e.g. `"v42"` or the weak `W/"v42"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+    /// The tag without quotes.
+    pub tag: String,
+    /// Weak tags only promise that the bodies are equivalent, not byte for byte equal.
+    pub weak: bool,
+}
+
+impl ETag {
+    pub fn strong(tag: impl ToString) -> Self {
+        Self {
+            tag: tag.to_string(),
+            weak: false,
+        }
+    }
+
+    pub fn weak(tag: impl ToString) -> Self {
+        Self {
+            tag: tag.to_string(),
+            weak: true,
+        }
+    }
+
+    /// Strong comparison, both tags are strong and equal, as used with `If-Match`.
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison, the tags are equal ignoring weakness, as used with `If-None-Match`.
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl TypedHeader for ETag {
+    const NAME: &'static str = "ETag";
+
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, value),
+        };
+        let tag = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        (!tag.contains('"')).then(|| Self {
+            tag: tag.to_string(),
+            weak,
+        })
+    }
+
+    fn encode(&self) -> String {
+        if self.weak {
+            format!("W/\"{}\"", self.tag)
+        } else {
+            format!("\"{}\"", self.tag)
+        }
+    }
+}
+
+/// A `Cache-Control` header with its directives parsed.
+///
+/// Unknown directives are kept in `extensions`, with their value if they have one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    pub max_age: Option<Duration>,
+    /// `s-maxage`, the max age for shared caches.
+    pub shared_max_age: Option<Duration>,
+    pub stale_while_revalidate: Option<Duration>,
+    pub stale_if_error: Option<Duration>,
+    pub no_cache: bool,
+    pub no_store: bool,
+    pub must_revalidate: bool,
+    pub public: bool,
+    pub private: bool,
+    pub immutable: bool,
+    pub extensions: Vec<(String, Option<String>)>,
+}
+
+impl CacheControl {
+    /// Whether a cache may reuse the response without asking the server, and for how long.
+    pub fn freshness(&self) -> Option<Duration> {
+        if self.no_store || self.no_cache {
+            return None;
+        }
+        self.max_age
+    }
+}
+
+impl TypedHeader for CacheControl {
+    const NAME: &'static str = "Cache-Control";
+
+    fn parse(value: &str) -> Option<Self> {
+        let mut cache_control = Self::default();
+        let seconds = |value: Option<&str>| {
+            value
+                .and_then(|value| value.trim_matches('"').parse().ok())
+                .map(Duration::from_secs)
+        };
+        for directive in value.split(',') {
+            let directive = directive.trim();
+            if directive.is_empty() {
+                continue;
+            }
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim())),
+                None => (directive, None),
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "max-age" => cache_control.max_age = seconds(value),
+                "s-maxage" => cache_control.shared_max_age = seconds(value),
+                "stale-while-revalidate" => cache_control.stale_while_revalidate = seconds(value),
+                "stale-if-error" => cache_control.stale_if_error = seconds(value),
+                "no-cache" => cache_control.no_cache = true,
+                "no-store" => cache_control.no_store = true,
+                "must-revalidate" => cache_control.must_revalidate = true,
+                "public" => cache_control.public = true,
+                "private" => cache_control.private = true,
+                "immutable" => cache_control.immutable = true,
+                _ => cache_control
+                    .extensions
+                    .push((name.to_string(), value.map(ToString::to_string))),
+            }
+        }
+        Some(cache_control)
+    }
+
+    fn encode(&self) -> String {
+        let mut directives = vec![];
+        let flags = [
+            (self.no_cache, "no-cache"),
+            (self.no_store, "no-store"),
+            (self.must_revalidate, "must-revalidate"),
+            (self.public, "public"),
+            (self.private, "private"),
+            (self.immutable, "immutable"),
+        ];
+        directives.extend(
+            flags
+                .iter()
+                .filter(|(set, _)| *set)
+                .map(|(_, name)| name.to_string()),
+        );
+        let durations = [
+            (self.max_age, "max-age"),
+            (self.shared_max_age, "s-maxage"),
+            (self.stale_while_revalidate, "stale-while-revalidate"),
+            (self.stale_if_error, "stale-if-error"),
+        ];
+        for (duration, name) in durations {
+            if let Some(duration) = duration {
+                directives.push(format!("{name}={}", duration.as_secs()));
+            }
+        }
+        for (name, value) in &self.extensions {
+            directives.push(match value {
+                Some(value) => format!("{name}={value}"),
+                None => name.clone(),
+            });
+        }
+        directives.join(", ")
+    }
+}
+
+/// An `Authorization` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authorization {
+    /// `Bearer <token>`
+    Bearer(String),
+    /// `Basic <base64 of user:password>`
+    Basic { user: String, password: String },
+    /// Any other scheme, the whole header value.
+    Other(String),
+}
+
+impl TypedHeader for Authorization {
+    const NAME: &'static str = "Authorization";
+
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (scheme, credentials) = value.split_once(' ').unwrap_or((value, ""));
+        let credentials = credentials.trim();
+        if scheme.eq_ignore_ascii_case("bearer") && !credentials.is_empty() {
+            return Some(Self::Bearer(credentials.to_string()));
+        }
+        if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = String::from_utf8(STANDARD.decode(credentials).ok()?).ok()?;
+            let (user, password) = decoded.split_once(':')?;
+            return Some(Self::Basic {
+                user: user.to_string(),
+                password: password.to_string(),
+            });
+        }
+        Some(Self::Other(value.to_string()))
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Self::Bearer(token) => format!("Bearer {token}"),
+            Self::Basic { user, password } => {
+                format!("Basic {}", STANDARD.encode(format!("{user}:{password}")))
+            }
+            Self::Other(value) => value.clone(),
+        }
+    }
+}
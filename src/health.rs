@@ -0,0 +1,280 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::{HttpClient, HttpClock, HttpRequest, HttpSchedule, HttpSet, OnComplete};
+
+/// Pings the configured endpoints on an interval and tracks their health in [`EndpointHealth`].
+///
+/// Sends [`EndpointDown`] once an endpoint failed `failure_threshold` checks in a row and
+/// [`EndpointRecovered`] on its next successful check. A check fails on a connection error, a
+/// timeout or a non-2xx status. Add it after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpClientPlugin::default()).add_plugins(
+///     HealthCheckPlugin::new(Duration::from_secs(30))
+///         .with_endpoint("matchmaking", "https://mm.example.com/health")
+///         .with_endpoint("leaderboard", "https://lb.example.com/health"),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct HealthCheckPlugin {
+    /// The endpoints by name, with the url that is pinged.
+    pub endpoints: Vec<(Cow<'static, str>, String)>,
+    /// Time between two checks of an endpoint.
+    pub interval: Duration,
+    /// How long a check may take before it fails.
+    pub timeout: Duration,
+    /// Failed checks in a row before an endpoint is reported down.
+    pub failure_threshold: u32,
+    /// Number of recent checks the success rate and latency are computed from.
+    pub window: usize,
+}
+
+impl HealthCheckPlugin {
+    /// create the plugin checking every `interval`, without any endpoint yet
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            endpoints: vec![],
+            interval,
+            timeout: Duration::from_secs(5),
+            failure_threshold: 3,
+            window: 20,
+        }
+    }
+
+    /// ping `url` under the name `name`
+    pub fn with_endpoint(mut self, name: impl Into<Cow<'static, str>>, url: impl ToString) -> Self {
+        self.endpoints.push((name.into(), url.to_string()));
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+}
+
+impl Plugin for HealthCheckPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        let mut health = EndpointHealth::default();
+        for (name, _) in &self.endpoints {
+            health
+                .endpoints
+                .insert(name.to_string(), EndpointStatus::default());
+        }
+        app.insert_resource(health);
+        app.insert_resource(HealthChecks {
+            config: self.clone(),
+            // The first check runs right away.
+            timer: Timer::new(Duration::ZERO, TimerMode::Once),
+        });
+        app.add_event::<EndpointDown>();
+        app.add_event::<EndpointRecovered>();
+        app.add_systems(schedule, send_health_checks.before(HttpSet::Queue));
+    }
+}
+
+/// The health of every endpoint of the [`HealthCheckPlugin`], by name.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct EndpointHealth {
+    pub endpoints: HashMap<String, EndpointStatus>,
+}
+
+impl EndpointHealth {
+    pub fn get(&self, name: &str) -> Option<&EndpointStatus> {
+        self.endpoints.get(name)
+    }
+
+    /// Whether the endpoint is up, `false` for unknown endpoints.
+    pub fn is_up(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|status| status.up)
+    }
+}
+
+/// The recent checks of an endpoint.
+#[derive(Debug, Clone)]
+pub struct EndpointStatus {
+    /// Assumed `true` until enough checks failed.
+    pub up: bool,
+    pub consecutive_failures: u32,
+    /// The error of the latest failed check.
+    pub last_error: Option<String>,
+    /// When the latest check finished, on the [`HttpClock`].
+    pub last_checked: Option<Instant>,
+    /// The latency of recent successful checks, `None` for failed ones, oldest first.
+    samples: VecDeque<Option<Duration>>,
+    /// Whether a check is in flight, so slow endpoints are not pinged twice.
+    checking: bool,
+}
+
+impl Default for EndpointStatus {
+    fn default() -> Self {
+        Self {
+            up: true,
+            consecutive_failures: 0,
+            last_error: None,
+            last_checked: None,
+            samples: VecDeque::new(),
+            checking: false,
+        }
+    }
+}
+
+impl EndpointStatus {
+    /// Share of recent checks that succeeded, `None` before the first check.
+    pub fn success_rate(&self) -> Option<f32> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let succeeded = self
+            .samples
+            .iter()
+            .filter(|sample| sample.is_some())
+            .count();
+        Some(succeeded as f32 / self.samples.len() as f32)
+    }
+
+    /// Average latency of recent successful checks.
+    pub fn average_latency(&self) -> Option<Duration> {
+        let latencies: Vec<Duration> = self.samples.iter().flatten().copied().collect();
+        let count = u32::try_from(latencies.len()).ok().filter(|n| *n > 0)?;
+        Some(latencies.iter().sum::<Duration>() / count)
+    }
+}
+
+/// Sent when an endpoint failed enough checks in a row.
+#[derive(Event, Debug, Clone)]
+pub struct EndpointDown {
+    pub name: String,
+    pub error: String,
+}
+
+/// Sent when an endpoint that was down passed a check.
+#[derive(Event, Debug, Clone)]
+pub struct EndpointRecovered {
+    pub name: String,
+}
+
+#[derive(Resource)]
+struct HealthChecks {
+    config: HealthCheckPlugin,
+    timer: Timer,
+}
+
+fn send_health_checks(
+    time: Res<Time>,
+    mut checks: ResMut<HealthChecks>,
+    mut health: ResMut<EndpointHealth>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    if !checks.timer.tick(time.delta()).finished() {
+        return;
+    }
+    let interval = checks.config.interval;
+    checks.timer = Timer::new(interval, TimerMode::Once);
+
+    for (name, url) in &checks.config.endpoints {
+        let Some(status) = health.endpoints.get_mut(name.as_ref()) else {
+            continue;
+        };
+        if status.checking {
+            continue;
+        }
+        status.checking = true;
+
+        let name = name.to_string();
+        let failure_threshold = checks.config.failure_threshold;
+        let window = checks.config.window;
+        let dispatched_at = Arc::new(Mutex::new(Instant::now()));
+        let on_dispatch = {
+            let dispatched_at = dispatched_at.clone();
+            move || {
+                if let Ok(mut dispatched_at) = dispatched_at.lock() {
+                    *dispatched_at = Instant::now();
+                }
+            }
+        };
+        let on_complete = OnComplete::new(move |world, _, response| {
+            let latency = dispatched_at
+                .lock()
+                .map_or(Duration::ZERO, |at| at.elapsed());
+            let result = match response {
+                Ok(res) if res.ok => Ok(latency),
+                Ok(res) => Err(format!("{} {}", res.status, res.status_text)),
+                Err(e) => Err(e),
+            };
+            record_check(world, name, result, failure_threshold, window);
+        })
+        .with_on_dispatch(on_dispatch);
+
+        requests.send(HttpRequest {
+            on_complete: Some(on_complete),
+            ..HttpClient::new()
+                .get(url)
+                .timeout(checks.config.timeout)
+                .label("health-check")
+                .build()
+        });
+    }
+}
+
+fn record_check(
+    world: &mut World,
+    name: String,
+    result: Result<Duration, String>,
+    failure_threshold: u32,
+    window: usize,
+) {
+    let now = world.resource::<HttpClock>().now();
+    let mut health = world.resource_mut::<EndpointHealth>();
+    let Some(status) = health.endpoints.get_mut(&name) else {
+        return;
+    };
+    status.checking = false;
+    status.last_checked = Some(now);
+    if status.samples.len() >= window {
+        status.samples.pop_front();
+    }
+    status.samples.push_back(result.as_ref().ok().copied());
+
+    match result {
+        Ok(_) => {
+            status.consecutive_failures = 0;
+            if !status.up {
+                status.up = true;
+                world.send_event(EndpointRecovered { name });
+            }
+        }
+        Err(error) => {
+            status.consecutive_failures += 1;
+            status.last_error = Some(error.clone());
+            if status.up && status.consecutive_failures >= failure_threshold {
+                status.up = false;
+                world.send_event(EndpointDown { name, error });
+            }
+        }
+    }
+}
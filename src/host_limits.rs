@@ -0,0 +1,58 @@
+use bevy::utils::HashMap;
+
+use crate::stats::metric_host;
+use crate::Environment;
+
+/// Caps how many requests run at once per host, on top of `HttpClientSettings::max_concurrent`,
+/// so one chatty backend cannot take every client and starve the calls to other services.
+///
+/// Hosts are named like in the metrics, `api.example.com`, with the port if it is not the default
+/// one, `localhost:8080`. Waiting requests to a host at its limit are passed over until one of its
+/// requests finishes. Browsers have their own limit of about 6 connections per host.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///     max_concurrent: 12,
+///     host_limits: Some(HostLimits::new(6).with_host("telemetry.example.com", 1)),
+///     ..default()
+/// }));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostLimits {
+    /// The limit of hosts without their own, unlimited if `None`.
+    pub default: Option<usize>,
+    /// Limits of single hosts.
+    pub hosts: HashMap<String, usize>,
+}
+
+impl HostLimits {
+    /// run at most `max_per_host` requests at once to each host
+    pub fn new(max_per_host: usize) -> Self {
+        Self {
+            default: Some(max_per_host),
+            hosts: HashMap::new(),
+        }
+    }
+
+    /// run at most `max` requests at once to `host`, see `hosts`
+    pub fn with_host(mut self, host: impl ToString, max: usize) -> Self {
+        self.hosts.insert(host.to_string(), max);
+        self
+    }
+
+    /// How many requests may run at once to `host`.
+    pub fn limit(&self, host: &str) -> Option<usize> {
+        self.hosts.get(host).copied().or(self.default)
+    }
+}
+
+/// The host `url` is sent to once `environment` is applied.
+pub(crate) fn queued_host(url: &str, environment: Option<&Environment>) -> String {
+    let host = metric_host(url);
+    match environment.and_then(|environment| environment.base_url.as_deref()) {
+        Some(base_url) if host.is_empty() => metric_host(base_url),
+        _ => host,
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::SystemTime;
+use ehttp::Headers;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+/// How many `Date` headers the clock skew is estimated from.
+const SKEW_SAMPLES: usize = 15;
+
+/// Parses an HTTP date, e.g. of a `Date`, `Expires`, `Last-Modified` or `Retry-After` header.
+///
+/// Accepts the IMF-fixdate format `Sun, 06 Nov 1994 08:49:37 GMT` servers should send, and the
+/// obsolete RFC 850 `Sunday, 06-Nov-94 08:49:37 GMT` and asctime `Sun Nov  6 08:49:37 1994`
+/// formats clients must still read. Weekday names are not checked, month names are matched
+/// case-insensitively, and `UTC` and `+0000` are accepted for `GMT`. Two-digit years are read as
+/// 1970 to 2069.
+///
+/// # Examples
+///
+/// ```
+/// let expires = res.headers.get("Expires").and_then(parse_http_date);
+/// ```
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let mut tokens: Vec<&str> = value
+        .split(|c: char| c.is_ascii_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .collect();
+    // The weekday, if any.
+    if tokens.first().is_some_and(|token| {
+        token.chars().all(|c| c.is_ascii_alphabetic()) && parse_month(token).is_none()
+    }) {
+        tokens.remove(0);
+    }
+    let (day, month, year, time, zone) = match tokens.as_slice() {
+        // RFC 850
+        [date, time, zone @ ..] if date.contains('-') => {
+            let mut parts = date.split('-');
+            let (day, month, year) = (parts.next()?, parts.next()?, parts.next()?);
+            (day, month, year, *time, zone)
+        }
+        // asctime
+        [month, day, time, year] if month.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+            (*day, *month, *year, *time, &[][..])
+        }
+        // IMF-fixdate
+        [day, month, year, time, zone @ ..] => (*day, *month, *year, *time, zone),
+        _ => return None,
+    };
+    if !matches!(zone, [] | ["GMT" | "UTC" | "UT" | "Z" | "+0000" | "-0000"]) {
+        return None;
+    }
+
+    let day: u32 = day.parse().ok()?;
+    let month = parse_month(month)?;
+    let year: i64 = match (year.len(), year.parse().ok()?) {
+        (2, year @ 0..=69) => 2000 + year,
+        (2, year) => 1900 + year,
+        (_, year) => year,
+    };
+    let mut clock = time.split(':');
+    let hour: u64 = clock.next()?.parse().ok()?;
+    let minute: u64 = clock.next()?.parse().ok()?;
+    let second: u64 = clock.next()?.parse().ok()?;
+    if clock.next().is_some()
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // A leap second is read as the second before it.
+    let seconds = days_from_civil(year, month, day) * 86_400
+        + (hour * 3600 + minute * 60 + second.min(59)) as i64;
+    if seconds >= 0 {
+        SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64))
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+    }
+}
+
+/// Formats `time` as an IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`, for `If-Modified-Since`
+/// or `Date` headers. Fractions of seconds are dropped.
+pub fn format_http_date(time: SystemTime) -> String {
+    let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    };
+    let days = seconds.div_euclid(86_400);
+    let clock = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{}, {day:02} {} {year:04} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[days.rem_euclid(7) as usize],
+        title_case(MONTHS[month as usize - 1]),
+        clock / 3600,
+        clock / 60 % 60,
+        clock % 60,
+    )
+}
+
+fn parse_month(name: &str) -> Option<u32> {
+    let name = name.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| *month == name)
+        .map(|index| index as u32 + 1)
+}
+
+fn title_case(name: &str) -> String {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a date of the proleptic Gregorian calendar.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date of the proleptic Gregorian calendar `days` after 1970-01-01.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// How far the clock of the servers is ahead of the device clock, estimated from the `Date`
+/// headers of responses, so expiry times stamped by the server aren't thrown off by a wrong device
+/// clock.
+///
+/// Every response with a `Date` header adds a sample, corrected for its round trip and for the
+/// header only having whole seconds. The skew is the median of the recent samples, so single slow
+/// or cached responses don't move it. Until a response arrived it is assumed to be zero. Browsers
+/// only expose the `Date` header of cross-origin responses the server lists in
+/// `Access-Control-Expose-Headers`.
+///
+/// # Examples
+///
+/// ```
+/// fn token_expiry(skew: Res<ClockSkew>, token: Res<Token>) -> Duration {
+///     // `expires_at` was stamped by the server clock.
+///     skew.until(token.expires_at)
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ClockSkew {
+    /// Server time minus device time, in milliseconds.
+    samples: VecDeque<i64>,
+    offset: Option<i64>,
+}
+
+impl ClockSkew {
+    /// Server time minus device time in milliseconds, positive if the server clock is ahead.
+    /// `None` until a response with a `Date` header arrived.
+    pub fn offset_millis(&self) -> Option<i64> {
+        self.offset
+    }
+
+    /// The current time on the server clock.
+    pub fn server_now(&self) -> SystemTime {
+        self.to_server(SystemTime::now())
+    }
+
+    /// Converts a time of the server clock to the device clock.
+    pub fn to_local(&self, server_time: SystemTime) -> SystemTime {
+        shift(server_time, -self.offset.unwrap_or(0))
+    }
+
+    /// Converts a time of the device clock to the server clock.
+    pub fn to_server(&self, local_time: SystemTime) -> SystemTime {
+        shift(local_time, self.offset.unwrap_or(0))
+    }
+
+    /// The time left until `server_time` of the server clock, zero if it passed.
+    pub fn until(&self, server_time: SystemTime) -> Duration {
+        server_time
+            .duration_since(self.server_now())
+            .unwrap_or_default()
+    }
+
+    /// The wait a `Retry-After` header asks for, either in seconds or until an HTTP date of the
+    /// server clock.
+    pub fn retry_after(&self, headers: &Headers) -> Option<Duration> {
+        let value = headers.get("Retry-After")?.trim();
+        match value.parse::<u64>() {
+            Ok(seconds) => Some(Duration::from_secs(seconds)),
+            Err(_) => parse_http_date(value).map(|date| self.until(date)),
+        }
+    }
+
+    /// Adds the `Date` header of a response that arrived `round_trip` after its request was sent.
+    pub(crate) fn observe(&mut self, headers: &Headers, round_trip: Duration) {
+        let Some(date) = headers.get("Date").and_then(parse_http_date) else {
+            return;
+        };
+        // The server stamped the response about halfway through the round trip, somewhere within
+        // the second of its `Date`.
+        let stamped = shift(SystemTime::now(), -(round_trip.as_millis() as i64 / 2));
+        let sample = millis_between(date, stamped) + 500;
+        if self.samples.len() == SKEW_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        let mut sorted: Vec<i64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        self.offset = Some(sorted[sorted.len() / 2]);
+    }
+}
+
+/// `time` moved by `millis`, backwards if negative.
+fn shift(time: SystemTime, millis: i64) -> SystemTime {
+    let by = Duration::from_millis(millis.unsigned_abs());
+    let shifted = if millis >= 0 {
+        time.checked_add(by)
+    } else {
+        time.checked_sub(by)
+    };
+    shifted.unwrap_or(time)
+}
+
+/// `a - b` in milliseconds.
+fn millis_between(a: SystemTime, b: SystemTime) -> i64 {
+    match a.duration_since(b) {
+        Ok(after) => after.as_millis() as i64,
+        Err(e) => -(e.duration().as_millis() as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1994-11-06 08:49:37 UTC, the example of RFC 7231.
+    fn example() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(784_111_777)
+    }
+
+    #[test]
+    fn parses_the_three_formats() {
+        for value in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+        ] {
+            assert_eq!(parse_http_date(value), Some(example()), "{value}");
+        }
+    }
+
+    #[test]
+    fn parses_lenient_variants() {
+        for value in [
+            "06 Nov 1994 08:49:37 GMT",
+            "Sun, 06 NOV 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 06 Nov 1994 08:49:37 +0000",
+            "Sun, 06 Nov 1994 08:49:37",
+            "  Sun,  06 Nov 1994   08:49:37 GMT ",
+            "Mon, 06 Nov 1994 08:49:37 GMT",
+        ] {
+            assert_eq!(parse_http_date(value), Some(example()), "{value}");
+        }
+    }
+
+    #[test]
+    fn two_digit_years() {
+        let year_2069 = parse_http_date("Fri, 01-Jan-69 00:00:00 GMT");
+        assert_eq!(year_2069, parse_http_date("Tue, 01 Jan 2069 00:00:00 GMT"));
+        assert_eq!(
+            parse_http_date("Thursday, 01-Jan-70 00:00:00 GMT"),
+            Some(SystemTime::UNIX_EPOCH)
+        );
+    }
+
+    #[test]
+    fn leap_days_and_seconds() {
+        assert_eq!(
+            parse_http_date("Thu, 29 Feb 2024 00:00:00 GMT"),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_164_800))
+        );
+        assert_eq!(
+            parse_http_date("Sat, 31 Dec 2016 23:59:60 GMT"),
+            parse_http_date("Sat, 31 Dec 2016 23:59:59 GMT")
+        );
+        assert_eq!(
+            parse_http_date("Wed, 31 Dec 1969 23:59:59 GMT"),
+            SystemTime::UNIX_EPOCH.checked_sub(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for value in [
+            "",
+            "now",
+            "1994-11-06T08:49:37Z",
+            "Sun, 06 Nov 1994 08:49:37 PST",
+            "Sun, 06 Nov 1994 08:49:37 +0100",
+            "Sun, 06 Foo 1994 08:49:37 GMT",
+            "Sun, 00 Nov 1994 08:49:37 GMT",
+            "Sun, 31 Nov 1994 08:49:37 GMT",
+            "Tue, 29 Feb 2023 00:00:00 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+            "Sun, 06 Nov 1994 08:60:00 GMT",
+            "Sun, 06 Nov 1994 08:49:61 GMT",
+            "Sun, 06 Nov 1994 08:49 GMT",
+            "Sun, 06 Nov 1994 08:49:37:00 GMT",
+            "Sun, 06 Nov 1994",
+            "Sunday, 06-Nov 08:49:37 GMT",
+        ] {
+            assert_eq!(parse_http_date(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn formats_imf_fixdate() {
+        assert_eq!(format_http_date(example()), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(
+            format_http_date(SystemTime::UNIX_EPOCH),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1_709_164_800_999);
+        assert_eq!(format_http_date(time), "Thu, 29 Feb 2024 00:00:00 GMT");
+        assert_eq!(
+            parse_http_date(&format_http_date(example())),
+            Some(example())
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use bevy::utils::Uuid;
+use ehttp::Request;
+
+/// Header letting the backend deduplicate the attempts of a request that is sent more than once.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Gives a POST or PATCH request a random `Idempotency-Key`, unless it already has one.
+///
+/// Called before the first attempt of a request that may be retried, every attempt then sends the
+/// same key. Other methods are idempotent by definition and are left alone.
+pub(crate) fn ensure_key(request: &mut Request) {
+    let retries_unsafe = matches!(
+        request.method.to_ascii_uppercase().as_str(),
+        "POST" | "PATCH"
+    );
+    if retries_unsafe && request.headers.get(IDEMPOTENCY_KEY_HEADER).is_none() {
+        request
+            .headers
+            .insert(IDEMPOTENCY_KEY_HEADER, Uuid::new_v4());
+    }
+}
@@ -0,0 +1,226 @@
+use async_channel::{Receiver, TryRecvError};
+use bevy::app::{App, Plugin, Update};
+use bevy::asset::{Assets, Handle};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::texture::Image;
+use bevy::tasks::AsyncComputeTaskPool;
+use ehttp::Response;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::DownloadCache;
+use crate::{HttpClient, HttpRequest, HttpSchedule, HttpSet, OnComplete};
+
+/// Downloads images straight into `Assets<Image>` through [`HttpImages`], e.g. avatars and
+/// thumbnails. Only available with the `image` feature.
+///
+/// The format is taken from the `Content-Type` header, then from the first bytes of the body and
+/// last from the extension of the url. Images are decoded on the `AsyncComputeTaskPool`, so large
+/// ones do not stall a frame. Decoding a format needs its bevy feature, e.g. `png`, `jpeg` or
+/// `webp`. Sends [`ImageDownloaded`] or [`ImageDownloadFailed`] for every image. With a
+/// `DownloadCache` the images are kept on disk and only downloaded again when they changed. Add it
+/// after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpImagePlugin);
+///
+/// fn show_avatar(mut commands: Commands, mut images: HttpImages, player: Res<Player>) {
+///     commands.spawn(SpriteBundle {
+///         texture: images.load(&player.avatar_url),
+///         ..default()
+///     });
+/// }
+///
+/// fn avatar_failed(mut ev_failed: EventReader<ImageDownloadFailed>) {
+///     for failed in ev_failed.read() {
+///         warn!("no avatar from {}: {}", failed.url, failed.error);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpImagePlugin;
+
+impl Plugin for HttpImagePlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        app.init_resource::<DecodingImages>();
+        app.add_event::<ImageDownloaded>();
+        app.add_event::<ImageDownloadFailed>();
+        app.add_systems(schedule, finish_decoding.after(HttpSet::HandleResponses));
+    }
+}
+
+/// Sent once a downloaded image was decoded and added to `Assets<Image>`.
+#[derive(Event, Debug, Clone)]
+pub struct ImageDownloaded {
+    pub handle: Handle<Image>,
+    pub url: String,
+}
+
+/// Sent when an image could not be downloaded or decoded, its handle stays empty.
+#[derive(Event, Debug, Clone)]
+pub struct ImageDownloadFailed {
+    pub handle: Handle<Image>,
+    pub url: String,
+    /// The status of a non-2xx response, `None` if the request failed or the image did not decode.
+    pub status: Option<u16>,
+    pub error: String,
+}
+
+/// Downloads images, see [`HttpImagePlugin`].
+#[derive(SystemParam)]
+pub struct HttpImages<'w> {
+    images: Res<'w, Assets<Image>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    cache: Option<Res<'w, DownloadCache>>,
+    requests: EventWriter<'w, HttpRequest>,
+}
+
+impl HttpImages<'_> {
+    /// Downloads the image at `url` with a GET request.
+    ///
+    /// The handle is returned right away, the image is added to it once decoded.
+    pub fn load(&mut self, url: impl ToString) -> Handle<Image> {
+        self.load_request(HttpClient::new().get(url).build())
+    }
+
+    /// Sends `request`, e.g. one with auth headers, and decodes its response body as an image.
+    ///
+    /// The `on_complete` and `respond_to` of the request are replaced, the result is only
+    /// reported through the image events.
+    pub fn load_request(
+        &mut self,
+        #[cfg_attr(target_arch = "wasm32", allow(unused_mut))] mut request: HttpRequest,
+    ) -> Handle<Image> {
+        let handle = self.images.reserve_handle();
+        let url = request.request.url.clone();
+        let image = handle.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        let cached = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.revalidate(&mut request.request));
+        self.requests.send(HttpRequest {
+            on_complete: Some(OnComplete::new(move |world, _, result| {
+                #[cfg(not(target_arch = "wasm32"))]
+                let result = match &cached {
+                    Some(cached) => cached.finish(result),
+                    None => result,
+                };
+                on_downloaded(world, image, url, result);
+            })),
+            respond_to: None,
+            ..request
+        });
+        handle
+    }
+}
+
+/// Images being decoded.
+#[derive(Resource, Default)]
+struct DecodingImages(Vec<DecodingImage>);
+
+struct DecodingImage {
+    handle: Handle<Image>,
+    url: String,
+    decoded: Receiver<Result<Image, String>>,
+}
+
+fn on_downloaded(
+    world: &mut World,
+    handle: Handle<Image>,
+    url: String,
+    result: ehttp::Result<Response>,
+) {
+    let (status, error) = match result {
+        Ok(res) if res.ok => {
+            // Detached since wasm builds cannot poll tasks, like the requests themselves.
+            let (tx, decoded) = async_channel::bounded(1);
+            AsyncComputeTaskPool::get()
+                .spawn(async move {
+                    let _ = tx.send(decode(&res)).await;
+                })
+                .detach();
+            world
+                .resource_mut::<DecodingImages>()
+                .0
+                .push(DecodingImage {
+                    handle,
+                    url,
+                    decoded,
+                });
+            return;
+        }
+        Ok(res) => (
+            Some(res.status),
+            format!("{} {}", res.status, res.status_text),
+        ),
+        Err(err) => (None, err),
+    };
+    world.send_event(ImageDownloadFailed {
+        handle,
+        url,
+        status,
+        error,
+    });
+}
+
+fn decode(res: &Response) -> Result<Image, String> {
+    let format = res
+        .content_type()
+        .and_then(|mime| image::ImageFormat::from_mime_type(mime.split(';').next()?.trim()))
+        .or_else(|| image::guess_format(&res.bytes).ok())
+        .or_else(|| {
+            let url = url::Url::parse(&res.url).ok()?;
+            let name = url.path_segments()?.next_back()?.to_owned();
+            image::ImageFormat::from_extension(name.rsplit_once('.')?.1)
+        })
+        .ok_or_else(|| "Unknown image format".to_string())?;
+    let decoded = image::load_from_memory_with_format(&res.bytes, format)
+        .map_err(|err| format!("Failed to decode {format:?} image: {err}"))?;
+    Ok(Image::from_dynamic(
+        decoded,
+        true,
+        RenderAssetUsages::default(),
+    ))
+}
+
+fn finish_decoding(
+    mut decoding: ResMut<DecodingImages>,
+    mut images: ResMut<Assets<Image>>,
+    mut downloaded: EventWriter<ImageDownloaded>,
+    mut failed: EventWriter<ImageDownloadFailed>,
+) {
+    decoding.0.retain_mut(|image| {
+        let result = match image.decoded.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return true,
+            Err(TryRecvError::Closed) => Err("Image decoding stopped unexpectedly".to_string()),
+        };
+        match result {
+            Ok(decoded) => {
+                images.insert(image.handle.id(), decoded);
+                downloaded.send(ImageDownloaded {
+                    handle: image.handle.clone(),
+                    url: image.url.clone(),
+                });
+            }
+            Err(error) => {
+                failed.send(ImageDownloadFailed {
+                    handle: image.handle.clone(),
+                    url: image.url.clone(),
+                    status: None,
+                    error,
+                });
+            }
+        }
+        false
+    });
+}
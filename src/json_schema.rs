@@ -0,0 +1,714 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use ehttp::Response;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::{HttpClient, HttpRequest, OnComplete};
+
+/// How deep `$ref`s may nest before validation gives up, against schemas referring to themselves.
+/// Only `$ref`s count, nesting within the schema is bounded by the schema itself.
+const MAX_DEPTH: usize = 64;
+
+/// A JSON Schema response bodies are checked against before they are deserialized, so contract
+/// changes of the backend show up as precise errors instead of missing fields.
+///
+/// Supports the keywords of draft 2020-12 and draft-07 that constrain values: `type`, `enum`,
+/// `const`, the number, string, array and object keywords, `allOf`, `anyOf`, `oneOf`, `not`,
+/// `if`/`then`/`else` and `$ref`s into the same schema, e.g. `#/$defs/player`. Annotations such as
+/// `format`, `title` or `description` are ignored, as are `$ref`s to other documents.
+///
+/// # Examples
+///
+/// ```
+/// let schema = JsonSchema::from_slice(include_bytes!("../schemas/player.json")).unwrap();
+/// if let Err(violations) = schema.validate(&serde_json::json!({ "id": "42" })) {
+///     for violation in violations {
+///         println!("{violation}");
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonSchema {
+    schema: Value,
+    /// The `pattern`s and `patternProperties` of the schema, compiled once, `None` if invalid.
+    patterns: HashMap<String, Option<Regex>>,
+}
+
+impl PartialEq for JsonSchema {
+    fn eq(&self, other: &Self) -> bool {
+        self.schema == other.schema
+    }
+}
+
+impl JsonSchema {
+    /// A schema from its JSON, an object or a boolean.
+    pub fn new(schema: Value) -> Result<Self, String> {
+        if !matches!(schema, Value::Object(_) | Value::Bool(_)) {
+            return Err("a JSON schema is an object or a boolean".to_string());
+        }
+        let mut patterns = HashMap::default();
+        collect_patterns(&schema, &mut patterns);
+        Ok(Self { schema, patterns })
+    }
+
+    /// Parses a schema, e.g. one bundled with `include_bytes!`.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, String> {
+        let schema = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+        Self::new(schema)
+    }
+
+    /// Checks `instance` against the schema, returning every place it does not match.
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<SchemaViolation>> {
+        let mut validator = Validator {
+            root: &self.schema,
+            patterns: &self.patterns,
+            violations: vec![],
+        };
+        validator.check(&self.schema, instance, &mut String::new(), 0);
+        if validator.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(validator.violations)
+        }
+    }
+}
+
+/// A place a JSON document does not match its [`JsonSchema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// The JSON pointer of the value, e.g. `/players/0/name`, empty for the whole document.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{path}: {}", self.message)
+    }
+}
+
+/// The schemas typed requests validate their responses against, by name, see
+/// `TypedRequest::with_schema`.
+///
+/// Schemas are bundled with `insert`, or fetched from the backend with the request of `fetch`.
+/// Responses of requests naming a schema that is not registered when they arrive fail, so fetch
+/// schemas before the requests that need them. Only available with the `json-schema` feature.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(JsonSchemas::default().with(
+///     "player",
+///     JsonSchema::from_slice(include_bytes!("../schemas/player.json")).unwrap(),
+/// ));
+///
+/// fn fetch_schemas(mut ev_request: EventWriter<HttpRequest>) {
+///     ev_request.send(JsonSchemas::fetch("inventory", "https://api.example.com/schemas/inventory.json"));
+/// }
+///
+/// let request = HttpClient::new()
+///     .get("https://api.example.com/players/42")
+///     .with_type::<Player>()
+///     .with_schema("player");
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct JsonSchemas(HashMap<String, JsonSchema>);
+
+impl JsonSchemas {
+    /// register `schema` as `name`, see `insert`
+    pub fn with(mut self, name: impl ToString, schema: JsonSchema) -> Self {
+        self.insert(name, schema);
+        self
+    }
+
+    /// Registers `schema` as `name`, replacing the schema registered under that name before.
+    pub fn insert(&mut self, name: impl ToString, schema: JsonSchema) {
+        self.0.insert(name.to_string(), schema);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&JsonSchema> {
+        self.0.get(name)
+    }
+
+    /// A request downloading the schema at `url` and registering it as `name` once it arrived.
+    /// Failures are logged as warnings.
+    pub fn fetch(name: impl ToString, url: impl ToString) -> HttpRequest {
+        let name = name.to_string();
+        let mut request = HttpClient::new().get(url.to_string()).build();
+        request.on_complete = Some(OnComplete::new(move |world, _, response| {
+            let schema = response
+                .and_then(|res| match res.ok {
+                    true => Ok(res),
+                    false => Err(format!("{} {}", res.status, res.status_text)),
+                })
+                .and_then(|res| JsonSchema::from_slice(&res.bytes));
+            match schema {
+                Ok(schema) => world
+                    .get_resource_or_insert_with(JsonSchemas::default)
+                    .insert(&name, schema),
+                Err(e) => warn!("Failed to fetch the JSON schema {name:?}: {e}"),
+            }
+        }));
+        request
+    }
+}
+
+/// Fails a response whose body does not match the schema `name`.
+pub(crate) fn validate_response(
+    world: &World,
+    name: Option<&str>,
+    response: ehttp::Result<Response>,
+) -> ehttp::Result<Response> {
+    let (Some(name), Ok(res)) = (name, &response) else {
+        return response;
+    };
+    let Some(schema) = world
+        .get_resource::<JsonSchemas>()
+        .and_then(|schemas| schemas.get(name))
+    else {
+        return Err(format!("Unknown JSON schema {name:?}"));
+    };
+    let body: Value = serde_json::from_slice(&res.bytes).map_err(|e| e.to_string())?;
+    match schema.validate(&body) {
+        Ok(()) => response,
+        Err(violations) => {
+            let violations: Vec<String> = violations.iter().map(ToString::to_string).collect();
+            Err(format!(
+                "Response does not match the JSON schema {name:?}: {}",
+                violations.join("; ")
+            ))
+        }
+    }
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    patterns: &'a HashMap<String, Option<Regex>>,
+    violations: Vec<SchemaViolation>,
+}
+
+impl<'a> Validator<'a> {
+    fn violation(&mut self, path: &str, message: impl ToString) {
+        self.violations.push(SchemaViolation {
+            path: path.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    /// Whether `instance` matches `schema`, without reporting where it does not.
+    fn matches(&self, schema: &Value, instance: &Value, path: &str, depth: usize) -> bool {
+        let mut validator = Validator {
+            root: self.root,
+            patterns: self.patterns,
+            violations: vec![],
+        };
+        validator.check(schema, instance, &mut path.to_string(), depth);
+        validator.violations.is_empty()
+    }
+
+    fn check(&mut self, schema: &Value, instance: &Value, path: &mut String, depth: usize) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return self.violation(path, "no value is allowed here"),
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+        if depth > MAX_DEPTH {
+            return self.violation(path, "the schema nests too deep");
+        }
+
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            match self.resolve(reference) {
+                Some(target) => self.check(target, instance, path, depth + 1),
+                None => self.violation(path, format!("unsupported $ref {reference:?}")),
+            }
+        }
+        self.check_type(schema, instance, path);
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if !values.iter().any(|value| json_eq(value, instance)) {
+                self.violation(
+                    path,
+                    format!("{instance} is not one of {}", Value::Array(values.clone())),
+                );
+            }
+        }
+        if let Some(value) = schema.get("const") {
+            if !json_eq(value, instance) {
+                self.violation(path, format!("expected {value}, got {instance}"));
+            }
+        }
+        match instance {
+            Value::Number(number) => {
+                if let Some(number) = number.as_f64() {
+                    self.check_number(schema, number, path);
+                }
+            }
+            Value::String(string) => self.check_string(schema, string, path),
+            Value::Array(items) => self.check_array(schema, items, path, depth),
+            Value::Object(fields) => self.check_object(schema, fields, path, depth),
+            _ => {}
+        }
+        self.check_combinators(schema, instance, path, depth);
+    }
+
+    fn regex(&self, pattern: &str) -> Option<&'a Regex> {
+        self.patterns.get(pattern)?.as_ref()
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        let pointer = reference.strip_prefix('#')?;
+        let pointer = percent_encoding::percent_decode_str(pointer)
+            .decode_utf8()
+            .ok()?;
+        self.root.pointer(&pointer)
+    }
+
+    fn check_type(&mut self, schema: &Map<String, Value>, instance: &Value, path: &str) {
+        let types: Vec<&str> = match schema.get("type") {
+            Some(Value::String(name)) => vec![name],
+            Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+            _ => return,
+        };
+        if !types.iter().any(|name| is_type(instance, name)) {
+            self.violation(
+                path,
+                format!(
+                    "expected {}, got {}",
+                    types.join(" or "),
+                    type_name(instance)
+                ),
+            );
+        }
+    }
+
+    fn check_number(&mut self, schema: &Map<String, Value>, number: f64, path: &str) {
+        let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+        if let Some(minimum) = bound("minimum").filter(|minimum| number < *minimum) {
+            self.violation(path, format!("{number} is less than the minimum {minimum}"));
+        }
+        if let Some(maximum) = bound("maximum").filter(|maximum| number > *maximum) {
+            self.violation(path, format!("{number} is more than the maximum {maximum}"));
+        }
+        if let Some(minimum) = bound("exclusiveMinimum").filter(|minimum| number <= *minimum) {
+            self.violation(path, format!("{number} is not more than {minimum}"));
+        }
+        if let Some(maximum) = bound("exclusiveMaximum").filter(|maximum| number >= *maximum) {
+            self.violation(path, format!("{number} is not less than {maximum}"));
+        }
+        if let Some(divisor) = bound("multipleOf").filter(|divisor| *divisor > 0.0) {
+            let quotient = number / divisor;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+                self.violation(path, format!("{number} is not a multiple of {divisor}"));
+            }
+        }
+    }
+
+    fn check_string(&mut self, schema: &Map<String, Value>, string: &str, path: &str) {
+        let length = string.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                self.violation(
+                    path,
+                    format!("is {length} characters long, at least {min} expected"),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                self.violation(
+                    path,
+                    format!("is {length} characters long, at most {max} expected"),
+                );
+            }
+        }
+        if let Some(Value::String(pattern)) = schema.get("pattern") {
+            match self.regex(pattern) {
+                Some(regex) if regex.is_match(string) => {}
+                Some(_) => self.violation(path, format!("{string:?} does not match {pattern:?}")),
+                None => self.violation(path, format!("invalid pattern {pattern:?}")),
+            }
+        }
+    }
+
+    fn check_array(
+        &mut self,
+        schema: &Map<String, Value>,
+        items: &[Value],
+        path: &mut String,
+        depth: usize,
+    ) {
+        let count = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if count < min {
+                self.violation(path, format!("has {count} items, at least {min} expected"));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if count > max {
+                self.violation(path, format!("has {count} items, at most {max} expected"));
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicate = items.iter().enumerate().find_map(|(index, item)| {
+                items[..index]
+                    .iter()
+                    .any(|earlier| json_eq(earlier, item))
+                    .then_some(index)
+            });
+            if let Some(index) = duplicate {
+                self.violation(path, format!("item {index} is a duplicate"));
+            }
+        }
+
+        // `prefixItems` and an array of `items` of draft-07 check items by position, `items` or
+        // `additionalItems` the rest.
+        let (prefix, rest) = match (schema.get("prefixItems"), schema.get("items")) {
+            (Some(Value::Array(prefix)), rest) => (prefix.as_slice(), rest),
+            (None, Some(Value::Array(prefix))) => {
+                (prefix.as_slice(), schema.get("additionalItems"))
+            }
+            (_, rest) => (&[][..], rest),
+        };
+        for (index, item) in items.iter().enumerate() {
+            let item_schema = match prefix.get(index) {
+                Some(item_schema) => item_schema,
+                None => match rest {
+                    Some(rest) => rest,
+                    None => break,
+                },
+            };
+            let len = push_segment(path, &index.to_string());
+            self.check(item_schema, item, path, depth);
+            path.truncate(len);
+        }
+        if let Some(contains) = schema.get("contains") {
+            if !items
+                .iter()
+                .any(|item| self.matches(contains, item, path, depth))
+            {
+                self.violation(path, "no item matches `contains`");
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &Map<String, Value>,
+        fields: &Map<String, Value>,
+        path: &mut String,
+        depth: usize,
+    ) {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    self.violation(path, format!("missing required property {name:?}"));
+                }
+            }
+        }
+        let count = fields.len() as u64;
+        if let Some(min) = schema.get("minProperties").and_then(Value::as_u64) {
+            if count < min {
+                self.violation(
+                    path,
+                    format!("has {count} properties, at least {min} expected"),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxProperties").and_then(Value::as_u64) {
+            if count > max {
+                self.violation(
+                    path,
+                    format!("has {count} properties, at most {max} expected"),
+                );
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns: Vec<(&Regex, &Value)> = schema
+            .get("patternProperties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(pattern, schema)| Some((self.regex(pattern)?, schema)))
+            .collect();
+        for (name, value) in fields {
+            if let Some(names) = schema.get("propertyNames") {
+                if !self.matches(names, &Value::String(name.clone()), path, depth) {
+                    self.violation(path, format!("property name {name:?} is not allowed"));
+                }
+            }
+            let len = push_segment(path, name);
+            let mut known = false;
+            if let Some(property) = properties.and_then(|properties| properties.get(name)) {
+                known = true;
+                self.check(property, value, path, depth);
+            }
+            for (pattern, property) in &patterns {
+                if pattern.is_match(name) {
+                    known = true;
+                    self.check(property, value, path, depth);
+                }
+            }
+            if !known {
+                match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        path.truncate(len);
+                        self.violation(path, format!("unexpected property {name:?}"));
+                    }
+                    Some(additional) => self.check(additional, value, path, depth),
+                    None => {}
+                }
+            }
+            path.truncate(len);
+        }
+    }
+
+    fn check_combinators(
+        &mut self,
+        schema: &Map<String, Value>,
+        instance: &Value,
+        path: &mut String,
+        depth: usize,
+    ) {
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            for schema in schemas {
+                self.check(schema, instance, path, depth);
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+            if !schemas
+                .iter()
+                .any(|schema| self.matches(schema, instance, path, depth))
+            {
+                self.violation(path, "matches none of the `anyOf` schemas");
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+            let matching = schemas
+                .iter()
+                .filter(|schema| self.matches(schema, instance, path, depth))
+                .count();
+            if matching != 1 {
+                self.violation(
+                    path,
+                    format!("matches {matching} of the `oneOf` schemas instead of 1"),
+                );
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.matches(not, instance, path, depth) {
+                self.violation(path, "matches the `not` schema");
+            }
+        }
+        if let Some(condition) = schema.get("if") {
+            let branch = if self.matches(condition, instance, path, depth) {
+                schema.get("then")
+            } else {
+                schema.get("else")
+            };
+            if let Some(branch) = branch {
+                self.check(branch, instance, path, depth);
+            }
+        }
+    }
+}
+
+/// Compiles the `pattern`s and the `patternProperties` names anywhere in `schema`.
+fn collect_patterns(schema: &Value, patterns: &mut HashMap<String, Option<Regex>>) {
+    let mut add = |pattern: &str| {
+        if !patterns.contains_key(pattern) {
+            patterns.insert(pattern.to_string(), Regex::new(pattern).ok());
+        }
+    };
+    match schema {
+        Value::Object(schema) => {
+            if let Some(Value::String(pattern)) = schema.get("pattern") {
+                add(pattern);
+            }
+            if let Some(Value::Object(properties)) = schema.get("patternProperties") {
+                properties.keys().for_each(|pattern| add(pattern));
+            }
+            for value in schema.values() {
+                collect_patterns(value, patterns);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_patterns(value, patterns);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Appends `segment` to the JSON pointer `path`, returning the length to truncate it back to.
+fn push_segment(path: &mut String, segment: &str) -> usize {
+    let len = path.len();
+    path.push('/');
+    path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    len
+}
+
+fn is_type(instance: &Value, name: &str) -> bool {
+    match name {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance
+                    .as_f64()
+                    .is_some_and(|number| number.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Equality of JSON values where `1` and `1.0` are the same number.
+fn json_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_eq(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| json_eq(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn violations(schema: Value, instance: Value) -> Vec<String> {
+        match JsonSchema::new(schema).unwrap().validate(&instance) {
+            Ok(()) => vec![],
+            Err(violations) => violations.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn checks_types_and_values() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": { "type": "integer", "minimum": 1 },
+                "name": { "type": "string", "minLength": 2, "pattern": "^[a-z]+$" },
+                "mode": { "enum": ["solo", "duo"] },
+                "tags": { "type": "array", "items": { "type": "string" }, "uniqueItems": true },
+            },
+            "additionalProperties": false,
+        });
+        let valid = json!({ "id": 1, "name": "ada", "mode": "duo", "tags": ["a", "b"] });
+        assert!(violations(schema.clone(), valid).is_empty());
+
+        let invalid = json!({ "id": 0, "name": "A", "mode": "trio", "tags": ["a", "a"], "x": 1 });
+        assert_eq!(
+            violations(schema, invalid),
+            [
+                "/id: 0 is less than the minimum 1",
+                "/mode: \"trio\" is not one of [\"solo\",\"duo\"]",
+                "/name: is 1 characters long, at least 2 expected",
+                "/name: \"A\" does not match \"^[a-z]+$\"",
+                "/tags: item 1 is a duplicate",
+                "/: unexpected property \"x\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_pattern_properties_and_combinators() {
+        let schema = json!({
+            "patternProperties": { "^score_": { "type": "number" } },
+            "oneOf": [{ "required": ["a"] }, { "required": ["b"] }],
+            "not": { "required": ["c"] },
+        });
+        assert!(violations(schema.clone(), json!({ "a": 1, "score_1": 2 })).is_empty());
+        assert_eq!(
+            violations(schema, json!({ "a": 1, "b": 2, "c": 3, "score_1": "high" })),
+            [
+                "/score_1: expected number, got string",
+                "/: matches 2 of the `oneOf` schemas instead of 1",
+                "/: matches the `not` schema",
+            ]
+        );
+    }
+
+    #[test]
+    fn follows_refs() {
+        let schema = json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "value": { "type": "integer" },
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/node" } },
+                    },
+                },
+            },
+            "$ref": "#/$defs/node",
+        });
+        let tree =
+            json!({ "value": 1, "children": [{ "value": 2, "children": [{ "value": "3" }] }] });
+        assert_eq!(
+            violations(schema, tree),
+            ["/children/0/children/0/value: expected integer, got string"]
+        );
+        assert_eq!(
+            violations(json!({ "$ref": "other.json#/player" }), json!({})),
+            ["/: unsupported $ref \"other.json#/player\""]
+        );
+    }
+
+    #[test]
+    fn only_refs_count_towards_the_depth() {
+        // Deeper than `MAX_DEPTH` in properties, without a single `$ref`.
+        let mut schema = json!({ "type": "integer" });
+        let mut instance = json!("deep");
+        for _ in 0..MAX_DEPTH + 10 {
+            schema = json!({ "properties": { "a": schema } });
+            instance = json!({ "a": instance });
+        }
+        let found = violations(schema, instance);
+        assert_eq!(found.len(), 1);
+        assert!(
+            found[0].ends_with(": expected integer, got string"),
+            "{found:?}"
+        );
+
+        let endless =
+            json!({ "$defs": { "loop": { "$ref": "#/$defs/loop" } }, "$ref": "#/$defs/loop" });
+        assert_eq!(
+            violations(endless, json!(1)),
+            ["/: the schema nests too deep"]
+        );
+    }
+}
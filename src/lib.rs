@@ -1,14 +1,232 @@
 #![doc = include_str!("../README.md")]
 
-use bevy::ecs::system::CommandQueue;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::ecs::entity::Entities;
+use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
+use bevy::ecs::system::{RunSystemOnce, SystemParam};
 use bevy::prelude::*;
-use bevy::tasks::{block_on, poll_once, IoTaskPool, Task};
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::tasks::{block_on, poll_once, Task};
+use bevy::tasks::{AsyncComputeTaskPool, IoTaskPool, TaskPool};
+use bevy::utils::{HashMap, HashSet, Instant, Uuid};
+use bytes::Bytes;
 
+use crate::logging::HttpLogging;
 use crate::prelude::TypedRequest;
+use crate::response_meta::ResponseHead;
+use crate::simulation::SimulatedRequest;
+use crate::streaming::{BodyMode, BodyPart, BodySink, HttpProgress, HttpResponseChunk};
+use crate::timing::Phases;
+use crate::transport::FetchContext;
+#[cfg(target_arch = "wasm32")]
+use crate::transport::{CacheMode, Credentials, FetchOptions, ReferrerPolicy};
+#[cfg(target_arch = "wasm32")]
+use ehttp::Mode;
 use ehttp::{Headers, Request, Response};
 
+#[cfg(feature = "asset")]
+pub use asset::{HttpAssetPlugin, HttpAssets};
+pub use backend::BackendOptions;
+#[cfg(not(target_arch = "wasm32"))]
+pub use bandwidth::DownloadLimits;
+pub use batch::{BatchResponse, RaceResponse, RequestBatch, RequestRace};
+pub use bulk::SendBatch;
+pub use cache::{CacheEvicted, EvictionReason, RequestCaching, ResponseCache};
+pub use chain::{ChainError, ChainErrorKind, ChainResponse, RequestChain};
+pub use chaos::{Fault, FaultInjection, FaultInjectionPlugin};
+pub use cleanup::{CleanupAfter, ResponseCleanup};
+pub use clock::{ClockSource, HttpClock};
+pub use cloud_save::{
+    CloudSavePlugin, CloudSaves, DownloadSave, SaveConflict, SaveDownloaded, SaveSyncFailed,
+    SaveUploaded, UploadSave,
+};
+pub use control::HttpClientControl;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crash::{CrashReport, CrashReportConsent, CrashReportPlugin, PendingCrashReports};
+pub use deadline::Deadline;
+#[cfg(all(not(target_arch = "wasm32"), any(feature = "asset", feature = "image")))]
+pub use download_cache::DownloadCache;
+pub use durable::{
+    DeliveryId, DurableDelivered, DurableDeliveries, DurableDeliveryPlugin, DurableExpired,
+    SendDurable,
+};
+pub use encryption::{PayloadCipher, PayloadEncryption};
+pub use environment::{Environment, Environments};
+pub use error::{HttpBuildError, HttpErrorKind};
+pub use fallback::FallbackUrls;
+pub use formats::{BodyFormat, JsonFormat};
+pub use graph::{GraphError, GraphErrorKind, GraphResponse, GraphResponses, RequestGraph};
+pub use handle::{RequestHandle, RequestStatus};
+pub use headers::{Authorization, CacheControl, ContentType, ETag, TypedHeader};
+pub use health::{
+    EndpointDown, EndpointHealth, EndpointRecovered, EndpointStatus, HealthCheckPlugin,
+};
+pub use host_limits::HostLimits;
+pub use http_date::{format_http_date, parse_http_date, ClockSkew};
+pub use idempotency::IDEMPOTENCY_KEY_HEADER;
+#[cfg(feature = "image")]
+pub use images::{HttpImagePlugin, HttpImages, ImageDownloadFailed, ImageDownloaded};
+#[cfg(feature = "json-schema")]
+pub use json_schema::{JsonSchema, JsonSchemas, SchemaViolation};
+pub use localization::{
+    Locale, Localization, LocalizationChanged, LocalizationFailed, LocalizationPlugin,
+};
+pub use logging::{HttpLogTarget, HttpLoggingPlugin, LogVerbosity, HTTP_LOG_TARGET};
+pub use memory::{HttpMemory, MemoryBudget};
+pub use metadata::ClientMetadata;
+pub use network_policy::NetworkPolicy;
+pub use news::{NewsFeed, NewsFeedPlugin};
+pub use overflow::{QueueLimit, QueueOverflow, QueueSaturated};
+pub use pagination::{PageFetched, PageProgress, Paginate, PaginationFailed, PaginationFinished};
+pub use persist::QueuePersistencePlugin;
+pub use preconnect::{Preconnect, Preconnections};
+pub use priority::{PriorityAging, RequestPriority};
+pub use redact::Redaction;
+pub use remote_config::{RemoteConfigChanged, RemoteConfigFailed, RemoteConfigPlugin};
+pub use response_meta::{RedirectHop, ResponseMeta};
+pub use retry::RetryPolicy;
+pub use retry_budget::{BackendDegraded, RetryBudget};
+#[cfg(feature = "s3")]
+pub use s3::{S3Bucket, S3Credentials, S3Listing, S3Object};
+pub use scheduling::FairScheduling;
+pub use scope::{abort_requests_on_exit, RequestStateScope};
+pub use server_browser::{RefreshServerList, ServerBrowser, ServerBrowserPlugin, ServerInfo};
+pub use shutdown::{GracefulShutdownPlugin, HttpShutdown, PendingRequests};
+pub use signature::{RequestSigner, ResponseSignatures, SignatureEncoding, SignatureVerifier};
+pub use simulation::{NetworkConditions, NetworkSimulation, NetworkSimulationPlugin};
+pub use single_flight::{Duplicates, SingleFlight, SingleFlights};
+pub use slo::{ServiceLevelObjective, SloBreach, SloPlugin, SloRecovered, SloViolated};
+#[cfg(feature = "snapshot-testing")]
+pub use snapshot::TypedSnapshots;
+pub use sse::{ConnectionState, ConnectionStateChanged, EventSource, ServerSentEvent};
+pub use stats::{ConnectionStats, HttpStats, RequestLabel, RequestStats};
+pub use status::{HttpStatusClass, StatusCode};
+pub use telemetry::{Telemetry, TelemetryPlugin};
+#[cfg(feature = "asset")]
+pub use template_files::{RequestTemplateFile, RequestTemplateFilesPlugin, RequestTemplateLoader};
+pub use templates::{RequestTemplate, RequestTemplates, SendTemplate, TemplateError};
+pub use timing::RequestTiming;
+pub use transform::{ResponseTransform, ResponseTransforms};
+pub use typed_registry::{TypedRequestRegistry, TypedRequestStats};
+#[cfg(not(target_arch = "wasm32"))]
+pub use upload::StreamingBody;
+pub use url_policy::{RequestBlocked, UrlPolicy};
+pub use urls::{join_url, QueryArrays};
+pub use version::{compare_versions, UpdateAvailable, VersionCheckPlugin, VersionManifest};
+pub use watch::{RemoteChanged, RemoteContent, RemoteWatchFailed, WatchRemote};
+#[cfg(feature = "webdav")]
+pub use webdav::{DavEntry, WebDav};
+#[cfg(all(feature = "webhook-listener", not(target_arch = "wasm32")))]
+pub use webhooks::{
+    HttpWebhookTrait, Webhook, WebhookListener, WebhookListenerPlugin, WebhookReceived,
+};
+#[cfg(feature = "websocket")]
+pub use websocket::{
+    WebSocket, WebSocketClosed, WebSocketError, WebSocketMessage, WebSocketOpened, WebSocketState,
+    WsMessage,
+};
+
+#[cfg(feature = "asset")]
+mod asset;
+mod backend;
+#[cfg(not(target_arch = "wasm32"))]
+mod bandwidth;
+mod batch;
+mod bulk;
+mod cache;
+mod chain;
+mod chaos;
+mod cleanup;
+mod clock;
+mod cloud_save;
+mod control;
+#[cfg(not(target_arch = "wasm32"))]
+mod crash;
+mod deadline;
+#[cfg(all(not(target_arch = "wasm32"), any(feature = "asset", feature = "image")))]
+mod download_cache;
+mod durable;
+mod encryption;
+mod environment;
+mod error;
+mod fallback;
+mod formats;
+mod graph;
+mod handle;
+mod headers;
+mod health;
+mod host_limits;
+mod http_date;
+mod idempotency;
+#[cfg(feature = "image")]
+mod images;
+#[cfg(feature = "json-schema")]
+mod json_schema;
+mod localization;
+mod logging;
+mod memory;
+mod metadata;
+mod method;
+mod network_policy;
+mod news;
+mod overflow;
+mod pagination;
+mod patch;
+mod persist;
+mod preconnect;
 pub mod prelude;
+mod priority;
+mod redact;
+mod remote_config;
+mod response_meta;
+mod retry;
+mod retry_budget;
+#[cfg(feature = "s3")]
+mod s3;
+mod scheduling;
+mod scope;
+mod server_browser;
+mod shutdown;
+mod signature;
+#[cfg(feature = "s3")]
+mod sigv4;
+mod simulation;
+mod single_flight;
+mod slo;
+#[cfg(feature = "snapshot-testing")]
+mod snapshot;
+mod sse;
+mod stats;
+mod status;
+mod storage;
+mod streaming;
+mod telemetry;
+#[cfg(feature = "asset")]
+mod template_files;
+mod templates;
+mod timing;
+mod transform;
+mod transport;
 mod typed;
+mod typed_registry;
+#[cfg(not(target_arch = "wasm32"))]
+mod upload;
+mod url_policy;
+mod urls;
+mod version;
+mod watch;
+#[cfg(feature = "webdav")]
+mod webdav;
+#[cfg(all(feature = "webhook-listener", not(target_arch = "wasm32")))]
+mod webhooks;
+#[cfg(feature = "websocket")]
+mod websocket;
+#[cfg(any(feature = "webdav", feature = "s3"))]
+mod xml;
 
 /// Plugin that provides support for send http request and handle response.
 ///
@@ -19,36 +237,434 @@ mod typed;
 ///
 /// App::new()
 ///     .add_plugins(DefaultPlugins)
-///     .add_plugins(HttpClientPlugin).run();
+///     .add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///         max_concurrent: 10,
+///         default_timeout: Some(Duration::from_secs(10)),
+///         ..default()
+///     }))
+///     .run();
 /// ```
 #[derive(Default)]
-pub struct HttpClientPlugin;
+pub struct HttpClientPlugin {
+    pub settings: HttpClientSettings,
+    run_conditions: Vec<RunCondition>,
+}
+
+/// Adds a run condition to the pipeline sets in the given schedule.
+type RunCondition = Box<dyn Fn(&mut App, InternedScheduleLabel) + Send + Sync>;
+
+impl HttpClientPlugin {
+    /// create the plugin with the given settings
+    pub fn new(settings: HttpClientSettings) -> Self {
+        Self {
+            settings,
+            run_conditions: vec![],
+        }
+    }
+
+    /// Only dispatches requests and handles responses while `condition` holds.
+    ///
+    /// Requests sent while the pipeline is suspended stay queued, and in-flight requests keep their
+    /// entities and deliver their results once it resumes. Can be called multiple times, all
+    /// conditions have to hold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.add_plugins(HttpClientPlugin::default().run_if(in_state(AppState::Online)));
+    /// ```
+    pub fn run_if<M>(
+        mut self,
+        condition: impl Condition<M> + Clone + Send + Sync + 'static,
+    ) -> Self {
+        self.run_conditions.push(Box::new(move |app, schedule| {
+            app.configure_sets(schedule, HttpSet::Dispatch.run_if(condition.clone()));
+            app.configure_sets(schedule, HttpSet::HandleResponses.run_if(condition.clone()));
+        }));
+        self
+    }
+}
 
 impl Plugin for HttpClientPlugin {
     fn build(&self, app: &mut App) {
+        // A `HttpClientSetting` inserted before the plugin takes precedence over the plugin settings.
         if !app.world.contains_resource::<HttpClientSetting>() {
-            app.init_resource::<HttpClientSetting>();
+            app.insert_resource(HttpClientSetting::from(&self.settings));
         }
+        app.insert_resource(HttpSchedule(self.settings.schedule));
+        if !app.world.contains_resource::<HttpClock>() {
+            app.insert_resource(HttpClock::new(self.settings.clock));
+        }
+        app.init_resource::<RequestQueue>();
+        app.init_resource::<FinishedRequests>();
+        app.init_resource::<HttpStats>();
+        app.init_resource::<batch::PendingBatches>();
+        app.init_resource::<Preconnections>();
+        app.init_resource::<retry::PendingRetries>();
+        app.init_resource::<retry_budget::RetryTokens>();
+        app.init_resource::<Redaction>();
+        app.init_resource::<ClockSkew>();
+        app.init_resource::<HttpClientControl>();
+        app.init_resource::<SingleFlights>();
+        app.init_resource::<NetworkPolicy>();
+        memory::register(app);
         app.add_event::<HttpRequest>();
         app.add_event::<HttpResponse>();
         app.add_event::<HttpResponseError>();
-        app.add_systems(Update, (handle_request, handle_tasks));
+        app.add_event::<AbortRequest>();
+        app.add_event::<AbortRequestId>();
+        app.add_event::<HttpProgress>();
+        app.add_event::<HttpResponseChunk>();
+        app.add_event::<ResponseMeta>();
+        app.add_event::<RequestTiming>();
+        app.add_event::<RequestBatch>();
+        app.add_event::<BatchResponse>();
+        app.add_event::<RequestRace>();
+        app.add_event::<RaceResponse>();
+        app.add_event::<RequestChain>();
+        app.add_event::<ChainResponse>();
+        app.add_event::<RequestGraph>();
+        app.add_event::<GraphResponse>();
+        app.add_event::<Preconnect>();
+        app.add_event::<RemoteChanged>();
+        app.add_event::<RemoteWatchFailed>();
+        app.init_resource::<TypedRequestRegistry>();
+        app.add_event::<PageFetched>();
+        app.add_event::<PaginationFinished>();
+        app.add_event::<PaginationFailed>();
+        app.add_event::<ServerSentEvent>();
+        app.add_event::<ConnectionStateChanged>();
+        app.init_resource::<sse::EventStreams>();
+        app.add_event::<QueueSaturated>();
+        app.add_event::<CacheEvicted>();
+        app.add_event::<RequestBlocked>();
+        app.add_event::<BackendDegraded>();
+        app.add_event::<SendTemplate>();
+        app.configure_sets(
+            self.settings.schedule,
+            (HttpSet::Queue, HttpSet::Dispatch, HttpSet::HandleResponses).chain(),
+        );
+        for run_condition in &self.run_conditions {
+            run_condition(app, self.settings.schedule);
+        }
+        app.add_systems(
+            self.settings.schedule,
+            (
+                (
+                    templates::send_templates,
+                    (
+                        handle_request,
+                        batch::handle_batches,
+                        chain::handle_chains,
+                        graph::handle_graphs,
+                        preconnect::handle_preconnects,
+                        watch::poll_watched,
+                        pagination::fetch_pages,
+                        sse::connect_event_sources,
+                        sse::close_removed_event_sources,
+                        retry::queue_due_retries,
+                    ),
+                    single_flight::guard_duplicates,
+                    deadline::expire_queued_requests,
+                    overflow::limit_queue,
+                )
+                    .chain()
+                    .in_set(HttpSet::Queue),
+                clock::advance_clock.before(HttpSet::Queue),
+                (abort_queued_requests, dispatch_requests)
+                    .chain()
+                    .in_set(HttpSet::Dispatch),
+                (
+                    handle_tasks,
+                    deliver_responses,
+                    batch::deliver_batches,
+                    memory::track_memory,
+                    cache::send_evictions,
+                )
+                    .chain()
+                    .in_set(HttpSet::HandleResponses),
+                (sse::read_event_streams, sse::end_event_streams)
+                    .chain()
+                    .after(handle_tasks)
+                    .in_set(HttpSet::HandleResponses),
+                scope::despawn_exited_scopes
+                    .after(handle_tasks)
+                    .in_set(HttpSet::HandleResponses),
+                cleanup::clean_up_responses
+                    .after(deliver_responses)
+                    .in_set(HttpSet::HandleResponses),
+            ),
+        );
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(
+            self.settings.schedule,
+            network_policy::detect_metered.before(HttpSet::Dispatch),
+        );
+        #[cfg(feature = "websocket")]
+        {
+            app.add_event::<WebSocketOpened>();
+            app.add_event::<WebSocketMessage>();
+            app.add_event::<WebSocketClosed>();
+            app.add_event::<WebSocketError>();
+            app.add_systems(
+                self.settings.schedule,
+                (
+                    websocket::open_websockets.in_set(HttpSet::Dispatch),
+                    websocket::poll_websockets.in_set(HttpSet::HandleResponses),
+                ),
+            );
+        }
+    }
+}
+
+/// System sets of the request pipeline, in the schedule chosen with `HttpClientSettings::schedule`.
+///
+/// Response events are sent when `HandleResponses` applies its commands, so systems ordered
+/// `.after(HttpSet::HandleResponses)` read them in the same frame.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HttpSet {
+    /// Collects request events into the pending queue. Never suspended by `HttpClientPlugin::run_if`.
+    Queue,
+    /// Sends queued requests while clients are available.
+    Dispatch,
+    /// Polls in-flight requests and delivers their results.
+    HandleResponses,
+}
+
+/// Settings for [`HttpClientPlugin`], applied to every request.
+#[derive(Debug, Clone)]
+pub struct HttpClientSettings {
+    /// max concurrent request
+    pub max_concurrent: usize,
+    /// Timeout for requests that don't set their own with `HttpClient::timeout`.
+    pub default_timeout: Option<Duration>,
+    /// Retries of requests that don't set their own with `HttpClient::retry`, see
+    /// [`RetryPolicy`]. Failed requests are reported right away if `None`.
+    pub default_retry: Option<RetryPolicy>,
+    /// How many redirects are followed before a request fails, for requests that don't set their
+    /// own with `HttpClient::max_redirects`. Browsers follow redirects on their own on wasm builds.
+    pub max_redirects: usize,
+    /// Headers sent with every request that doesn't set them itself.
+    pub default_headers: Vec<(String, String)>,
+    /// `User-Agent` sent with every request that doesn't set its own.
+    /// Browsers may ignore it on wasm builds.
+    pub user_agent: Option<String>,
+    /// Build and platform headers sent with every request, see [`client_metadata!`].
+    pub client_metadata: Option<ClientMetadata>,
+    /// Header carrying the [`RequestId`] of every request, e.g. `X-Request-Id`.
+    pub request_id_header: Option<String>,
+    /// The task pool requests are dispatched on.
+    pub task_pool: HttpTaskPool,
+    /// How many finished requests deliver their results per frame.
+    pub response_budget: ResponseBudget,
+    /// Caps on the download rate, not available on wasm builds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub download_limits: DownloadLimits,
+    /// Shares the clients between request labels while requests wait for one, see
+    /// [`FairScheduling`]. Requests are dispatched in the order they were queued if `None`.
+    pub fair_scheduling: Option<FairScheduling>,
+    /// Caps how many requests wait for a client, see [`QueueLimit`]. Unbounded if `None`.
+    pub queue_limit: Option<QueueLimit>,
+    /// Raises the priority of requests while they wait, see [`PriorityAging`]. Priorities stay as
+    /// they were set if `None`.
+    pub priority_aging: Option<PriorityAging>,
+    /// Caps how many requests run at once per host, see [`HostLimits`]. Only `max_concurrent`
+    /// applies if `None`.
+    pub host_limits: Option<HostLimits>,
+    /// Limits the schemes and hosts requests may be sent to, see [`UrlPolicy`]. Every url is
+    /// allowed if `None`.
+    pub url_policy: Option<UrlPolicy>,
+    /// Caps the retries of all requests together, see [`RetryBudget`]. Requests retry as their
+    /// `RetryPolicy` allows if `None`.
+    pub retry_budget: Option<RetryBudget>,
+    /// Typed response bodies at least this many bytes long are deserialized on the
+    /// `AsyncComputeTaskPool` and delivered in a later frame, so the schedule never waits for serde
+    /// on huge payloads. Every body is deserialized in the schedule if `None`.
+    pub background_parse_bytes: Option<usize>,
+    /// Caps the bytes of response bodies kept in memory, see [`MemoryBudget`]. The cache only
+    /// keeps to its `max_entries` if `None`, bodies are still counted in [`HttpMemory`].
+    pub memory_budget: Option<MemoryBudget>,
+    /// Removes the responses left on entities after a while, see [`ResponseCleanup`]. They stay
+    /// until removed if `None`.
+    pub response_cleanup: Option<ResponseCleanup>,
+    /// The schedule the request systems run in, e.g. `Update`, `PostUpdate` or `FixedUpdate`.
+    /// Typed requests registered after the plugin run in the same schedule.
+    pub schedule: InternedScheduleLabel,
+    /// Where retry backoffs, timeouts, deadlines, poll intervals and cache lifetimes read the time
+    /// from, see [`HttpClock`]. A `HttpClock` inserted before the plugin takes precedence.
+    pub clock: ClockSource,
+}
+
+impl Default for HttpClientSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 5,
+            default_timeout: None,
+            default_retry: None,
+            max_redirects: MAX_REDIRECTS,
+            default_headers: vec![],
+            user_agent: None,
+            client_metadata: None,
+            request_id_header: None,
+            task_pool: HttpTaskPool::default(),
+            response_budget: ResponseBudget::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            download_limits: DownloadLimits::default(),
+            fair_scheduling: None,
+            queue_limit: None,
+            priority_aging: None,
+            host_limits: None,
+            url_policy: None,
+            retry_budget: None,
+            background_parse_bytes: None,
+            memory_budget: None,
+            response_cleanup: None,
+            schedule: Update.intern(),
+            clock: ClockSource::Real,
+        }
+    }
+}
+
+/// The task pool requests are dispatched on.
+#[derive(Debug, Clone, Default)]
+pub enum HttpTaskPool {
+    /// bevy's `IoTaskPool`
+    #[default]
+    Io,
+    /// bevy's `AsyncComputeTaskPool`
+    AsyncCompute,
+    /// A dedicated pool, so heavy HTTP traffic and asset IO can't starve each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let pool = TaskPoolBuilder::new().num_threads(2).thread_name("http".to_string()).build();
+    /// let task_pool = HttpTaskPool::Custom(Arc::new(pool));
+    /// ```
+    Custom(Arc<TaskPool>),
+}
+
+impl HttpTaskPool {
+    fn get(&self) -> &TaskPool {
+        match self {
+            HttpTaskPool::Io => IoTaskPool::get(),
+            HttpTaskPool::AsyncCompute => AsyncComputeTaskPool::get(),
+            HttpTaskPool::Custom(pool) => pool,
+        }
+    }
+}
+
+/// Limits the work `HttpSet::HandleResponses` does per frame.
+///
+/// Results beyond the budget are delivered in the following frames, in the order the requests finished.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseBudget {
+    /// Every finished request is delivered in the frame it finished.
+    #[default]
+    Unlimited,
+    /// At most this many results are delivered per frame.
+    Count(usize),
+    /// Results are delivered until this much time was spent in the frame.
+    /// At least one result is delivered per frame, so the budget can be exceeded by a single slow handler.
+    Time(Duration),
+}
+
+impl ResponseBudget {
+    fn allows(&self, delivered: usize, elapsed: Duration) -> bool {
+        match *self {
+            ResponseBudget::Unlimited => true,
+            ResponseBudget::Count(count) => delivered < count,
+            ResponseBudget::Time(time) => delivered == 0 || elapsed < time,
+        }
     }
 }
 
+/// The schedule the request systems were added to, so typed requests can join them.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct HttpSchedule(pub(crate) InternedScheduleLabel);
+
 /// The setting of http client.
-/// can set the max concurrent request.
+/// can set the max concurrent request and the headers sent with every request.
 #[derive(Resource, Debug)]
 pub struct HttpClientSetting {
     /// max concurrent request
     pub client_limits: usize,
+    /// Timeout for requests that don't set their own.
+    pub default_timeout: Option<Duration>,
+    /// Retries of requests that don't set their own.
+    pub default_retry: Option<RetryPolicy>,
+    /// Redirects followed by requests that don't set their own limit.
+    pub max_redirects: usize,
+    /// Headers sent with every request that doesn't set them itself.
+    pub default_headers: Headers,
+    /// `User-Agent` sent with every request that doesn't set its own.
+    /// Browsers may ignore it on wasm builds.
+    pub user_agent: Option<String>,
+    /// Build and platform headers sent with every request.
+    pub client_metadata: Option<ClientMetadata>,
+    /// Header carrying the [`RequestId`] of every request.
+    pub request_id_header: Option<String>,
+    /// The task pool requests are dispatched on.
+    pub task_pool: HttpTaskPool,
+    /// How many finished requests deliver their results per frame.
+    pub response_budget: ResponseBudget,
+    /// Caps on the download rate, can be changed while downloads are running.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub download_limits: DownloadLimits,
+    /// Shares the clients between request labels while requests wait for one, see
+    /// [`FairScheduling`]. Requests are dispatched in the order they were queued if `None`.
+    pub fair_scheduling: Option<FairScheduling>,
+    /// Caps how many requests wait for a client, see [`QueueLimit`]. Unbounded if `None`.
+    pub queue_limit: Option<QueueLimit>,
+    /// Raises the priority of requests while they wait, see [`PriorityAging`].
+    pub priority_aging: Option<PriorityAging>,
+    /// Caps how many requests run at once per host, see [`HostLimits`].
+    pub host_limits: Option<HostLimits>,
+    /// Limits the schemes and hosts requests may be sent to, see [`UrlPolicy`].
+    pub url_policy: Option<UrlPolicy>,
+    /// Caps the retries of all requests together, see [`RetryBudget`].
+    pub retry_budget: Option<RetryBudget>,
+    /// Typed response bodies at least this many bytes long are deserialized off the schedule.
+    pub background_parse_bytes: Option<usize>,
+    /// Caps the bytes of response bodies kept in memory, see [`MemoryBudget`].
+    pub memory_budget: Option<MemoryBudget>,
+    /// Removes the responses left on entities after a while, see [`ResponseCleanup`].
+    pub response_cleanup: Option<ResponseCleanup>,
     current_clients: usize,
 }
 
 impl Default for HttpClientSetting {
     fn default() -> Self {
+        Self::from(&HttpClientSettings::default())
+    }
+}
+
+impl From<&HttpClientSettings> for HttpClientSetting {
+    fn from(settings: &HttpClientSettings) -> Self {
         Self {
-            client_limits: 5,
+            client_limits: settings.max_concurrent,
+            default_timeout: settings.default_timeout,
+            default_retry: settings.default_retry,
+            max_redirects: settings.max_redirects,
+            default_headers: Headers {
+                headers: settings.default_headers.clone(),
+            },
+            user_agent: settings.user_agent.clone(),
+            client_metadata: settings.client_metadata.clone(),
+            request_id_header: settings.request_id_header.clone(),
+            task_pool: settings.task_pool.clone(),
+            response_budget: settings.response_budget,
+            #[cfg(not(target_arch = "wasm32"))]
+            download_limits: settings.download_limits.clone(),
+            fair_scheduling: settings.fair_scheduling.clone(),
+            queue_limit: settings.queue_limit,
+            priority_aging: settings.priority_aging,
+            host_limits: settings.host_limits.clone(),
+            url_policy: settings.url_policy.clone(),
+            retry_budget: settings.retry_budget,
+            background_parse_bytes: settings.background_parse_bytes,
+            memory_budget: settings.memory_budget,
+            response_cleanup: settings.response_cleanup,
             current_clients: 0,
         }
     }
@@ -57,9 +673,36 @@ impl Default for HttpClientSetting {
 impl HttpClientSetting {
     /// create a new http client setting
     pub fn new(max_concurrent: usize) -> Self {
-        Self {
-            client_limits: max_concurrent,
-            current_clients: 0,
+        Self::from(&HttpClientSettings {
+            max_concurrent,
+            ..default()
+        })
+    }
+
+    /// set the `User-Agent` sent with every request
+    pub fn with_user_agent(mut self, user_agent: impl ToString) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// set the build and platform headers sent with every request, see [`client_metadata!`]
+    pub fn with_client_metadata(mut self, client_metadata: ClientMetadata) -> Self {
+        self.client_metadata = Some(client_metadata);
+        self
+    }
+
+    /// Adds the configured headers, keeping any the request already sets.
+    fn apply_default_headers(&self, headers: &mut Headers) {
+        for (key, value) in &self.default_headers {
+            metadata::insert_default_header(headers, key, value);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            metadata::insert_default_header(headers, "User-Agent", user_agent);
+        }
+        if let Some(client_metadata) = &self.client_metadata {
+            for (key, value) in client_metadata.headers() {
+                metadata::insert_default_header(headers, key, value);
+            }
         }
     }
 
@@ -70,10 +713,195 @@ impl HttpClientSetting {
     }
 }
 
+/// Identifies a request in its events, results and the `HttpClientSettings::request_id_header`,
+/// so client and backend logs can be joined.
+///
+/// Also inserted on the request entity while the request is in flight.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(pub Uuid);
+
+impl RequestId {
+    /// a new random request id
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 #[derive(Event, Debug, Clone)]
 pub struct HttpRequest {
+    /// Unique for every built request, clones share it.
+    pub id: RequestId,
     pub from_entity: Option<Entity>,
+    /// The request entity is spawned as a child of this entity, see `HttpClient::child_of`.
+    pub parent: Option<Entity>,
+    /// The result is inserted on this entity instead of being sent as an event, see `HttpClient::respond_to`.
+    pub respond_to: Option<Entity>,
+    /// Runs with the result instead of delivering it, see `HttpClient::on_complete`.
+    pub on_complete: Option<OnComplete>,
+    /// Groups the request in [`HttpStats`].
+    pub label: Option<RequestLabel>,
     pub request: Request,
+    /// How the response body is read.
+    pub body_mode: BodyMode,
+    /// Overrides `HttpClientSetting::default_timeout`.
+    pub timeout: Option<Duration>,
+    /// Runs without a timeout, whatever the environment and the plugin set, see
+    /// `HttpClient::no_timeout`.
+    pub no_timeout: bool,
+    /// Mirrors tried when the request fails, `None` to use the [`FallbackUrls`] of `from_entity`.
+    pub fallback_urls: Option<FallbackUrls>,
+    /// Sends the request again when it fails, see `HttpClient::retry`.
+    pub retry: Option<RetryPolicy>,
+    /// When the request fails, however often it was retried, `None` to use the [`Deadline`] of
+    /// `from_entity`.
+    pub deadline: Option<Deadline>,
+    /// Where the response is kept in the [`ResponseCache`].
+    pub caching: RequestCaching,
+    /// Waiting requests with a higher priority are dispatched first.
+    pub priority: RequestPriority,
+    /// When the request entered the queue on the [`HttpClock`], set once it is queued.
+    pub queued_at: Option<Instant>,
+    /// Run in order on a 2xx response before it is delivered, see [`ResponseTransforms`].
+    pub transforms: Vec<ResponseTransform>,
+    /// Sent and received without the [`PayloadEncryption`], see `HttpClient::plaintext`.
+    pub plaintext: bool,
+    /// Fails a 2xx response without a valid signature, see [`ResponseSignatures`].
+    pub verify_signature: bool,
+    /// Deferred on a metered connection, see [`NetworkPolicy`].
+    pub large_transfer: bool,
+    /// Options passed through to the backend, see [`BackendOptions`].
+    pub backend: Option<BackendOptions>,
+    /// Sent in place of the body of `request`, see [`StreamingBody`]. Only available on native
+    /// builds
+    #[cfg(not(target_arch = "wasm32"))]
+    pub streaming_body: Option<StreamingBody>,
+    /// Kept in the store of the [`QueuePersistencePlugin`] while it waits, see `HttpClient::persist`.
+    pub persist: bool,
+    /// Not sent while another request with its key is in flight, see [`SingleFlight`].
+    pub single_flight: Option<SingleFlight>,
+    /// Overrides `HttpClientSettings::max_redirects`, see `HttpClient::max_redirects`.
+    pub max_redirects: Option<usize>,
+    /// Dropped when a native redirect leads to another origin, the keys and headers of the active
+    /// [`Environment`] are added at dispatch.
+    pub private_headers: Vec<String>,
+    /// Overrides the [`HostLimits`] of the host of the request, see `HttpClient::host_limit`.
+    pub host_limit: Option<usize>,
+    /// Signs the request once it is ready to be sent, see [`RequestSigner`].
+    pub signer: Option<RequestSigner>,
+    /// Extra fetch options. Only available on wasm builds
+    #[cfg(target_arch = "wasm32")]
+    pub fetch_options: FetchOptions,
+}
+
+impl HttpRequest {
+    /// Wraps an ehttp request, with every other option left at its default.
+    pub fn new(request: Request) -> Self {
+        Self {
+            id: RequestId::new(),
+            from_entity: None,
+            parent: None,
+            respond_to: None,
+            on_complete: None,
+            label: None,
+            request,
+            body_mode: BodyMode::default(),
+            timeout: None,
+            no_timeout: false,
+            fallback_urls: None,
+            retry: None,
+            deadline: None,
+            caching: RequestCaching::default(),
+            priority: RequestPriority::NORMAL,
+            queued_at: None,
+            transforms: Vec::new(),
+            plaintext: false,
+            verify_signature: false,
+            large_transfer: false,
+            backend: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            streaming_body: None,
+            persist: false,
+            single_flight: None,
+            max_redirects: None,
+            private_headers: vec![],
+            host_limit: None,
+            signer: None,
+            #[cfg(target_arch = "wasm32")]
+            fetch_options: FetchOptions::default(),
+        }
+    }
+}
+
+/// The error of requests aborted with `AbortRequest`.
+pub(crate) const REQUEST_ABORTED: &str = "Request aborted";
+
+/// The error of requests that took longer than their timeout.
+pub(crate) const REQUEST_TIMED_OUT: &str = "Request timed out";
+
+/// The error of requests that did not finish by their `Deadline`.
+pub(crate) const REQUEST_DEADLINE_EXCEEDED: &str = "Request deadline exceeded";
+
+/// The error of requests dropped from the queue over its `QueueLimit`.
+pub(crate) const REQUEST_QUEUE_FULL: &str = "Request queue is full";
+
+/// The error of requests to a url the `UrlPolicy` does not allow.
+pub(crate) const REQUEST_BLOCKED: &str = "Request blocked by the url policy";
+
+/// The error of requests sent while another request with their `SingleFlight` key is in flight.
+pub(crate) const REQUEST_DUPLICATE: &str = "Request already in flight";
+
+/// The default of `HttpClientSettings::max_redirects`.
+pub(crate) const MAX_REDIRECTS: usize = 5;
+
+/// A one-shot callback that takes the place of the usual result delivery, see `HttpClient::on_complete`.
+///
+/// Clones of a request share the callback, it runs for the first of them that is sent.
+#[derive(Clone)]
+pub struct OnComplete {
+    on_response: Arc<Mutex<Option<ResponseHandler>>>,
+    on_dispatch: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl OnComplete {
+    pub(crate) fn new(
+        on_response: impl FnOnce(&mut World, RequestId, ehttp::Result<Response>) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            on_response: Arc::new(Mutex::new(Some(Box::new(on_response)))),
+            on_dispatch: None,
+        }
+    }
+
+    /// Also calls `on_dispatch` when the request is sent.
+    pub(crate) fn with_on_dispatch(
+        mut self,
+        on_dispatch: impl Fn() + Send + Sync + 'static,
+    ) -> Self {
+        self.on_dispatch = Some(Arc::new(on_dispatch));
+        self
+    }
+
+    fn take(&self) -> Option<ResponseHandler> {
+        self.on_response.lock().ok()?.take()
+    }
+}
+
+impl std::fmt::Debug for OnComplete {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("OnComplete")
+    }
 }
 
 /// builder  for ehttp request
@@ -81,33 +909,129 @@ pub struct HttpRequest {
 pub struct HttpClient {
     /// The entity that the request is associated with.
     from_entity: Option<Entity>,
-    /// "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", …
+    /// The entity owning the request entity.
+    parent: Option<Entity>,
+    /// The entity the result is inserted on.
+    respond_to: Option<Entity>,
+    /// Runs with the result instead of delivering it.
+    on_complete: Option<OnComplete>,
+    /// Groups the request in the stats.
+    label: Option<RequestLabel>,
+    /// "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", …
     method: Option<String>,
 
     /// https://…
     url: Option<String>,
 
+    /// The url `url` is joined onto.
+    base_url: Option<String>,
+
     /// The data you send with e.g. "POST".
     body: Vec<u8>,
 
     /// ("Accept", "*/*"), …
     headers: Option<Headers>,
 
+    /// How the response body is read.
+    body_mode: BodyMode,
+
+    /// How long the request may take before it fails.
+    timeout: Option<Duration>,
+
+    /// Whether the request ignores the default timeouts.
+    no_timeout: bool,
+
+    /// Mirrors tried when the request fails.
+    fallback_urls: Option<FallbackUrls>,
+
+    /// How often the request is sent again when it fails.
+    retry: Option<RetryPolicy>,
+
+    /// When the request fails, however often it was retried.
+    deadline: Option<Deadline>,
+
+    /// Where the response is kept in the response cache.
+    caching: RequestCaching,
+
+    /// How urgent the request is while it waits for a client.
+    priority: RequestPriority,
+
+    /// Rewrite the response before it is delivered.
+    transforms: Vec<ResponseTransform>,
+
+    /// Whether the payload encryption is skipped.
+    plaintext: bool,
+
+    /// Whether the response must be signed.
+    verify_signature: bool,
+
+    /// Whether the request waits for an unmetered connection.
+    large_transfer: bool,
+
+    /// Backend specific options.
+    backend: Option<BackendOptions>,
+
+    /// Body read from its source while it is sent.
+    #[cfg(not(target_arch = "wasm32"))]
+    streaming_body: Option<StreamingBody>,
+
+    /// Whether the request survives a restart while queued.
+    persist: bool,
+
+    /// Keeps duplicates from being sent while the request is in flight.
+    single_flight: Option<SingleFlight>,
+
+    /// How many redirects are followed.
+    max_redirects: Option<usize>,
+
+    /// How many requests may run at once to the host of the request.
+    host_limit: Option<usize>,
+
     /// Request mode used on fetch. Only available on wasm builds
     #[cfg(target_arch = "wasm32")]
     pub mode: Option<Mode>,
+
+    /// Credentials, cache and referrer policy used on fetch. Only available on wasm builds
+    #[cfg(target_arch = "wasm32")]
+    fetch_options: FetchOptions,
 }
 
 impl Default for HttpClient {
     fn default() -> Self {
         Self {
             from_entity: None,
+            parent: None,
+            respond_to: None,
+            on_complete: None,
+            label: None,
             method: None,
             url: None,
             body: vec![],
             headers: Some(Headers::new(&[("Accept", "*/*")])),
+            body_mode: BodyMode::default(),
+            timeout: None,
+            no_timeout: false,
+            fallback_urls: None,
+            retry: None,
+            deadline: None,
+            caching: RequestCaching::default(),
+            priority: RequestPriority::NORMAL,
+            transforms: Vec::new(),
+            plaintext: false,
+            verify_signature: false,
+            large_transfer: false,
+            backend: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            streaming_body: None,
+            persist: false,
+            single_flight: None,
+            max_redirects: None,
+            host_limit: None,
+            base_url: None,
             #[cfg(target_arch = "wasm32")]
             mode: None,
+            #[cfg(target_arch = "wasm32")]
+            fetch_options: FetchOptions::default(),
         }
     }
 }
@@ -233,7 +1157,9 @@ impl HttpClient {
         self
     }
 
-    /// This method is used to create a `HEAD` HTTP request.
+    /// This method is used to create a `HEAD` HTTP request, e.g. to check that a file exists or how big
+    /// it is before downloading it. The response has headers but no body, and any body set on the
+    /// request is dropped with a warning in debug builds.
     ///
     /// # Arguments
     ///
@@ -254,11 +1180,11 @@ impl HttpClient {
         self
     }
 
-    /// This method is used to set the headers of the HTTP request.
+    /// This method is used to create an `OPTIONS` HTTP request, e.g. to ask which methods a url allows.
     ///
     /// # Arguments
     ///
-    /// * `headers` - A slice of tuples where each tuple represents a header. The first element of the tuple is the header name and the second element is the header value.
+    /// * `url` - A value that can be converted into a string. This is the URL to which the HTTP request will be sent.
     ///
     /// # Returns
     ///
@@ -267,55 +1193,45 @@ impl HttpClient {
     /// # Examples
     ///
     /// ```
-    /// let http_client = HttpClient::new().post("http://example.com")
-    ///     .headers(&[("Content-Type", "application/json"), ("Accept", "*/*")]);
+    /// let http_client = HttpClient::new().options("http://example.com");
     /// ```
-    pub fn headers(mut self, headers: &[(&str, &str)]) -> Self {
-        self.headers = Some(Headers::new(headers));
+    pub fn options(mut self, url: impl ToString) -> Self {
+        self.method = Some("OPTIONS".to_string());
+        self.url = Some(url.to_string());
         self
     }
 
-    /// This method is used to set the body of the HTTP request as a JSON payload.
-    /// It also sets the "Content-Type" header of the request to "application/json".
+    /// This method is used to create an HTTP request with any method, e.g. `REPORT`, `PROPFIND` or a
+    /// vendor specific one.
+    ///
+    /// Native builds send any method. Browsers refuse `CONNECT`, `TRACE` and `TRACK`, on wasm
+    /// builds `try_build` fails for them and sending them fails with an `InvalidRequest` error.
     ///
     /// # Arguments
     ///
-    /// * `body` - A reference to any type that implements the `serde::Serialize` trait. This is the data that will be serialized to JSON and set as the body of the HTTP request.
+    /// * `method` - The method, sent as it is.
+    /// * `url` - A value that can be converted into a string. This is the URL to which the HTTP request will be sent.
     ///
     /// # Returns
     ///
     /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
     ///
-    /// # Panics
-    ///
-    /// * This method will panic if the serialization of the `body` to JSON fails.
-    ///
     /// # Examples
     ///
     /// ```
-    /// let http_client = HttpClient::new().post("http://example.com")
-    ///     .json(&data);
+    /// let http_client = HttpClient::new().method("PROPFIND", "http://example.com/dav/");
     /// ```
-    pub fn json(mut self, body: &impl serde::Serialize) -> Self {
-        if let Some(headers) = self.headers.as_mut() {
-            headers.insert("Content-Type".to_string(), "application/json".to_string());
-        } else {
-            self.headers = Some(Headers::new(&[
-                ("Content-Type", "application/json"),
-                ("Accept", "*/*"),
-            ]));
-        }
-
-        self.body = serde_json::to_vec(body).unwrap();
+    pub fn method(mut self, method: impl ToString, url: impl ToString) -> Self {
+        self.method = Some(method.to_string());
+        self.url = Some(url.to_string());
         self
     }
 
-    /// This method is used to set the properties of the `HttpClient` instance using an `Request` instance.
-    /// This version of the method is used when the target architecture is not `wasm32`.
+    /// This method is used to set the headers of the HTTP request.
     ///
     /// # Arguments
     ///
-    /// * `request` - An instance of `Request` which includes the HTTP method, URL, body, and headers.
+    /// * `headers` - A slice of tuples where each tuple represents a header. The first element of the tuple is the header name and the second element is the header value.
     ///
     /// # Returns
     ///
@@ -324,206 +1240,2323 @@ impl HttpClient {
     /// # Examples
     ///
     /// ```
-    /// let request = Request {
-    ///     method: "POST".to_string(),
-    ///     url: "http://example.com".to_string(),
-    ///     body: vec![],
-    ///     headers: Headers::new(&[("Content-Type", "application/json"), ("Accept", "*/*")]),
-    /// };
-    /// let http_client = HttpClient::new().request(request);
+    /// let http_client = HttpClient::new().post("http://example.com")
+    ///     .headers(&[("Content-Type", "application/json"), ("Accept", "*/*")]);
     /// ```
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn request(mut self, request: Request) -> Self {
-        self.method = Some(request.method);
-        self.url = Some(request.url);
-        self.body = request.body;
-        self.headers = Some(request.headers);
-
+    pub fn headers(mut self, headers: &[(&str, &str)]) -> Self {
+        self.headers = Some(Headers::new(headers));
         self
     }
 
-    /// Associates an `Entity` with the `HttpClient`.
+    /// This method is used to add a header to the HTTP request, keeping the headers set before.
     ///
-    /// This method is used to associate an `Entity` with the `HttpClient`. This can be useful when you want to track which entity initiated the HTTP request.
+    /// Adding a header twice sends both values, folded into one comma separated header where the
+    /// backend cannot send repeated headers.
     ///
-    /// # Parameters
+    /// # Arguments
     ///
-    /// * `entity`: The `Entity` that you want to associate with the `HttpClient`.
+    /// * `name` - The header name.
+    /// * `value` - The header value.
     ///
     /// # Returns
     ///
-    /// A mutable reference to the `HttpClient`. This is used to allow method chaining.
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
     ///
     /// # Examples
     ///
     /// ```
-    /// let entity = commands.spawn().id();
-    /// let http_client = HttpClient::new().entity(entity);
+    /// let http_client = HttpClient::new().get("http://example.com")
+    ///     .header("Accept-Language", "de")
+    ///     .header("Accept-Language", "en;q=0.5");
     /// ```
-    pub fn entity(mut self, entity: Entity) -> Self {
-        self.from_entity = Some(entity);
+    pub fn header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.headers
+            .get_or_insert_with(|| Headers::new(&[("Accept", "*/*")]))
+            .insert(name, value);
         self
     }
 
-    /// This method is used to set the properties of the `HttpClient` instance using an `Request` instance.
-    /// This version of the method is used when the target architecture is `wasm32`.
+    /// This method is used to set a typed header, replacing any value of it set before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com")
+    ///     .typed_header(&CacheControl { no_cache: true, ..default() });
+    /// ```
+    pub fn typed_header<H: TypedHeader>(mut self, header: &H) -> Self {
+        let headers = self
+            .headers
+            .get_or_insert_with(|| Headers::new(&[("Accept", "*/*")]));
+        headers
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(H::NAME));
+        headers.insert(H::NAME, header.encode());
+        self
+    }
+
+    /// Sets an `Authorization: Bearer <token>` header.
+    pub fn bearer_auth(self, token: impl ToString) -> Self {
+        self.typed_header(&Authorization::Bearer(token.to_string()))
+    }
+
+    /// Sets an `Authorization: Basic` header with the encoded credentials.
+    pub fn basic_auth(self, user: impl ToString, password: impl ToString) -> Self {
+        self.typed_header(&Authorization::Basic {
+            user: user.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Sets an `If-None-Match` header, the server answers `304 Not Modified` if `etag` is current.
+    pub fn if_none_match(self, etag: &ETag) -> Self {
+        self.header("If-None-Match", etag.encode())
+    }
+
+    /// This method is used to set the body of the HTTP request as a JSON payload.
+    /// It also sets the "Content-Type" header of the request to "application/json", unless it is already set.
     ///
     /// # Arguments
     ///
-    /// * `request` - An instance of `Request` which includes the HTTP method, URL, body, headers, and mode.
+    /// * `body` - A reference to any type that implements the `serde::Serialize` trait. This is the data that will be serialized to JSON and set as the body of the HTTP request.
     ///
     /// # Returns
     ///
     /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
     ///
+    /// # Panics
+    ///
+    /// * This method will panic if the serialization of the `body` to JSON fails.
+    ///
     /// # Examples
     ///
     /// ```
-    /// let request = Request {
-    ///     method: "POST".to_string(),
-    ///     url: "http://example.com".to_string(),
-    ///     body: vec![],
-    ///     headers: Headers::new(&[("Content-Type", "application/json"), ("Accept", "*/*")]),
-    ///     mode: Mode::Cors,
-    /// };
-    /// let http_client = HttpClient::new().request(request);
+    /// let http_client = HttpClient::new().post("http://example.com")
+    ///     .json(&data);
     /// ```
-    #[cfg(target_arch = "wasm32")]
-    pub fn request(mut self, request: Request) -> Self {
-        self.method = Some(request.method);
-        self.url = Some(request.url);
-        self.body = request.body;
-        self.headers = Some(request.headers);
-        self.mode = Some(request.mode);
-
-        self
+    pub fn json(self, body: &impl serde::Serialize) -> Self {
+        self.with_body(serde_json::to_vec(body).unwrap(), "application/json")
     }
 
-    /// Builds an `HttpRequest` from the `HttpClient` instance.
+    /// This method is used to set the body of the HTTP request as a JSON merge patch, the fields of
+    /// `new` that differ from `old`, so a partial update does not need a hand-written document.
+    /// It also sets the "Content-Type" header to "application/merge-patch+json", unless it is already set.
     ///
-    /// This method is used to construct an `HttpRequest` from the current state of the `HttpClient` instance. The resulting `HttpRequest` includes the HTTP method, URL, body, headers, and mode (only available on wasm builds).
+    /// Fields missing from `new`, or serialized as `null`, are removed by the patch: a merge patch
+    /// cannot set a field to `null`, use `json_patch` for that.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The value as the server has it.
+    /// * `new` - The value it should become.
     ///
     /// # Returns
     ///
-    /// An `HttpRequest` instance which includes the HTTP method, URL, body, headers, and mode (only available on wasm builds).
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
     ///
     /// # Panics
     ///
-    /// This method will panic if the HTTP method, URL, or headers are not set in the `HttpClient` instance.
+    /// * This method will panic if the serialization of `old` or `new` to JSON fails.
     ///
     /// # Examples
     ///
     /// ```
-    /// let http_request = HttpClient::new().post("http://example.com")
-    ///     .headers(&[("Content-Type", "application/json"), ("Accept", "*/*")])
-    ///     .json(&data)
-    ///     .build();
+    /// let http_client = HttpClient::new().patch("https://api.example.com/players/42")
+    ///     .merge_patch(&profile, &edited_profile);
     /// ```
+    pub fn merge_patch(self, old: &impl serde::Serialize, new: &impl serde::Serialize) -> Self {
+        let old = serde_json::to_value(old).unwrap();
+        let new = serde_json::to_value(new).unwrap();
+        let patch = patch::merge_patch(&old, &new);
+        self.with_body(
+            serde_json::to_vec(&patch).unwrap(),
+            "application/merge-patch+json",
+        )
+    }
+
+    /// This method is used to set the body of the HTTP request as a JSON patch, the `add`,
+    /// `remove` and `replace` operations turning `old` into `new`.
+    /// It also sets the "Content-Type" header to "application/json-patch+json", unless it is already set.
     ///
-    /// # Note
+    /// # Arguments
     ///
-    /// This method consumes the `HttpClient` instance, meaning it can only be called once per instance.
-    pub fn build(self) -> HttpRequest {
-        HttpRequest {
+    /// * `old` - The value as the server has it.
+    /// * `new` - The value it should become.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Panics
+    ///
+    /// * This method will panic if the serialization of `old` or `new` to JSON fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().patch("https://api.example.com/players/42")
+    ///     .json_patch(&profile, &edited_profile);
+    /// ```
+    pub fn json_patch(self, old: &impl serde::Serialize, new: &impl serde::Serialize) -> Self {
+        let old = serde_json::to_value(old).unwrap();
+        let new = serde_json::to_value(new).unwrap();
+        let patch = patch::json_patch(&old, &new);
+        self.with_body(
+            serde_json::to_vec(&patch).unwrap(),
+            "application/json-patch+json",
+        )
+    }
+
+    /// This method is used to set the body of the HTTP request as an url-encoded form.
+    /// It also sets the "Content-Type" header to "application/x-www-form-urlencoded", unless it is already set.
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - The name-value pairs of the form, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().post("http://example.com/login")
+    ///     .form(&[("user", "ferris"), ("password", "hunter2")]);
+    /// ```
+    pub fn form(self, fields: &[(&str, &str)]) -> Self {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish();
+        self.with_body(body, "application/x-www-form-urlencoded")
+    }
+
+    /// This method is used to set raw bytes as the body of the HTTP request.
+    /// It also sets the "Content-Type" header to "application/octet-stream", unless it is already set.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The bytes sent as the body.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().put("http://example.com/saves/1")
+    ///     .bytes(save_data);
+    /// ```
+    pub fn bytes(self, body: impl Into<Vec<u8>>) -> Self {
+        self.with_body(body, "application/octet-stream")
+    }
+
+    /// Sends the body from `body` while the request is sent instead of from memory, e.g. to upload
+    /// a large file. It also sets the "Content-Type" header to "application/octet-stream", unless
+    /// it is already set. See [`StreamingBody`]. Only available on native builds.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The source the body is read from.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().put("https://replays.example.com/upload/42")
+    ///     .streaming_body(StreamingBody::file("replays/42.replay"));
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn streaming_body(mut self, body: StreamingBody) -> Self {
+        self.streaming_body = Some(body);
+        self.with_body(Vec::new(), "application/octet-stream")
+    }
+
+    /// Sets the body, and the content type unless a `Content-Type` header was set before.
+    fn with_body(mut self, body: impl Into<Vec<u8>>, content_type: &str) -> Self {
+        let headers = self
+            .headers
+            .get_or_insert_with(|| Headers::new(&[("Accept", "*/*")]));
+        if headers.get("Content-Type").is_none() {
+            headers.insert("Content-Type", content_type);
+        }
+        self.body = body.into();
+        self
+    }
+
+    /// This method is used to set the properties of the `HttpClient` instance using an `Request` instance.
+    /// This version of the method is used when the target architecture is not `wasm32`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - An instance of `Request` which includes the HTTP method, URL, body, and headers.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = Request {
+    ///     method: "POST".to_string(),
+    ///     url: "http://example.com".to_string(),
+    ///     body: vec![],
+    ///     headers: Headers::new(&[("Content-Type", "application/json"), ("Accept", "*/*")]),
+    /// };
+    /// let http_client = HttpClient::new().request(request);
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn request(mut self, request: Request) -> Self {
+        self.method = Some(request.method);
+        self.url = Some(request.url);
+        self.body = request.body;
+        self.headers = Some(request.headers);
+
+        self
+    }
+
+    /// Associates an `Entity` with the `HttpClient`.
+    ///
+    /// This method is used to associate an `Entity` with the `HttpClient`. This can be useful when you want to track which entity initiated the HTTP request.
+    ///
+    /// # Parameters
+    ///
+    /// * `entity`: The `Entity` that you want to associate with the `HttpClient`.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the `HttpClient`. This is used to allow method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let entity = commands.spawn().id();
+    /// let http_client = HttpClient::new().entity(entity);
+    /// ```
+    pub fn entity(mut self, entity: Entity) -> Self {
+        self.from_entity = Some(entity);
+        self
+    }
+
+    /// Spawns the request entity as a child of `owner`.
+    ///
+    /// In-flight requests can be found through the owner's `Children`, and the request is aborted
+    /// when the owner is despawned recursively, e.g. when the player logs out or a UI screen closes.
+    /// A queued request whose owner is gone is dropped without being sent. Ignored when the request
+    /// is sent on an existing entity with `entity`.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The entity the request belongs to.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com").child_of(player);
+    /// ```
+    pub fn child_of(mut self, owner: Entity) -> Self {
+        self.parent = Some(owner);
+        self
+    }
+
+    /// Inserts the result on `target` instead of sending it as an event.
+    ///
+    /// The target receives an `HttpResponse` or `HttpResponseError` component, or a `TypedResponse<T>`
+    /// for typed requests, replacing the result of an earlier request. Use `Added` or `Changed` filters
+    /// to react to it. The result is dropped if the target is gone by the time it arrives.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The entity the result is inserted on, e.g. a UI node or the player entity.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com/profile").respond_to(profile_panel);
+    /// ```
+    pub fn respond_to(mut self, target: Entity) -> Self {
+        self.respond_to = Some(target);
+        self
+    }
+
+    /// Tags the request, so [`HttpStats`] counts its traffic, failures and latency under `label`.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The feature the request belongs to, e.g. `"leaderboard"`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com/scores").label("leaderboard");
+    /// ```
+    pub fn label(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.label = Some(RequestLabel::new(label));
+        self
+    }
+
+    /// Runs `system` once with the result, instead of sending it as an event or inserting it on `respond_to`.
+    ///
+    /// Meant for simple cases where a dedicated handler system is overkill. Aborts and timeouts are
+    /// passed to the system as an error too. Use `TypedRequest::on_complete` for typed results.
+    ///
+    /// # Arguments
+    ///
+    /// * `system` - A system taking the `Result<HttpResponse, HttpResponseError>` as its `In` parameter.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .get("http://example.com/motd")
+    ///     .on_complete(|In(result): In<Result<HttpResponse, HttpResponseError>>, mut commands: Commands| {
+    ///         if let Ok(response) = result {
+    ///             commands.insert_resource(Motd(response.text().unwrap_or_default().to_string()));
+    ///         }
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn on_complete<M>(
+        mut self,
+        system: impl IntoSystem<Result<HttpResponse, HttpResponseError>, (), M>,
+    ) -> Self {
+        let system = IntoSystem::into_system(system);
+        self.on_complete = Some(OnComplete::new(move |world, request_id, response| {
+            let result = response
+                .map(|res| HttpResponse::new(request_id, res))
+                .map_err(|e| HttpResponseError::new(request_id, e));
+            world.run_system_once_with(result, system);
+        }));
+        self
+    }
+
+    /// This method is used to set the properties of the `HttpClient` instance using an `Request` instance.
+    /// This version of the method is used when the target architecture is `wasm32`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - An instance of `Request` which includes the HTTP method, URL, body, headers, and mode.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = Request {
+    ///     method: "POST".to_string(),
+    ///     url: "http://example.com".to_string(),
+    ///     body: vec![],
+    ///     headers: Headers::new(&[("Content-Type", "application/json"), ("Accept", "*/*")]),
+    ///     mode: Mode::Cors,
+    /// };
+    /// let http_client = HttpClient::new().request(request);
+    /// ```
+    #[cfg(target_arch = "wasm32")]
+    pub fn request(mut self, request: Request) -> Self {
+        self.method = Some(request.method);
+        self.url = Some(request.url);
+        self.body = request.body;
+        self.headers = Some(request.headers);
+        self.mode = Some(request.mode);
+
+        self
+    }
+
+    /// Sets how long the request may take, overriding the timeout of the active [`Environment`] and
+    /// the plugin's `default_timeout`.
+    ///
+    /// A request that takes longer is dropped and ends with an `HttpResponseError`. Native builds
+    /// give ureq the timeout as well, so the connection is closed rather than left waiting.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The time from dispatching the request until the full response has arrived.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com").timeout(Duration::from_secs(5));
+    /// ```
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Lets the request run however long it takes, ignoring the timeout of the active
+    /// [`Environment`] and the plugin's `default_timeout`, e.g. for a live stream. A `Deadline`
+    /// still applies.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com/feed").streaming().no_timeout();
+    /// ```
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self.no_timeout = true;
+        self
+    }
+
+    /// Appends `value` to the path of the url as one segment, percent-encoding everything but letters,
+    /// digits and `-._~`.
+    ///
+    /// Meant for user supplied values such as player names, a `/` or `?` in the value stays part of
+    /// the segment. Unlike query encoding spaces become `%20`, not `+`. Segments that are exactly `.`
+    /// or `..` are still resolved as dot segments by url parsers, so check for them first.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The segment, appended after a `/`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // https://api.example.com/players/J%C3%BCrgen%20%2F%20%F0%9F%98%80/stats
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/players")
+    ///     .path_segment("Jürgen / 😀")
+    ///     .path_segment("stats");
+    /// ```
+    pub fn path_segment(mut self, value: impl AsRef<str>) -> Self {
+        let url = self.url.take().unwrap_or_default();
+        self.url = Some(urls::push_path_segment(&url, value.as_ref()));
+        self
+    }
+
+    /// Appends the fields of `query` to the query string of the url, e.g. the filters of a search.
+    ///
+    /// `None` fields are left out, sequences such as a `Vec` become one parameter per item,
+    /// `tag=a&tag=b`, see `query_struct_with` for comma-separated items. The parameters are
+    /// sorted by name and appended after any query the url already has, before its fragment.
+    /// Fields are read like JSON, so `NaN` and infinite floats are left out like `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - A struct or map whose fields are strings, numbers, booleans, options or sequences of them.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Panics
+    ///
+    /// * This method will panic if `query` does not serialize to a struct or map of such fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Serialize)]
+    /// struct Search {
+    ///     name: String,
+    ///     region: Option<String>,
+    ///     tag: Vec<String>,
+    /// }
+    ///
+    /// // https://api.example.com/servers?name=my+server&tag=pvp&tag=eu
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/servers")
+    ///     .query_struct(&Search { name: "my server".into(), region: None, tag: vec!["pvp".into(), "eu".into()] });
+    /// ```
+    pub fn query_struct(self, query: &impl serde::Serialize) -> Self {
+        self.query_struct_with(query, QueryArrays::Repeat)
+    }
+
+    /// Appends the fields of `query` to the query string of the url like `query_struct`, encoding
+    /// sequences as `arrays` says.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - A struct or map whose fields are strings, numbers, booleans, options or sequences of them.
+    /// * `arrays` - How sequences are encoded, e.g. `QueryArrays::Comma` for `tag=pvp,eu`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Panics
+    ///
+    /// * This method will panic if `query` does not serialize to a struct or map of such fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // https://api.example.com/servers?name=my+server&tag=pvp,eu
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/servers")
+    ///     .query_struct_with(&search, QueryArrays::Comma);
+    /// ```
+    pub fn query_struct_with(mut self, query: &impl serde::Serialize, arrays: QueryArrays) -> Self {
+        let params = urls::encode_query(query, arrays).unwrap();
+        let url = self.url.take().unwrap_or_default();
+        self.url = Some(urls::append_query(&url, &params));
+        self
+    }
+
+    /// Sets the url the request url is joined onto, see [`join_url`] for how paths are joined.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The url of the api, e.g. `https://api.example.com/v1`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // https://api.example.com/v1/players/42
+    /// let http_client = HttpClient::new()
+    ///     .base_url("https://api.example.com/v1")
+    ///     .get("/players/42");
+    /// ```
+    pub fn base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Retries the request against the next mirror when it fails, before reporting the failure.
+    ///
+    /// Connection errors, timeouts and 5xx responses move on to the next url, the `url` of the
+    /// response tells which one answered. See [`FallbackUrls`].
+    ///
+    /// # Arguments
+    ///
+    /// * `urls` - The mirrors to try after the primary url, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://cdn1.example.com/patch.bin")
+    ///     .fallback_urls(["https://cdn2.example.com/patch.bin"]);
+    /// ```
+    pub fn fallback_urls(mut self, urls: impl IntoIterator<Item = impl ToString>) -> Self {
+        self.fallback_urls = Some(FallbackUrls(
+            urls.into_iter().map(|url| url.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// Sends the request again when it fails, before reporting the failure.
+    ///
+    /// Connection errors, timeouts, 5xx, `408` and `429` responses are retried with exponential
+    /// backoff, see [`RetryPolicy`]. Overrides the retries of the active [`Environment`] and the
+    /// plugin's `default_retry`, `RetryPolicy::new(0)` turns them off for this request.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - How often to retry and how long to wait in between.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/inventory")
+    ///     .retry(RetryPolicy::new(3));
+    /// ```
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Sets how many redirects are followed before the request fails, overriding the limit of the
+    /// active [`Environment`] and the plugin's `max_redirects`.
+    ///
+    /// Browsers follow redirects on their own on wasm builds, where this has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_redirects` - The redirects to follow, `0` to fail on the first one.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://cdn.example.com/latest/manifest.json")
+    ///     .max_redirects(10);
+    /// ```
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = Some(max_redirects);
+        self
+    }
+
+    /// Caps how many requests run at once to the host of the request while it waits, overriding
+    /// the limit of the host in `HttpClientSettings::host_limits`. Other requests to the host keep
+    /// their own limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The requests to the host that may run at once, this one included.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Waits until at most one other request to the host is running.
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/player/export")
+    ///     .host_limit(2);
+    /// ```
+    pub fn host_limit(mut self, limit: usize) -> Self {
+        self.host_limit = Some(limit);
+        self
+    }
+
+    /// Sets how urgent the request is, while every client is busy waiting requests with a higher
+    /// priority are dispatched first, see [`PriorityAging`] to keep low priorities from starving.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - The priority, `RequestPriority::NORMAL` unless set.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://cdn.example.com/dlc/maps.pak")
+    ///     .priority(RequestPriority::LOW);
+    /// ```
+    pub fn priority(mut self, priority: RequestPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Rewrites a successful response in the request task before it is delivered or deserialized,
+    /// after the [`ResponseTransforms`] of its label and type. Can be called more than once.
+    ///
+    /// # Arguments
+    ///
+    /// * `transform` - Returns the new response, or an error to fail the request with.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/leaderboard")
+    ///     .transform_response(ResponseTransform::json_pointer("/data/entries"));
+    /// ```
+    pub fn transform_response(mut self, transform: ResponseTransform) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Fails the request at `deadline`, counting the time it waits in the queue and for retries.
+    ///
+    /// Overrides the [`Deadline`] of the entity passed to `entity`. Retries that would start after
+    /// the deadline are not sent, the request fails with a `HttpErrorKind::DeadlineExceeded` error.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - The instant the request must have finished by, on the [`HttpClock`].
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .post("https://api.example.com/matchmaking/join")
+    ///     .retry(RetryPolicy::new(5))
+    ///     .deadline(clock.now() + Duration::from_secs(10));
+    /// ```
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(Deadline(deadline));
+        self
+    }
+
+    /// Keeps the response in the [`ResponseCache`] under `key` instead of the url, e.g. to cache a
+    /// POST query or to invalidate related entries with `ResponseCache::invalidate_prefix`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The cache key, e.g. `inventory/items`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .post("https://api.example.com/graphql")
+    ///     .json(&leaderboard_query)
+    ///     .cache_key("leaderboard/weekly");
+    /// ```
+    pub fn cache_key(mut self, key: impl ToString) -> Self {
+        self.caching.key = Some(key.to_string());
+        self
+    }
+
+    /// Keeps the response in the [`ResponseCache`] for `ttl` if it has no freshness headers,
+    /// overriding `ResponseCache::default_ttl`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl` - How long the response is fresh.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/news")
+    ///     .cache_ttl(Duration::from_secs(600));
+    /// ```
+    pub fn cache_ttl(mut self, ttl: Duration) -> Self {
+        self.caching.ttl = Some(ttl);
+        self
+    }
+
+    /// Keeps the response in the `namespace` of the [`ResponseCache`], apart from the responses of
+    /// the same key in other namespaces, e.g. those of other players on the same device.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace, e.g. the id of the player.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/me/inventory")
+    ///     .bearer_auth(&player.token)
+    ///     .cache_namespace(&player.id);
+    /// ```
+    pub fn cache_namespace(mut self, namespace: impl ToString) -> Self {
+        self.caching.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Answers the request right away with the stale response in the [`ResponseCache`] once the
+    /// cached response went stale, and sends it anyway to deliver the fresh response after it, e.g.
+    /// for shop and news screens that should show up instantly.
+    ///
+    /// The stale response has `HttpResponse::stale` set, or `TypedResponse::is_stale` for typed
+    /// requests. Fresh cached responses are delivered once, as usual. Requests with an `on_complete`
+    /// callback or a handle only get the fresh response.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/store/offers")
+    ///     .stale_while_revalidate();
+    /// ```
+    pub fn stale_while_revalidate(mut self) -> Self {
+        self.caching.stale_while_revalidate = true;
+        self
+    }
+
+    /// Sends the request and receives its response without the [`PayloadEncryption`], e.g. for the
+    /// login that establishes the session key.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .post("https://api.example.com/login")
+    ///     .json(&credentials)
+    ///     .plaintext();
+    /// ```
+    pub fn plaintext(mut self) -> Self {
+        self.plaintext = true;
+        self
+    }
+
+    /// Fails the request unless its 2xx response carries a signature the [`ResponseSignatures`]
+    /// verify, e.g. for manifests and configs that must not be tampered with.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://cdn.example.com/dlc/manifest.json")
+    ///     .verify_signature();
+    /// ```
+    pub fn verify_signature(mut self) -> Self {
+        self.verify_signature = true;
+        self
+    }
+
+    /// Marks the request as a large transfer, deferred while the [`NetworkPolicy`] reports a
+    /// metered connection until the player allows large transfers, e.g. for background downloads.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://cdn.example.com/dlc/forest.pak")
+    ///     .large_transfer();
+    /// ```
+    pub fn large_transfer(mut self) -> Self {
+        self.large_transfer = true;
+        self
+    }
+
+    /// Passes options the crate does not wrap through to the backend the request is sent with. See
+    /// [`BackendOptions`].
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The hooks into the ureq request on native builds, or the fetch on wasm builds.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .get("https://api.example.com/matchmaking")
+    ///     .backend_options(BackendOptions::new().with_ureq_request(|request| {
+    ///         request.timeout_read(Duration::from_secs(2))
+    ///     }));
+    /// ```
+    pub fn backend_options(mut self, options: BackendOptions) -> Self {
+        self.backend = Some(options);
+        self
+    }
+
+    /// Keeps the request in the store of the [`QueuePersistencePlugin`] while it waits in the queue,
+    /// so it is sent in the next session if the game exits or crashes before it went out.
+    ///
+    /// Only the ehttp request, label and timeout are kept. A restored request is sent without its
+    /// entities or `on_complete`, its result arrives as an `HttpResponse` or `HttpResponseError`
+    /// event. Meant for telemetry and confirmations that must not be lost.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .post("https://shop.example.com/confirm")
+    ///     .json(&receipt)
+    ///     .persist();
+    /// ```
+    pub fn persist(mut self) -> Self {
+        self.persist = true;
+        self
+    }
+
+    /// Keeps the request from being sent while another request with the same key is in flight, so
+    /// a button pressed twice sends one purchase. See [`SingleFlight`].
+    ///
+    /// # Arguments
+    ///
+    /// * `single_flight` - The key shared with the duplicates, and whether they fail or share the result.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new()
+    ///     .post("https://shop.example.com/purchase")
+    ///     .json(&purchase)
+    ///     .single_flight(SingleFlight::new("purchase").coalesce());
+    /// ```
+    pub fn single_flight(mut self, single_flight: SingleFlight) -> Self {
+        self.single_flight = Some(single_flight);
+        self
+    }
+
+    /// Reports download progress with `HttpProgress` events while the body is read.
+    ///
+    /// The full body is still delivered with the response.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com/level.bin").progress();
+    /// ```
+    pub fn progress(mut self) -> Self {
+        self.body_mode = BodyMode::Progress;
+        self
+    }
+
+    /// Delivers the body as `HttpResponseChunk` events while it downloads, together with `HttpProgress` events.
+    ///
+    /// The final `HttpResponse` has an empty body, so this is not meant for typed requests.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com/replay.bin").streaming();
+    /// ```
+    pub fn streaming(mut self) -> Self {
+        self.body_mode = BodyMode::Streaming;
+        self
+    }
+
+    /// Sets the fetch `mode` of the request. Only available on wasm builds.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - Whether cross-origin requests are allowed, see [`Mode`]. Defaults to `Mode::Cors`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com").mode(Mode::NoCors);
+    /// ```
+    #[cfg(target_arch = "wasm32")]
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets the fetch `credentials` of the request. Only available on wasm builds.
+    ///
+    /// # Arguments
+    ///
+    /// * `credentials` - Whether the browser sends cookies and HTTP auth, see [`Credentials`].
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com").credentials(Credentials::Include);
+    /// ```
+    #[cfg(target_arch = "wasm32")]
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.fetch_options.credentials = Some(credentials);
+        self
+    }
+
+    /// Sets the fetch `cache` mode of the request. Only available on wasm builds.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - How the request uses the browser HTTP cache, see [`CacheMode`].
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com").cache(CacheMode::NoStore);
+    /// ```
+    #[cfg(target_arch = "wasm32")]
+    pub fn cache(mut self, cache: CacheMode) -> Self {
+        self.fetch_options.cache = Some(cache);
+        self
+    }
+
+    /// Sets the fetch `referrerPolicy` of the request. Only available on wasm builds.
+    ///
+    /// # Arguments
+    ///
+    /// * `referrer_policy` - Which referrer information is sent, see [`ReferrerPolicy`].
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the instance of the `HttpClient` struct, allowing for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_client = HttpClient::new().get("http://example.com")
+    ///     .referrer_policy(ReferrerPolicy::NoReferrer);
+    /// ```
+    #[cfg(target_arch = "wasm32")]
+    pub fn referrer_policy(mut self, referrer_policy: ReferrerPolicy) -> Self {
+        self.fetch_options.referrer_policy = Some(referrer_policy);
+        self
+    }
+
+    /// Builds an `HttpRequest` from the `HttpClient` instance.
+    ///
+    /// This method is used to construct an `HttpRequest` from the current state of the `HttpClient` instance. The resulting `HttpRequest` includes the HTTP method, URL, body, headers, and mode (only available on wasm builds).
+    ///
+    /// # Returns
+    ///
+    /// An `HttpRequest` instance which includes the HTTP method, URL, body, headers, and mode (only available on wasm builds).
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the HTTP method, URL, or headers are not set in the `HttpClient` instance.
+    /// The fetch mode defaults to `Mode::Cors` when it is not set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let http_request = HttpClient::new().post("http://example.com")
+    ///     .headers(&[("Content-Type", "application/json"), ("Accept", "*/*")])
+    ///     .json(&data)
+    ///     .build();
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// This method consumes the `HttpClient` instance, meaning it can only be called once per instance.
+    pub fn build(self) -> HttpRequest {
+        let method = self.method.expect("method is required");
+        let url = self.url.expect("url is required");
+        // Invalid urls are sent as they are and fail with the error of the backend, `try_build`
+        // reports them up front.
+        let url = match &self.base_url {
+            Some(base_url) => join_url(base_url, &url).unwrap_or_else(|_| {
+                format!(
+                    "{}/{}",
+                    base_url.trim_end_matches('/'),
+                    url.trim_start_matches('/')
+                )
+            }),
+            None => urls::normalize(&url).unwrap_or(url),
+        };
+        // A HEAD request asks for the headers of a GET, it cannot carry a body.
+        let body = if method.eq_ignore_ascii_case("HEAD") {
+            if cfg!(debug_assertions) && !self.body.is_empty() {
+                warn!("HEAD request to {url} has a body, it is dropped");
+            }
+            vec![]
+        } else {
+            self.body
+        };
+        if cfg!(debug_assertions) && !body.is_empty() && method.eq_ignore_ascii_case("GET") {
+            warn!("GET request to {url} has a body, many servers and proxies reject or drop it");
+        }
+        // The transport sets `Content-Length` from the body it sends, which may still be rewritten
+        // at dispatch, e.g. encrypted.
+        let headers = self.headers.expect("headers is required");
+        HttpRequest {
+            id: RequestId::new(),
             from_entity: self.from_entity,
+            parent: self.parent,
+            respond_to: self.respond_to,
+            on_complete: self.on_complete,
+            label: self.label,
             request: Request {
-                method: self.method.expect("method is required"),
-                url: self.url.expect("url is required"),
-                body: self.body,
-                headers: self.headers.expect("headers is required"),
+                method,
+                url,
+                body,
+                headers,
                 #[cfg(target_arch = "wasm32")]
-                mode: self.mode.expect("mode is required"),
+                mode: self.mode.unwrap_or_default(),
             },
+            body_mode: self.body_mode,
+            timeout: self.timeout,
+            no_timeout: self.no_timeout,
+            fallback_urls: self.fallback_urls,
+            retry: self.retry,
+            deadline: self.deadline,
+            caching: self.caching,
+            priority: self.priority,
+            queued_at: None,
+            transforms: self.transforms,
+            plaintext: self.plaintext,
+            verify_signature: self.verify_signature,
+            large_transfer: self.large_transfer,
+            backend: self.backend,
+            #[cfg(not(target_arch = "wasm32"))]
+            streaming_body: self.streaming_body,
+            persist: self.persist,
+            single_flight: self.single_flight,
+            max_redirects: self.max_redirects,
+            private_headers: vec![],
+            host_limit: self.host_limit,
+            signer: None,
+            #[cfg(target_arch = "wasm32")]
+            fetch_options: self.fetch_options,
         }
     }
 
+    /// Builds the request like `build`, but fails instead of panicking when the method or url are
+    /// missing, and fails on invalid methods and on urls that are not absolute http or https urls
+    /// with a host, or `unix://` urls.
+    ///
+    /// The url is normalized: the scheme and host are lowercased, `.` and `..` segments resolved and
+    /// characters not allowed in urls percent-encoded. On wasm builds urls without a scheme are
+    /// resolved against the page by the browser.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// match HttpClient::new().get(&typed_in_url).try_build() {
+    ///     Ok(request) => {
+    ///         ev_request.send(request);
+    ///     }
+    ///     Err(e) => println!("{e}"),
+    /// }
+    /// ```
+    pub fn try_build(mut self) -> Result<HttpRequest, HttpBuildError> {
+        method::check_method(
+            self.method
+                .as_deref()
+                .ok_or(HttpBuildError::MissingMethod)?,
+        )?;
+        let url = self.url.take().ok_or(HttpBuildError::MissingUrl)?;
+        self.url = Some(match self.base_url.take() {
+            Some(base_url) => join_url(&base_url, &url)?,
+            None => urls::normalize(&url)?,
+        });
+        Ok(self.build())
+    }
+
+    /// Builds the request together with a handle resolving to its result, see [`RequestHandle`].
+    ///
+    /// Replaces any `on_complete` callback.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (request, handle) = HttpClient::new().get("http://example.com").build_with_handle();
+    /// ```
+    pub fn build_with_handle(self) -> (HttpRequest, RequestHandle) {
+        let mut request = self.build();
+        let (sender, handle) = RequestHandle::new(request.id);
+        let on_dispatch = sender.on_dispatch();
+        request.on_complete = Some(
+            OnComplete::new(move |_, request_id, response| {
+                let status_code = response.as_ref().ok().map(|res| res.status);
+                sender.finish(
+                    status_code,
+                    response
+                        .map(|res| HttpResponse::new(request_id, res))
+                        .map_err(|e| HttpResponseError::new(request_id, e)),
+                );
+            })
+            .with_on_dispatch(on_dispatch),
+        );
+        (request, handle)
+    }
+
+    /// Builds the request for the [`DurableDeliveryPlugin`], which retries it until it is delivered.
+    ///
+    /// Only the method, url, headers and body are kept, callbacks and targets are not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let send = HttpClient::new().post("http://example.com/confirm").json(&receipt).build_durable();
+    /// ```
+    pub fn build_durable(self) -> SendDurable {
+        SendDurable::new(self.build().request)
+    }
+
+    /// Queues many requests at once, e.g. a burst of telemetry pings, with a single command
+    /// instead of one `HttpRequest` event each.
+    ///
+    /// The requests are moved into the queue without being cloned, and the resources queueing
+    /// looks up are fetched once for the whole batch. They are dispatched, cached, retried and
+    /// delivered like requests sent as events, only `max_concurrent` of them run at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - The requests to queue, in the order they should be dispatched.
+    ///
+    /// # Returns
+    ///
+    /// * `SendBatch` - The command queueing the requests, add it with `Commands::add`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fn send_pings(mut commands: Commands, pings: Res<PendingPings>) {
+    ///     commands.add(HttpClient::send_batch(pings.iter().map(|ping| {
+    ///         HttpClient::new()
+    ///             .post("https://telemetry.example.com/ping")
+    ///             .json(ping)
+    ///             .build()
+    ///     })));
+    /// }
+    /// ```
+    pub fn send_batch(requests: impl IntoIterator<Item = HttpRequest>) -> SendBatch {
+        SendBatch::new(requests)
+    }
+
     pub fn with_type<T: for<'a> serde::Deserialize<'a>>(self) -> TypedRequest<T> {
-        TypedRequest::new(
-            Request {
-                method: self.method.expect("method is required"),
-                url: self.url.expect("url is required"),
-                body: self.body,
-                headers: self.headers.expect("headers is required"),
-                #[cfg(target_arch = "wasm32")]
-                mode: self.mode.expect("mode is required"),
-            },
-            self.from_entity,
-        )
+        TypedRequest::from(self.build())
+    }
+}
+
+/// A finished response.
+///
+/// The body is a shared [`Bytes`] buffer, cloning or slicing the response doesn't copy it.
+///
+/// Responses are events, they are dropped after two frames like any other event. The entity spawned
+/// for a request without `HttpClient::entity` is despawned once its result is delivered, an entity
+/// passed to `HttpClient::entity` only loses its `RequestTask`. Responses inserted on entities with
+/// `HttpClient::respond_to` stay until removed, or as long as a [`ResponseCleanup`] keeps them.
+#[derive(Event, Component, Debug, Clone)]
+pub struct HttpResponse {
+    /// The request this is the response to.
+    pub request_id: RequestId,
+    /// The URL we ended up at. This can differ from the request url when we have followed redirects.
+    pub url: String,
+    /// Did we get a 2xx response code?
+    pub ok: bool,
+    /// Status code (e.g. `404` for "File not found").
+    pub status: u16,
+    /// Status text (e.g. "File not found" for status code `404`).
+    pub status_text: String,
+    /// The returned headers. Repeated headers such as `Set-Cookie` keep every value, see
+    /// `Headers::get_all`, browsers join them into one comma separated value.
+    pub headers: Headers,
+    /// The raw bytes of the response body.
+    pub bytes: Bytes,
+    /// Served from the [`ResponseCache`] after it went stale, the fresh response follows, see
+    /// `HttpClient::stale_while_revalidate`.
+    pub stale: bool,
+}
+
+impl HttpResponse {
+    /// Did we get a 2xx response code?
+    pub fn is_success(&self) -> bool {
+        self.ok
+    }
+
+    /// The status code, to compare against the `StatusCode` constants.
+    pub fn status(&self) -> StatusCode {
+        StatusCode(self.status)
+    }
+
+    /// The first value of the header, looked up case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    /// Every value of the header in order, looked up case-insensitively.
+    pub fn header_values(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.headers.get_all(name)
+    }
+
+    /// The header parsed as `H`, `None` if it is missing or malformed.
+    pub fn typed_header<H: TypedHeader>(&self) -> Option<H> {
+        self.header(H::NAME).and_then(H::parse)
+    }
+
+    /// The `ETag` header, to send back with `HttpClient::if_none_match`.
+    pub fn etag(&self) -> Option<ETag> {
+        self.typed_header()
+    }
+
+    /// The `Cache-Control` header with its directives parsed.
+    pub fn cache_control(&self) -> Option<CacheControl> {
+        self.typed_header()
+    }
+
+    /// the body as utf-8 text
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.bytes).ok()
+    }
+
+    /// The body as utf-8 text, with invalid sequences replaced by `U+FFFD`.
+    pub fn text_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.bytes)
+    }
+
+    /// Convenience for getting json body
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.bytes)
+    }
+
+    /// Deserializes a json body that borrows from the response, e.g. `&str` fields, without allocating copies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(Deserialize)]
+    /// struct Entry<'a> {
+    ///     name: &'a str,
+    /// }
+    ///
+    /// for response in ev_response.read() {
+    ///     let entries: Vec<Entry> = response.json_borrowed()?;
+    /// }
+    /// ```
+    pub fn json_borrowed<'de, T: serde::Deserialize<'de>>(&'de self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.bytes)
+    }
+
+    /// Convenience for getting the `content-type` header.
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("content-type")
+    }
+
+    /// The class of the status code, to match on categories of failures.
+    pub fn status_class(&self) -> HttpStatusClass {
+        HttpStatusClass::from(self.status)
     }
 }
 
-/// wrap for ehttp response
-#[derive(Event, Debug, Clone, Deref)]
-pub struct HttpResponse(pub Response);
+impl HttpResponse {
+    pub(crate) fn new(request_id: RequestId, response: Response) -> Self {
+        Self {
+            request_id,
+            url: response.url,
+            ok: response.ok,
+            status: response.status,
+            status_text: response.status_text,
+            headers: response.headers,
+            bytes: Bytes::from(response.bytes),
+            stale: false,
+        }
+    }
+}
 
 /// wrap for ehttp error
-#[derive(Event, Debug, Clone, Deref)]
-pub struct HttpResponseError(pub String);
+///
+/// Derefs to the message of the backend, `kind` tells what failed.
+#[derive(Event, Component, Debug, Clone, Deref)]
+pub struct HttpResponseError {
+    /// The request that failed.
+    pub request_id: RequestId,
+    #[deref]
+    pub message: String,
+    pub kind: HttpErrorKind,
+}
+
+impl HttpResponseError {
+    /// create an error for the request, classifying the message
+    pub fn new(request_id: RequestId, message: impl ToString) -> Self {
+        let message = message.to_string();
+        Self {
+            request_id,
+            kind: HttpErrorKind::classify(&message),
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for HttpResponseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for HttpResponseError {}
+
+/// Inserts a result on the request's `respond_to` target, or sends it as an event if it has none.
+pub(crate) fn deliver<T: Event + Component>(
+    world: &mut World,
+    respond_to: Option<Entity>,
+    result: T,
+) {
+    match respond_to {
+        Some(target) => {
+            if let Some(mut entity) = world.get_entity_mut(target) {
+                entity.insert(result);
+                cleanup::track::<T>(world, target);
+            }
+        }
+        None => {
+            world.send_event(result);
+        }
+    }
+}
+
+/// Aborts the queued or in-flight requests attached to the entity.
+///
+/// The request ends with an `HttpResponseError` instead of a response. On native the
+/// result is discarded once it arrives, on wasm the browser fetch is cancelled through
+/// its `AbortController`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AbortRequest(pub Entity);
+
+/// Aborts a single queued or in-flight request like `AbortRequest`, leaving the other requests of
+/// its entity alone.
+#[derive(Event, Debug, Clone, Copy)]
+pub(crate) struct AbortRequestId(pub(crate) RequestId);
+
+/// Despawns an entity passed to `HttpClient::entity` once its request delivered a response or error.
+///
+/// Entities spawned for requests without `HttpClient::entity` are always despawned.
+///
+/// # Examples
+///
+/// ```
+/// let entity = commands.spawn((DespawnOnResponse, Name::new("telemetry"))).id();
+/// ev_request.send(HttpClient::new().post("http://example.com").entity(entity).build());
+/// ```
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct DespawnOnResponse;
 
 /// task for ehttp response result
+///
+/// Dropping the task cancels the request.
 #[derive(Component)]
-pub struct RequestTask(pub Task<CommandQueue>);
+pub struct RequestTask {
+    request_id: RequestId,
+    #[cfg(not(target_arch = "wasm32"))]
+    task: Task<ehttp::Result<Response>>,
+    /// On wasm the task pool cannot hand back a pollable task, so the result arrives through a channel instead.
+    #[cfg(target_arch = "wasm32")]
+    result: async_channel::Receiver<ehttp::Result<Response>>,
+    /// Closed when the task is dropped, which aborts the browser fetch.
+    #[cfg(target_arch = "wasm32")]
+    _abort: async_channel::Sender<()>,
+    /// Body progress and chunks, for requests that read the body incrementally.
+    parts: Option<async_channel::Receiver<BodyPart>>,
+    /// The response metadata, once the headers arrived.
+    head: async_channel::Receiver<ResponseHead>,
+    /// Written by the transport once the body was read.
+    #[cfg(not(target_arch = "wasm32"))]
+    phases: Arc<Mutex<Phases>>,
+    /// When the request fails with a timeout or deadline error, and that error.
+    deadline: Option<(Instant, &'static str)>,
+    /// Whether the entity was spawned for this request and is despawned with it.
+    owns_entity: bool,
+    /// Taken once the request finished and its result waits in `FinishedRequests`.
+    on_response: Option<ResponseHandler>,
+    label: Option<RequestLabel>,
+    /// The host of the request url, for the metrics.
+    host: String,
+    dispatched_at: Instant,
+    /// Body bytes received so far, for requests that read the body incrementally.
+    received: u64,
+    /// How long the result is held back, see `NetworkSimulationPlugin`.
+    delay: Duration,
+    /// The result and when it is released, while it is held back.
+    held: Option<(Instant, ehttp::Result<Response>)>,
+}
+
+impl RequestTask {
+    /// Whether the request still occupies a client.
+    pub(crate) fn in_flight(&self) -> bool {
+        self.on_response.is_some()
+    }
+
+    /// Returns the result without blocking, if the request is done and its delay passed.
+    fn poll(&mut self, now: Instant) -> Option<ehttp::Result<Response>> {
+        if self.held.is_none() {
+            #[cfg(not(target_arch = "wasm32"))]
+            let result = block_on(poll_once(&mut self.task))?;
+            #[cfg(target_arch = "wasm32")]
+            let result = self.result.try_recv().ok()?;
+            self.held = Some((now + self.delay, result));
+        }
+        match self.held.take()? {
+            (release_at, result) if now < release_at => {
+                self.held = Some((release_at, result));
+                None
+            }
+            (_, result) => Some(result),
+        }
+    }
+}
+
+/// Called with exclusive world access once a request finished, before the task is removed from the entity.
+pub(crate) type ResponseHandler =
+    Box<dyn FnOnce(&mut World, RequestId, ehttp::Result<Response>) + Send + Sync>;
+
+/// A request that finished, waiting for its result to be delivered.
+pub(crate) struct FinishedRequest {
+    request_id: RequestId,
+    entity: Entity,
+    owns_entity: bool,
+    on_response: ResponseHandler,
+    result: ehttp::Result<Response>,
+}
+
+impl FinishedRequest {
+    /// Hands the result to the response handler, then despawns the request entity or removes its task.
+    fn apply(self, world: &mut World) {
+        (self.on_response)(world, self.request_id, self.result);
+
+        if let Some(mut entity) = world.get_entity_mut(self.entity) {
+            if self.owns_entity || entity.contains::<DespawnOnResponse>() {
+                entity.despawn_recursive();
+            } else {
+                entity.remove::<(RequestTask, RequestId)>();
+                let entity = entity.id();
+                cleanup::track::<ResponseMeta>(world, entity);
+                cleanup::track::<RequestTiming>(world, entity);
+            }
+        }
+    }
+}
+
+/// Results of finished requests waiting to be delivered, in the order the requests finished.
+///
+/// Results of entities despawned in the meantime are dropped, like the task would have been.
+#[derive(Resource, Default)]
+pub(crate) struct FinishedRequests(VecDeque<FinishedRequest>);
+
+/// Requests waiting for a free client, in the order they were sent.
+#[derive(Resource, Default)]
+pub struct RequestQueue(VecDeque<(HttpRequest, ResponseHandler)>);
+
+impl RequestQueue {
+    /// number of requests waiting to be sent
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// check if no request is waiting to be sent
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Drops the queued requests with the given ids.
+    pub(crate) fn remove_requests(&mut self, request_ids: &HashSet<RequestId>) {
+        self.0
+            .retain(|(request, _)| !request_ids.contains(&request.id));
+    }
+
+    /// Removes the queued requests of the entity, returning their ids and response handlers.
+    pub(crate) fn take_entity(&mut self, entity: Entity) -> Vec<(RequestId, ResponseHandler)> {
+        self.take_where(|request| request.from_entity == Some(entity))
+    }
+
+    /// Removes the queued requests with the given ids, returning them like `take_entity`.
+    pub(crate) fn take_requests(
+        &mut self,
+        request_ids: &HashSet<RequestId>,
+    ) -> Vec<(RequestId, ResponseHandler)> {
+        self.take_where(|request| request_ids.contains(&request.id))
+    }
+
+    fn take_where(
+        &mut self,
+        taken: impl Fn(&HttpRequest) -> bool,
+    ) -> Vec<(RequestId, ResponseHandler)> {
+        let (taken, kept): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition(|(request, _)| taken(request));
+        self.0 = kept;
+        taken
+            .into_iter()
+            .map(|(request, on_response)| (request.id, on_response))
+            .collect()
+    }
+
+    /// Queues the request, with its `on_complete` callback taking the place of `on_response` if it has one.
+    pub(crate) fn push(
+        &mut self,
+        request: HttpRequest,
+        on_response: impl FnOnce(&mut World, RequestId, ehttp::Result<Response>) + Send + Sync + 'static,
+    ) {
+        let on_response = request
+            .on_complete
+            .as_ref()
+            .and_then(OnComplete::take)
+            .unwrap_or_else(|| Box::new(on_response));
+        self.0.push_back((request, on_response));
+    }
+
+    /// Queues the request like `push`, with `observe` called before whichever handler gets the result.
+    pub(crate) fn push_observed(
+        &mut self,
+        request: HttpRequest,
+        on_response: impl FnOnce(&mut World, RequestId, ehttp::Result<Response>) + Send + Sync + 'static,
+        observe: impl FnOnce(&mut World, &ehttp::Result<Response>) + Send + Sync + 'static,
+    ) {
+        let on_response = request
+            .on_complete
+            .as_ref()
+            .and_then(OnComplete::take)
+            .unwrap_or_else(|| Box::new(on_response));
+        self.push(request, move |world, request_id, result| {
+            observe(world, &result);
+            on_response(world, request_id, result);
+        });
+    }
+}
+
+/// Spawns `request` on the configured task pool and attaches the task to its entity.
+fn spawn_request(
+    commands: &mut Commands,
+    settings: &mut HttpClientSetting,
+    mut request: HttpRequest,
+    on_response: ResponseHandler,
+    simulated: SimulatedRequest,
+    logging: Option<&HttpLogging>,
+    now: Instant,
+) {
+    let on_response = match logging {
+        Some(logging) => logging.with_logging(&request, on_response),
+        None => on_response,
+    };
+    let request_id = request.id;
+    let label = request.label.clone();
+    let host = stats::metric_host(&request.request.url);
+    if let Some(on_dispatch) = request
+        .on_complete
+        .as_ref()
+        .and_then(|on_complete| on_complete.on_dispatch.as_ref())
+    {
+        on_dispatch();
+    }
+    let timeout = request
+        .timeout
+        .or(settings.default_timeout.filter(|_| !request.no_timeout))
+        .map(|timeout| (now + timeout, REQUEST_TIMED_OUT));
+    let deadline = match (timeout, request.deadline) {
+        (Some(timeout), Some(deadline)) if timeout.0 < deadline.0 => Some(timeout),
+        (_, Some(deadline)) => Some((deadline.0, REQUEST_DEADLINE_EXCEEDED)),
+        (timeout, None) => timeout,
+    };
+
+    let (entity, owns_entity) = if let Some(entity) = request.from_entity {
+        (entity, false)
+    } else {
+        let mut entity = commands.spawn_empty();
+        if let Some(parent) = request.parent {
+            entity.set_parent(parent);
+        }
+        (entity.id(), true)
+    };
+
+    let (parts_tx, parts) = match request.body_mode {
+        BodyMode::Buffered => (None, None),
+        _ => {
+            let (tx, rx) = async_channel::unbounded();
+            (Some(tx), Some(rx))
+        }
+    };
+    let (head_tx, head) = async_channel::bounded(1);
+    #[cfg(not(target_arch = "wasm32"))]
+    let phases = Arc::new(Mutex::new(Phases::default()));
+    #[cfg(target_arch = "wasm32")]
+    let (abort_tx, abort_rx) = async_channel::bounded::<()>(1);
+    let context = FetchContext {
+        body: BodySink::new(request.body_mode, parts_tx),
+        head: head_tx,
+        #[cfg(not(target_arch = "wasm32"))]
+        phases: phases.clone(),
+        #[cfg(not(target_arch = "wasm32"))]
+        throttle: settings
+            .download_limits
+            .throttle(request.label.as_ref())
+            .with(simulated.limiter),
+        #[cfg(not(target_arch = "wasm32"))]
+        url_policy: settings.url_policy.clone(),
+        #[cfg(target_arch = "wasm32")]
+        abort: abort_rx,
+    };
+
+    let transforms = std::mem::take(&mut request.transforms);
+    let fetch = transport::fetch(request, context);
+    // An injected result replaces the request, its fetch is dropped without being polled.
+    let injected = simulated.injected;
+    let future = async move {
+        let result = match injected {
+            Some(result) => result,
+            None => fetch.await,
+        };
+        transform::apply(&transforms, result)
+    };
+
+    let thread_pool = settings.task_pool.get();
+    #[cfg(not(target_arch = "wasm32"))]
+    let task = RequestTask {
+        request_id,
+        task: thread_pool.spawn(future),
+        parts,
+        head,
+        phases,
+        deadline,
+        owns_entity,
+        on_response: Some(on_response),
+        label,
+        host,
+        dispatched_at: Instant::now(),
+        received: 0,
+        delay: simulated.delay,
+        held: None,
+    };
+    #[cfg(target_arch = "wasm32")]
+    let task = {
+        let (tx, rx) = async_channel::bounded(1);
+        thread_pool
+            .spawn(async move {
+                let _ = tx.send(future.await).await;
+            })
+            .detach();
+        RequestTask {
+            request_id,
+            result: rx,
+            _abort: abort_tx,
+            parts,
+            head,
+            deadline,
+            owns_entity,
+            on_response: Some(on_response),
+            label,
+            host,
+            dispatched_at: Instant::now(),
+            received: 0,
+            delay: simulated.delay,
+            held: None,
+        }
+    };
+
+    commands
+        .entity(entity)
+        .insert((task, request_id))
+        .remove::<(ResponseMeta, RequestTiming)>();
+    settings.current_clients += 1;
+}
 
 fn handle_request(
     mut commands: Commands,
-    mut req_res: ResMut<HttpClientSetting>,
+    mut queue: ResMut<RequestQueue>,
     mut requests: EventReader<HttpRequest>,
+    (mut cache, environments): (Option<ResMut<ResponseCache>>, Option<Res<Environments>>),
+    clock: Res<HttpClock>,
 ) {
-    let thread_pool = IoTaskPool::get();
     for request in requests.read() {
-        if req_res.is_available() {
-            let req = request.clone();
-            let (entity, has_from_entity) = if let Some(entity) = req.from_entity {
-                (entity, true)
-            } else {
-                (commands.spawn_empty().id(), false)
-            };
+        let respond_to = request.respond_to;
+        let stale = queue_request(
+            &mut queue,
+            cache.as_deref_mut(),
+            environments.as_deref(),
+            clock.now(),
+            request.clone(),
+        );
+        if let Some(response) = stale {
+            commands.add(move |world: &mut World| deliver(world, respond_to, response));
+        }
+    }
+}
 
-            let task = thread_pool.spawn(async move {
-                let mut command_queue = CommandQueue::default();
-
-                let response = ehttp::fetch_async(req.request).await;
-                command_queue.push(move |world: &mut World| {
-                    match response {
-                        Ok(res) => {
-                            world
-                                .get_resource_mut::<Events<HttpResponse>>()
-                                .unwrap()
-                                .send(HttpResponse(res));
-                        }
-                        Err(e) => {
-                            world
-                                .get_resource_mut::<Events<HttpResponseError>>()
-                                .unwrap()
-                                .send(HttpResponseError(e.to_string()));
-                        }
-                    }
+/// Queues `request` with a handler delivering its response, the stale response it is answered with
+/// right away if it revalidates a cached one.
+pub(crate) fn queue_request(
+    queue: &mut RequestQueue,
+    cache: Option<&mut ResponseCache>,
+    environments: Option<&Environments>,
+    now: Instant,
+    request: HttpRequest,
+) -> Option<HttpResponse> {
+    let respond_to = request.respond_to;
+    let stale = request
+        .on_complete
+        .is_none()
+        .then(|| cache::stale_response(cache, environments, &request, now))
+        .flatten()
+        .map(|res| HttpResponse {
+            stale: true,
+            ..HttpResponse::new(request.id, res)
+        });
+    queue.push(request, move |world, request_id, response| match response {
+        Ok(res) => deliver(world, respond_to, HttpResponse::new(request_id, res)),
+        Err(e) => deliver(world, respond_to, HttpResponseError::new(request_id, e)),
+    });
+    stale
+}
 
-                    if has_from_entity {
-                        world.entity_mut(entity).remove::<RequestTask>();
-                    } else {
-                        world.entity_mut(entity).despawn_recursive();
-                    }
-                });
+/// Aborts queued requests and requests waiting for a retry before they are sent, their handlers
+/// get the abort error.
+fn abort_queued_requests(
+    mut commands: Commands,
+    mut queue: ResMut<RequestQueue>,
+    mut retries: ResMut<retry::PendingRetries>,
+    mut aborts: EventReader<AbortRequest>,
+    mut id_aborts: EventReader<AbortRequestId>,
+) {
+    let mut taken = vec![];
+    for abort in aborts.read() {
+        taken.extend(queue.take_entity(abort.0));
+        taken.extend(retries.take_entity(abort.0));
+    }
+    let ids: HashSet<RequestId> = id_aborts.read().map(|abort| abort.0).collect();
+    if !ids.is_empty() {
+        taken.extend(queue.take_requests(&ids));
+        taken.extend(retries.take_requests(&ids));
+    }
+    for (request_id, on_response) in taken {
+        commands.add(move |world: &mut World| {
+            on_response(world, request_id, Err(REQUEST_ABORTED.to_string()));
+        });
+    }
+}
 
-                command_queue
-            });
+/// The resources that rewrite the bodies of dispatched requests and their responses.
+#[derive(SystemParam)]
+struct PayloadHooks<'w> {
+    transforms: Option<Res<'w, ResponseTransforms>>,
+    encryption: Option<Res<'w, PayloadEncryption>>,
+    signatures: Option<Res<'w, ResponseSignatures>>,
+}
+
+impl PayloadHooks<'_> {
+    /// Adds the transforms of the label of `request`, then encrypts it, the response is verified
+    /// first and decrypted second.
+    fn resolve(&self, request: &mut HttpRequest) -> Result<(), String> {
+        if let Some(transforms) = &self.transforms {
+            transforms.resolve_label(request);
+        }
+        if let Some(encryption) = &self.encryption {
+            encryption.resolve(request)?;
+        }
+        signature::resolve(self.signatures.as_deref(), request)
+    }
+}
+
+fn dispatch_requests(
+    mut commands: Commands,
+    mut req_res: ResMut<HttpClientSetting>,
+    (mut queue, control, network, skew): (
+        ResMut<RequestQueue>,
+        Res<HttpClientControl>,
+        Res<NetworkPolicy>,
+        Res<ClockSkew>,
+    ),
+    (mut stats, environments, logging): (
+        ResMut<HttpStats>,
+        Option<Res<Environments>>,
+        Option<Res<HttpLogging>>,
+    ),
+    (entities, mut cache, payloads): (&Entities, Option<ResMut<ResponseCache>>, PayloadHooks),
+    (request_tasks, fallbacks, deadlines, backends): (
+        Query<&RequestTask>,
+        Query<&FallbackUrls>,
+        Query<&Deadline>,
+        Query<&BackendOptions>,
+    ),
+    (mut simulation, mut faults, clock): (
+        Option<ResMut<NetworkSimulation>>,
+        Option<ResMut<FaultInjection>>,
+        Res<HttpClock>,
+    ),
+) {
+    // Despawning an entity drops its task without going through `handle_tasks`, so the clients in
+    // use are counted from the tasks that are still running.
+    req_res.current_clients = request_tasks.iter().filter(|task| task.in_flight()).count();
+    if control.is_paused() {
+        return;
+    }
+    // Stamped here rather than in `RequestQueue::push`, which has no clock.
+    let now = clock.now();
+    for (request, _) in queue.0.iter_mut() {
+        request.queued_at.get_or_insert(now);
+    }
+    let mut per_host: HashMap<String, usize> = HashMap::new();
+    let count_hosts = req_res.host_limits.is_some()
+        || queue
+            .0
+            .iter()
+            .any(|(request, _)| request.host_limit.is_some());
+    if count_hosts {
+        for task in request_tasks.iter().filter(|task| task.in_flight()) {
+            *per_host.entry(task.host.clone()).or_default() += 1;
+        }
+    }
+    let environment = environments.as_ref().and_then(|e| e.active());
+    let defer_large = network.defers_large_transfers();
+    let mut ceiling = None;
 
-            commands.entity(entity).insert(RequestTask(task));
-            req_res.current_clients += 1;
+    while req_res.is_available() {
+        let settings = &mut *req_res;
+        let host_limits = settings.host_limits.as_ref();
+        let dispatchable = |request: &HttpRequest| {
+            if defer_large && request.large_transfer {
+                return false;
+            }
+            if host_limits.is_none() && request.host_limit.is_none() {
+                return true;
+            }
+            let host = host_limits::queued_host(&request.request.url, environment);
+            request
+                .host_limit
+                .or_else(|| host_limits.and_then(|limits| limits.limit(&host)))
+                .is_none_or(|limit| per_host.get(&host).copied().unwrap_or(0) < limit)
+        };
+        let next = priority::next_index(
+            &queue.0,
+            settings.priority_aging.as_ref(),
+            settings.fair_scheduling.as_mut(),
+            dispatchable,
+            &mut ceiling,
+            now,
+        )
+        .and_then(|index| queue.0.remove(index));
+        let Some((mut request, on_response)) = next else {
+            break;
+        };
+        let entity_gone = match request.from_entity {
+            Some(entity) => !entities.contains(entity),
+            None => request
+                .parent
+                .is_some_and(|parent| !entities.contains(parent)),
+        };
+        if entity_gone {
+            continue;
+        }
+        if let Some(environment) = environment {
+            let added = environment.apply(&mut request.request);
+            request.private_headers.extend(added);
+        }
+        // The request's own settings win over the environment's, which win over the plugin's.
+        if !request.no_timeout {
+            request.timeout = request
+                .timeout
+                .or(environment.and_then(|environment| environment.timeout))
+                .or(req_res.default_timeout);
+        }
+        request.retry = request
+            .retry
+            .or(environment.and_then(|environment| environment.retry))
+            .or(req_res.default_retry);
+        request.max_redirects = request
+            .max_redirects
+            .or(environment.and_then(|environment| environment.max_redirects))
+            .or(Some(req_res.max_redirects));
+        if let Some(Err(reason)) = req_res
+            .url_policy
+            .as_ref()
+            .map(|policy| policy.check(&request.request.url))
+        {
+            warn!("Blocked request to {}: {reason}", request.request.url);
+            let blocked = RequestBlocked {
+                request_id: Some(request.id),
+                url: request.request.url.clone(),
+                reason,
+            };
+            let request_id = request.id;
+            commands.add(move |world: &mut World| {
+                world.send_event(blocked);
+                on_response(world, request_id, Err(REQUEST_BLOCKED.to_string()));
+            });
+            continue;
+        }
+        // Fresh cached responses are answered right away, without taking a client.
+        let cache_key = match cache
+            .as_mut()
+            .and_then(|cache| cache::lookup(cache, &request, clock.now()))
+        {
+            Some((_, Some(response))) => {
+                let request_id = request.id;
+                commands.add(move |world: &mut World| {
+                    on_response(world, request_id, Ok(response));
+                });
+                continue;
+            }
+            Some((key, None)) => Some(key),
+            None => None,
+        };
+        fallback::resolve(&mut request, &fallbacks);
+        backend::resolve(&mut request, &backends);
+        deadline::resolve(&mut request, &deadlines);
+        retry::resolve(&mut request);
+        let on_response = retry::with_retries(&request, on_response);
+        let on_response = fallback::with_fallbacks(&request, on_response);
+        let on_response = cache::with_cache(&request, cache_key, on_response);
+        // After the handlers keep their copy of the request, so a retry resolves them again.
+        if let Err(e) = payloads.resolve(&mut request) {
+            let request_id = request.id;
+            commands.add(move |world: &mut World| on_response(world, request_id, Err(e)));
+            continue;
+        }
+        req_res.apply_default_headers(&mut request.request.headers);
+        if let Some(header) = &req_res.request_id_header {
+            metadata::insert_default_header(
+                &mut request.request.headers,
+                header,
+                &request.id.to_string(),
+            );
         }
+        // Last, so the signature covers the headers and body that are sent.
+        if let Some(Err(e)) = request
+            .signer
+            .clone()
+            .map(|signer| signer.sign(&mut request.request, skew.server_now()))
+        {
+            let request_id = request.id;
+            commands.add(move |world: &mut World| on_response(world, request_id, Err(e)));
+            continue;
+        }
+        let host = stats::metric_host(&request.request.url);
+        stats.record_sent(request.label.as_ref(), &host);
+        if count_hosts {
+            *per_host.entry(host).or_default() += 1;
+        }
+        let mut simulated = simulation
+            .as_mut()
+            .map(|simulation| simulation.simulate(&request.request.url))
+            .unwrap_or_default();
+        if let Some(fault) = faults
+            .as_mut()
+            .and_then(|faults| faults.inject(&request.request.url))
+        {
+            simulated.injected = Some(fault);
+        }
+        spawn_request(
+            &mut commands,
+            &mut req_res,
+            request,
+            on_response,
+            simulated,
+            logging.as_deref(),
+            clock.now(),
+        );
     }
 }
 
 fn handle_tasks(
     mut commands: Commands,
     mut req_res: ResMut<HttpClientSetting>,
-    mut request_tasks: Query<&mut RequestTask>,
+    (mut aborts, mut id_aborts): (EventReader<AbortRequest>, EventReader<AbortRequestId>),
+    (mut progress, mut chunks, mut metas, mut timings): (
+        EventWriter<HttpProgress>,
+        EventWriter<HttpResponseChunk>,
+        EventWriter<ResponseMeta>,
+        EventWriter<RequestTiming>,
+    ),
+    (mut finished, mut stats, mut clock_skew, mut slo): (
+        ResMut<FinishedRequests>,
+        ResMut<HttpStats>,
+        ResMut<ClockSkew>,
+        Option<ResMut<slo::SloSamples>>,
+    ),
+    mut request_tasks: Query<(Entity, &mut RequestTask)>,
+    clock: Res<HttpClock>,
 ) {
-    for mut task in request_tasks.iter_mut() {
-        if let Some(mut commands_queue) = block_on(poll_once(&mut task.0)) {
-            commands.append(&mut commands_queue);
-            req_res.current_clients -= 1;
+    let aborted: HashSet<Entity> = aborts.read().map(|abort| abort.0).collect();
+    let aborted_ids: HashSet<RequestId> = id_aborts.read().map(|abort| abort.0).collect();
+    let now = clock.now();
+
+    for (entity, mut task) in request_tasks.iter_mut() {
+        if let Ok(head) = task.head.try_recv() {
+            clock_skew.observe(&head.headers, task.dispatched_at.elapsed());
+            let meta = ResponseMeta::new(task.request_id, entity, head);
+            commands.entity(entity).try_insert(meta.clone());
+            metas.send(meta);
+        }
+
+        // Parts are sent before the task finishes, so draining them first keeps chunks ahead of the response.
+        if let Some(parts) = &task.parts {
+            let mut latest = None;
+            while let Ok(part) = parts.try_recv() {
+                if let Some(bytes) = part.chunk {
+                    chunks.send(HttpResponseChunk {
+                        entity,
+                        request_id: task.request_id,
+                        bytes,
+                    });
+                }
+                latest = Some((part.received, part.total));
+            }
+            if let Some((received, total)) = latest {
+                task.received = received;
+                progress.send(HttpProgress {
+                    entity,
+                    request_id: task.request_id,
+                    received,
+                    total,
+                });
+            }
+        }
+
+        let error = if aborted.contains(&entity) || aborted_ids.contains(&task.request_id) {
+            Some(REQUEST_ABORTED)
+        } else if let Some((_, error)) = task
+            .deadline
+            .filter(|(deadline, _)| task.in_flight() && now >= *deadline)
+        {
+            Some(error)
+        } else {
+            None
+        };
+
+        if let Some(error) = error {
+            let on_response = if let Some(on_response) = task.on_response.take() {
+                req_res.current_clients -= 1;
+                stats.record_finished(
+                    task.label.as_ref(),
+                    &task.host,
+                    None,
+                    task.received,
+                    task.dispatched_at.elapsed(),
+                );
+                if let Some(slo) = &mut slo {
+                    slo.record(
+                        task.label.as_ref(),
+                        false,
+                        task.dispatched_at.elapsed(),
+                        now,
+                    );
+                }
+                on_response
+            } else {
+                // The request already finished, its result is waiting to be delivered.
+                let Some(index) = finished
+                    .0
+                    .iter()
+                    .position(|request| request.entity == entity)
+                else {
+                    continue;
+                };
+                finished.0.remove(index).unwrap().on_response
+            };
+            let request = FinishedRequest {
+                request_id: task.request_id,
+                entity,
+                owns_entity: task.owns_entity,
+                on_response,
+                result: Err(error.to_string()),
+            };
+            commands.add(move |world: &mut World| request.apply(world));
+        } else if task.in_flight() {
+            if let Some(result) = task.poll(now) {
+                let (status, bytes) = match &result {
+                    Ok(res) => (Some(res.status), task.received.max(res.bytes.len() as u64)),
+                    Err(_) => (None, task.received),
+                };
+                stats.record_finished(
+                    task.label.as_ref(),
+                    &task.host,
+                    status,
+                    bytes,
+                    task.dispatched_at.elapsed(),
+                );
+                if let Some(slo) = &mut slo {
+                    let ok = status.is_some_and(|status| (200..300).contains(&status));
+                    slo.record(task.label.as_ref(), ok, task.dispatched_at.elapsed(), now);
+                }
+                if result.is_ok() {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let phases = task.phases.lock().map(|phases| *phases).unwrap_or_default();
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let (Ok(res), Some(reused)) = (&result, phases.reused) {
+                        stats.record_connection(&res.url, reused);
+                    }
+                    #[cfg(target_arch = "wasm32")]
+                    let phases = Phases::default();
+                    let timing = RequestTiming::new(
+                        task.request_id,
+                        entity,
+                        phases,
+                        task.dispatched_at.elapsed(),
+                    );
+                    commands.entity(entity).try_insert(timing.clone());
+                    timings.send(timing);
+                }
+                finished.0.push_back(FinishedRequest {
+                    request_id: task.request_id,
+                    entity,
+                    owns_entity: task.owns_entity,
+                    on_response: task.on_response.take().unwrap(),
+                    result,
+                });
+                req_res.current_clients -= 1;
+            }
+        }
+    }
+}
+
+/// Delivers the results of finished requests, as many as `HttpClientSetting::response_budget` allows.
+fn deliver_responses(world: &mut World) {
+    let budget = world.resource::<HttpClientSetting>().response_budget;
+    let start = Instant::now();
+    let mut delivered = 0;
+    while budget.allows(delivered, start.elapsed()) {
+        let Some(request) = world.resource_mut::<FinishedRequests>().0.pop_front() else {
+            break;
+        };
+        if world.get_entity(request.entity).is_some() {
+            request.apply(world);
+            delivered += 1;
         }
     }
 }
@@ -0,0 +1,375 @@
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::storage::Store;
+use crate::{
+    HttpClient, HttpClock, HttpRequest, HttpSchedule, HttpSet, OnComplete, REQUEST_ABORTED,
+};
+
+/// Downloads the localization file of the selected [`Locale`] into the [`Localization`] resource,
+/// and swaps it whenever the locale changes.
+///
+/// `{locale}` in the url is replaced by the locale, e.g.
+/// `https://cdn.example.com/i18n/{locale}.json`. Files are parsed as JSON objects of strings,
+/// nested objects giving dotted keys, unless another parser is set with `with_parser`, e.g. for
+/// Fluent files. Every downloaded file is kept, so switching back to a locale is instant, and with
+/// a cache location it is also shown right away on the next startup until the fresh file arrives.
+/// Downloads that fail with a connection error, a
+/// timeout or a 5xx status are retried with exponential backoff while the locale stays selected.
+/// Sends [`LocalizationChanged`] whenever the messages changed and [`LocalizationFailed`] for
+/// failed downloads. Add it after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(
+///     LocalizationPlugin::new("https://cdn.example.com/i18n/{locale}.json")
+///         .with_locale("en")
+///         .with_cache("i18n.json"),
+/// );
+///
+/// fn pick_language(mut locale: ResMut<Locale>, settings: Res<Settings>) {
+///     if settings.is_changed() {
+///         locale.0 = settings.language.clone();
+///     }
+/// }
+///
+/// fn main_menu(localization: Res<Localization>) {
+///     let title = localization.text("menu.title");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocalizationPlugin {
+    /// Url of the files, with `{locale}` where the locale goes.
+    pub url: String,
+    /// Locale selected at startup, unless a [`Locale`] resource was inserted already.
+    pub locale: String,
+    /// A file path on native builds, a `localStorage` key on wasm builds.
+    pub cache: Option<String>,
+    /// Backoff after the first failed download, doubled for every further failure.
+    pub retry_backoff: Duration,
+    pub max_retry_backoff: Duration,
+    /// Turns a file into messages by key.
+    pub parser: fn(&str) -> Result<HashMap<String, String>, String>,
+}
+
+impl LocalizationPlugin {
+    /// create the plugin downloading JSON files from `url`, for the `en` locale
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            locale: "en".to_string(),
+            cache: None,
+            retry_backoff: Duration::from_secs(1),
+            max_retry_backoff: Duration::from_secs(60),
+            parser: parse_json,
+        }
+    }
+
+    /// select `locale` at startup, see `locale`
+    pub fn with_locale(mut self, locale: impl ToString) -> Self {
+        self.locale = locale.to_string();
+        self
+    }
+
+    /// keep the downloaded files at `location`, see `cache`
+    pub fn with_cache(mut self, location: impl ToString) -> Self {
+        self.cache = Some(location.to_string());
+        self
+    }
+
+    pub fn with_retry_backoff(
+        mut self,
+        retry_backoff: Duration,
+        max_retry_backoff: Duration,
+    ) -> Self {
+        self.retry_backoff = retry_backoff;
+        self.max_retry_backoff = max_retry_backoff;
+        self
+    }
+
+    /// parse the files with `parser` instead of as JSON
+    pub fn with_parser(
+        mut self,
+        parser: fn(&str) -> Result<HashMap<String, String>, String>,
+    ) -> Self {
+        self.parser = parser;
+        self
+    }
+}
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        let store = self.cache.as_ref().map(Store::new);
+        let files = store
+            .as_ref()
+            .and_then(Store::load::<HashMap<String, String>>)
+            .unwrap_or_default();
+        if !app.world.contains_resource::<Locale>() {
+            app.insert_resource(Locale(self.locale.clone()));
+        }
+        app.init_resource::<Localization>();
+        app.insert_resource(LocalizationFetcher {
+            url: self.url.clone(),
+            retry_backoff: self.retry_backoff,
+            max_retry_backoff: self.max_retry_backoff,
+            parser: self.parser,
+            store,
+            files,
+            selected: None,
+            due: false,
+            fetching: false,
+            attempts: 0,
+            retry_at: None,
+        });
+        app.add_event::<LocalizationChanged>();
+        app.add_event::<LocalizationFailed>();
+        app.add_systems(schedule, fetch_localization.before(HttpSet::Queue));
+    }
+}
+
+/// The selected locale, e.g. `en` or `pt-BR`. Changing it swaps the [`Localization`].
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct Locale(pub String);
+
+/// The messages of the selected locale, downloaded by the [`LocalizationPlugin`].
+#[derive(Resource, Debug, Default)]
+pub struct Localization {
+    locale: Option<String>,
+    source: String,
+    messages: HashMap<String, String>,
+    from_cache: bool,
+}
+
+impl Localization {
+    /// The locale of the messages, `None` before a file was loaded. Differs from [`Locale`] until
+    /// the file of a newly selected locale arrived.
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+
+    /// The message of `key`, or the key itself when it is missing.
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get(key).unwrap_or(key)
+    }
+
+    pub fn messages(&self) -> &HashMap<String, String> {
+        &self.messages
+    }
+
+    /// The file as downloaded, e.g. to build a `FluentBundle` from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the file was kept from earlier and not downloaded since.
+    pub fn is_from_cache(&self) -> bool {
+        self.from_cache
+    }
+}
+
+/// Sent when the messages of [`Localization`] changed, after a locale change or a newer file.
+#[derive(Event, Debug, Clone)]
+pub struct LocalizationChanged {
+    pub locale: String,
+    /// The locale before the change, `None` for the first file.
+    pub previous: Option<String>,
+}
+
+/// Sent when the file of a locale could not be downloaded or parsed, the current messages are kept.
+#[derive(Event, Debug, Clone)]
+pub struct LocalizationFailed {
+    pub locale: String,
+    pub error: String,
+}
+
+#[derive(Resource)]
+struct LocalizationFetcher {
+    url: String,
+    retry_backoff: Duration,
+    max_retry_backoff: Duration,
+    parser: fn(&str) -> Result<HashMap<String, String>, String>,
+    store: Option<Store>,
+    /// Every downloaded file, by locale.
+    files: HashMap<String, String>,
+    /// The locale the files are fetched for, to notice changes of [`Locale`].
+    selected: Option<String>,
+    /// Whether the file of the selected locale is to be downloaded.
+    due: bool,
+    /// Whether a download is in flight, of the selected locale or an earlier one.
+    fetching: bool,
+    /// Failed downloads of the selected locale in a row.
+    attempts: u32,
+    retry_at: Option<Instant>,
+}
+
+/// Parses a JSON object, `{"menu": {"title": "Play"}}` gives `menu.title`.
+fn parse_json(source: &str) -> Result<HashMap<String, String>, String> {
+    fn flatten(prefix: &str, value: serde_json::Value, messages: &mut HashMap<String, String>) {
+        match value {
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    let key = match prefix {
+                        "" => key,
+                        prefix => format!("{prefix}.{key}"),
+                    };
+                    flatten(&key, value, messages);
+                }
+            }
+            serde_json::Value::String(text) => {
+                messages.insert(prefix.to_string(), text);
+            }
+            serde_json::Value::Null => {}
+            value => {
+                messages.insert(prefix.to_string(), value.to_string());
+            }
+        }
+    }
+
+    let value: serde_json::Value = serde_json::from_str(source).map_err(|e| e.to_string())?;
+    if !value.is_object() {
+        return Err("Localization file is not a JSON object".to_string());
+    }
+    let mut messages = HashMap::new();
+    flatten("", value, &mut messages);
+    Ok(messages)
+}
+
+/// Shows `source` for `locale`, returns whether the messages changed.
+fn apply(
+    localization: &mut Localization,
+    locale: &str,
+    source: &str,
+    parser: fn(&str) -> Result<HashMap<String, String>, String>,
+    from_cache: bool,
+) -> Result<bool, String> {
+    if localization.locale.as_deref() == Some(locale) && localization.source == source {
+        localization.from_cache &= from_cache;
+        return Ok(false);
+    }
+    localization.messages = parser(source)?;
+    localization.source = source.to_string();
+    localization.locale = Some(locale.to_string());
+    localization.from_cache = from_cache;
+    Ok(true)
+}
+
+fn fetch_localization(
+    locale: Res<Locale>,
+    mut fetcher: ResMut<LocalizationFetcher>,
+    mut localization: ResMut<Localization>,
+    mut changed: EventWriter<LocalizationChanged>,
+    mut requests: EventWriter<HttpRequest>,
+    clock: Res<HttpClock>,
+) {
+    let fetcher = &mut *fetcher;
+    if fetcher.selected.as_ref() != Some(&locale.0) {
+        fetcher.selected = Some(locale.0.clone());
+        fetcher.attempts = 0;
+        fetcher.retry_at = None;
+        if let Some(source) = fetcher.files.get(&locale.0) {
+            let previous = localization.locale.clone();
+            // A broken cached file is replaced by the download.
+            if let Ok(true) = apply(&mut localization, &locale.0, source, fetcher.parser, true) {
+                changed.send(LocalizationChanged {
+                    locale: locale.0.clone(),
+                    previous,
+                });
+            }
+        }
+        fetcher.due = true;
+    }
+    if fetcher
+        .retry_at
+        .is_some_and(|retry_at| clock.now() >= retry_at)
+    {
+        fetcher.retry_at = None;
+        fetcher.due = true;
+    }
+    // A download of the previous locale is still cached, the new one follows once it finished.
+    if !fetcher.due || fetcher.fetching {
+        return;
+    }
+    fetcher.due = false;
+    fetcher.fetching = true;
+
+    let requested = locale.0.clone();
+    let on_complete = OnComplete::new(move |world, _, response| {
+        on_downloaded(world, requested, response);
+    });
+    requests.send(HttpRequest {
+        on_complete: Some(on_complete),
+        ..HttpClient::new()
+            .get(fetcher.url.replace("{locale}", &locale.0))
+            .build()
+    });
+}
+
+fn on_downloaded(world: &mut World, locale: String, response: ehttp::Result<ehttp::Response>) {
+    let now = world.resource::<HttpClock>().now();
+    let mut fetcher = world.resource_mut::<LocalizationFetcher>();
+    fetcher.fetching = false;
+    let selected = fetcher.selected.as_ref() == Some(&locale);
+    let retry = match &response {
+        Ok(res) => res.status >= 500 || matches!(res.status, 408 | 429),
+        Err(e) => e != REQUEST_ABORTED,
+    };
+    let source = response.and_then(|res| {
+        if !res.ok {
+            return Err(format!("{} {}", res.status, res.status_text));
+        }
+        String::from_utf8(res.bytes).map_err(|e| e.to_string())
+    });
+    let parser = fetcher.parser;
+    let source = source.and_then(|source| parser(&source).map(|_| source));
+    let error = match source {
+        Ok(source) => {
+            if selected {
+                fetcher.attempts = 0;
+            }
+            if fetcher.files.get(&locale) != Some(&source) {
+                fetcher.files.insert(locale.clone(), source.clone());
+                if let Some(store) = &fetcher.store {
+                    store.save(&fetcher.files);
+                }
+            }
+            if !selected {
+                return;
+            }
+            let mut localization = world.resource_mut::<Localization>();
+            let previous = localization.locale.clone();
+            match apply(&mut localization, &locale, &source, parser, false) {
+                Ok(true) => {
+                    world.send_event(LocalizationChanged { locale, previous });
+                    return;
+                }
+                Ok(false) => return,
+                Err(error) => error,
+            }
+        }
+        Err(error) => {
+            if selected && retry {
+                fetcher.attempts += 1;
+                let backoff = fetcher
+                    .retry_backoff
+                    .saturating_mul(2u32.saturating_pow(fetcher.attempts - 1))
+                    .min(fetcher.max_retry_backoff);
+                fetcher.retry_at = Some(now + backoff);
+            }
+            error
+        }
+    };
+    world.send_event(LocalizationFailed { locale, error });
+}
@@ -0,0 +1,232 @@
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::app::{App, Plugin};
+use bevy::log::Level;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use ehttp::Headers;
+
+use crate::{HttpRequest, Redaction, ResponseHandler, REQUEST_ABORTED};
+
+/// The `tracing` target of the request logs, e.g. for `LogPlugin::filter`.
+pub const HTTP_LOG_TARGET: &str = "bevy_http_client::http";
+
+/// Logs every request once it finished, with its status, duration and size, and optionally its
+/// headers and truncated bodies.
+///
+/// Successful requests are logged at `info` level, with `sample_rate` of them picked at random
+/// when they are sent. Failures, 4xx and 5xx responses and requests slower than `slow_threshold`
+/// are always logged, as warnings. Every attempt of a retried request is logged on its own.
+/// Headers and bodies go through the [`Redaction`] resource before they are logged. Add it after
+/// `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(
+///     HttpLoggingPlugin::default()
+///         .with_verbosity(LogVerbosity::Headers)
+///         .with_slow_threshold(Duration::from_secs(2))
+///         .with_sample_rate(0.1),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpLoggingPlugin {
+    pub verbosity: LogVerbosity,
+    /// Most bytes of a body that are logged, with `LogVerbosity::Bodies`.
+    pub max_body_len: usize,
+    /// Requests that take longer are logged as warnings, even when they were not sampled.
+    pub slow_threshold: Option<Duration>,
+    /// The share of successful requests that are logged, from `0.0` to `1.0`.
+    pub sample_rate: f32,
+    pub target: HttpLogTarget,
+}
+
+impl Default for HttpLoggingPlugin {
+    fn default() -> Self {
+        Self {
+            verbosity: LogVerbosity::Line,
+            max_body_len: 1024,
+            slow_threshold: None,
+            sample_rate: 1.0,
+            target: HttpLogTarget::Tracing,
+        }
+    }
+}
+
+impl HttpLoggingPlugin {
+    pub fn with_verbosity(mut self, verbosity: LogVerbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// log at most `max_body_len` bytes of a body, see `max_body_len`
+    pub fn with_max_body_len(mut self, max_body_len: usize) -> Self {
+        self.max_body_len = max_body_len;
+        self
+    }
+
+    /// warn about requests slower than `slow_threshold`, see `slow_threshold`
+    pub fn with_slow_threshold(mut self, slow_threshold: Duration) -> Self {
+        self.slow_threshold = Some(slow_threshold);
+        self
+    }
+
+    /// log `sample_rate` of the successful requests, see `sample_rate`
+    pub fn with_sample_rate(mut self, sample_rate: f32) -> Self {
+        self.sample_rate = sample_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_target(mut self, target: HttpLogTarget) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl Plugin for HttpLoggingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HttpLogging(self.clone()));
+    }
+}
+
+/// How much of a request is logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogVerbosity {
+    /// One line with the method, url, status, duration and size.
+    Line,
+    /// The line, followed by the request and response headers.
+    Headers,
+    /// The headers, followed by the request and response bodies, truncated to `max_body_len`.
+    Bodies,
+}
+
+/// Writes one request log somewhere.
+type LogFn = Arc<dyn Fn(Level, &str) + Send + Sync>;
+
+/// Where the request logs go.
+#[derive(Clone)]
+pub enum HttpLogTarget {
+    /// The `tracing` logs of Bevy, under the [`HTTP_LOG_TARGET`] target.
+    Tracing,
+    /// A function called with the level and text of every log, e.g. to write them to a file.
+    Custom(LogFn),
+}
+
+impl std::fmt::Debug for HttpLogTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tracing => f.write_str("Tracing"),
+            Self::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// The configuration of the [`HttpLoggingPlugin`].
+#[derive(Resource)]
+pub(crate) struct HttpLogging(HttpLoggingPlugin);
+
+impl HttpLogging {
+    /// Wraps `on_response` so the attempt is logged once it finished, before its result is handled.
+    pub(crate) fn with_logging(
+        &self,
+        request: &HttpRequest,
+        on_response: ResponseHandler,
+    ) -> ResponseHandler {
+        let config = self.0.clone();
+        let sampled = fastrand::f32() < config.sample_rate;
+        let label = request.label.clone();
+        let method = request.request.method.clone();
+        let url = request.request.url.clone();
+        let sent = (config.verbosity >= LogVerbosity::Headers).then(|| {
+            let body = if config.verbosity >= LogVerbosity::Bodies {
+                request.request.body.clone()
+            } else {
+                vec![]
+            };
+            (request.request.headers.clone(), body)
+        });
+        let started = Instant::now();
+
+        Box::new(move |world, request_id, response| {
+            let elapsed = started.elapsed();
+            let slow = config
+                .slow_threshold
+                .is_some_and(|threshold| elapsed >= threshold);
+            let (failed, outcome) = match &response {
+                Ok(res) => (
+                    res.status >= 400,
+                    format!(
+                        "{} {} in {elapsed:?}, {} bytes",
+                        res.status,
+                        res.status_text,
+                        res.bytes.len()
+                    ),
+                ),
+                Err(e) if e == REQUEST_ABORTED => (false, format!("aborted after {elapsed:?}")),
+                Err(e) => (true, format!("failed after {elapsed:?}: {e}")),
+            };
+            if sampled || failed || slow {
+                let mut text = String::new();
+                if slow {
+                    text.push_str("slow ");
+                }
+                if let Some(label) = &label {
+                    let _ = write!(text, "[{label}] ");
+                }
+                let default_redaction = Redaction::default();
+                let redaction = world
+                    .get_resource::<Redaction>()
+                    .unwrap_or(&default_redaction);
+                let _ = write!(text, "{method} {} {outcome}", redaction.url(&url));
+                if let Some((headers, body)) = &sent {
+                    write_headers(&mut text, '>', &redaction.headers(headers));
+                    if let Ok(res) = &response {
+                        write_headers(&mut text, '<', &redaction.headers(&res.headers));
+                    }
+                    if config.verbosity >= LogVerbosity::Bodies {
+                        write_body(&mut text, '>', &redaction.body(body), config.max_body_len);
+                        if let Ok(res) = &response {
+                            let bytes = redaction.body(&res.bytes);
+                            write_body(&mut text, '<', &bytes, config.max_body_len);
+                        }
+                    }
+                }
+                let level = if failed || slow {
+                    Level::WARN
+                } else {
+                    Level::INFO
+                };
+                emit(&config.target, level, &text);
+            }
+            on_response(world, request_id, response);
+        })
+    }
+}
+
+fn write_headers(text: &mut String, direction: char, headers: &Headers) {
+    for (name, value) in &headers.headers {
+        let _ = write!(text, "\n{direction} {name}: {value}");
+    }
+}
+
+fn write_body(text: &mut String, direction: char, body: &[u8], max_len: usize) {
+    if body.is_empty() {
+        return;
+    }
+    let shown = &body[..body.len().min(max_len)];
+    let _ = write!(text, "\n{direction} {}", String::from_utf8_lossy(shown));
+    if shown.len() < body.len() {
+        let _ = write!(text, "… ({} bytes)", body.len());
+    }
+}
+
+fn emit(target: &HttpLogTarget, level: Level, text: &str) {
+    match target {
+        HttpLogTarget::Tracing if level == Level::WARN => warn!(target: HTTP_LOG_TARGET, "{text}"),
+        HttpLogTarget::Tracing => info!(target: HTTP_LOG_TARGET, "{text}"),
+        HttpLogTarget::Custom(log) => log(level, text),
+    }
+}
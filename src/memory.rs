@@ -0,0 +1,162 @@
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+
+use crate::{HttpClientSetting, HttpResponse, ResponseCache};
+
+/// Caps the bytes of response bodies the client keeps in memory, see
+/// `HttpClientSettings::memory_budget` and [`HttpMemory`].
+///
+/// Checked every frame once responses are delivered. While the bodies held are over `max_bytes`,
+/// the least recently used entries of the [`ResponseCache`] are evicted with
+/// `EvictionReason::MemoryBudget` until they fit. Bodies of `HttpResponse` components are never
+/// removed, if they alone are over the budget a warning is logged instead, as is one when more
+/// than `max_response_entities` entities keep an `HttpResponse` component, which usually means a
+/// system handles the responses of `HttpClient::respond_to` without despawning the entities or
+/// removing the component.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///     memory_budget: Some(MemoryBudget::new(64 * 1024 * 1024).with_max_response_entities(256)),
+///     ..default()
+/// }));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// Most bytes of response bodies kept by the cache and `HttpResponse` components together.
+    pub max_bytes: usize,
+    /// More entities with an `HttpResponse` component than this log a warning.
+    pub max_response_entities: usize,
+}
+
+impl MemoryBudget {
+    /// keep at most `max_bytes` of response bodies, warning about more than 1024 response entities
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            max_response_entities: 1024,
+        }
+    }
+
+    /// warn once more than `max_response_entities` entities keep a response, see
+    /// `max_response_entities`
+    pub fn with_max_response_entities(mut self, max_response_entities: usize) -> Self {
+        self.max_response_entities = max_response_entities;
+        self
+    }
+}
+
+/// The bytes of response bodies the client keeps in memory, updated every frame once responses are
+/// delivered. Bodies of response events and in-flight requests are not counted, they are gone
+/// within a few frames.
+///
+/// The totals are also recorded as Bevy diagnostics, e.g. for `LogDiagnosticsPlugin`, under
+/// [`HttpMemory::RETAINED_BYTES`], [`HttpMemory::CACHE_BYTES`] and
+/// [`HttpMemory::RESPONSE_ENTITIES`].
+///
+/// # Examples
+///
+/// ```
+/// fn memory_overlay(memory: Res<HttpMemory>, mut text: Query<&mut Text, With<MemoryText>>) {
+///     text.single_mut().sections[0].value = format!(
+///         "http: {} KiB, peak {} KiB",
+///         memory.total_bytes() / 1024,
+///         memory.peak_bytes / 1024
+///     );
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct HttpMemory {
+    /// Bytes of the bodies in the [`ResponseCache`].
+    pub cache_bytes: usize,
+    /// Bytes of the bodies of `HttpResponse` components, e.g. of `HttpClient::respond_to`.
+    pub response_bytes: usize,
+    /// Entities with an `HttpResponse` component.
+    pub response_entities: usize,
+    /// Most bytes held at once since the app started.
+    pub peak_bytes: usize,
+    /// Cache entries evicted to stay within the [`MemoryBudget`].
+    pub evicted: u64,
+    /// Whether the last check warned, so a warning is only logged when it starts.
+    over_budget: bool,
+    too_many_entities: bool,
+}
+
+impl HttpMemory {
+    /// Bytes of response bodies held in total.
+    pub const RETAINED_BYTES: DiagnosticPath = DiagnosticPath::const_new("http/retained_bytes");
+    /// Bytes of the bodies in the [`ResponseCache`].
+    pub const CACHE_BYTES: DiagnosticPath = DiagnosticPath::const_new("http/cache_bytes");
+    /// Entities with an `HttpResponse` component.
+    pub const RESPONSE_ENTITIES: DiagnosticPath =
+        DiagnosticPath::const_new("http/response_entities");
+
+    /// Bytes of response bodies held in total.
+    pub fn total_bytes(&self) -> usize {
+        self.cache_bytes + self.response_bytes
+    }
+}
+
+/// Adds the resource and diagnostics of [`HttpMemory`].
+pub(crate) fn register(app: &mut App) {
+    app.init_resource::<HttpMemory>();
+    app.register_diagnostic(Diagnostic::new(HttpMemory::RETAINED_BYTES).with_suffix(" B"));
+    app.register_diagnostic(Diagnostic::new(HttpMemory::CACHE_BYTES).with_suffix(" B"));
+    app.register_diagnostic(Diagnostic::new(HttpMemory::RESPONSE_ENTITIES));
+}
+
+/// Counts the bodies held, and evicts cache entries and warns while they are over the budget.
+pub(crate) fn track_memory(
+    settings: Res<HttpClientSetting>,
+    mut cache: Option<ResMut<ResponseCache>>,
+    responses: Query<&HttpResponse>,
+    mut memory: ResMut<HttpMemory>,
+    mut diagnostics: Diagnostics,
+) {
+    let response_bytes = responses.iter().map(|response| response.bytes.len()).sum();
+    memory.response_bytes = response_bytes;
+    memory.response_entities = responses.iter().count();
+    memory.cache_bytes = cache.as_ref().map_or(0, |cache| cache.size_bytes());
+
+    if let Some(budget) = settings.memory_budget {
+        if let Some(cache) = cache
+            .as_mut()
+            .filter(|_| memory.total_bytes() > budget.max_bytes)
+        {
+            let evicted = cache.shrink_to(budget.max_bytes.saturating_sub(response_bytes));
+            memory.evicted += evicted as u64;
+            memory.cache_bytes = cache.size_bytes();
+        }
+
+        let over_budget = memory.total_bytes() > budget.max_bytes;
+        if over_budget && !memory.over_budget {
+            warn!(
+                "HTTP responses hold {} bytes, over the memory budget of {} bytes, {} of them in \
+                 the HttpResponse components of {} entities",
+                memory.total_bytes(),
+                budget.max_bytes,
+                memory.response_bytes,
+                memory.response_entities
+            );
+        }
+        memory.over_budget = over_budget;
+
+        let too_many_entities = memory.response_entities > budget.max_response_entities;
+        if too_many_entities && !memory.too_many_entities {
+            warn!(
+                "{} entities keep an HttpResponse component, over the limit of {}, are they \
+                 despawned once their responses are handled?",
+                memory.response_entities, budget.max_response_entities
+            );
+        }
+        memory.too_many_entities = too_many_entities;
+    }
+
+    memory.peak_bytes = memory.peak_bytes.max(memory.total_bytes());
+    diagnostics.add_measurement(&HttpMemory::RETAINED_BYTES, || memory.total_bytes() as f64);
+    diagnostics.add_measurement(&HttpMemory::CACHE_BYTES, || memory.cache_bytes as f64);
+    diagnostics.add_measurement(&HttpMemory::RESPONSE_ENTITIES, || {
+        memory.response_entities as f64
+    });
+}
@@ -0,0 +1,62 @@
+use ehttp::Headers;
+
+/// Build and platform information sent with every request, so the backend can segment traffic by game build.
+///
+/// Sent as the `X-Client-Name`, `X-Client-Version` and `X-Client-Platform` headers.
+/// Use the [`client_metadata!`](crate::client_metadata) macro to fill it from the game's own cargo metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientMetadata {
+    pub name: String,
+    pub version: String,
+    /// e.g. `linux-x86_64`, `windows-x86_64` or `web-wasm32`
+    pub platform: String,
+}
+
+impl ClientMetadata {
+    /// create client metadata for the current platform
+    pub fn new(name: impl ToString, version: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            platform: current_platform(),
+        }
+    }
+
+    pub(crate) fn headers(&self) -> [(&'static str, &str); 3] {
+        [
+            ("X-Client-Name", &self.name),
+            ("X-Client-Version", &self.version),
+            ("X-Client-Platform", &self.platform),
+        ]
+    }
+}
+
+fn current_platform() -> String {
+    let os = if cfg!(target_arch = "wasm32") {
+        "web"
+    } else {
+        std::env::consts::OS
+    };
+    format!("{os}-{}", std::env::consts::ARCH)
+}
+
+/// Creates a [`ClientMetadata`] from the `CARGO_PKG_NAME` and `CARGO_PKG_VERSION` of the crate invoking the macro.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(HttpClientSetting::default().with_client_metadata(client_metadata!()));
+/// ```
+#[macro_export]
+macro_rules! client_metadata {
+    () => {
+        $crate::ClientMetadata::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    };
+}
+
+/// Inserts the header unless the request already sets it.
+pub(crate) fn insert_default_header(headers: &mut Headers, key: &str, value: &str) {
+    if headers.get(key).is_none() {
+        headers.insert(key, value);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::HttpBuildError;
+
+/// Methods the fetch api refuses to send.
+const FORBIDDEN_FETCH_METHODS: [&str; 3] = ["CONNECT", "TRACE", "TRACK"];
+
+/// Checks that `method` is a valid method token, and one the backend can send.
+///
+/// Native backends send any method, e.g. `REPORT`, `PROPFIND` or vendor specific ones. Browsers
+/// refuse `CONNECT`, `TRACE` and `TRACK`.
+pub(crate) fn check_method(method: &str) -> Result<(), HttpBuildError> {
+    // The token characters of RFC 9110.
+    let is_token = !method.is_empty()
+        && method
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+    if !is_token {
+        return Err(HttpBuildError::InvalidMethod(method.to_string()));
+    }
+    if cfg!(target_arch = "wasm32") && is_forbidden_fetch_method(method) {
+        return Err(HttpBuildError::UnsupportedMethod(method.to_string()));
+    }
+    Ok(())
+}
+
+pub(crate) fn is_forbidden_fetch_method(method: &str) -> bool {
+    FORBIDDEN_FETCH_METHODS
+        .iter()
+        .any(|forbidden| method.eq_ignore_ascii_case(forbidden))
+}
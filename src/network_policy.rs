@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use bevy::utils::{Duration, Instant};
+
+#[cfg(target_arch = "wasm32")]
+use crate::HttpClock;
+
+/// Whether the connection is metered, and whether the player allowed large transfers on it.
+///
+/// Requests built with `HttpClient::large_transfer`, such as background DLC downloads, are deferred
+/// while the connection is metered until the player opts in with `allow_large_transfers`. They wait
+/// in the `RequestQueue` without holding back the other requests, and are sent once the
+/// connection is no longer metered or the player opted in.
+///
+/// The setting of the player takes precedence over the detected one. On wasm builds the connection
+/// is detected as metered when the browser reports a cellular connection or the data saver through
+/// the Network Information API. Other platforms report it with `set_detected`.
+///
+/// # Examples
+///
+/// ```
+/// fn download_prompt(
+///     policy: Res<NetworkPolicy>,
+///     pending: PendingRequests,
+///     mut prompt: Query<&mut Visibility, With<MeteredDownloadPrompt>>,
+/// ) {
+///     let deferred = policy.defers_large_transfers() && pending.large_transfers() > 0;
+///     *prompt.single_mut() = if deferred { Visibility::Visible } else { Visibility::Hidden };
+/// }
+///
+/// fn on_download_anyway(mut policy: ResMut<NetworkPolicy>) {
+///     policy.allow_large_transfers();
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct NetworkPolicy {
+    /// Set by the player, e.g. in the settings menu, takes precedence over `detected`.
+    pub user_metered: Option<bool>,
+    /// Reported by the platform, `None` if it is unknown.
+    detected: Option<bool>,
+    /// Whether the player opted in to large transfers on a metered connection.
+    pub large_transfers_allowed: bool,
+}
+
+impl NetworkPolicy {
+    /// check if the connection is metered, unmetered if neither the player nor the platform said
+    pub fn is_metered(&self) -> bool {
+        self.user_metered.or(self.detected).unwrap_or(false)
+    }
+
+    /// The connection cost reported by the platform, `None` if it is unknown.
+    pub fn detected(&self) -> Option<bool> {
+        self.detected
+    }
+
+    /// report whether the platform connection is metered, see `detected`
+    pub fn set_detected(&mut self, metered: Option<bool>) {
+        self.detected = metered;
+    }
+
+    /// treat the connection as metered or not regardless of the platform, see `user_metered`
+    pub fn set_metered(&mut self, metered: bool) {
+        self.user_metered = Some(metered);
+    }
+
+    /// send large transfers on a metered connection too, see `large_transfers_allowed`
+    pub fn allow_large_transfers(&mut self) {
+        self.large_transfers_allowed = true;
+    }
+
+    /// check if requests built with `HttpClient::large_transfer` are being held back
+    pub fn defers_large_transfers(&self) -> bool {
+        self.is_metered() && !self.large_transfers_allowed
+    }
+}
+
+/// Reads the connection of the browser every few seconds, it can change while the game runs.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn detect_metered(
+    mut policy: ResMut<NetworkPolicy>,
+    mut next: Local<Option<Instant>>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    if next.is_some_and(|next| now < next) {
+        return;
+    }
+    *next = Some(now + Duration::from_secs(5));
+    let detected = browser_metered();
+    if policy.detected != detected {
+        policy.detected = detected;
+    }
+}
+
+/// Whether `navigator.connection` reports a cellular connection or the data saver.
+#[cfg(target_arch = "wasm32")]
+fn browser_metered() -> Option<bool> {
+    use js_sys::Reflect;
+    use wasm_bindgen::JsValue;
+
+    let navigator = Reflect::get(&js_sys::global(), &JsValue::from_str("navigator")).ok()?;
+    let connection = Reflect::get(&navigator, &JsValue::from_str("connection")).ok()?;
+    if connection.is_undefined() || connection.is_null() {
+        return None;
+    }
+    let save_data = Reflect::get(&connection, &JsValue::from_str("saveData"))
+        .ok()
+        .is_some_and(|save_data| save_data.is_truthy());
+    let cellular = Reflect::get(&connection, &JsValue::from_str("type"))
+        .ok()
+        .and_then(|connection_type| connection_type.as_string())
+        .is_some_and(|connection_type| connection_type == "cellular");
+    Some(save_data || cellular)
+}
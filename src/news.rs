@@ -0,0 +1,223 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::SystemTime;
+use serde::de::DeserializeOwned;
+
+use crate::{HttpClient, HttpRequest, HttpSchedule, HttpSet, OnComplete};
+
+/// Fetches a news feed or message of the day of type `T` into the [`NewsFeed<T>`] resource, at
+/// startup and optionally on an interval.
+///
+/// The body is parsed as JSON. With a cache file the last fetched feed is shown right away on the
+/// next startup, with `last_updated` telling how old it is, until the fresh one arrives. Failed
+/// fetches keep the current content. Add it after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(
+///     NewsFeedPlugin::<Vec<NewsItem>>::new("https://cdn.example.com/news.json")
+///         .with_refresh(Duration::from_secs(600))
+///         .with_cache_file("news.json"),
+/// );
+///
+/// fn title_screen(news: Res<NewsFeed<Vec<NewsItem>>>) {
+///     if let (Some(items), Some(age)) = (news.content(), news.age()) {
+///         println!("{} items, updated {}s ago", items.len(), age.as_secs());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NewsFeedPlugin<T> {
+    pub url: String,
+    /// Time between two fetches, `None` to only fetch at startup.
+    pub refresh: Option<Duration>,
+    /// File the last fetched body is kept in. Not available on wasm builds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub cache_file: Option<PathBuf>,
+    _feed: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> NewsFeedPlugin<T> {
+    /// create the plugin fetching `url` once at startup
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            refresh: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            cache_file: None,
+            _feed: std::marker::PhantomData,
+        }
+    }
+
+    /// fetch the feed again every `interval`
+    pub fn with_refresh(mut self, interval: Duration) -> Self {
+        self.refresh = Some(interval);
+        self
+    }
+
+    /// keep the last fetched feed in `path`, see `cache_file`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_cache_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cache_file = Some(path.into());
+        self
+    }
+}
+
+impl<T: DeserializeOwned + Send + Sync + 'static> Plugin for NewsFeedPlugin<T> {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        #[cfg(not(target_arch = "wasm32"))]
+        let cached = self.cache_file.as_ref().and_then(read_cache_file);
+        #[cfg(target_arch = "wasm32")]
+        let cached: Option<(T, Option<SystemTime>)> = None;
+        let from_cache = cached.is_some();
+        let (content, last_updated) = cached.unzip();
+        app.insert_resource(NewsFeed::<T> {
+            content,
+            last_updated: last_updated.flatten(),
+            from_cache,
+            last_error: None,
+        });
+        app.insert_resource(NewsFeedFetcher::<T> {
+            url: self.url.clone(),
+            refresh: self.refresh,
+            #[cfg(not(target_arch = "wasm32"))]
+            cache_file: self.cache_file.clone(),
+            timer: Some(Timer::new(Duration::ZERO, TimerMode::Once)),
+            fetching: false,
+            _feed: std::marker::PhantomData,
+        });
+        app.add_systems(schedule, fetch_news_feed::<T>.before(HttpSet::Queue));
+    }
+}
+
+/// The feed fetched by the [`NewsFeedPlugin<T>`], with how fresh it is.
+#[derive(Resource, Debug)]
+pub struct NewsFeed<T> {
+    content: Option<T>,
+    last_updated: Option<SystemTime>,
+    from_cache: bool,
+    last_error: Option<String>,
+}
+
+impl<T> NewsFeed<T> {
+    /// The latest feed, `None` before the first fetch succeeded or a cached one was found.
+    pub fn content(&self) -> Option<&T> {
+        self.content.as_ref()
+    }
+
+    /// When the feed was fetched, from the file time for cached feeds.
+    pub fn last_updated(&self) -> Option<SystemTime> {
+        self.last_updated
+    }
+
+    /// Time since the feed was fetched.
+    pub fn age(&self) -> Option<Duration> {
+        SystemTime::now().duration_since(self.last_updated?).ok()
+    }
+
+    /// Whether there is no feed, or it was fetched longer than `max_age` ago.
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age().is_none_or(|age| age > max_age)
+    }
+
+    /// Whether the content was read from the cache file and not fetched in this run yet.
+    pub fn is_from_cache(&self) -> bool {
+        self.from_cache
+    }
+
+    /// The error of the latest fetch, cleared by the next successful one.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+}
+
+/// Kept apart from `NewsFeed<T>`, so change detection on the feed only fires when it changed.
+#[derive(Resource)]
+struct NewsFeedFetcher<T> {
+    url: String,
+    refresh: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    cache_file: Option<PathBuf>,
+    /// Until the next fetch, `None` once the only fetch was sent.
+    timer: Option<Timer>,
+    /// Whether a fetch is in flight, so slow servers are not asked twice.
+    fetching: bool,
+    _feed: std::marker::PhantomData<fn() -> T>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_cache_file<T: DeserializeOwned>(path: &PathBuf) -> Option<(T, Option<SystemTime>)> {
+    let bytes = std::fs::read(path).ok()?;
+    let content = serde_json::from_slice(&bytes).ok()?;
+    let modified = std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok();
+    Some((content, modified))
+}
+
+fn fetch_news_feed<T: DeserializeOwned + Send + Sync + 'static>(
+    time: Res<Time>,
+    mut fetcher: ResMut<NewsFeedFetcher<T>>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    let fetcher = &mut *fetcher;
+    let Some(timer) = &mut fetcher.timer else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() || fetcher.fetching {
+        return;
+    }
+    fetcher.timer = fetcher
+        .refresh
+        .map(|refresh| Timer::new(refresh, TimerMode::Once));
+    fetcher.fetching = true;
+
+    let on_complete = OnComplete::new(move |world, _, response| {
+        let mut fetcher = world.resource_mut::<NewsFeedFetcher<T>>();
+        fetcher.fetching = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        let cache_file = fetcher.cache_file.clone();
+        let body = response.and_then(|res| {
+            if res.ok {
+                Ok(res.bytes)
+            } else {
+                Err(format!("{} {}", res.status, res.status_text))
+            }
+        });
+        let content = body.and_then(|bytes| {
+            let content = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+            Ok((content, bytes))
+        });
+        match content {
+            Ok((content, bytes)) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(path) = cache_file {
+                    // Best effort, the feed is fetched again next time otherwise.
+                    let _ = std::fs::write(path, &bytes);
+                }
+                #[cfg(target_arch = "wasm32")]
+                drop(bytes);
+                let mut feed = world.resource_mut::<NewsFeed<T>>();
+                feed.content = Some(content);
+                feed.last_updated = Some(SystemTime::now());
+                feed.from_cache = false;
+                feed.last_error = None;
+            }
+            Err(error) => world.resource_mut::<NewsFeed<T>>().last_error = Some(error),
+        }
+    });
+    requests.send(HttpRequest {
+        on_complete: Some(on_complete),
+        ..HttpClient::new().get(&fetcher.url).build()
+    });
+}
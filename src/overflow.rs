@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{
+    HttpClientSetting, HttpRequest, RequestId, RequestLabel, RequestQueue, ResponseHandler,
+    REQUEST_QUEUE_FULL,
+};
+
+/// Caps how many requests wait for a client, so runaway request spawning fails predictably instead
+/// of growing the queue without bound.
+///
+/// The depth is checked every frame once the requests of the frame are queued, before any of them
+/// are dispatched, also while dispatching is suspended with `HttpClientPlugin::run_if`. Requests
+/// over the cap fail with a `HttpErrorKind::QueueFull` error without being sent, and a
+/// [`QueueSaturated`] event is sent for every label that lost requests. Requests waiting for a
+/// retry are only counted once they are queued again.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///     queue_limit: Some(QueueLimit::new(256).with_overflow(QueueOverflow::BlockLabel)),
+///     ..default()
+/// }));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueLimit {
+    /// Most requests waiting for a client, per label with `QueueOverflow::BlockLabel`.
+    pub max_queued: usize,
+    pub overflow: QueueOverflow,
+}
+
+impl QueueLimit {
+    /// queue at most `max_queued` requests, rejecting new ones beyond that
+    pub fn new(max_queued: usize) -> Self {
+        Self {
+            max_queued,
+            overflow: QueueOverflow::default(),
+        }
+    }
+
+    /// handle requests beyond `max_queued` with `overflow`, see `overflow`
+    pub fn with_overflow(mut self, overflow: QueueOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+/// Which requests fail when the queue is over its [`QueueLimit`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueueOverflow {
+    /// The newest requests fail, the ones that waited longest are kept.
+    #[default]
+    RejectNew,
+    /// The requests that waited longest fail, e.g. for position updates where only the latest
+    /// matters.
+    DropOldest,
+    /// The cap applies to every label on its own, the newest requests of a label over it fail
+    /// while other labels keep queueing. Requests without a label share one cap.
+    BlockLabel,
+}
+
+/// Requests of `label` failed because the queue was over its [`QueueLimit`].
+#[derive(Event, Debug, Clone)]
+pub struct QueueSaturated {
+    pub label: Option<RequestLabel>,
+    /// The failed requests, in the order they were queued.
+    pub dropped: Vec<RequestId>,
+    /// The requests still waiting once the queue was trimmed.
+    pub queued: usize,
+}
+
+/// Fails the requests over the queue limit, their handlers get the queue full error.
+pub(crate) fn limit_queue(
+    mut commands: Commands,
+    settings: Res<HttpClientSetting>,
+    mut queue: ResMut<RequestQueue>,
+    mut ev_saturated: EventWriter<QueueSaturated>,
+) {
+    let Some(limit) = settings.queue_limit else {
+        return;
+    };
+    let dropped = overflowing(&mut queue, limit);
+    if dropped.is_empty() {
+        return;
+    }
+
+    let mut by_label: Vec<(Option<RequestLabel>, Vec<RequestId>)> = vec![];
+    for (request, on_response) in dropped {
+        match by_label
+            .iter_mut()
+            .find(|(label, _)| *label == request.label)
+        {
+            Some((_, ids)) => ids.push(request.id),
+            None => by_label.push((request.label.clone(), vec![request.id])),
+        }
+        let request_id = request.id;
+        commands.add(move |world: &mut World| {
+            on_response(world, request_id, Err(REQUEST_QUEUE_FULL.to_string()));
+        });
+    }
+    for (label, dropped) in by_label {
+        ev_saturated.send(QueueSaturated {
+            label,
+            dropped,
+            queued: queue.len(),
+        });
+    }
+}
+
+/// Removes the requests over `limit` from the queue, in the order they were queued.
+fn overflowing(queue: &mut RequestQueue, limit: QueueLimit) -> Vec<(HttpRequest, ResponseHandler)> {
+    match limit.overflow {
+        QueueOverflow::RejectNew => {
+            let keep = queue.0.len().min(limit.max_queued);
+            queue.0.split_off(keep).into()
+        }
+        QueueOverflow::DropOldest => {
+            let drop = queue.0.len().saturating_sub(limit.max_queued);
+            queue.0.drain(..drop).collect()
+        }
+        QueueOverflow::BlockLabel => {
+            let mut counts: HashMap<Option<RequestLabel>, usize> = HashMap::new();
+            let (kept, dropped) = std::mem::take(&mut queue.0)
+                .into_iter()
+                .partition::<Vec<_>, _>(|(request, _)| {
+                    let count = counts.entry(request.label.clone()).or_default();
+                    *count += 1;
+                    *count <= limit.max_queued
+                });
+            queue.0 = kept.into();
+            dropped
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{Instant, SystemTime};
+use ehttp::{Headers, Response};
+
+use crate::{ClockSkew, HttpClient, HttpClock, HttpResponse, RequestId, RequestQueue};
+
+/// Reads the url of the next page from a page, `None` on the last one.
+type NextPageFn = Arc<dyn Fn(&Response) -> Option<String> + Send + Sync>;
+
+/// How often a page answered with `429 Too Many Requests` is asked for again before giving up.
+const MAX_RATE_LIMITED: u32 = 5;
+
+/// Follows a paginated listing from `url` page by page, paced so that fetching a 10k-entry
+/// leaderboard doesn't trip the rate limits of the server.
+///
+/// Every page is sent as a [`PageFetched`] event, and the [`PageProgress`] of the entity counts the
+/// pages fetched out of the estimated total. The next page is the `next` link of the `Link` header
+/// unless another one is set with `with_next`, relative urls are resolved against the page.
+///
+/// Page requests start at least `min_interval` apart. When a response says the server quota is
+/// used up with `RateLimit-Remaining: 0` or `X-RateLimit-Remaining: 0`, the next page waits until
+/// its `RateLimit-Reset` or `X-RateLimit-Reset`, and a `429 Too Many Requests` is asked for again
+/// after its `Retry-After`. Other failures stop the pagination with a [`PaginationFailed`] event,
+/// the last page with a [`PaginationFinished`] one. Removing the component or despawning the
+/// entity stops it too.
+///
+/// # Examples
+///
+/// ```
+/// fn fetch_leaderboard(mut commands: Commands) {
+///     commands.spawn(
+///         Paginate::new("https://api.example.com/leaderboard?per_page=100")
+///             .with_min_interval(Duration::from_millis(250))
+///             .with_next(|page| {
+///                 let body: serde_json::Value = serde_json::from_slice(&page.bytes).ok()?;
+///                 let cursor = body["next_cursor"].as_str()?;
+///                 Some(format!("/leaderboard?per_page=100&cursor={cursor}"))
+///             }),
+///     );
+/// }
+///
+/// fn show_progress(pages: Query<&PageProgress>) {
+///     for progress in pages.iter() {
+///         if let Some(fraction) = progress.fraction() {
+///             info!("leaderboard {:.0}%", fraction * 100.0);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Component, Clone)]
+pub struct Paginate {
+    /// The first page.
+    pub url: String,
+    /// Sent with every page request, e.g. `Authorization`.
+    pub headers: Vec<(String, String)>,
+    /// Time between the start of a page request and the next one.
+    pub min_interval: Duration,
+    /// Stop after this many pages, `None` to follow the listing to its end.
+    pub max_pages: Option<usize>,
+    next_page: NextPageFn,
+    next_url: Option<String>,
+    next_fetch: Option<Instant>,
+    rate_limited: u32,
+    fetching: bool,
+}
+
+impl Paginate {
+    /// follow the listing starting at `url` as fast as the server allows
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            headers: Vec::new(),
+            min_interval: Duration::ZERO,
+            max_pages: None,
+            next_page: Arc::new(link_next),
+            next_url: Some(url.to_string()),
+            next_fetch: None,
+            rate_limited: 0,
+            fetching: false,
+        }
+    }
+
+    /// send `name: value` with every page request, see `headers`
+    pub fn with_header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// start page requests at least `interval` apart, see `min_interval`
+    pub fn with_min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// stop after `max_pages` pages, see `max_pages`
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// Reads the url of the next page with `next` instead of from the `Link` header, e.g. to build
+    /// it from a cursor in the body. Returns `None` on the last page.
+    pub fn with_next(
+        mut self,
+        next: impl Fn(&Response) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.next_page = Arc::new(next);
+        self
+    }
+}
+
+/// How far the [`Paginate`] on the same entity got.
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageProgress {
+    pub pages_fetched: usize,
+    /// From an `X-Total-Pages` header or the `page` of the `last` link of the `Link` header, if
+    /// the server sent either.
+    pub estimated_total: Option<usize>,
+    /// Whether the last page was fetched.
+    pub finished: bool,
+}
+
+impl PageProgress {
+    /// The share of the pages fetched, `None` while the total is unknown.
+    pub fn fraction(&self) -> Option<f32> {
+        if self.finished {
+            return Some(1.0);
+        }
+        let total = self.estimated_total.filter(|total| *total > 0)?;
+        Some((self.pages_fetched as f32 / total as f32).min(1.0))
+    }
+}
+
+/// Sent for every page of a [`Paginate`], in order.
+#[derive(Event, Debug, Clone)]
+pub struct PageFetched {
+    /// The entity of the `Paginate`.
+    pub entity: Entity,
+    /// The page number, counting from 0.
+    pub page: usize,
+    pub response: HttpResponse,
+}
+
+/// Sent when a [`Paginate`] fetched its last page.
+#[derive(Event, Debug, Clone)]
+pub struct PaginationFinished {
+    /// The entity of the `Paginate`.
+    pub entity: Entity,
+    pub pages: usize,
+}
+
+/// Sent when a page of a [`Paginate`] failed, no further pages are fetched.
+#[derive(Event, Debug, Clone)]
+pub struct PaginationFailed {
+    /// The entity of the `Paginate`.
+    pub entity: Entity,
+    /// The page number, counting from 0.
+    pub page: usize,
+    pub error: String,
+}
+
+pub(crate) fn fetch_pages(
+    mut commands: Commands,
+    mut queue: ResMut<RequestQueue>,
+    mut paginations: Query<(Entity, &mut Paginate, Option<&PageProgress>)>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    for (entity, mut pagination, progress) in paginations.iter_mut() {
+        if progress.is_none() {
+            commands.entity(entity).insert(PageProgress::default());
+        }
+        if pagination.fetching || pagination.next_fetch.is_some_and(|next| now < next) {
+            continue;
+        }
+        let Some(url) = pagination.next_url.clone() else {
+            continue;
+        };
+        pagination.fetching = true;
+        pagination.next_fetch = Some(now + pagination.min_interval);
+        let mut request = HttpClient::new().get(&url).label("pagination");
+        for (name, value) in &pagination.headers {
+            request = request.header(name, value);
+        }
+        queue.push(request.build(), move |world, request_id, result| {
+            on_page(world, entity, request_id, result);
+        });
+    }
+}
+
+fn on_page(
+    world: &mut World,
+    entity: Entity,
+    request_id: RequestId,
+    result: ehttp::Result<Response>,
+) {
+    let skew = world.resource::<ClockSkew>().clone();
+    let now = world.resource::<HttpClock>().now();
+    let Some(mut entity_mut) = world.get_entity_mut(entity) else {
+        return;
+    };
+    let mut progress = entity_mut
+        .get::<PageProgress>()
+        .cloned()
+        .unwrap_or_default();
+    let page = progress.pages_fetched;
+    let Some(mut pagination) = entity_mut.get_mut::<Paginate>() else {
+        return;
+    };
+    pagination.fetching = false;
+    let response = match result {
+        Ok(res) if res.status == 429 && pagination.rate_limited < MAX_RATE_LIMITED => {
+            pagination.rate_limited += 1;
+            let wait = skew
+                .retry_after(&res.headers)
+                .unwrap_or(Duration::from_secs(1));
+            pagination.next_fetch = Some(now + wait.max(pagination.min_interval));
+            return;
+        }
+        Ok(res) if res.ok => Ok(res),
+        Ok(res) => Err(format!("{} {}", res.status, res.status_text)),
+        Err(error) => Err(error),
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(error) => {
+            pagination.next_url = None;
+            world.send_event(PaginationFailed {
+                entity,
+                page,
+                error,
+            });
+            return;
+        }
+    };
+
+    pagination.rate_limited = 0;
+    let next_url = (pagination.next_page)(&response)
+        .filter(|_| pagination.max_pages.is_none_or(|max| page + 1 < max))
+        .map(|next| resolve(&response.url, &next));
+    if let Some(wait) = quota_wait(&response.headers, &skew) {
+        let reset = now + wait;
+        pagination.next_fetch = Some(pagination.next_fetch.map_or(reset, |next| next.max(reset)));
+    }
+    let max_pages = pagination.max_pages;
+    let finished = next_url.is_none();
+    pagination.next_url = next_url;
+
+    progress.pages_fetched += 1;
+    progress.finished = finished;
+    if let Some(total) = total_pages(&response.headers) {
+        progress.estimated_total = Some(max_pages.map_or(total, |max| total.min(max)));
+    }
+    entity_mut.insert(progress);
+    world.send_event(PageFetched {
+        entity,
+        page,
+        response: HttpResponse::new(request_id, response),
+    });
+    if finished {
+        world.send_event(PaginationFinished {
+            entity,
+            pages: page + 1,
+        });
+    }
+}
+
+/// The target of the link of the `Link` header with the relation `rel`.
+fn link(headers: &Headers, rel: &str) -> Option<String> {
+    headers
+        .get_all("Link")
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let (target, params) = link.trim().strip_prefix('<')?.split_once('>')?;
+            params
+                .split(';')
+                .filter_map(|param| param.trim().strip_prefix("rel="))
+                .any(|rels| {
+                    rels.trim_matches('"')
+                        .split_whitespace()
+                        .any(|name| name.eq_ignore_ascii_case(rel))
+                })
+                .then(|| target.to_string())
+        })
+}
+
+fn link_next(res: &Response) -> Option<String> {
+    link(&res.headers, "next")
+}
+
+/// `url` relative to the page at `base`.
+fn resolve(base: &str, url: &str) -> String {
+    url::Url::parse(base)
+        .and_then(|base| base.join(url))
+        .map_or_else(|_| url.to_string(), String::from)
+}
+
+fn total_pages(headers: &Headers) -> Option<usize> {
+    if let Some(total) = headers.get("X-Total-Pages") {
+        return total.trim().parse().ok();
+    }
+    let last = url::Url::parse("http://localhost/")
+        .ok()?
+        .join(&link(headers, "last")?)
+        .ok()?;
+    let page = last.query_pairs().find(|(name, _)| name == "page")?.1;
+    page.parse().ok()
+}
+
+/// How long until the quota of the server refills, if the response says it is used up.
+fn quota_wait(headers: &Headers, skew: &ClockSkew) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .or_else(|| headers.get(&format!("X-{name}")))
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    if header("RateLimit-Remaining")? > 0 {
+        return None;
+    }
+    match header("RateLimit-Reset") {
+        // Some servers send the reset as a unix timestamp instead of the seconds left.
+        Some(reset) if reset > 1_000_000_000 => {
+            Some(skew.until(SystemTime::UNIX_EPOCH + Duration::from_secs(reset)))
+        }
+        Some(reset) => Some(Duration::from_secs(reset)),
+        None => skew.retry_after(headers),
+    }
+}
@@ -0,0 +1,72 @@
+use serde_json::{json, Map, Value};
+
+/// The `application/merge-patch+json` document turning `old` into `new`, see RFC 7386.
+///
+/// Fields missing from `new` are removed with `null`, objects are patched field by field and every
+/// other changed value, arrays included, is replaced whole. A root that is not an object on both
+/// sides is replaced by `new`.
+pub(crate) fn merge_patch(old: &Value, new: &Value) -> Value {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut patch = Map::new();
+            for key in old.keys().filter(|key| !new.contains_key(*key)) {
+                patch.insert(key.clone(), Value::Null);
+            }
+            for (key, value) in new {
+                match old.get(key) {
+                    Some(previous) if previous == value => {}
+                    Some(previous @ Value::Object(_)) if value.is_object() => {
+                        patch.insert(key.clone(), merge_patch(previous, value));
+                    }
+                    _ => {
+                        patch.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            Value::Object(patch)
+        }
+        _ => new.clone(),
+    }
+}
+
+/// The `application/json-patch+json` operations turning `old` into `new`, see RFC 6902.
+///
+/// Objects are compared field by field and arrays of the same length element by element, arrays
+/// that changed length are replaced whole.
+pub(crate) fn json_patch(old: &Value, new: &Value) -> Value {
+    let mut operations = Vec::new();
+    diff(&mut operations, String::new(), old, new);
+    Value::Array(operations)
+}
+
+fn diff(operations: &mut Vec<Value>, path: String, old: &Value, new: &Value) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for key in old.keys().filter(|key| !new.contains_key(*key)) {
+                let path = format!("{path}/{}", escape(key));
+                operations.push(json!({ "op": "remove", "path": path }));
+            }
+            for (key, value) in new {
+                let child = format!("{path}/{}", escape(key));
+                match old.get(key) {
+                    Some(previous) => diff(operations, child, previous, value),
+                    None => operations.push(json!({ "op": "add", "path": child, "value": value })),
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (index, (previous, value)) in old.iter().zip(new).enumerate() {
+                diff(operations, format!("{path}/{index}"), previous, value);
+            }
+        }
+        _ => operations.push(json!({ "op": "replace", "path": path, "value": new })),
+    }
+}
+
+/// Escapes a field name for a JSON pointer, see RFC 6901.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
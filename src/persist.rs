@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::{HashSet, Uuid};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{Store, StoredRequest};
+use crate::{
+    HttpRequest, HttpSchedule, HttpSet, RequestId, RequestLabel, RequestQueue, RequestTask,
+};
+
+/// Keeps the requests built with `HttpClient::persist` across restarts, so a crash does not lose
+/// telemetry or purchase confirmations.
+///
+/// The requests are written to the store whenever they enter or leave the queue, not only at exit,
+/// and stay there until they finished. At startup the stored requests are sent again with the same
+/// `RequestId`, their results arrive as `HttpResponse` and `HttpResponseError` events. A request
+/// that was in flight during a crash may be sent twice, give it an `Idempotency-Key` header if that
+/// matters. The store is a file on native builds and a `localStorage` entry on wasm builds. Add it
+/// after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(QueuePersistencePlugin::new("queue.json"));
+///
+/// fn send_receipt(mut requests: EventWriter<HttpRequest>, receipt: Res<Receipt>) {
+///     requests.send(
+///         HttpClient::new()
+///             .post("https://shop.example.com/confirm")
+///             .json(&*receipt)
+///             .persist()
+///             .build(),
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueuePersistencePlugin {
+    /// A file path on native builds, a `localStorage` key on wasm builds.
+    pub location: String,
+}
+
+impl QueuePersistencePlugin {
+    /// keep the persisted requests at `location`, see `location`
+    pub fn new(location: impl ToString) -> Self {
+        Self {
+            location: location.to_string(),
+        }
+    }
+}
+
+impl Plugin for QueuePersistencePlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        let store = Store::new(&self.location);
+        let requests: Vec<(RequestId, StoredQueuedRequest)> = store
+            .load::<Vec<StoredQueuedRequest>>()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|stored| match Uuid::parse_str(&stored.id) {
+                Ok(id) => Some((RequestId(id), stored)),
+                Err(e) => {
+                    warn!(
+                        "Dropped persisted request {:?} from {store}: {e}",
+                        stored.id
+                    );
+                    None
+                }
+            })
+            .collect();
+        if !requests.is_empty() {
+            info!("restoring {} persisted requests", requests.len());
+            app.world.send_event_batch(
+                requests
+                    .iter()
+                    .map(|(id, stored)| stored.to_request(*id))
+                    .collect::<Vec<_>>(),
+            );
+        }
+        app.insert_resource(PersistedQueue { store, requests });
+        app.add_systems(
+            schedule,
+            // Before the queued requests are sent, so every one of them is seen in the queue.
+            persist_queue
+                .after(HttpSet::Queue)
+                .before(HttpSet::Dispatch),
+        );
+    }
+}
+
+/// The requests in the store, in the order they were queued.
+#[derive(Resource)]
+struct PersistedQueue {
+    store: Store,
+    requests: Vec<(RequestId, StoredQueuedRequest)>,
+}
+
+/// A persisted request as written to the store.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredQueuedRequest {
+    id: String,
+    #[serde(flatten)]
+    request: StoredRequest,
+    label: Option<String>,
+    /// Milliseconds.
+    timeout: Option<u64>,
+}
+
+impl StoredQueuedRequest {
+    fn new(request: &HttpRequest) -> Self {
+        Self {
+            id: request.id.to_string(),
+            request: StoredRequest::from(&request.request),
+            label: request.label.as_ref().map(|label| label.to_string()),
+            timeout: request
+                .timeout
+                .map(|timeout| timeout.as_millis().try_into().unwrap_or(u64::MAX)),
+        }
+    }
+
+    fn to_request(&self, id: RequestId) -> HttpRequest {
+        HttpRequest {
+            id,
+            label: self.label.clone().map(RequestLabel::new),
+            timeout: self.timeout.map(Duration::from_millis),
+            persist: true,
+            ..HttpRequest::new(self.request.clone().into())
+        }
+    }
+}
+
+/// Rewrites the store when persisted requests were queued or finished.
+fn persist_queue(
+    mut persisted: ResMut<PersistedQueue>,
+    queue: Res<RequestQueue>,
+    tasks: Query<&RequestId, With<RequestTask>>,
+) {
+    let in_flight: HashSet<RequestId> = tasks.iter().copied().collect();
+    let queued: Vec<&HttpRequest> = queue
+        .0
+        .iter()
+        .map(|(request, _)| request)
+        .filter(|request| request.persist)
+        .collect();
+    let queued_ids: HashSet<RequestId> = queued.iter().map(|request| request.id).collect();
+
+    let previous = std::mem::take(&mut persisted.requests);
+    let before: Vec<RequestId> = previous.iter().map(|(id, _)| *id).collect();
+    // Sent requests stay until they finished, the queued ones follow in queue order.
+    let (mut requests, mut previous): (Vec<_>, Vec<_>) = previous
+        .into_iter()
+        .partition(|(id, _)| in_flight.contains(id) && !queued_ids.contains(id));
+    for request in queued {
+        let stored = match previous.iter().position(|(id, _)| *id == request.id) {
+            Some(index) => previous.swap_remove(index).1,
+            None => StoredQueuedRequest::new(request),
+        };
+        requests.push((request.id, stored));
+    }
+
+    let changed = requests.len() != before.len()
+        || requests
+            .iter()
+            .zip(&before)
+            .any(|((id, _), before)| id != before);
+    persisted.requests = requests;
+    if changed {
+        if persisted.requests.is_empty() {
+            persisted.store.clear();
+        } else {
+            let stored: Vec<_> = persisted
+                .requests
+                .iter()
+                .map(|(_, stored)| stored)
+                .collect();
+            persisted.store.save(&stored);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::HttpClientPlugin;
+
+    fn restore(name: &str, contents: &str) -> (App, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!(
+            "bevy_http_client_persist_{name}_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, contents).unwrap();
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            HttpClientPlugin::default(),
+            QueuePersistencePlugin::new(path.display()),
+        ));
+        (app, path)
+    }
+
+    #[test]
+    fn restores_stored_requests() {
+        let id = RequestId(Uuid::new_v4());
+        let stored = format!(
+            r#"[
+                {{"id": "{id}", "method": "POST", "url": "http://localhost/confirm", "headers": [], "body": [], "label": null, "timeout": 500}},
+                {{"id": "not a uuid", "method": "GET", "url": "http://localhost/", "headers": [], "body": [], "label": null, "timeout": null}}
+            ]"#
+        );
+        let (app, path) = restore("valid", &stored);
+        let restored = &app.world.resource::<PersistedQueue>().requests;
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].0, id);
+        let request = restored[0].1.to_request(id);
+        assert_eq!(request.request.url, "http://localhost/confirm");
+        assert_eq!(request.timeout, Some(Duration::from_millis(500)));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn starts_empty_from_a_corrupt_store() {
+        let (app, path) = restore("corrupt", "[{");
+        assert!(app.world.resource::<PersistedQueue>().requests.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
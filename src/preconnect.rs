@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::{HttpClient, HttpClock, RequestQueue};
+
+/// Opens a connection to the origin of `url` ahead of the first real request, e.g. during the
+/// loading screen, so the first call after matchmaking does not pay for the DNS lookup and the
+/// TCP and TLS handshakes.
+///
+/// The connection is opened with a `HEAD` request to the origin, whatever the answer, within the
+/// usual concurrency limit and labelled `preconnect` in `HttpStats`. It then stays in the
+/// connection pool, of the shared agent on native builds and of the browser on wasm builds.
+/// Servers close idle connections after a while, with a keep-alive interval the connection is
+/// opened again at that interval until it is removed from [`Preconnections`].
+///
+/// # Examples
+///
+/// ```
+/// fn warm_up(mut ev_preconnect: EventWriter<Preconnect>) {
+///     ev_preconnect.send(Preconnect::new("https://api.example.com"));
+///     ev_preconnect.send(
+///         Preconnect::new("https://match.example.com").keep_alive(Duration::from_secs(30)),
+///     );
+/// }
+/// ```
+#[derive(Event, Debug, Clone)]
+pub struct Preconnect {
+    /// Any url of the host, only its scheme, host and port are used.
+    pub url: String,
+    pub keep_alive: Option<Duration>,
+}
+
+impl Preconnect {
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            keep_alive: None,
+        }
+    }
+
+    /// open the connection again every `interval`, see [`Preconnections`]
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+}
+
+/// The origins kept connected by [`Preconnect`] events with a keep-alive interval.
+#[derive(Resource, Debug, Default)]
+pub struct Preconnections(HashMap<String, KeptAlive>);
+
+#[derive(Debug)]
+struct KeptAlive {
+    interval: Duration,
+    next_at: Instant,
+}
+
+impl Preconnections {
+    /// Whether the origin of `url` is kept connected.
+    pub fn contains(&self, url: &str) -> bool {
+        origin(url).is_some_and(|origin| self.0.contains_key(&origin))
+    }
+
+    /// Stops keeping the origin of `url` connected, the pooled connection is left to time out.
+    pub fn remove(&mut self, url: &str) -> bool {
+        origin(url).is_some_and(|origin| self.0.remove(&origin).is_some())
+    }
+
+    /// The origins kept connected, e.g. `https://api.example.com`.
+    pub fn origins(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+/// The scheme, host and port of `url`, e.g. `https://api.example.com:8443`.
+fn origin(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.origin().ascii_serialization())
+}
+
+pub(crate) fn handle_preconnects(
+    mut queue: ResMut<RequestQueue>,
+    mut preconnections: ResMut<Preconnections>,
+    mut preconnects: EventReader<Preconnect>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    let mut origins = vec![];
+    for preconnect in preconnects.read() {
+        let Some(origin) = origin(&preconnect.url) else {
+            warn!(
+                "cannot preconnect to {}, it is not an http url",
+                preconnect.url
+            );
+            continue;
+        };
+        if let Some(interval) = preconnect.keep_alive {
+            preconnections.0.insert(
+                origin.clone(),
+                KeptAlive {
+                    interval,
+                    next_at: now + interval,
+                },
+            );
+        }
+        origins.push(origin);
+    }
+    for (origin, kept_alive) in preconnections.0.iter_mut() {
+        if kept_alive.next_at <= now {
+            kept_alive.next_at = now + kept_alive.interval;
+            origins.push(origin.clone());
+        }
+    }
+
+    for origin in origins {
+        let request = HttpClient::new().head(&origin).label("preconnect");
+        // The answer is not read, only the connection matters.
+        #[cfg(target_arch = "wasm32")]
+        let request = request.mode(ehttp::Mode::NoCors);
+        queue.push(request.build(), move |_, _, result| {
+            if let Err(err) = result {
+                debug!("preconnect to {origin} failed: {err}");
+            }
+        });
+    }
+}
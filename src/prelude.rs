@@ -1,5 +1,74 @@
+pub use super::streaming::{BodyMode, HttpProgress, HttpResponseChunk};
 pub use super::typed::{HttpTypedRequestTrait, TypedRequest, TypedResponse};
 pub use super::{
-    HttpClient, HttpClientPlugin, HttpClientSetting, HttpRequest, HttpResponse, HttpResponseError,
-    RequestTask,
+    abort_requests_on_exit, format_http_date, join_url, parse_http_date, AbortRequest,
+    Authorization, BackendDegraded, BackendOptions, BatchResponse, BodyFormat, CacheControl,
+    CacheEvicted, ChainError, ChainErrorKind, ChainResponse, CleanupAfter, ClientMetadata,
+    ClockSkew, ClockSource, CloudSavePlugin, CloudSaves, ConnectionState, ConnectionStateChanged,
+    ConnectionStats, ContentType, Deadline, DeliveryId, DespawnOnResponse, DownloadSave,
+    Duplicates, DurableDelivered, DurableDeliveries, DurableDeliveryPlugin, DurableExpired, ETag,
+    EndpointDown, EndpointHealth, EndpointRecovered, EndpointStatus, Environment, Environments,
+    EventSource, EvictionReason, FairScheduling, FallbackUrls, Fault, FaultInjection,
+    FaultInjectionPlugin, GracefulShutdownPlugin, GraphError, GraphErrorKind, GraphResponse,
+    GraphResponses, HealthCheckPlugin, HostLimits, HttpBuildError, HttpClient, HttpClientControl,
+    HttpClientPlugin, HttpClientSetting, HttpClientSettings, HttpClock, HttpErrorKind,
+    HttpLogTarget, HttpLoggingPlugin, HttpMemory, HttpRequest, HttpResponse, HttpResponseError,
+    HttpSet, HttpShutdown, HttpStats, HttpStatusClass, HttpTaskPool, JsonFormat, Locale,
+    Localization, LocalizationChanged, LocalizationFailed, LocalizationPlugin, LogVerbosity,
+    MemoryBudget, NetworkConditions, NetworkPolicy, NetworkSimulation, NetworkSimulationPlugin,
+    NewsFeed, NewsFeedPlugin, OnComplete, PageFetched, PageProgress, Paginate, PaginationFailed,
+    PaginationFinished, PayloadCipher, PayloadEncryption, PendingRequests, Preconnect,
+    Preconnections, PriorityAging, QueryArrays, QueueLimit, QueueOverflow, QueuePersistencePlugin,
+    QueueSaturated, RaceResponse, Redaction, RedirectHop, RefreshServerList, RemoteChanged,
+    RemoteConfigChanged, RemoteConfigFailed, RemoteConfigPlugin, RemoteContent, RemoteWatchFailed,
+    RequestBatch, RequestBlocked, RequestCaching, RequestChain, RequestGraph, RequestHandle,
+    RequestId, RequestLabel, RequestPriority, RequestQueue, RequestRace, RequestStateScope,
+    RequestStats, RequestStatus, RequestTask, RequestTemplate, RequestTemplates, RequestTiming,
+    ResponseBudget, ResponseCache, ResponseCleanup, ResponseMeta, ResponseSignatures,
+    ResponseTransform, ResponseTransforms, RetryBudget, RetryPolicy, SaveConflict, SaveDownloaded,
+    SaveSyncFailed, SaveUploaded, SendBatch, SendDurable, SendTemplate, ServerBrowser,
+    ServerBrowserPlugin, ServerInfo, ServerSentEvent, ServiceLevelObjective, SignatureEncoding,
+    SignatureVerifier, SingleFlight, SingleFlights, SloBreach, SloPlugin, SloRecovered,
+    SloViolated, StatusCode, Telemetry, TelemetryPlugin, TemplateError, TypedHeader,
+    TypedRequestRegistry, TypedRequestStats, UpdateAvailable, UploadSave, UrlPolicy,
+    VersionCheckPlugin, VersionManifest, WatchRemote,
 };
+pub use crate::client_metadata;
+
+#[cfg(unix)]
+pub use super::transport::unix_socket_url;
+#[cfg(target_arch = "wasm32")]
+pub use super::transport::{CacheMode, Credentials, FetchOptions, ReferrerPolicy};
+#[cfg(all(not(target_arch = "wasm32"), any(feature = "asset", feature = "image")))]
+pub use super::DownloadCache;
+#[cfg(feature = "snapshot-testing")]
+pub use super::TypedSnapshots;
+#[cfg(not(target_arch = "wasm32"))]
+pub use super::{
+    CrashReport, CrashReportConsent, CrashReportPlugin, DownloadLimits, PendingCrashReports,
+    StreamingBody,
+};
+#[cfg(feature = "webdav")]
+pub use super::{DavEntry, WebDav};
+#[cfg(feature = "asset")]
+pub use super::{
+    HttpAssetPlugin, HttpAssets, RequestTemplateFile, RequestTemplateFilesPlugin,
+    RequestTemplateLoader,
+};
+#[cfg(feature = "image")]
+pub use super::{HttpImagePlugin, HttpImages, ImageDownloadFailed, ImageDownloaded};
+#[cfg(all(feature = "webhook-listener", not(target_arch = "wasm32")))]
+pub use super::{
+    HttpWebhookTrait, Webhook, WebhookListener, WebhookListenerPlugin, WebhookReceived,
+};
+#[cfg(feature = "json-schema")]
+pub use super::{JsonSchema, JsonSchemas, SchemaViolation};
+#[cfg(feature = "s3")]
+pub use super::{S3Bucket, S3Credentials, S3Listing, S3Object};
+#[cfg(feature = "websocket")]
+pub use super::{
+    WebSocket, WebSocketClosed, WebSocketError, WebSocketMessage, WebSocketOpened, WebSocketState,
+    WsMessage,
+};
+#[cfg(target_arch = "wasm32")]
+pub use ehttp::Mode;
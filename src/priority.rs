@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use bevy::utils::Instant;
+
+use crate::{FairScheduling, HttpRequest, ResponseHandler};
+
+/// How urgent a request is, waiting requests with a higher priority are dispatched first, see
+/// `HttpClient::priority`.
+///
+/// Requests of the same priority keep their order, or share the clients by label with
+/// [`FairScheduling`]. Any `i32` works, the constants are only a convention.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RequestPriority(pub i32);
+
+impl RequestPriority {
+    /// Background work such as downloads and telemetry.
+    pub const LOW: Self = Self(-1);
+    pub const NORMAL: Self = Self(0);
+    /// Requests the player is waiting for.
+    pub const HIGH: Self = Self(1);
+}
+
+/// Raises the priority of waiting requests by 1 for every `every` they waited, so low priority
+/// requests are not starved by a steady stream of high priority ones.
+///
+/// The wait counts from when the request last entered the queue, a retry starts over.
+///
+/// # Examples
+///
+/// ```
+/// // A `LOW` request goes ahead of new `HIGH` requests after waiting 10 seconds.
+/// app.add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///     priority_aging: Some(PriorityAging::new(Duration::from_secs(5))),
+///     ..default()
+/// }));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityAging {
+    /// How long a request waits for each step up.
+    pub every: Duration,
+    /// At most this many steps up.
+    pub max_boost: i32,
+}
+
+impl PriorityAging {
+    /// raise waiting requests a step every `every`, without limit
+    pub fn new(every: Duration) -> Self {
+        Self {
+            every,
+            max_boost: i32::MAX,
+        }
+    }
+
+    /// raise waiting requests at most `max_boost` steps, see `max_boost`
+    pub fn with_max_boost(mut self, max_boost: i32) -> Self {
+        self.max_boost = max_boost;
+        self
+    }
+
+    fn boost(&self, waited: Duration) -> i32 {
+        if self.every.is_zero() {
+            return self.max_boost;
+        }
+        let steps = waited.as_nanos() / self.every.as_nanos();
+        steps.min(self.max_boost.max(0) as u128) as i32
+    }
+}
+
+/// The index of the next request to dispatch among the `eligible` ones: the first of the highest
+/// priority, or the one `fair` picks among them.
+///
+/// `ceiling` is the highest priority an eligible request had in an earlier call of the same frame,
+/// requests only leave the queue and get no more eligible within a frame, so the search stops at
+/// the first request reaching it instead of going through a long queue for every client.
+pub(crate) fn next_index(
+    queue: &std::collections::VecDeque<(HttpRequest, ResponseHandler)>,
+    aging: Option<&PriorityAging>,
+    fair: Option<&mut FairScheduling>,
+    eligible: impl Fn(&HttpRequest) -> bool,
+    ceiling: &mut Option<i32>,
+    now: Instant,
+) -> Option<usize> {
+    let priority = |request: &HttpRequest| {
+        let boost = match (aging, request.queued_at) {
+            (Some(aging), Some(queued_at)) => aging.boost(now.saturating_duration_since(queued_at)),
+            _ => 0,
+        };
+        request.priority.0.saturating_add(boost)
+    };
+    let Some(fair) = fair else {
+        // The first request of the highest priority, without collecting a long queue.
+        let mut first: Option<(usize, i32)> = None;
+        for (index, (request, _)) in queue.iter().enumerate() {
+            if !eligible(request) {
+                continue;
+            }
+            let priority = priority(request);
+            if ceiling.is_some_and(|ceiling| priority >= ceiling) {
+                return Some(index);
+            }
+            if first.is_none_or(|(_, highest)| priority > highest) {
+                first = Some((index, priority));
+            }
+        }
+        *ceiling = first.map(|(_, priority)| priority);
+        return first.map(|(index, _)| index);
+    };
+    let eligible: Vec<(usize, i32)> = queue
+        .iter()
+        .enumerate()
+        .filter(|(_, (request, _))| eligible(request))
+        .map(|(index, (request, _))| (index, priority(request)))
+        .collect();
+    let highest = eligible.iter().map(|(_, priority)| *priority).max()?;
+    let candidates: Vec<usize> = eligible
+        .into_iter()
+        .filter(|(_, priority)| *priority == highest)
+        .map(|(index, _)| index)
+        .collect();
+    let labels = candidates
+        .iter()
+        .map(|index| queue[*index].0.label.as_ref());
+    fair.pick(labels).map(|pick| candidates[pick])
+}
@@ -0,0 +1,334 @@
+use bevy::prelude::*;
+use ehttp::{Headers, Request, Response};
+use serde_json::Value;
+
+/// The value secrets are replaced with.
+const REDACTED: &str = "<redacted>";
+
+/// Which headers, query parameters and JSON body fields are secrets, so logged and exported
+/// requests are safe to share.
+///
+/// Request logs and exports use the `Redaction` resource, e.g. `Redaction::to_curl`. Sensitive
+/// headers are matched case-insensitively, by default `Authorization`, `Proxy-Authorization`,
+/// `Cookie`, `Set-Cookie`, `X-Api-Key` and `X-Amz-Security-Token`; the headers of
+/// [`Environment::api_key`](crate::Environment::api_key) need to be added if they differ. Query
+/// parameters are matched case-insensitively too, by default the signature and session token of
+/// pre-signed S3 urls and common API key names such as `api_key` and `access_token`. Body
+/// fields are matched by dotted paths into JSON bodies, e.g. `password` or `players.*.token`, where
+/// `*` matches every key or array element. Bodies that are not JSON are kept as they are. Requests
+/// that are sent are never changed.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(
+///     Redaction::default()
+///         .with_header("X-Session")
+///         .with_query_param("sig")
+///         .with_body_path("password")
+///         .with_body_path("account.*.token"),
+/// );
+///
+/// fn copy_as_curl(redaction: Res<Redaction>, request: &HttpRequest) -> String {
+///     redaction.to_curl(&request.request)
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct Redaction {
+    /// Names of the headers whose values are replaced, matched case-insensitively.
+    pub headers: Vec<String>,
+    /// Names of the url query parameters whose values are replaced, matched case-insensitively.
+    pub query_params: Vec<String>,
+    /// Dotted paths of the JSON body fields whose values are replaced.
+    pub body_paths: Vec<String>,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self {
+            headers: [
+                "authorization",
+                "proxy-authorization",
+                "cookie",
+                "set-cookie",
+                "x-api-key",
+                "x-amz-security-token",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            query_params: [
+                "x-amz-signature",
+                "x-amz-security-token",
+                "x-amz-credential",
+                "api_key",
+                "api-key",
+                "apikey",
+                "access_token",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            body_paths: vec![],
+        }
+    }
+}
+
+impl Redaction {
+    /// redaction of nothing
+    pub fn none() -> Self {
+        Self {
+            headers: vec![],
+            query_params: vec![],
+            body_paths: vec![],
+        }
+    }
+
+    /// also redact the header `name`, see `headers`
+    pub fn with_header(mut self, name: impl AsRef<str>) -> Self {
+        self.headers.push(name.as_ref().to_ascii_lowercase());
+        self
+    }
+
+    /// also redact the url query parameter `name`, see `query_params`
+    pub fn with_query_param(mut self, name: impl AsRef<str>) -> Self {
+        self.query_params.push(name.as_ref().to_ascii_lowercase());
+        self
+    }
+
+    /// also redact the JSON body field at `path`, see `body_paths`
+    pub fn with_body_path(mut self, path: impl ToString) -> Self {
+        self.body_paths.push(path.to_string());
+        self
+    }
+
+    pub fn is_sensitive_header(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case(name))
+    }
+
+    pub fn is_sensitive_query_param(&self, name: &str) -> bool {
+        self.query_params
+            .iter()
+            .any(|param| param.eq_ignore_ascii_case(name))
+    }
+
+    /// The url with the values of sensitive query parameters replaced, the rest of it is kept as
+    /// it is.
+    pub fn url(&self, url: &str) -> String {
+        let (rest, fragment) = match url.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (url, None),
+        };
+        let Some((base, query)) = rest.split_once('?') else {
+            return url.to_string();
+        };
+        let params: Vec<String> = query
+            .split('&')
+            .map(|param| match param.split_once('=') {
+                Some((name, _)) if self.is_sensitive_query_param(&decode(name)) => {
+                    format!("{name}={REDACTED}")
+                }
+                _ => param.to_string(),
+            })
+            .collect();
+        let mut redacted = format!("{base}?{}", params.join("&"));
+        if let Some(fragment) = fragment {
+            redacted.push('#');
+            redacted.push_str(fragment);
+        }
+        redacted
+    }
+
+    /// The headers with the values of sensitive ones replaced.
+    pub fn headers(&self, headers: &Headers) -> Headers {
+        Headers {
+            headers: headers
+                .headers
+                .iter()
+                .map(|(name, value)| {
+                    if self.is_sensitive_header(name) {
+                        (name.clone(), REDACTED.to_string())
+                    } else {
+                        (name.clone(), value.clone())
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// The body with the values of the sensitive JSON fields replaced.
+    pub fn body(&self, body: &[u8]) -> Vec<u8> {
+        if self.body_paths.is_empty() {
+            return body.to_vec();
+        }
+        let Ok(mut json) = serde_json::from_slice::<Value>(body) else {
+            return body.to_vec();
+        };
+        for path in &self.body_paths {
+            let segments: Vec<&str> = path.split('.').collect();
+            redact_path(&mut json, &segments);
+        }
+        serde_json::to_vec(&json).unwrap_or_else(|_| body.to_vec())
+    }
+
+    /// The request with its secrets replaced, for logs and exports.
+    pub fn request(&self, request: &Request) -> Request {
+        let body = self.body(&request.body);
+        let mut headers = self.headers(&request.headers);
+        // The length of the redacted body.
+        for (name, value) in &mut headers.headers {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                *value = body.len().to_string();
+            }
+        }
+        Request {
+            url: self.url(&request.url),
+            headers,
+            body,
+            ..request.clone()
+        }
+    }
+
+    /// The response with its secrets replaced, for logs and exports.
+    pub fn response(&self, response: &Response) -> Response {
+        Response {
+            url: self.url(&response.url),
+            headers: self.headers(&response.headers),
+            bytes: self.body(&response.bytes),
+            ..response.clone()
+        }
+    }
+
+    /// The request as a `curl` command with its secrets replaced, e.g. to attach to bug reports.
+    /// Bodies that are not UTF-8 are left out.
+    pub fn to_curl(&self, request: &Request) -> String {
+        let request = self.request(request);
+        let mut command = format!("curl -X {} {}", request.method, shell_quote(&request.url));
+        for (name, value) in &request.headers.headers {
+            // curl sets the length itself.
+            if name.eq_ignore_ascii_case("Content-Length") {
+                continue;
+            }
+            command.push_str(" -H ");
+            command.push_str(&shell_quote(&format!("{name}: {value}")));
+        }
+        if !request.body.is_empty() {
+            match std::str::from_utf8(&request.body) {
+                Ok(body) => {
+                    command.push_str(" --data-binary ");
+                    command.push_str(&shell_quote(body));
+                }
+                Err(_) => {
+                    command.push_str(&format!(" # {} bytes of binary body", request.body.len()))
+                }
+            }
+        }
+        command
+    }
+}
+
+fn redact_path(value: &mut Value, path: &[&str]) {
+    let Some((segment, rest)) = path.split_first() else {
+        *value = Value::String(REDACTED.to_string());
+        return;
+    };
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if *segment == "*" || key == segment {
+                    redact_path(field, rest);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                if *segment == "*" || segment.parse() == Ok(index) {
+                    redact_path(item, rest);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A query parameter name as it is meant, with `+` for spaces and percent-encoding undone.
+fn decode(name: &str) -> String {
+    let name = name.replace('+', " ");
+    percent_encoding::percent_decode_str(&name)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Quotes `value` for POSIX shells.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> Request {
+        let mut request = Request::post(
+            "https://bucket.s3.amazonaws.com/save.bin?X-Amz-Credential=AKIA%2F20240101&X-Amz-Signature=abc123&part=2",
+            br#"{"user":"ada","password":"hunter2","players":[{"token":"t1"},{"token":"t2"}]}"#.to_vec(),
+        );
+        request.headers.insert("Authorization", "Bearer secret");
+        request.headers.insert("X-Amz-Security-Token", "session");
+        request.headers.insert("X-Request-Id", "42");
+        request
+    }
+
+    #[test]
+    fn redacts_headers() {
+        let headers = Redaction::default().headers(&request().headers);
+        assert_eq!(headers.get("authorization"), Some(REDACTED));
+        assert_eq!(headers.get("x-amz-security-token"), Some(REDACTED));
+        assert_eq!(headers.get("x-request-id"), Some("42"));
+        let headers = Redaction::none().headers(&request().headers);
+        assert_eq!(headers.get("authorization"), Some("Bearer secret"));
+    }
+
+    #[test]
+    fn redacts_body_paths() {
+        let redaction = Redaction::default()
+            .with_body_path("password")
+            .with_body_path("players.*.token");
+        let body: Value = serde_json::from_slice(&redaction.body(&request().body)).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "user": "ada",
+                "password": REDACTED,
+                "players": [{ "token": REDACTED }, { "token": REDACTED }],
+            })
+        );
+        assert_eq!(redaction.body(b"not json"), b"not json");
+    }
+
+    #[test]
+    fn redacts_query_params() {
+        let redaction = Redaction::default().with_query_param("Sig");
+        assert_eq!(
+            redaction.url(&request().url),
+            "https://bucket.s3.amazonaws.com/save.bin?X-Amz-Credential=<redacted>&X-Amz-Signature=<redacted>&part=2"
+        );
+        assert_eq!(
+            redaction.url("https://api.example.com/v1?api%5Fkey=k&sig=s&q=a+b#top"),
+            "https://api.example.com/v1?api%5Fkey=<redacted>&sig=<redacted>&q=a+b#top"
+        );
+        assert_eq!(
+            redaction.url("https://api.example.com/v1#api_key=k"),
+            "https://api.example.com/v1#api_key=k"
+        );
+    }
+
+    #[test]
+    fn curl_commands_leave_out_secrets() {
+        let command = Redaction::default().to_curl(&request());
+        assert!(!command.contains("abc123"), "{command}");
+        assert!(!command.contains("Bearer secret"), "{command}");
+        assert!(!command.contains("session"), "{command}");
+        assert!(command.starts_with("curl -X POST 'https://bucket.s3.amazonaws.com/save.bin?"));
+    }
+}
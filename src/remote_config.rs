@@ -0,0 +1,179 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+
+use crate::{HttpClient, HttpRequest, HttpSchedule, HttpSet, OnComplete};
+
+/// Fetches the resource `T` from a url at startup, and optionally on an interval, for live tuning
+/// without shipping a build.
+///
+/// The body is parsed as JSON unless another parser is set with `with_parser`. `T` is inserted once
+/// the first fetch succeeded, and replaced whenever a later fetch returns different values, each time
+/// with a [`RemoteConfigChanged<T>`] event. Failed fetches and parse errors keep the current values
+/// and send a [`RemoteConfigFailed<T>`] event. Add it after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Resource, Deserialize, PartialEq)]
+/// struct Tuning {
+///     enemy_speed: f32,
+///     xp_multiplier: f32,
+/// }
+///
+/// app.add_plugins(
+///     RemoteConfigPlugin::<Tuning>::new("https://cdn.example.com/tuning.toml")
+///         .with_refresh(Duration::from_secs(300))
+///         .with_parser(|bytes| {
+///             let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+///             toml::from_str(text).map_err(|e| e.to_string())
+///         }),
+/// );
+/// ```
+pub struct RemoteConfigPlugin<T> {
+    pub url: String,
+    /// Time between two fetches, `None` to only fetch at startup.
+    pub refresh: Option<Duration>,
+    /// Turns the response body into the config.
+    pub parser: fn(&[u8]) -> Result<T, String>,
+    /// Rejects configs without a valid signature, see [`ResponseSignatures`].
+    ///
+    /// [`ResponseSignatures`]: crate::ResponseSignatures
+    pub signed: bool,
+}
+
+impl<T: Resource + PartialEq + DeserializeOwned> RemoteConfigPlugin<T> {
+    /// create the plugin fetching JSON from `url` once at startup
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            refresh: None,
+            parser: parse_json::<T>,
+            signed: false,
+        }
+    }
+
+    /// fetch the config again every `interval`
+    pub fn with_refresh(mut self, interval: Duration) -> Self {
+        self.refresh = Some(interval);
+        self
+    }
+
+    /// parse the body with `parser` instead of as JSON, e.g. for TOML or RON configs
+    pub fn with_parser(mut self, parser: fn(&[u8]) -> Result<T, String>) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    /// reject configs whose signature does not verify, see `signed`
+    pub fn with_signature(mut self) -> Self {
+        self.signed = true;
+        self
+    }
+}
+
+impl<T: Resource + PartialEq> Plugin for RemoteConfigPlugin<T> {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        app.insert_resource(RemoteConfigState::<T> {
+            url: self.url.clone(),
+            refresh: self.refresh,
+            parser: self.parser,
+            signed: self.signed,
+            // The first fetch runs right away.
+            timer: Some(Timer::new(Duration::ZERO, TimerMode::Once)),
+            fetching: false,
+        });
+        app.add_event::<RemoteConfigChanged<T>>();
+        app.add_event::<RemoteConfigFailed<T>>();
+        app.add_systems(schedule, fetch_remote_config::<T>.before(HttpSet::Queue));
+    }
+}
+
+/// Sent when the fetched config was inserted or differs from the current one.
+#[derive(Event, Debug)]
+pub struct RemoteConfigChanged<T> {
+    /// The values before the change, `None` for the first fetch.
+    pub previous: Option<T>,
+}
+
+/// Sent when fetching or parsing the config failed, the current values are kept.
+#[derive(Event, Debug)]
+pub struct RemoteConfigFailed<T> {
+    pub error: String,
+    _config: PhantomData<T>,
+}
+
+#[derive(Resource)]
+struct RemoteConfigState<T> {
+    url: String,
+    refresh: Option<Duration>,
+    parser: fn(&[u8]) -> Result<T, String>,
+    signed: bool,
+    /// Until the next fetch, `None` once the only fetch was sent.
+    timer: Option<Timer>,
+    /// Whether a fetch is in flight, so slow servers are not asked twice.
+    fetching: bool,
+}
+
+fn parse_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    serde_json::from_slice(bytes).map_err(|e| e.to_string())
+}
+
+fn fetch_remote_config<T: Resource + PartialEq>(
+    time: Res<Time>,
+    mut state: ResMut<RemoteConfigState<T>>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    let state = &mut *state;
+    let Some(timer) = &mut state.timer else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() || state.fetching {
+        return;
+    }
+    state.timer = state
+        .refresh
+        .map(|refresh| Timer::new(refresh, TimerMode::Once));
+    state.fetching = true;
+
+    let parser = state.parser;
+    let on_complete = OnComplete::new(move |world, _, response| {
+        world.resource_mut::<RemoteConfigState<T>>().fetching = false;
+        let config = response.and_then(|res| {
+            if res.ok {
+                parser(&res.bytes)
+            } else {
+                Err(format!("{} {}", res.status, res.status_text))
+            }
+        });
+        match config {
+            Ok(config) => {
+                if world.get_resource::<T>() == Some(&config) {
+                    return;
+                }
+                let previous = world.remove_resource::<T>();
+                world.insert_resource(config);
+                world.send_event(RemoteConfigChanged::<T> { previous });
+            }
+            Err(error) => {
+                world.send_event(RemoteConfigFailed::<T> {
+                    error,
+                    _config: PhantomData,
+                });
+            }
+        }
+    });
+    requests.send(HttpRequest {
+        on_complete: Some(on_complete),
+        verify_signature: state.signed,
+        ..HttpClient::new().get(&state.url).build()
+    });
+}
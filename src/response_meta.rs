@@ -0,0 +1,112 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use ehttp::Headers;
+#[cfg(unix)]
+use ehttp::Response;
+
+use crate::RequestId;
+
+/// What is known about a response before its body, sent as an event and inserted on the request
+/// entity as soon as the headers arrive.
+///
+/// Streamed requests get it before their first `HttpResponseChunk`. The entity spawned for a
+/// request is despawned with it, an entity passed to `HttpClient::entity` keeps its `ResponseMeta`
+/// until its next request is sent.
+///
+/// # Examples
+///
+/// ```
+/// fn log_meta(mut ev_meta: EventReader<ResponseMeta>) {
+///     for meta in ev_meta.read() {
+///         println!("{} {} from {:?} over {:?}", meta.status, meta.url, meta.remote_addr, meta.version);
+///     }
+/// }
+/// ```
+#[derive(Event, Component, Debug, Clone)]
+pub struct ResponseMeta {
+    pub request_id: RequestId,
+    /// The entity carrying the request task.
+    pub entity: Entity,
+    /// The URL we ended up at, after following redirects.
+    pub url: String,
+    pub status: u16,
+    /// The protocol version, e.g. `HTTP/1.1`. `None` where the transport does not tell, e.g. on
+    /// wasm builds and for `unix://` urls.
+    pub version: Option<String>,
+    /// The address of the server, `None` where the transport does not tell, e.g. on wasm builds.
+    pub remote_addr: Option<SocketAddr>,
+    pub headers: Headers,
+    /// The redirects followed to get to `url`, in order. Always empty where the transport does not
+    /// tell, e.g. on wasm builds, where the browser follows redirects on its own.
+    pub redirects: Vec<RedirectHop>,
+}
+
+/// A redirect followed on the way to a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectHop {
+    /// The redirect status, e.g. `301` or `307`.
+    pub status: u16,
+    /// The url that answered with the redirect.
+    pub url: String,
+    /// Where the redirect pointed, resolved against `url`.
+    pub location: String,
+    /// How long the redirecting request took.
+    pub elapsed: Duration,
+}
+
+/// The response metadata as the transport reports it, before it is tied to its request.
+pub(crate) struct ResponseHead {
+    pub(crate) url: String,
+    pub(crate) status: u16,
+    pub(crate) version: Option<String>,
+    pub(crate) remote_addr: Option<SocketAddr>,
+    pub(crate) headers: Headers,
+    pub(crate) redirects: Vec<RedirectHop>,
+}
+
+#[cfg(unix)]
+impl ResponseHead {
+    /// The head of a response the transport has no connection details for.
+    pub(crate) fn from_response(response: &Response) -> Self {
+        Self {
+            url: response.url.clone(),
+            status: response.status,
+            version: None,
+            remote_addr: None,
+            headers: response.headers.clone(),
+            redirects: vec![],
+        }
+    }
+}
+
+impl ResponseMeta {
+    pub(crate) fn new(request_id: RequestId, entity: Entity, head: ResponseHead) -> Self {
+        Self {
+            request_id,
+            entity,
+            url: head.url,
+            status: head.status,
+            version: head.version,
+            remote_addr: head.remote_addr,
+            headers: head.headers,
+            redirects: head.redirects,
+        }
+    }
+
+    /// Did we get a 2xx response code?
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Whether the response came from a redirect.
+    pub fn was_redirected(&self) -> bool {
+        !self.redirects.is_empty()
+    }
+
+    /// The first value of the header, looked up case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+}
@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{HashSet, Instant};
+use ehttp::Response;
+
+use crate::{
+    idempotency, retry_budget, ClockSkew, DespawnOnResponse, HttpClock, HttpErrorKind, HttpRequest,
+    RequestId, RequestQueue, ResponseHandler, REQUEST_DEADLINE_EXCEEDED,
+};
+
+/// How often a failed request is sent again before its failure is reported, set with
+/// `HttpClient::retry`.
+///
+/// Transient errors, see `HttpErrorKind::is_transient`, and 5xx, `408` and `429` responses are
+/// retried. Other errors, e.g. blocked or duplicate requests and failed decryption, would fail
+/// again. The wait is random up to `backoff` for the first retry and up to twice as long for each
+/// next one, capped at `max_backoff`, so clients that failed together do not retry together. A
+/// `Retry-After` header of a `429` or `503` response is waited for at least. Mirrors of
+/// `HttpClient::fallback_urls` are all tried before a retry starts over at the primary url. POST
+/// and PATCH requests get an `Idempotency-Key` header shared by every attempt, so the backend can
+/// tell retries apart from new requests. Only the last failure is reported, unless the next retry
+/// would start after the [`Deadline`](crate::Deadline) of the request or the
+/// [`RetryBudget`](crate::RetryBudget) ran out.
+///
+/// # Examples
+///
+/// ```
+/// let policy = RetryPolicy::new(3).with_backoff(Duration::from_millis(500), Duration::from_secs(5));
+/// let http_client = HttpClient::new().get("https://api.example.com/inventory").retry(policy);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts after the first one.
+    pub max_retries: u32,
+    /// The wait before the first retry.
+    pub backoff: Duration,
+    /// The longest wait between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// retry up to `max_retries` times, waiting 1 second at first and at most 30 seconds
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    /// wait `backoff` before the first retry and at most `max_backoff`, see `backoff`
+    pub fn with_backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// The policy of the attempt after this one.
+    fn next(self) -> Self {
+        Self {
+            max_retries: self.max_retries - 1,
+            backoff: self.backoff.saturating_mul(2).min(self.max_backoff),
+            max_backoff: self.max_backoff,
+        }
+    }
+}
+
+/// Requests waiting for their next attempt.
+#[derive(Resource, Default)]
+pub(crate) struct PendingRetries(Vec<(Instant, HttpRequest, ResponseHandler)>);
+
+impl PendingRetries {
+    /// The waiting requests, in no particular order.
+    pub(crate) fn requests(&self) -> impl Iterator<Item = &HttpRequest> {
+        self.0.iter().map(|(_, request, _)| request)
+    }
+
+    /// Removes the waiting requests of the entity, returning their ids and response handlers.
+    pub(crate) fn take_entity(&mut self, entity: Entity) -> Vec<(RequestId, ResponseHandler)> {
+        self.take_where(|request| request.from_entity == Some(entity))
+    }
+
+    /// Removes the waiting requests with the given ids, returning them like `take_entity`.
+    pub(crate) fn take_requests(
+        &mut self,
+        request_ids: &HashSet<RequestId>,
+    ) -> Vec<(RequestId, ResponseHandler)> {
+        self.take_where(|request| request_ids.contains(&request.id))
+    }
+
+    fn take_where(
+        &mut self,
+        taken: impl Fn(&HttpRequest) -> bool,
+    ) -> Vec<(RequestId, ResponseHandler)> {
+        let (taken, kept) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, request, _)| taken(request));
+        self.0 = kept;
+        taken
+            .into_iter()
+            .map(|(_, request, on_response)| (request.id, on_response))
+            .collect()
+    }
+
+    /// Drops the waiting requests with the given ids.
+    pub(crate) fn remove_requests(&mut self, request_ids: &HashSet<RequestId>) {
+        self.0
+            .retain(|(_, request, _)| !request_ids.contains(&request.id));
+    }
+}
+
+/// Keys the request for its retries.
+pub(crate) fn resolve(request: &mut HttpRequest) {
+    if request.retry.is_some_and(|retry| retry.max_retries > 0) {
+        idempotency::ensure_key(&mut request.request);
+    }
+}
+
+/// Wraps `on_response` so a failed attempt waits for the backoff and queues the request again.
+pub(crate) fn with_retries(request: &HttpRequest, on_response: ResponseHandler) -> ResponseHandler {
+    let Some(retry) = request.retry.filter(|retry| retry.max_retries > 0) else {
+        return on_response;
+    };
+    let mut next = request.clone();
+    next.retry = Some(retry.next());
+    next.on_complete = None;
+
+    Box::new(move |world, request_id, response| {
+        // The entity of a `DespawnOnResponse` request is gone after this attempt.
+        let entity_despawned = next
+            .from_entity
+            .is_some_and(|entity| world.get::<DespawnOnResponse>(entity).is_some());
+        if !entity_despawned && should_retry(&response) {
+            let retry_after = response.as_ref().ok().and_then(|res| {
+                [429, 503]
+                    .contains(&res.status)
+                    .then(|| world.resource::<ClockSkew>().retry_after(&res.headers))
+                    .flatten()
+            });
+            let at = world.resource::<HttpClock>().now() + wait(retry, retry_after);
+            if next.deadline.is_some_and(|deadline| at >= deadline.0) {
+                // The retry would start too late.
+                let error = REQUEST_DEADLINE_EXCEEDED.to_string();
+                on_response(world, request_id, Err(error));
+                return;
+            }
+            if !retry_budget::allows_retry(world, &next) {
+                on_response(world, request_id, response);
+                return;
+            }
+            world
+                .resource_mut::<PendingRetries>()
+                .0
+                .push((at, next, on_response));
+        } else {
+            on_response(world, request_id, response);
+        }
+    })
+}
+
+/// Moves the requests whose backoff is over to the front of the queue.
+pub(crate) fn queue_due_retries(
+    mut retries: ResMut<PendingRetries>,
+    mut queue: ResMut<RequestQueue>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    let (due, waiting) = std::mem::take(&mut retries.0)
+        .into_iter()
+        .partition::<Vec<_>, _>(|(at, _, _)| *at <= now);
+    retries.0 = waiting;
+    for (_, request, on_response) in due.into_iter().rev() {
+        queue.0.push_front((request, on_response));
+    }
+}
+
+fn should_retry(response: &ehttp::Result<Response>) -> bool {
+    match response {
+        Ok(res) => res.status >= 500 || res.status == 408 || res.status == 429,
+        Err(e) => HttpErrorKind::classify(e).is_transient(),
+    }
+}
+
+/// The wait before the next attempt, random up to the backoff and at least `retry_after`.
+fn wait(retry: RetryPolicy, retry_after: Option<Duration>) -> Duration {
+    let backoff = retry
+        .backoff
+        .min(retry.max_backoff)
+        .mul_f64(fastrand::f64());
+    backoff.max(retry_after.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        REQUEST_ABORTED, REQUEST_BLOCKED, REQUEST_DUPLICATE, REQUEST_QUEUE_FULL, REQUEST_TIMED_OUT,
+    };
+
+    fn status(status: u16) -> ehttp::Result<Response> {
+        Ok(Response {
+            url: String::new(),
+            ok: (200..300).contains(&status),
+            status,
+            status_text: String::new(),
+            headers: ehttp::Headers::new(&[]),
+            bytes: vec![],
+        })
+    }
+
+    #[test]
+    fn retries_transient_failures() {
+        for code in [408, 429, 500, 502, 503] {
+            assert!(should_retry(&status(code)), "{code}");
+        }
+        for message in [
+            "Dns Failed: resolve api.example.com",
+            "Connection Failed: Connect error: connection refused",
+            "Connection Failed: timed out",
+            REQUEST_TIMED_OUT,
+            "Network Error: Connection reset by peer",
+        ] {
+            assert!(should_retry(&Err(message.to_string())), "{message}");
+        }
+    }
+
+    #[test]
+    fn does_not_retry_other_failures() {
+        for code in [200, 304, 400, 401, 404] {
+            assert!(!should_retry(&status(code)), "{code}");
+        }
+        let blocked = format!("{REQUEST_BLOCKED}: http://evil.example.com");
+        for message in [
+            REQUEST_ABORTED,
+            REQUEST_DEADLINE_EXCEEDED,
+            REQUEST_QUEUE_FULL,
+            REQUEST_DUPLICATE,
+            &blocked,
+            "Response signature is invalid",
+            "Response has no X-Signature header",
+            "Failed to decrypt response body: bad tag",
+            "Failed to sign request: no credentials",
+            "Bad URL: empty host",
+        ] {
+            assert!(!should_retry(&Err(message.to_string())), "{message}");
+        }
+    }
+
+    #[test]
+    fn waits_are_jittered_up_to_the_backoff() {
+        let retry =
+            RetryPolicy::new(3).with_backoff(Duration::from_secs(1), Duration::from_millis(500));
+        let waits: Vec<_> = (0..100).map(|_| wait(retry, None)).collect();
+        assert!(waits.iter().all(|wait| *wait <= Duration::from_millis(500)));
+        assert!(waits.iter().any(|wait| *wait != waits[0]));
+    }
+
+    #[test]
+    fn waits_at_least_retry_after() {
+        let retry = RetryPolicy::new(3);
+        let retry_after = Some(Duration::from_secs(5));
+        assert!((0..100).all(|_| wait(retry, retry_after) == Duration::from_secs(5)));
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::{HttpClientSetting, HttpClock, HttpRequest, RequestId};
+
+/// Caps how many retries all requests get together, so thousands of requests retrying through a
+/// backend outage do not hit it again in lockstep once it comes back.
+///
+/// Every retry takes a token from a shared bucket, which refills by `refill_per_second` up to
+/// `max_tokens`. Once it is empty, failed requests are not retried: their failure is reported right
+/// away, whatever their `RetryPolicy`, until tokens come back. A [`BackendDegraded`] event is sent
+/// when the bucket runs empty, and again the next time it runs empty after it was full.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///     retry_budget: Some(RetryBudget::new(50.0).with_refill(2.0)),
+///     ..default()
+/// }));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryBudget {
+    /// Retries that can happen in a burst.
+    pub max_tokens: f32,
+    /// Tokens added back every second.
+    pub refill_per_second: f32,
+}
+
+impl RetryBudget {
+    /// allow bursts of `max_tokens` retries, refilling the bucket in 10 seconds
+    pub fn new(max_tokens: f32) -> Self {
+        Self {
+            max_tokens,
+            refill_per_second: max_tokens / 10.0,
+        }
+    }
+
+    /// add back `refill_per_second` tokens every second, see `refill_per_second`
+    pub fn with_refill(mut self, refill_per_second: f32) -> Self {
+        self.refill_per_second = refill_per_second;
+        self
+    }
+}
+
+/// Sent when the [`RetryBudget`] ran out and failed requests stopped being retried.
+#[derive(Event, Debug, Clone)]
+pub struct BackendDegraded {
+    /// The first request whose failure was reported without a retry.
+    pub request_id: RequestId,
+    pub url: String,
+}
+
+/// The tokens left in the bucket of the [`RetryBudget`].
+#[derive(Resource, Default)]
+pub(crate) struct RetryTokens {
+    tokens: Option<f32>,
+    /// `None` until the first retry, the bucket starts full.
+    refilled_at: Option<Instant>,
+    /// Set when the bucket ran empty, cleared once it is full again.
+    degraded: bool,
+}
+
+impl RetryTokens {
+    /// Takes a token for a retry, `Err` with whether the budget just ran out if none is left.
+    fn take(&mut self, budget: RetryBudget, now: Instant) -> Result<(), bool> {
+        let elapsed = self
+            .refilled_at
+            .replace(now)
+            .map_or(0.0, |at| now.saturating_duration_since(at).as_secs_f32());
+        let tokens = self.tokens.get_or_insert(budget.max_tokens);
+        *tokens = (*tokens + elapsed * budget.refill_per_second).min(budget.max_tokens);
+        if *tokens >= budget.max_tokens {
+            self.degraded = false;
+        }
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            return Ok(());
+        }
+        Err(!std::mem::replace(&mut self.degraded, true))
+    }
+}
+
+/// Whether the retry of `request` fits the budget, sending [`BackendDegraded`] if it just ran out.
+pub(crate) fn allows_retry(world: &mut World, request: &HttpRequest) -> bool {
+    let Some(budget) = world
+        .get_resource::<HttpClientSetting>()
+        .and_then(|settings| settings.retry_budget)
+    else {
+        return true;
+    };
+    let now = world.resource::<HttpClock>().now();
+    let ran_out = match world.resource_mut::<RetryTokens>().take(budget, now) {
+        Ok(()) => return true,
+        Err(ran_out) => ran_out,
+    };
+    if ran_out {
+        warn!(
+            "Retry budget exhausted at {}, failed requests are not retried",
+            request.request.url
+        );
+        world.send_event(BackendDegraded {
+            request_id: request.id,
+            url: request.request.url.clone(),
+        });
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn refills_with_the_time_it_is_given() {
+        let budget = RetryBudget::new(2.0).with_refill(1.0);
+        let mut tokens = RetryTokens::default();
+        // Long after the bucket was created, as on a virtual clock that started late.
+        let start = Instant::now() + Duration::from_secs(60);
+        assert_eq!(tokens.take(budget, start), Ok(()));
+        assert_eq!(tokens.take(budget, start), Ok(()));
+        assert_eq!(tokens.take(budget, start), Err(true));
+        assert_eq!(
+            tokens.take(budget, start + Duration::from_millis(500)),
+            Err(false)
+        );
+        assert_eq!(
+            tokens.take(budget, start + Duration::from_millis(1100)),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use std::time::Duration;
+
+use bevy::utils::SystemTime;
+
+use crate::http_date::days_from_civil;
+use crate::sigv4::{self, Scope, UNSIGNED_PAYLOAD};
+use crate::xml::{self, XmlTag};
+use crate::{ClockSkew, ETag, HttpClient, HttpRequest, RequestSigner, TypedHeader};
+
+/// An S3 bucket, on AWS or an S3-compatible store such as MinIO, Cloudflare R2 or Backblaze B2,
+/// for user generated content and saves kept in object storage without an api server of their
+/// own. Only available with the `s3` feature.
+///
+/// Requests are signed with AWS Signature Version 4 as the last step of dispatch, see
+/// [`RequestSigner`], against the server clock estimated by [`ClockSkew`], so the headers of an
+/// `Environment`, idempotency keys and `PayloadEncryption` are signed as they are sent, and every
+/// retry is signed again. A request that fails to sign fails without being sent. Pre-signed urls
+/// can be handed to players or other services to download or upload one object without the
+/// credentials.
+///
+/// # Examples
+///
+/// ```
+/// let bucket = S3Bucket::new(
+///     "https://s3.eu-west-1.amazonaws.com",
+///     "eu-west-1",
+///     "my-game-ugc",
+///     S3Credentials::new(access_key_id, secret_access_key),
+/// );
+///
+/// ev_request.send(bucket.put("levels/42.json", level_json, "application/json"));
+/// ev_request.send(bucket.list("levels/", None));
+/// let share_url = bucket.presign_get("levels/42.json", Duration::from_secs(3600), &skew)?;
+///
+/// for response in ev_response.read() {
+///     let listing = S3Bucket::parse_listing(&response.bytes)?;
+///     println!("{} levels", listing.objects.len());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct S3Bucket {
+    /// The url of the service, e.g. `https://s3.eu-west-1.amazonaws.com`.
+    pub endpoint: String,
+    /// The region the requests are signed for, e.g. `eu-west-1`, or `auto` on R2.
+    pub region: String,
+    pub bucket: String,
+    pub credentials: S3Credentials,
+    /// Whether the bucket is the first segment of the path, rather than a subdomain of the
+    /// endpoint. Most S3-compatible stores only support path-style urls.
+    pub path_style: bool,
+}
+
+impl S3Bucket {
+    /// address `bucket` at `endpoint` with path-style urls
+    pub fn new(
+        endpoint: impl ToString,
+        region: impl ToString,
+        bucket: impl ToString,
+        credentials: S3Credentials,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.to_string().trim_end_matches('/').to_string(),
+            region: region.to_string(),
+            bucket: bucket.to_string(),
+            credentials,
+            path_style: true,
+        }
+    }
+
+    /// address the bucket as a subdomain of the endpoint, see `path_style`
+    pub fn with_virtual_hosted_style(mut self) -> Self {
+        self.path_style = false;
+        self
+    }
+
+    /// The url of the object `key`, or of the bucket if `key` is empty.
+    pub fn object_url(&self, key: &str) -> String {
+        let key: Vec<String> = key.split('/').map(sigv4::encode).collect();
+        let key = key.join("/");
+        if self.path_style {
+            return format!("{}/{}/{key}", self.endpoint, self.bucket);
+        }
+        match self.endpoint.split_once("://") {
+            Some((scheme, host)) => format!("{scheme}://{}.{host}/{key}", self.bucket),
+            None => format!("{}.{}/{key}", self.bucket, self.endpoint),
+        }
+    }
+
+    /// Builds the request and has it signed when it is dispatched, e.g. one with a label or
+    /// `respond_to` entity for `object_url`. The body is hashed, streamed bodies are sent unsigned.
+    pub fn sign(&self, client: HttpClient) -> HttpRequest {
+        let mut request = client.build();
+        let streams = request_streams(&request);
+        let credentials = self.credentials.clone();
+        let region = self.region.clone();
+        request.signer = Some(RequestSigner::new(move |request, now| {
+            let payload_hash = if streams {
+                UNSIGNED_PAYLOAD.to_string()
+            } else {
+                sigv4::hex(&sigv4::sha256(&request.body))
+            };
+            sigv4::set_header(request, "x-amz-content-sha256", &payload_hash);
+            let scope = Scope {
+                credentials: &credentials,
+                region: &region,
+                service: "s3",
+                time: now,
+            };
+            sigv4::sign_request(request, &scope, &payload_hash)
+        }));
+        request
+    }
+
+    /// A signed request downloading the object `key`.
+    pub fn get(&self, key: &str) -> HttpRequest {
+        self.sign(HttpClient::new().get(self.object_url(key)))
+    }
+
+    /// A signed request uploading `body` as the object `key`, replacing it if it exists.
+    pub fn put(&self, key: &str, body: impl Into<Vec<u8>>, content_type: &str) -> HttpRequest {
+        self.sign(
+            HttpClient::new()
+                .put(self.object_url(key))
+                .header("Content-Type", content_type)
+                .bytes(body),
+        )
+    }
+
+    /// A signed request uploading `body` as the object `key` while it is read, see
+    /// [`StreamingBody`]. Only available on native builds.
+    ///
+    /// [`StreamingBody`]: crate::StreamingBody
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn put_streaming(&self, key: &str, body: crate::StreamingBody) -> HttpRequest {
+        self.sign(
+            HttpClient::new()
+                .put(self.object_url(key))
+                .streaming_body(body),
+        )
+    }
+
+    /// A signed request deleting the object `key`.
+    pub fn delete(&self, key: &str) -> HttpRequest {
+        self.sign(HttpClient::new().delete(self.object_url(key)))
+    }
+
+    /// A signed request listing the objects starting with `prefix`, up to 1000 of them, read it
+    /// with [`S3Bucket::parse_listing`]. Keys are grouped into prefixes at the next `/`, like
+    /// folders. Pass the `next_continuation_token` of the previous listing for the next page.
+    pub fn list(&self, prefix: &str, continuation_token: Option<&str>) -> HttpRequest {
+        let mut url = format!(
+            "{}?list-type=2&delimiter=%2F&prefix={}",
+            self.object_url(""),
+            sigv4::encode(prefix)
+        );
+        if let Some(token) = continuation_token {
+            url.push_str(&format!("&continuation-token={}", sigv4::encode(token)));
+        }
+        self.sign(HttpClient::new().get(url))
+    }
+
+    /// A url anyone can download the object `key` from for `expires_in`, at most 7 days, from the
+    /// current time of the server clock.
+    pub fn presign_get(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        skew: &ClockSkew,
+    ) -> Result<String, String> {
+        self.presign("GET", key, expires_in, skew.server_now())
+    }
+
+    /// A url anyone can upload the object `key` to with a `PUT` for `expires_in`, at most 7 days,
+    /// from the current time of the server clock.
+    pub fn presign_put(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        skew: &ClockSkew,
+    ) -> Result<String, String> {
+        self.presign("PUT", key, expires_in, skew.server_now())
+    }
+
+    fn presign(
+        &self,
+        method: &str,
+        key: &str,
+        expires_in: Duration,
+        now: SystemTime,
+    ) -> Result<String, String> {
+        let scope = Scope {
+            credentials: &self.credentials,
+            region: &self.region,
+            service: "s3",
+            time: now,
+        };
+        sigv4::presign_url(method, &self.object_url(key), &scope, expires_in)
+    }
+
+    /// Reads the `ListBucketResult` body of a `list` response.
+    pub fn parse_listing(body: &[u8]) -> Result<S3Listing, String> {
+        let body = std::str::from_utf8(body).map_err(|e| format!("Invalid listing: {e}"))?;
+        let mut listing = S3Listing::default();
+        let mut object: Option<S3Object> = None;
+        let mut in_prefixes = false;
+        for tag in xml::tags(body) {
+            match tag? {
+                XmlTag::Open(name) | XmlTag::Empty(name) => match name {
+                    "Contents" => object = Some(S3Object::default()),
+                    "CommonPrefixes" => in_prefixes = true,
+                    _ => {}
+                },
+                XmlTag::Close(name, value) => match (name, object.as_mut()) {
+                    ("Key", Some(object)) => object.key = value,
+                    ("Size", Some(object)) => object.size = value.parse().unwrap_or_default(),
+                    ("ETag", Some(object)) => object.etag = ETag::parse(&value),
+                    ("LastModified", Some(object)) => {
+                        object.last_modified = parse_timestamp(&value);
+                    }
+                    ("Contents", _) => listing.objects.extend(object.take()),
+                    ("Prefix", None) if in_prefixes => listing.prefixes.push(value),
+                    ("CommonPrefixes", _) => in_prefixes = false,
+                    ("NextContinuationToken", None) => {
+                        listing.next_continuation_token = Some(value);
+                    }
+                    _ => {}
+                },
+            }
+        }
+        Ok(listing)
+    }
+}
+
+/// The access key the S3 requests are signed with.
+#[derive(Clone, PartialEq, Eq)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// The session token of temporary credentials, e.g. from an identity pool.
+    pub session_token: Option<String>,
+}
+
+impl S3Credentials {
+    pub fn new(access_key_id: impl ToString, secret_access_key: impl ToString) -> Self {
+        Self {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            session_token: None,
+        }
+    }
+
+    /// sign with temporary credentials, see `session_token`
+    pub fn with_session_token(mut self, session_token: impl ToString) -> Self {
+        self.session_token = Some(session_token.to_string());
+        self
+    }
+}
+
+impl std::fmt::Debug for S3Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A page of the objects in an [`S3Bucket`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct S3Listing {
+    pub objects: Vec<S3Object>,
+    /// The common prefixes of the keys up to their next `/`, e.g. `levels/castles/`.
+    pub prefixes: Vec<String>,
+    /// Passed to `S3Bucket::list` for the next page, `None` on the last page.
+    pub next_continuation_token: Option<String>,
+}
+
+/// An object in an [`S3Listing`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct S3Object {
+    pub key: String,
+    /// The size in bytes.
+    pub size: u64,
+    pub etag: Option<ETag>,
+    pub last_modified: Option<SystemTime>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn request_streams(request: &HttpRequest) -> bool {
+    request.streaming_body.is_some()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn request_streams(_request: &HttpRequest) -> bool {
+    false
+}
+
+/// Parses an ISO 8601 timestamp in UTC such as `2026-10-12T17:50:30.000Z`.
+fn parse_timestamp(value: &str) -> Option<SystemTime> {
+    let (date, time) = value.strip_suffix('Z')?.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(str::parse::<u64>);
+    let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    let days = days_from_civil(year, u32::try_from(month).ok()?, u32::try_from(day).ok()?);
+    let secs = u64::try_from(days).ok()? * 86400 + hour * 3600 + minute * 60 + second;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `ListObjectsV2` answer of AWS, with a delimiter and a next page.
+    const AWS_LISTING: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Name>my-game-ugc</Name><Prefix>levels/</Prefix><NextContinuationToken>1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=</NextContinuationToken><KeyCount>3</KeyCount><MaxKeys>2</MaxKeys><Delimiter>/</Delimiter><IsTruncated>true</IsTruncated><Contents><Key>levels/42.json</Key><LastModified>2023-10-09T12:36:50.000Z</LastModified><ETag>&quot;9b2cf535f27731c974343645a3985328&quot;</ETag><Size>2048</Size><StorageClass>STANDARD</StorageClass></Contents><Contents><Key>levels/a&amp;b.json</Key><LastModified>2023-10-10T08:15:02.000Z</LastModified><ETag>&quot;3858f62230ac3c915f300c664312c11f-2&quot;</ETag><Size>10485760</Size><StorageClass>STANDARD</StorageClass></Contents><CommonPrefixes><Prefix>levels/castles/</Prefix></CommonPrefixes></ListBucketResult>"#;
+
+    #[test]
+    fn parses_an_aws_listing() {
+        let listing = S3Bucket::parse_listing(AWS_LISTING.as_bytes()).unwrap();
+        assert_eq!(listing.objects.len(), 2);
+        assert_eq!(listing.objects[0].key, "levels/42.json");
+        assert_eq!(listing.objects[0].size, 2048);
+        assert_eq!(
+            listing.objects[0].etag,
+            Some(ETag::strong("9b2cf535f27731c974343645a3985328"))
+        );
+        assert_eq!(
+            listing.objects[0].last_modified,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1696855010))
+        );
+        assert_eq!(listing.objects[1].key, "levels/a&b.json");
+        assert_eq!(listing.objects[1].size, 10485760);
+        assert_eq!(listing.prefixes, ["levels/castles/"]);
+        assert_eq!(
+            listing.next_continuation_token.as_deref(),
+            Some("1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=")
+        );
+    }
+
+    #[test]
+    fn parses_comments_and_cdata() {
+        let body = "<?xml version=\"1.0\"?>\n<!-- served by MinIO -->\n<ListBucketResult>\
+            <Contents><Key><![CDATA[levels/<draft>.json]]></Key><Size>7</Size></Contents>\
+            <!-- <Contents><Key>hidden</Key></Contents> -->\
+            </ListBucketResult>";
+        let listing = S3Bucket::parse_listing(body.as_bytes()).unwrap();
+        assert_eq!(listing.objects.len(), 1);
+        assert_eq!(listing.objects[0].key, "levels/<draft>.json");
+        assert_eq!(listing.objects[0].size, 7);
+        assert!(listing.next_continuation_token.is_none());
+    }
+}
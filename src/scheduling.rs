@@ -0,0 +1,86 @@
+use bevy::utils::HashMap;
+
+use crate::RequestLabel;
+
+/// The pass a label advances by per dispatched request, divided by its weight.
+const STRIDE: u64 = 1 << 20;
+
+/// Shares the clients between the [`RequestLabel`]s of waiting requests, so a burst of one kind of
+/// request, e.g. telemetry uploads, cannot starve another, e.g. matchmaking calls.
+///
+/// Only matters once every client is busy and requests wait in the queue. Each label then gets
+/// clients in proportion to its weight, 1 unless configured, and requests of the same label keep
+/// their order. Requests without a label share one group. A label that had nothing waiting does
+/// not save up its share, it starts level with the labels that did.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///     max_concurrent: 4,
+///     fair_scheduling: Some(FairScheduling::new().with_weight("matchmaking", 4)),
+///     ..default()
+/// }));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FairScheduling {
+    /// Weights by label, from 1 up.
+    pub weights: HashMap<String, u32>,
+    /// Where each label is in the schedule, lower goes first.
+    passes: HashMap<Option<RequestLabel>, u64>,
+    /// The pass of the last dispatched request.
+    now: u64,
+}
+
+impl FairScheduling {
+    /// fair scheduling with every label weighted 1
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// give `label` `weight` times the share of an unweighted label, see `weights`
+    pub fn with_weight(mut self, label: impl ToString, weight: u32) -> Self {
+        self.weights.insert(label.to_string(), weight);
+        self
+    }
+
+    fn weight(&self, label: Option<&RequestLabel>) -> u32 {
+        label
+            .and_then(|label| self.weights.get(label.0.as_ref()))
+            .copied()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// Where `label` is in the schedule, a label that fell behind while it had nothing waiting
+    /// catches up to the last dispatched request.
+    fn pass(&self, label: Option<&RequestLabel>) -> u64 {
+        let pass = self.passes.get(&label.cloned()).copied().unwrap_or(0);
+        pass.max(self.now)
+    }
+
+    /// Picks the index of the next request to dispatch from the labels of the waiting requests, in
+    /// queue order.
+    pub(crate) fn pick<'a>(
+        &mut self,
+        labels: impl Iterator<Item = Option<&'a RequestLabel>>,
+    ) -> Option<usize> {
+        let mut best: Option<(usize, Option<&RequestLabel>, u64)> = None;
+        let mut seen = Vec::new();
+        for (index, label) in labels.enumerate() {
+            if seen.contains(&label) {
+                continue;
+            }
+            seen.push(label);
+            let pass = self.pass(label);
+            if best.is_none_or(|(_, _, best)| pass < best) {
+                best = Some((index, label, pass));
+            }
+        }
+        let (index, label, pass) = best?;
+        let stride = STRIDE / u64::from(self.weight(label));
+        self.now = pass;
+        self.passes.insert(label.cloned(), pass + stride);
+        Some(index)
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+use crate::AbortRequest;
+
+/// Ties the requests of an entity to a state, see [`abort_requests_on_exit`].
+///
+/// # Examples
+///
+/// ```
+/// let entity = commands.spawn(RequestStateScope(AppState::InGame)).id();
+/// ev_request.send(HttpClient::new().get("http://example.com").entity(entity).build());
+/// ```
+#[derive(Component, Debug, Clone)]
+pub struct RequestStateScope<S: States>(pub S);
+
+/// Creates a system that aborts the queued and in-flight requests of every entity scoped to `state`
+/// and despawns those entities.
+///
+/// The aborted requests end like those of `AbortRequest`, their response handlers get an
+/// `HttpResponseError` of kind `HttpErrorKind::Aborted`. The entities are despawned once the
+/// errors are delivered, in `HttpSet::HandleResponses`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_systems(
+///     OnExit(AppState::InGame),
+///     abort_requests_on_exit(AppState::InGame),
+/// );
+/// ```
+pub fn abort_requests_on_exit<S: States>(state: S) -> impl System<In = (), Out = ()> {
+    IntoSystem::into_system(
+        move |mut commands: Commands,
+              mut aborts: EventWriter<AbortRequest>,
+              scoped: Query<(Entity, &RequestStateScope<S>)>| {
+            for (entity, scope) in scoped.iter() {
+                if scope.0 != state {
+                    continue;
+                }
+                aborts.send(AbortRequest(entity));
+                commands.entity(entity).insert(ScopeExited);
+            }
+        },
+    )
+}
+
+/// Marks an entity whose state scope exited, despawned once its requests are aborted.
+#[derive(Component)]
+pub(crate) struct ScopeExited;
+
+/// Despawns the entities of exited scopes, after `handle_tasks` delivered their abort errors.
+pub(crate) fn despawn_exited_scopes(
+    mut commands: Commands,
+    exited: Query<Entity, With<ScopeExited>>,
+) {
+    for entity in exited.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
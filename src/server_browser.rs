@@ -0,0 +1,278 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use serde::de::DeserializeOwned;
+
+use crate::{HttpClient, HttpClock, HttpRequest, HttpSchedule, HttpSet, OnComplete};
+
+/// Polls a server list into the [`ServerBrowser<T>`] resource and pings every server on it.
+///
+/// The list is a JSON array of `T`, fetched at startup, every `refresh_interval` and on
+/// [`RefreshServerList`]. Each server is pinged with a HEAD request to the url `ping_url` returns
+/// for it, measuring latency from dispatch to response; any response counts as reachable. Add it
+/// after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Deserialize)]
+/// struct GameServer {
+///     name: String,
+///     address: String,
+/// }
+///
+/// app.add_plugins(ServerBrowserPlugin::<GameServer>::new(
+///     "https://lobby.example.com/servers",
+///     |server| Some(format!("http://{}/ping", server.address)),
+/// ));
+///
+/// fn server_list_ui(mut browser: ResMut<ServerBrowser<GameServer>>) {
+///     browser.sort_by_latency();
+///     for server in browser.servers() {
+///         println!("{} {:?}", server.entry.name, server.latency);
+///     }
+/// }
+/// ```
+pub struct ServerBrowserPlugin<T> {
+    pub url: String,
+    /// The url a server is pinged at, `None` to skip the ping.
+    pub ping_url: fn(&T) -> Option<String>,
+    /// Time between two fetches of the list, `None` to only fetch on startup and on request.
+    pub refresh_interval: Option<Duration>,
+    /// How long a ping may take before the server counts as unreachable.
+    pub ping_timeout: Duration,
+}
+
+impl<T> ServerBrowserPlugin<T> {
+    /// create the plugin polling `url` every 30 seconds
+    pub fn new(url: impl ToString, ping_url: fn(&T) -> Option<String>) -> Self {
+        Self {
+            url: url.to_string(),
+            ping_url,
+            refresh_interval: Some(Duration::from_secs(30)),
+            ping_timeout: Duration::from_secs(2),
+        }
+    }
+
+    pub fn with_refresh_interval(mut self, refresh_interval: Option<Duration>) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    pub fn with_ping_timeout(mut self, ping_timeout: Duration) -> Self {
+        self.ping_timeout = ping_timeout;
+        self
+    }
+}
+
+impl<T: DeserializeOwned + Send + Sync + 'static> Plugin for ServerBrowserPlugin<T> {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        app.insert_resource(ServerBrowser::<T> {
+            servers: vec![],
+            last_refreshed: None,
+            last_error: None,
+            generation: 0,
+        });
+        app.insert_resource(ServerListPoller::<T> {
+            url: self.url.clone(),
+            ping_url: self.ping_url,
+            refresh_interval: self.refresh_interval,
+            ping_timeout: self.ping_timeout,
+            // The first fetch runs right away.
+            timer: Some(Timer::new(Duration::ZERO, TimerMode::Once)),
+            fetching: false,
+        });
+        app.add_event::<RefreshServerList>();
+        app.add_systems(schedule, poll_server_list::<T>.before(HttpSet::Queue));
+    }
+}
+
+/// Fetches the server list again, e.g. from a refresh button.
+#[derive(Event, Debug, Clone, Default)]
+pub struct RefreshServerList;
+
+/// The servers of the latest list fetched by the [`ServerBrowserPlugin<T>`].
+#[derive(Resource, Debug)]
+pub struct ServerBrowser<T> {
+    servers: Vec<ServerInfo<T>>,
+    last_refreshed: Option<Instant>,
+    last_error: Option<String>,
+    /// Increased with every list, so pings of an older list are ignored.
+    generation: u64,
+}
+
+impl<T> ServerBrowser<T> {
+    pub fn servers(&self) -> &[ServerInfo<T>] {
+        &self.servers
+    }
+
+    /// When the current list was fetched, on the [`HttpClock`].
+    pub fn last_refreshed(&self) -> Option<Instant> {
+        self.last_refreshed
+    }
+
+    /// The error of the latest list fetch, cleared by the next successful one.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Sorts the servers fastest first, followed by those not pinged yet, then unreachable ones.
+    pub fn sort_by_latency(&mut self) {
+        self.servers.sort_by_key(ServerInfo::ping_rank);
+    }
+
+    /// Sorts the servers with a comparator on their entries, e.g. by name or player count.
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&T, &T) -> Ordering) {
+        self.servers.sort_by(|a, b| compare(&a.entry, &b.entry));
+    }
+}
+
+/// A server of the list, with the result of its ping.
+#[derive(Debug)]
+pub struct ServerInfo<T> {
+    pub entry: T,
+    /// Time from dispatching the ping to its response.
+    pub latency: Option<Duration>,
+    /// `None` until the ping finished.
+    pub reachable: Option<bool>,
+    /// Position in the list as fetched, sorting keeps it.
+    index: usize,
+}
+
+impl<T> ServerInfo<T> {
+    fn ping_rank(&self) -> (u8, Duration) {
+        match (self.reachable, self.latency) {
+            (Some(true), Some(latency)) => (0, latency),
+            (None, _) => (1, Duration::ZERO),
+            _ => (2, Duration::ZERO),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ServerListPoller<T> {
+    url: String,
+    ping_url: fn(&T) -> Option<String>,
+    refresh_interval: Option<Duration>,
+    ping_timeout: Duration,
+    /// Until the next fetch, `None` without a refresh interval.
+    timer: Option<Timer>,
+    /// Whether the list is being fetched, so slow servers are not asked twice.
+    fetching: bool,
+}
+
+fn poll_server_list<T: DeserializeOwned + Send + Sync + 'static>(
+    time: Res<Time>,
+    mut poller: ResMut<ServerListPoller<T>>,
+    mut refreshes: EventReader<RefreshServerList>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    let refresh = refreshes.read().count() > 0;
+    let poller = &mut *poller;
+    let due = poller
+        .timer
+        .as_mut()
+        .is_some_and(|timer| timer.tick(time.delta()).finished());
+    if !(due || refresh) || poller.fetching {
+        return;
+    }
+    poller.timer = poller
+        .refresh_interval
+        .map(|interval| Timer::new(interval, TimerMode::Once));
+    poller.fetching = true;
+
+    let on_complete = OnComplete::new(move |world, _, response| {
+        world.resource_mut::<ServerListPoller<T>>().fetching = false;
+        let list = response.and_then(|res| {
+            if res.ok {
+                serde_json::from_slice::<Vec<T>>(&res.bytes).map_err(|e| e.to_string())
+            } else {
+                Err(format!("{} {}", res.status, res.status_text))
+            }
+        });
+        match list {
+            Ok(entries) => replace_servers(world, entries),
+            Err(error) => world.resource_mut::<ServerBrowser<T>>().last_error = Some(error),
+        }
+    });
+    requests.send(HttpRequest {
+        on_complete: Some(on_complete),
+        ..HttpClient::new().get(&poller.url).build()
+    });
+}
+
+/// Replaces the server list and pings every server on it.
+fn replace_servers<T: Send + Sync + 'static>(world: &mut World, entries: Vec<T>) {
+    let poller = world.resource::<ServerListPoller<T>>();
+    let ping_url = poller.ping_url;
+    let ping_timeout = poller.ping_timeout;
+    let pings: Vec<_> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| Some((index, ping_url(entry)?)))
+        .collect();
+
+    let now = world.resource::<HttpClock>().now();
+    let mut browser = world.resource_mut::<ServerBrowser<T>>();
+    browser.generation += 1;
+    let generation = browser.generation;
+    browser.last_refreshed = Some(now);
+    browser.last_error = None;
+    browser.servers = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| ServerInfo {
+            entry,
+            latency: None,
+            reachable: None,
+            index,
+        })
+        .collect();
+
+    for (index, url) in pings {
+        let dispatched_at = Arc::new(Mutex::new(Instant::now()));
+        let on_dispatch = {
+            let dispatched_at = dispatched_at.clone();
+            move || {
+                if let Ok(mut dispatched_at) = dispatched_at.lock() {
+                    *dispatched_at = Instant::now();
+                }
+            }
+        };
+        let on_complete = OnComplete::new(move |world, _, response| {
+            let latency = dispatched_at
+                .lock()
+                .map_or(Duration::ZERO, |at| at.elapsed());
+            let mut browser = world.resource_mut::<ServerBrowser<T>>();
+            if browser.generation != generation {
+                return;
+            }
+            if let Some(server) = browser
+                .servers
+                .iter_mut()
+                .find(|server| server.index == index)
+            {
+                server.reachable = Some(response.is_ok());
+                server.latency = response.is_ok().then_some(latency);
+            }
+        })
+        .with_on_dispatch(on_dispatch);
+        world.send_event(HttpRequest {
+            on_complete: Some(on_complete),
+            ..HttpClient::new()
+                .head(&url)
+                .timeout(ping_timeout)
+                .label("server-ping")
+                .build()
+        });
+    }
+}
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use bevy::app::{App, AppExit, Last, Plugin};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::retry::PendingRetries;
+use crate::{HttpRequest, RequestLabel, RequestQueue, RequestTask, Telemetry};
+
+/// Holds back `AppExit` while requests are still pending, for at most `grace`, so quitting the game
+/// doesn't silently drop the session-end event.
+///
+/// The app keeps updating while it waits, queued requests are still sent and the waiting events of
+/// the [`TelemetryPlugin`] are uploaded right away instead of at the next flush interval. The app
+/// exits once the pending requests finished or the grace period ran out, whichever comes first.
+/// Requests that are still pending then are dropped, except those kept by the
+/// [`QueuePersistencePlugin`] or the [`DurableDeliveryPlugin`], and the telemetry events written to
+/// the spill file. Add it after `HttpClientPlugin`.
+///
+/// [`TelemetryPlugin`]: crate::TelemetryPlugin
+/// [`QueuePersistencePlugin`]: crate::QueuePersistencePlugin
+/// [`DurableDeliveryPlugin`]: crate::DurableDeliveryPlugin
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(GracefulShutdownPlugin::new(Duration::from_secs(2)).with_label("telemetry"));
+///
+/// fn quit_screen(shutdown: Res<HttpShutdown>, pending: PendingRequests, mut text: Query<&mut Text>) {
+///     if shutdown.is_flushing() {
+///         text.single_mut().sections[0].value = format!("Saving... ({} left)", pending.total());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GracefulShutdownPlugin {
+    /// Longest time the exit is held back.
+    pub grace: Duration,
+    /// Only requests with one of these labels hold back the exit, every request if empty. The
+    /// telemetry events count as requests labelled `telemetry`.
+    pub labels: Vec<RequestLabel>,
+}
+
+impl GracefulShutdownPlugin {
+    /// hold back the exit for at most `grace`
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            labels: Vec::new(),
+        }
+    }
+
+    /// only wait for requests labelled `label`, see `labels`
+    pub fn with_label(mut self, label: impl ToString) -> Self {
+        self.labels.push(RequestLabel::new(label.to_string()));
+        self
+    }
+}
+
+impl Plugin for GracefulShutdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HttpShutdown {
+            config: self.clone(),
+            deadline: None,
+            released: false,
+        });
+        app.add_systems(Last, hold_app_exit);
+    }
+}
+
+/// Whether `AppExit` is being held back by the [`GracefulShutdownPlugin`].
+#[derive(Resource, Debug, Clone)]
+pub struct HttpShutdown {
+    config: GracefulShutdownPlugin,
+    /// When the app exits anyway, while the exit is held back. On the wall clock, a paused virtual
+    /// clock would hold the exit back forever.
+    deadline: Option<Instant>,
+    /// Whether `AppExit` was sent again, it is not held back twice.
+    released: bool,
+}
+
+impl HttpShutdown {
+    /// check if the exit is held back while pending requests finish
+    pub fn is_flushing(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    /// The time left until the app exits anyway, `None` if the exit is not held back.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+/// The requests that were sent and did not finish yet, e.g. to tell the player that progress is
+/// still being saved.
+///
+/// # Examples
+///
+/// ```
+/// fn saving_indicator(pending: PendingRequests, mut indicator: Query<&mut Visibility, With<SavingIcon>>) {
+///     *indicator.single_mut() = if pending.with_label("cloud_save") > 0 {
+///         Visibility::Visible
+///     } else {
+///         Visibility::Hidden
+///     };
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct PendingRequests<'w, 's> {
+    queue: Res<'w, RequestQueue>,
+    retries: Res<'w, PendingRetries>,
+    tasks: Query<'w, 's, &'static RequestTask>,
+}
+
+impl PendingRequests<'_, '_> {
+    /// number of requests being sent
+    pub fn in_flight(&self) -> usize {
+        self.tasks.iter().filter(|task| task.in_flight()).count()
+    }
+
+    /// number of requests waiting for a free client
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// number of requests waiting for their next attempt
+    pub fn retrying(&self) -> usize {
+        self.retries.requests().count()
+    }
+
+    /// number of requests in flight, queued or waiting for a retry
+    pub fn total(&self) -> usize {
+        self.in_flight() + self.queued() + self.retrying()
+    }
+
+    /// number of waiting requests built with `HttpClient::large_transfer`, e.g. those deferred by
+    /// the [`NetworkPolicy`]
+    ///
+    /// [`NetworkPolicy`]: crate::NetworkPolicy
+    pub fn large_transfers(&self) -> usize {
+        self.queue
+            .0
+            .iter()
+            .filter(|(request, _)| request.large_transfer)
+            .count()
+    }
+
+    /// number of pending requests labelled `label`
+    pub fn with_label(&self, label: &str) -> usize {
+        self.matching(|request_label| request_label.is_some_and(|l| l.0 == label))
+    }
+
+    fn matching(&self, filter: impl Fn(Option<&RequestLabel>) -> bool) -> usize {
+        let tasks = self
+            .tasks
+            .iter()
+            .filter(|task| task.in_flight() && filter(task.label.as_ref()))
+            .count();
+        let waiting = self
+            .queue
+            .0
+            .iter()
+            .map(|(request, _)| request)
+            .chain(self.retries.requests())
+            .filter(|request: &&HttpRequest| filter(request.label.as_ref()))
+            .count();
+        tasks + waiting
+    }
+}
+
+fn hold_app_exit(
+    mut exits: ResMut<Events<AppExit>>,
+    mut shutdown: ResMut<HttpShutdown>,
+    pending: PendingRequests,
+    telemetry: Option<Res<Telemetry>>,
+) {
+    if shutdown.released || (shutdown.deadline.is_none() && exits.is_empty()) {
+        return;
+    }
+    let labels = &shutdown.config.labels;
+    let mut waiting = pending
+        .matching(|label| labels.is_empty() || label.is_some_and(|label| labels.contains(label)));
+    if labels.is_empty() || labels.iter().any(|label| label.0 == "telemetry") {
+        waiting += telemetry.map_or(0, |telemetry| telemetry.len());
+    }
+
+    let Some(deadline) = shutdown.deadline else {
+        if waiting > 0 {
+            exits.clear();
+            info!("Delaying exit until {waiting} pending requests finished");
+            shutdown.deadline = Some(Instant::now() + shutdown.config.grace);
+        }
+        return;
+    };
+    // Exits sent again in the meantime wait as well.
+    exits.clear();
+    if waiting > 0 && Instant::now() < deadline {
+        return;
+    }
+    if waiting > 0 {
+        warn!("Exiting with {waiting} requests still pending");
+    }
+    shutdown.deadline = None;
+    shutdown.released = true;
+    exits.send(AppExit);
+}
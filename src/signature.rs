@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use bevy::prelude::*;
+use bevy::utils::SystemTime;
+use ehttp::Request;
+
+use crate::{HttpRequest, ResponseTransform};
+
+/// Checks the signature of a response body for [`ResponseSignatures`], e.g. Ed25519 with the public
+/// key shipped in the build, or an HMAC.
+///
+/// # Examples
+///
+/// ```
+/// struct ReleaseKey(VerifyingKey);
+///
+/// impl SignatureVerifier for ReleaseKey {
+///     fn verify(&self, body: &[u8], signature: &[u8]) -> bool {
+///         Signature::from_slice(signature).is_ok_and(|signature| self.0.verify(body, &signature).is_ok())
+///     }
+/// }
+/// ```
+pub trait SignatureVerifier: Send + Sync + 'static {
+    fn verify(&self, body: &[u8], signature: &[u8]) -> bool;
+}
+
+/// How the signature is written in its header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    /// Standard base64, with or without padding.
+    #[default]
+    Base64,
+    Hex,
+}
+
+/// Verifies the signature header of the responses to requests sent with
+/// `HttpClient::verify_signature`, so tampered payloads never reach game logic.
+///
+/// The signature covers the body as it was received, before any [`PayloadEncryption`] or
+/// [`ResponseTransform`]. A 2xx response without the header or with a signature that does not
+/// verify fails the request, as does a request that asks for verification while the resource is
+/// missing. Cached responses were verified when they were stored.
+///
+/// [`PayloadEncryption`]: crate::PayloadEncryption
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(ResponseSignatures::new(ReleaseKey(RELEASE_PUBLIC_KEY)));
+/// app.add_plugins(RemoteConfigPlugin::<Tuning>::new("https://cdn.example.com/tuning.json").with_signature());
+/// ```
+#[derive(Resource, Clone)]
+pub struct ResponseSignatures {
+    verifier: Arc<dyn SignatureVerifier>,
+    /// The response header with the signature, `X-Signature` by default.
+    pub header: String,
+    pub encoding: SignatureEncoding,
+}
+
+impl ResponseSignatures {
+    pub fn new(verifier: impl SignatureVerifier) -> Self {
+        Self {
+            verifier: Arc::new(verifier),
+            header: "X-Signature".to_string(),
+            encoding: SignatureEncoding::default(),
+        }
+    }
+
+    /// read the signature from `header`, see `header`
+    pub fn with_header(mut self, header: impl ToString) -> Self {
+        self.header = header.to_string();
+        self
+    }
+
+    /// decode the signature as `encoding`, see `encoding`
+    pub fn with_encoding(mut self, encoding: SignatureEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    fn transform(&self) -> ResponseTransform {
+        let signatures = self.clone();
+        ResponseTransform::new(move |res| {
+            let signature = res
+                .headers
+                .get(&signatures.header)
+                .ok_or_else(|| format!("Response has no {} header", signatures.header))?;
+            let signature = decode(signatures.encoding, signature.trim())
+                .ok_or_else(|| format!("Response has a malformed {} header", signatures.header))?;
+            if !signatures.verifier.verify(&res.bytes, &signature) {
+                return Err("Response signature is invalid".to_string());
+            }
+            Ok(res)
+        })
+    }
+}
+
+impl std::fmt::Debug for ResponseSignatures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseSignatures")
+            .field("header", &self.header)
+            .field("encoding", &self.encoding)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Signs a request as the last step of dispatch, once every header is set and the body is
+/// encrypted, so the signature covers the request as it is sent, e.g. AWS Signature Version 4 for
+/// an `S3Bucket`. Runs again for every retry.
+///
+/// The signer gets the current time of the server clock, see `ClockSkew`. A request that fails to
+/// sign fails without being sent.
+///
+/// # Examples
+///
+/// ```
+/// let mut request = HttpClient::new().post("https://api.example.com/scores").json(&score).build();
+/// request.signer = Some(RequestSigner::new(move |request, now| {
+///     let mac = hmac(&key, &request.body);
+///     request.headers.insert("X-Signature", hex(&mac));
+///     Ok(())
+/// }));
+/// ```
+#[derive(Clone)]
+pub struct RequestSigner(Arc<SignFn>);
+
+type SignFn = dyn Fn(&mut Request, SystemTime) -> Result<(), String> + Send + Sync;
+
+impl RequestSigner {
+    pub fn new(
+        sign: impl Fn(&mut Request, SystemTime) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(sign))
+    }
+
+    pub(crate) fn sign(&self, request: &mut Request, now: SystemTime) -> Result<(), String> {
+        (self.0)(request, now).map_err(|e| format!("Failed to sign request: {e}"))
+    }
+}
+
+impl std::fmt::Debug for RequestSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestSigner").finish_non_exhaustive()
+    }
+}
+
+/// Has the response of `request` verified ahead of every other transform, if it asks for it.
+pub(crate) fn resolve(
+    signatures: Option<&ResponseSignatures>,
+    request: &mut HttpRequest,
+) -> Result<(), String> {
+    if !request.verify_signature {
+        return Ok(());
+    }
+    let signatures =
+        signatures.ok_or("Response signature can not be verified without ResponseSignatures")?;
+    request.transforms.insert(0, signatures.transform());
+    Ok(())
+}
+
+fn decode(encoding: SignatureEncoding, signature: &str) -> Option<Vec<u8>> {
+    match encoding {
+        SignatureEncoding::Base64 => STANDARD
+            .decode(signature)
+            .or_else(|_| STANDARD_NO_PAD.decode(signature))
+            .ok(),
+        SignatureEncoding::Hex => (0..signature.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(signature.get(i..i + 2)?, 16).ok())
+            .collect(),
+    }
+}
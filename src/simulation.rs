@@ -0,0 +1,215 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::app::{App, Plugin};
+use bevy::log::warn;
+use bevy::prelude::Resource;
+use bevy::utils::HashMap;
+use ehttp::Response;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::bandwidth::RateLimiter;
+
+/// Error of requests dropped by the [`NetworkSimulationPlugin`].
+const SIMULATED_DROP: &str = "Connection failed: dropped by the network simulation";
+
+/// Makes requests behave as if they went over a bad network, to try loading screens and error
+/// handling without external tooling. Meant for development builds only.
+///
+/// Every request gets the [`NetworkConditions`] of its host, or the default ones. Its result is
+/// held back by the latency plus a random part of the jitter, streamed chunks are not delayed.
+/// Dropped requests fail with a `HttpErrorKind::Connect` error without being sent. The random
+/// draws come from the seed, so the same requests in the same order see the same network. Change
+/// the conditions at runtime through the [`NetworkSimulation`] resource. Add it after
+/// `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(
+///     NetworkSimulationPlugin::new(NetworkConditions::new().with_latency(
+///         Duration::from_millis(300),
+///         Duration::from_millis(100),
+///     ))
+///     .with_host("cdn.example.com", NetworkConditions::new().with_drop_rate(0.2))
+///     .with_seed(7),
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NetworkSimulationPlugin {
+    /// Conditions of hosts without their own.
+    pub conditions: NetworkConditions,
+    /// Conditions by host name, e.g. `cdn.example.com`.
+    pub hosts: Vec<(String, NetworkConditions)>,
+    pub seed: u64,
+}
+
+impl NetworkSimulationPlugin {
+    pub fn new(conditions: NetworkConditions) -> Self {
+        Self {
+            conditions,
+            ..Self::default()
+        }
+    }
+
+    /// simulate `conditions` for requests to `host` instead of the default ones
+    pub fn with_host(mut self, host: impl ToString, conditions: NetworkConditions) -> Self {
+        self.hosts.push((host.to_string(), conditions));
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Plugin for NetworkSimulationPlugin {
+    fn build(&self, app: &mut App) {
+        warn!("network simulation is active, requests are delayed and dropped on purpose");
+        let mut simulation = NetworkSimulation {
+            enabled: true,
+            conditions: Link::new(self.conditions.clone()),
+            hosts: HashMap::default(),
+            rng: fastrand::Rng::with_seed(self.seed),
+        };
+        for (host, conditions) in &self.hosts {
+            simulation.set_host(host, Some(conditions.clone()));
+        }
+        app.insert_resource(simulation);
+    }
+}
+
+/// How bad the simulated network is.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkConditions {
+    /// Added to every request.
+    pub latency: Duration,
+    /// Up to this much is added on top of the latency, drawn anew for every request.
+    pub jitter: Duration,
+    /// Download rate in bytes per second, shared by the requests with these conditions. Not
+    /// available on wasm builds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub bandwidth: Option<u64>,
+    /// Fraction of requests that fail, from `0.0` to `1.0`.
+    pub drop_rate: f32,
+}
+
+impl NetworkConditions {
+    /// a perfect network, to change with the `with_` methods
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    /// cap the download rate at `bytes_per_second`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_bandwidth(mut self, bytes_per_second: u64) -> Self {
+        self.bandwidth = Some(bytes_per_second);
+        self
+    }
+
+    pub fn with_drop_rate(mut self, drop_rate: f32) -> Self {
+        self.drop_rate = drop_rate.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// The conditions the [`NetworkSimulationPlugin`] simulates.
+#[derive(Resource, Debug)]
+pub struct NetworkSimulation {
+    /// Turns the simulation off without removing the plugin.
+    pub enabled: bool,
+    conditions: Link,
+    hosts: HashMap<String, Link>,
+    rng: fastrand::Rng,
+}
+
+impl NetworkSimulation {
+    /// The conditions of hosts without their own.
+    pub fn conditions(&self) -> &NetworkConditions {
+        &self.conditions.conditions
+    }
+
+    pub fn set_conditions(&mut self, conditions: NetworkConditions) {
+        self.conditions = Link::new(conditions);
+    }
+
+    /// The conditions of `host`, if it has its own.
+    pub fn host(&self, host: &str) -> Option<&NetworkConditions> {
+        self.hosts
+            .get(&host.to_ascii_lowercase())
+            .map(|link| &link.conditions)
+    }
+
+    /// Sets the conditions of `host`, or with `None` gives it the default ones again.
+    pub fn set_host(&mut self, host: &str, conditions: Option<NetworkConditions>) {
+        let host = host.to_ascii_lowercase();
+        match conditions {
+            Some(conditions) => {
+                self.hosts.insert(host, Link::new(conditions));
+            }
+            None => {
+                self.hosts.remove(&host);
+            }
+        }
+    }
+
+    /// Draws what the network does to a request to `url`.
+    pub(crate) fn simulate(&mut self, url: &str) -> SimulatedRequest {
+        if !self.enabled {
+            return SimulatedRequest::default();
+        }
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase));
+        let link = host
+            .and_then(|host| self.hosts.get(&host))
+            .unwrap_or(&self.conditions);
+        let conditions = &link.conditions;
+        let jitter = conditions.jitter.mul_f32(self.rng.f32());
+        SimulatedRequest {
+            delay: conditions.latency + jitter,
+            injected: (conditions.drop_rate > 0.0 && self.rng.f32() < conditions.drop_rate)
+                .then(|| Err(SIMULATED_DROP.to_string())),
+            #[cfg(not(target_arch = "wasm32"))]
+            limiter: link.limiter.clone(),
+        }
+    }
+}
+
+/// Conditions with the download cap their requests share.
+#[derive(Debug)]
+struct Link {
+    conditions: NetworkConditions,
+    #[cfg(not(target_arch = "wasm32"))]
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Link {
+    fn new(conditions: NetworkConditions) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            limiter: conditions
+                .bandwidth
+                .map(|rate| Arc::new(RateLimiter::new(Some(rate)))),
+            conditions,
+        }
+    }
+}
+
+/// What the simulated network does to a single request.
+#[derive(Default)]
+pub(crate) struct SimulatedRequest {
+    pub(crate) delay: Duration,
+    /// The result of a request that is not sent.
+    pub(crate) injected: Option<ehttp::Result<Response>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) limiter: Option<Arc<RateLimiter>>,
+}
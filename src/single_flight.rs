@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::{
+    retry, RequestId, RequestQueue, RequestTask, ResponseHandler, REQUEST_ABORTED,
+    REQUEST_DUPLICATE,
+};
+
+/// Keeps a request from being sent twice while it is in flight, e.g. so a player mashing the buy
+/// button does not purchase twice. Set it with `HttpClient::single_flight`.
+///
+/// Requests with the same `key` share one flight: while one of them is queued, sent or waiting for
+/// a retry, the others are not sent. They fail with `HttpErrorKind::Duplicate` right away, or get
+/// a copy of the result of the request in flight once it finishes with
+/// [`Duplicates::Coalesce`]. The key is free again once the result was handed to the handler of
+/// the request, check it with `SingleFlights::is_in_flight`, e.g. to grey out the button.
+///
+/// # Examples
+///
+/// ```
+/// let request = HttpClient::new()
+///     .post("https://shop.example.com/purchase")
+///     .json(&Purchase { item: "sword" })
+///     .single_flight(SingleFlight::new("purchase-sword"))
+///     .build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SingleFlight {
+    /// Requests with the same key are not in flight at the same time.
+    pub key: String,
+    pub duplicates: Duplicates,
+}
+
+impl SingleFlight {
+    /// fail requests with `key` while one of them is in flight
+    pub fn new(key: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+            duplicates: Duplicates::default(),
+        }
+    }
+
+    /// hand duplicates the result of the request in flight instead, see `duplicates`
+    pub fn coalesce(mut self) -> Self {
+        self.duplicates = Duplicates::Coalesce;
+        self
+    }
+}
+
+/// What happens to a request sent while another one with its [`SingleFlight`] key is in flight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Duplicates {
+    /// The duplicate fails with `HttpErrorKind::Duplicate` without being sent.
+    #[default]
+    Ignore,
+    /// The duplicate is not sent, its handler gets a copy of the result of the request in flight.
+    Coalesce,
+}
+
+/// The [`SingleFlight`] keys of the requests in flight.
+#[derive(Resource, Default)]
+pub struct SingleFlights {
+    flights: HashMap<String, Flight>,
+}
+
+struct Flight {
+    request_id: RequestId,
+    /// The coalesced duplicates waiting for the result.
+    waiters: Vec<(RequestId, ResponseHandler)>,
+}
+
+impl SingleFlights {
+    /// check if a request with `key` is in flight
+    pub fn is_in_flight(&self, key: &str) -> bool {
+        self.flights.contains_key(key)
+    }
+
+    /// The coalesced duplicates of the request, if it was the one in flight for `key`.
+    fn finish(&mut self, key: &str, request_id: RequestId) -> Vec<(RequestId, ResponseHandler)> {
+        match self.flights.get(key) {
+            Some(flight) if flight.request_id == request_id => self
+                .flights
+                .remove(key)
+                .map(|flight| flight.waiters)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Takes the duplicates of the requests in flight out of the queue, and frees the keys of requests
+/// dropped without a result, e.g. with their entity.
+pub(crate) fn guard_duplicates(
+    mut commands: Commands,
+    mut queue: ResMut<RequestQueue>,
+    mut flights: ResMut<SingleFlights>,
+    retries: Res<retry::PendingRetries>,
+    tasks: Query<&RequestTask>,
+) {
+    if flights.flights.is_empty()
+        && queue
+            .0
+            .iter()
+            .all(|(request, _)| request.single_flight.is_none())
+    {
+        return;
+    }
+
+    let pending: HashSet<RequestId> = queue
+        .0
+        .iter()
+        .map(|(request, _)| request.id)
+        .chain(retries.requests().map(|request| request.id))
+        .chain(tasks.iter().map(|task| task.request_id))
+        .collect();
+    let dropped: Vec<String> = flights
+        .flights
+        .iter()
+        .filter(|(_, flight)| !pending.contains(&flight.request_id))
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in dropped {
+        let Some(flight) = flights.flights.remove(&key) else {
+            continue;
+        };
+        for (request_id, on_response) in flight.waiters {
+            commands.add(move |world: &mut World| {
+                on_response(world, request_id, Err(REQUEST_ABORTED.to_string()));
+            });
+        }
+    }
+
+    let mut index = 0;
+    while index < queue.0.len() {
+        let (request, _) = &queue.0[index];
+        let Some(single_flight) = request.single_flight.clone() else {
+            index += 1;
+            continue;
+        };
+        let request_id = request.id;
+        match flights.flights.get_mut(&single_flight.key) {
+            // Queued again for a retry.
+            Some(flight) if flight.request_id == request_id => index += 1,
+            Some(flight) => {
+                let Some((_, on_response)) = queue.0.remove(index) else {
+                    break;
+                };
+                match single_flight.duplicates {
+                    Duplicates::Ignore => commands.add(move |world: &mut World| {
+                        on_response(world, request_id, Err(REQUEST_DUPLICATE.to_string()));
+                    }),
+                    Duplicates::Coalesce => flight.waiters.push((request_id, on_response)),
+                }
+            }
+            None => {
+                let (_, on_response) = &mut queue.0[index];
+                let inner = std::mem::replace(on_response, Box::new(|_, _, _| {}));
+                let key = single_flight.key.clone();
+                *on_response = Box::new(move |world, request_id, result| {
+                    let waiters = world
+                        .resource_mut::<SingleFlights>()
+                        .finish(&key, request_id);
+                    for (waiter_id, waiter) in waiters {
+                        waiter(world, waiter_id, result.clone());
+                    }
+                    inner(world, request_id, result);
+                });
+                flights.flights.insert(
+                    single_flight.key,
+                    Flight {
+                        request_id,
+                        waiters: Vec::new(),
+                    },
+                );
+                index += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet, Instant};
+
+use crate::{HttpClock, HttpSchedule, HttpSet, RequestLabel};
+
+/// Checks the latency and error rate of labeled requests against objectives over a rolling
+/// window, sending [`SloViolated`] when one is missed and [`SloRecovered`] once it is met again.
+///
+/// Requests are grouped by their [`RequestLabel`], a request counts as an error if it got no
+/// response or a non-2xx one, like in [`HttpStats`]. Objectives are checked every `interval`, and
+/// only once the window holds `min_requests` requests of the label, so a single slow request after
+/// startup is not reported, and a missed target is only met again once enough requests show it.
+/// Add it after `HttpClientPlugin`, e.g. only in QA builds.
+///
+/// [`HttpStats`]: crate::HttpStats
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpClientPlugin::default()).add_plugins(
+///     SloPlugin::new(Duration::from_secs(60)).with_objective(
+///         ServiceLevelObjective::new("matchmaking")
+///             .latency_below(0.95, Duration::from_millis(400))
+///             .error_rate_below(0.01),
+///     ),
+/// );
+///
+/// fn show_violations(mut ev_violated: EventReader<SloViolated>, mut overlay: ResMut<QaOverlay>) {
+///     for violated in ev_violated.read() {
+///         overlay.push(format!("{}: {}", violated.label, violated.breach));
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SloPlugin {
+    pub objectives: Vec<ServiceLevelObjective>,
+    /// How far back the requests of the objectives go.
+    pub window: Duration,
+    /// Time between two checks of the objectives.
+    pub interval: Duration,
+    /// Requests of a label the window must hold before its objectives are checked.
+    pub min_requests: usize,
+}
+
+impl SloPlugin {
+    /// create the plugin checking over the last `window`, without any objective yet
+    pub fn new(window: Duration) -> Self {
+        Self {
+            objectives: vec![],
+            window,
+            interval: Duration::from_secs(1),
+            min_requests: 20,
+        }
+    }
+
+    pub fn with_objective(mut self, objective: ServiceLevelObjective) -> Self {
+        self.objectives.push(objective);
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_min_requests(mut self, min_requests: usize) -> Self {
+        self.min_requests = min_requests.max(1);
+        self
+    }
+}
+
+impl Plugin for SloPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        app.insert_resource(SloSamples {
+            labels: self
+                .objectives
+                .iter()
+                .map(|objective| (objective.label.to_string(), VecDeque::new()))
+                .collect(),
+        });
+        app.insert_resource(SloChecks {
+            config: self.clone(),
+            timer: Timer::new(self.interval, TimerMode::Repeating),
+            violated: HashSet::new(),
+        });
+        app.add_event::<SloViolated>();
+        app.add_event::<SloRecovered>();
+        app.add_systems(schedule, check_objectives.after(HttpSet::HandleResponses));
+    }
+}
+
+/// The latency and error rate the requests of a label should stay within.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceLevelObjective {
+    pub label: Cow<'static, str>,
+    /// The percentile, from `0.0` to `1.0`, and the latency it should stay below.
+    pub latency: Option<(f32, Duration)>,
+    /// The share of errors, from `0.0` to `1.0`, that should not be reached.
+    pub error_rate: Option<f32>,
+}
+
+impl ServiceLevelObjective {
+    /// create an objective for the requests labeled `label`, without any target yet
+    pub fn new(label: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            label: label.into(),
+            latency: None,
+            error_rate: None,
+        }
+    }
+
+    /// keep the `percentile` latency below `latency`, e.g. `0.95` for p95, see `latency`
+    pub fn latency_below(mut self, percentile: f32, latency: Duration) -> Self {
+        self.latency = Some((percentile.clamp(0.0, 1.0), latency));
+        self
+    }
+
+    /// keep the share of errors below `error_rate`, e.g. `0.01` for 1%, see `error_rate`
+    pub fn error_rate_below(mut self, error_rate: f32) -> Self {
+        self.error_rate = Some(error_rate);
+        self
+    }
+}
+
+/// The target of an objective that was missed or met again, with what the window shows.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SloBreach {
+    Latency {
+        percentile: f32,
+        observed: Duration,
+        target: Duration,
+    },
+    ErrorRate {
+        observed: f32,
+        target: f32,
+    },
+}
+
+impl std::fmt::Display for SloBreach {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Latency {
+                percentile,
+                observed,
+                target,
+            } => write!(
+                f,
+                "p{} latency {}ms, target < {}ms",
+                percentile * 100.0,
+                observed.as_millis(),
+                target.as_millis()
+            ),
+            Self::ErrorRate { observed, target } => write!(
+                f,
+                "error rate {:.1}%, target < {:.1}%",
+                observed * 100.0,
+                target * 100.0
+            ),
+        }
+    }
+}
+
+/// Sent when the requests of a label started missing a target of their objective.
+#[derive(Event, Debug, Clone)]
+pub struct SloViolated {
+    pub label: String,
+    pub breach: SloBreach,
+}
+
+/// Sent when the requests of a label that missed a target meet it again.
+#[derive(Event, Debug, Clone)]
+pub struct SloRecovered {
+    pub label: String,
+    pub breach: SloBreach,
+}
+
+/// The latency and outcome of recent requests of every label with an objective.
+#[derive(Resource, Default)]
+pub(crate) struct SloSamples {
+    labels: HashMap<String, VecDeque<(Instant, Duration, bool)>>,
+}
+
+impl SloSamples {
+    pub(crate) fn record(
+        &mut self,
+        label: Option<&RequestLabel>,
+        ok: bool,
+        latency: Duration,
+        now: Instant,
+    ) {
+        if let Some(samples) = label.and_then(|label| self.labels.get_mut(label.0.as_ref())) {
+            samples.push_back((now, latency, ok));
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SloChecks {
+    config: SloPlugin,
+    timer: Timer,
+    /// The labels and targets currently missed, `false` for latency and `true` for the error rate.
+    violated: HashSet<(String, bool)>,
+}
+
+fn check_objectives(
+    time: Res<Time>,
+    mut checks: ResMut<SloChecks>,
+    mut samples: ResMut<SloSamples>,
+    mut ev_violated: EventWriter<SloViolated>,
+    mut ev_recovered: EventWriter<SloRecovered>,
+    clock: Res<HttpClock>,
+) {
+    if !checks.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let now = clock.now();
+    let SloChecks {
+        config, violated, ..
+    } = &mut *checks;
+    for objective in &config.objectives {
+        let Some(samples) = samples.labels.get_mut(objective.label.as_ref()) else {
+            continue;
+        };
+        while samples
+            .front()
+            .is_some_and(|(at, ..)| now.saturating_duration_since(*at) > config.window)
+        {
+            samples.pop_front();
+        }
+        if samples.len() < config.min_requests {
+            continue;
+        }
+
+        let mut breaches = vec![];
+        if let Some((percentile, target)) = objective.latency {
+            let mut latencies: Vec<Duration> =
+                samples.iter().map(|(_, latency, _)| *latency).collect();
+            latencies.sort();
+            let rank = (percentile * latencies.len() as f32).ceil() as usize;
+            let observed = latencies[rank.clamp(1, latencies.len()) - 1];
+            let breach = SloBreach::Latency {
+                percentile,
+                observed,
+                target,
+            };
+            breaches.push((false, observed >= target, breach));
+        }
+        if let Some(target) = objective.error_rate {
+            let errors = samples.iter().filter(|(.., ok)| !ok).count();
+            let observed = errors as f32 / samples.len() as f32;
+            let breach = SloBreach::ErrorRate { observed, target };
+            breaches.push((true, observed >= target, breach));
+        }
+
+        for (kind, missed, breach) in breaches {
+            let key = (objective.label.to_string(), kind);
+            let label = objective.label.to_string();
+            if missed && violated.insert(key.clone()) {
+                warn!("Service level objective of {label} violated: {breach}");
+                ev_violated.send(SloViolated { label, breach });
+            } else if !missed && violated.remove(&key) {
+                info!("Service level objective of {label} met again: {breach}");
+                ev_recovered.send(SloRecovered { label, breach });
+            }
+        }
+    }
+}
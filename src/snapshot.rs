@@ -0,0 +1,276 @@
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bevy::ecs::event::Events;
+use bevy::prelude::*;
+use ehttp::{Headers, Response};
+use serde::Deserialize;
+
+use crate::typed::{HttpTypedRequestTrait, TypedRequest, TypedResponse};
+use crate::{transform, HttpClientControl, HttpClientPlugin, HttpResponseError, RequestQueue};
+
+/// Set to write the snapshots of every `assert` instead of comparing them.
+const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+/// Frames a snapshot waits for its typed response, e.g. while it is parsed in the background.
+const MAX_FRAMES: usize = 100;
+
+/// Runs typed requests against recorded responses in `cargo test` and compares what they
+/// deserialize to with stored snapshots, so a backend contract change that breaks a type fails a
+/// test instead of a game. Only available with the `snapshot-testing` feature, add it to the
+/// crate in `dev-dependencies`.
+///
+/// `assert` answers the request with the fixture `name` instead of sending it, runs it through the
+/// registered response type in an app with the `HttpClientPlugin`, and compares the pretty `Debug`
+/// output of the value with the snapshot `name.snap`. A fixture is a recorded response,
+/// `name.http`, with its status line, headers, an empty line and the body, or a JSON body, `name.json`,
+/// answered with `200 OK`. A missing snapshot is written and the assertion passes, set the
+/// `UPDATE_SNAPSHOTS` environment variable to write every snapshot again. A changed value fails
+/// with a diff of the snapshot, `-`, and the value, `+`.
+///
+/// # Examples
+///
+/// ```
+/// // In a test of the game crate.
+/// fn inventory_contract() {
+///     let snapshots = TypedSnapshots::new("tests/fixtures", "tests/snapshots")
+///         .with_setup(|app| {
+///             app.register_response_format::<Inventory>(MessagePack);
+///         });
+///     let request = HttpClient::new().get("https://api.example.com/inventory");
+///     snapshots.assert("inventory", request.with_type::<Inventory>());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct TypedSnapshots {
+    /// Where the recorded responses are read from.
+    pub fixtures: PathBuf,
+    /// Where the snapshots are kept.
+    pub snapshots: PathBuf,
+    setup: Option<Setup>,
+}
+
+/// Runs on the app of every snapshot.
+type Setup = Arc<dyn Fn(&mut App) + Send + Sync>;
+
+impl TypedSnapshots {
+    /// read fixtures from `fixtures` and keep snapshots in `snapshots`
+    pub fn new(fixtures: impl Into<PathBuf>, snapshots: impl Into<PathBuf>) -> Self {
+        Self {
+            fixtures: fixtures.into(),
+            snapshots: snapshots.into(),
+            setup: None,
+        }
+    }
+
+    /// Runs `setup` on the app of every snapshot before the request is sent, e.g. to register
+    /// response formats, schemas or transforms.
+    pub fn with_setup(mut self, setup: impl Fn(&mut App) + Send + Sync + 'static) -> Self {
+        self.setup = Some(Arc::new(setup));
+        self
+    }
+
+    /// Answers `request` with the fixture `name` and asserts the value it deserializes to matches
+    /// the snapshot `name`.
+    ///
+    /// # Panics
+    ///
+    /// If the fixture can not be read, the response does not deserialize into `T`, or the value
+    /// does not match the snapshot.
+    pub fn assert<T>(&self, name: &str, request: TypedRequest<T>)
+    where
+        T: for<'a> Deserialize<'a> + Debug + Send + Sync + 'static,
+    {
+        let fixture = self.fixture(name).unwrap_or_else(|e| panic!("{e}"));
+        let value = self.run(fixture, request);
+        let actual = format!("{value:#?}\n");
+
+        let path = self.snapshots.join(format!("{name}.snap"));
+        let expected = std::fs::read_to_string(&path).ok();
+        if std::env::var_os(UPDATE_VAR).is_some() || expected.is_none() {
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            std::fs::write(&path, &actual)
+                .unwrap_or_else(|e| panic!("Failed to write snapshot {}: {e}", path.display()));
+            println!("Wrote snapshot {}", path.display());
+            return;
+        }
+        let expected = expected.unwrap_or_default();
+        if expected.replace("\r\n", "\n") != actual {
+            panic!(
+                "Snapshot {name} does not match, set {UPDATE_VAR}=1 to accept the change\n{}",
+                diff(&expected, &actual)
+            );
+        }
+    }
+
+    /// Reads the recorded response `name`.
+    fn fixture(&self, name: &str) -> Result<Response, String> {
+        let http = self.fixtures.join(format!("{name}.http"));
+        if http.exists() {
+            let recorded = std::fs::read(&http)
+                .map_err(|e| format!("Failed to read fixture {}: {e}", http.display()))?;
+            return parse_recorded(&recorded)
+                .map_err(|e| format!("Invalid fixture {}: {e}", http.display()));
+        }
+        let json = self.fixtures.join(format!("{name}.json"));
+        let bytes = std::fs::read(&json).map_err(|e| {
+            format!(
+                "No fixture {name}.http or {name}.json in {}: {e}",
+                self.fixtures.display()
+            )
+        })?;
+        Ok(Response {
+            url: String::new(),
+            ok: true,
+            status: 200,
+            status_text: "OK".to_string(),
+            headers: Headers::new(&[("Content-Type", "application/json")]),
+            bytes,
+        })
+    }
+
+    /// Sends `request` in a fresh app and answers it with `fixture`, the value it deserializes to.
+    fn run<T>(&self, fixture: Response, request: TypedRequest<T>) -> T
+    where
+        T: for<'a> Deserialize<'a> + Debug + Send + Sync + 'static,
+    {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, HttpClientPlugin::default()));
+        app.register_request_type::<T>();
+        if let Some(setup) = &self.setup {
+            setup(&mut app);
+        }
+        app.finish();
+        app.cleanup();
+        // Requests are queued but never dispatched, the fixture takes the place of the backend.
+        app.world.resource_mut::<HttpClientControl>().pause();
+        app.world.send_event(request);
+        app.update();
+
+        let queued: Vec<_> = app
+            .world
+            .resource_mut::<RequestQueue>()
+            .0
+            .drain(..)
+            .collect();
+        for (request, on_response) in queued {
+            let response = Response {
+                url: request.request.url.clone(),
+                ..fixture.clone()
+            };
+            let result = transform::apply(&request.transforms, Ok(response));
+            on_response(&mut app.world, request.id, result);
+        }
+
+        for _ in 0..MAX_FRAMES {
+            if let Some(response) = app
+                .world
+                .resource_mut::<Events<TypedResponse<T>>>()
+                .drain()
+                .next()
+            {
+                return response.into_inner();
+            }
+            if let Some(error) = app
+                .world
+                .resource_mut::<Events<HttpResponseError>>()
+                .drain()
+                .next()
+            {
+                panic!("Snapshot request failed: {}", error.message);
+            }
+            app.update();
+        }
+        panic!("Snapshot request got no response, was it sent with `respond_to`?");
+    }
+}
+
+impl Debug for TypedSnapshots {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypedSnapshots")
+            .field("fixtures", &self.fixtures)
+            .field("snapshots", &self.snapshots)
+            .finish()
+    }
+}
+
+/// Reads a response recorded as its status line, headers, an empty line and the body.
+fn parse_recorded(recorded: &[u8]) -> Result<Response, String> {
+    let (head, bytes) = match find(recorded, b"\r\n\r\n") {
+        Some(end) => (&recorded[..end], recorded[end + 4..].to_vec()),
+        None => match find(recorded, b"\n\n") {
+            Some(end) => (&recorded[..end], recorded[end + 2..].to_vec()),
+            None => (recorded, vec![]),
+        },
+    };
+    let head = std::str::from_utf8(head).map_err(|e| e.to_string())?;
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let mut parts = status_line.splitn(3, ' ');
+    let (Some(version), Some(status)) = (parts.next(), parts.next()) else {
+        return Err(format!("invalid status line {status_line:?}"));
+    };
+    if !version.starts_with("HTTP/") {
+        return Err(format!("invalid status line {status_line:?}"));
+    }
+    let status: u16 = status
+        .parse()
+        .map_err(|_| format!("invalid status {status:?}"))?;
+    let mut headers = Headers::default();
+    for line in lines {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("invalid header {line:?}"))?;
+        headers.insert(name.trim(), value.trim());
+    }
+    Ok(Response {
+        url: String::new(),
+        ok: (200..300).contains(&status),
+        status,
+        status_text: parts.next().unwrap_or_default().to_string(),
+        headers,
+        bytes,
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The lines of `expected` and `actual`, removed ones marked with `-` and added ones with `+`.
+fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    // The longest common subsequence of the lines after each position.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            out.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            out.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+    out
+}
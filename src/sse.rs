@@ -0,0 +1,585 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+use ehttp::Response;
+
+use crate::streaming::HttpResponseChunk;
+use crate::{
+    AbortRequestId, HttpClient, HttpClock, RequestId, RequestQueue, ResponseMeta, REQUEST_ABORTED,
+};
+
+/// A server-sent events stream, e.g. a live match feed, opened once the component is added to an
+/// entity.
+///
+/// The stream is a streamed GET with `Accept: text/event-stream`, every event it carries is sent
+/// as a [`ServerSentEvent`]. When the connection drops or the server ends the stream it is opened
+/// again, resending the id of the last event as `Last-Event-ID` so the server can pick up where it
+/// left off. The first reconnect waits for the `retry:` time the server sent, 3 seconds until it
+/// sent one, failed reconnects double that up to `max_backoff`. Network errors, 5xx, `408` and
+/// `429` answers are retried, a `204 No Content` or any other answer closes the stream for good.
+/// Every change of the [`ConnectionState`] is announced with a [`ConnectionStateChanged`].
+/// Removing the component or despawning the entity closes the stream.
+///
+/// The stream is a request of the entity without a timeout, neither the plugin's
+/// `default_timeout` nor that of the environment cut it short. Set an `idle_timeout` to reconnect
+/// when a server goes quiet without closing the connection. Aborting the requests of the entity
+/// closes the stream.
+///
+/// # Examples
+///
+/// ```
+/// fn follow_match(mut commands: Commands) {
+///     commands.spawn(
+///         EventSource::new("https://live.example.com/matches/42")
+///             .header("Authorization", "Bearer abc")
+///             .max_backoff(Duration::from_secs(10))
+///             .idle_timeout(Duration::from_secs(45)),
+///     );
+/// }
+///
+/// fn on_event(mut ev_event: EventReader<ServerSentEvent>) {
+///     for event in ev_event.read() {
+///         if event.event == "score" {
+///             println!("score: {}", event.data);
+///         }
+///     }
+/// }
+///
+/// fn on_state(mut ev_state: EventReader<ConnectionStateChanged>) {
+///     for changed in ev_state.read() {
+///         println!("live feed {:?} {:?}", changed.state, changed.error);
+///     }
+/// }
+/// ```
+#[derive(Component, Debug)]
+pub struct EventSource {
+    pub url: String,
+    /// The longest wait between two reconnects.
+    pub max_backoff: Duration,
+    /// How long the open stream may go without receiving anything before it is reconnected, no
+    /// limit by default.
+    pub idle_timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    state: ConnectionState,
+    last_event_id: Option<String>,
+    /// The reconnection time, as sent by the server in `retry:`.
+    retry: Duration,
+    /// Failed connections since the stream was last open.
+    attempts: u32,
+    reconnect_at: Option<Instant>,
+    /// The request of the current connection.
+    request_id: Option<RequestId>,
+    /// When the current connection last received its head or a chunk.
+    received_at: Option<Instant>,
+    /// The result of the current connection, waiting for its last chunks to be read.
+    ended: Option<ehttp::Result<Response>>,
+    parser: Parser,
+}
+
+impl EventSource {
+    /// open `url` once the component is added, reconnecting at most 30 seconds apart
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            max_backoff: Duration::from_secs(30),
+            idle_timeout: None,
+            headers: vec![],
+            state: ConnectionState::Connecting,
+            last_event_id: None,
+            retry: Duration::from_secs(3),
+            attempts: 0,
+            reconnect_at: None,
+            request_id: None,
+            received_at: None,
+            ended: None,
+            parser: Parser::default(),
+        }
+    }
+
+    /// Sends a header with every connection, e.g. `Authorization`.
+    pub fn header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// wait at most `max_backoff` between reconnects, see `max_backoff`
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// reconnect after `idle_timeout` without data, see `idle_timeout`
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// The id of the last event, sent as `Last-Event-ID` when reconnecting.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    fn set_state(
+        &mut self,
+        entity: Entity,
+        state: ConnectionState,
+        error: Option<String>,
+        changes: &mut EventWriter<ConnectionStateChanged>,
+    ) {
+        if self.state != state {
+            self.state = state;
+            changes.send(ConnectionStateChanged {
+                entity,
+                state,
+                error,
+            });
+        }
+    }
+}
+
+/// The state of an [`EventSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The request is on its way.
+    Connecting,
+    /// The server accepted the stream, events are arriving.
+    Open,
+    /// The connection dropped, it is opened again after the backoff.
+    Reconnecting,
+    /// The server refused the stream, or the requests of the entity were aborted.
+    Closed,
+}
+
+/// Sent when the [`ConnectionState`] of the [`EventSource`] on `entity` changed.
+#[derive(Event, Debug, Clone)]
+pub struct ConnectionStateChanged {
+    pub entity: Entity,
+    pub state: ConnectionState,
+    /// Why the connection dropped or was closed.
+    pub error: Option<String>,
+}
+
+/// An event received by the [`EventSource`] on `entity`.
+#[derive(Event, Debug, Clone)]
+pub struct ServerSentEvent {
+    pub entity: Entity,
+    /// The `event:` type, `message` if the server sent none.
+    pub event: String,
+    /// The `data:` lines, joined with newlines.
+    pub data: String,
+    /// The last `id:` the server sent, up to this event.
+    pub id: Option<String>,
+}
+
+/// Splits the stream into lines and lines into events, as the HTML spec describes.
+#[derive(Debug, Default)]
+struct Parser {
+    line: Vec<u8>,
+    /// A line ended with `\r`, a `\n` starting the next chunk belongs to it.
+    skip_lf: bool,
+    /// Past the optional byte order mark.
+    started: bool,
+    event: String,
+    data: String,
+    id: Option<String>,
+}
+
+/// The byte order mark a stream may start with.
+const BOM: [u8; 3] = [0xef, 0xbb, 0xbf];
+
+/// What one line of the stream did.
+enum Line {
+    Dispatch(Option<(String, String)>),
+    Retry(Duration),
+}
+
+impl Parser {
+    fn feed(&mut self, bytes: &[u8], mut on_line: impl FnMut(&mut Self, Line)) {
+        for &byte in bytes {
+            let skip_lf = std::mem::take(&mut self.skip_lf);
+            match byte {
+                b'\n' if skip_lf => {}
+                b'\r' | b'\n' => {
+                    self.skip_lf = byte == b'\r';
+                    let mut line = std::mem::take(&mut self.line);
+                    if !std::mem::replace(&mut self.started, true) && line.starts_with(&BOM) {
+                        line.drain(..BOM.len());
+                    }
+                    if let Some(line) = self.line(&String::from_utf8_lossy(&line)) {
+                        on_line(self, line);
+                    }
+                }
+                _ => self.line.push(byte),
+            }
+        }
+    }
+
+    fn line(&mut self, line: &str) -> Option<Line> {
+        if line.is_empty() {
+            let event = std::mem::take(&mut self.event);
+            let mut data = std::mem::take(&mut self.data);
+            if data.is_empty() {
+                return Some(Line::Dispatch(None));
+            }
+            data.pop();
+            let event = if event.is_empty() {
+                "message".to_string()
+            } else {
+                event
+            };
+            return Some(Line::Dispatch(Some((event, data))));
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => {
+                self.id = Some(value.to_string()).filter(|id| !id.is_empty());
+            }
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                return value
+                    .parse()
+                    .ok()
+                    .map(|millis| Line::Retry(Duration::from_millis(millis)));
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// The request of every connected [`EventSource`] by its entity, so a stream whose component is
+/// removed can be aborted without the other requests of the entity.
+#[derive(Resource, Default)]
+pub(crate) struct EventStreams(HashMap<Entity, RequestId>);
+
+pub(crate) fn connect_event_sources(
+    mut queue: ResMut<RequestQueue>,
+    mut streams: ResMut<EventStreams>,
+    mut sources: Query<(Entity, &mut EventSource)>,
+    mut changes: EventWriter<ConnectionStateChanged>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    for (entity, mut source) in sources.iter_mut() {
+        if source.request_id.is_some()
+            || source.state == ConnectionState::Closed
+            || source.reconnect_at.is_some_and(|at| now < at)
+        {
+            continue;
+        }
+        let mut request = HttpClient::new()
+            .get(&source.url)
+            .entity(entity)
+            .label("event-stream")
+            .streaming()
+            .no_timeout()
+            .header("Accept", "text/event-stream")
+            .header("Cache-Control", "no-cache");
+        for (name, value) in &source.headers {
+            request = request.header(name, value);
+        }
+        if let Some(id) = &source.last_event_id {
+            request = request.header("Last-Event-ID", id);
+        }
+        let request = request.build();
+        source.request_id = Some(request.id);
+        source.received_at = None;
+        streams.0.insert(entity, request.id);
+        source.set_state(entity, ConnectionState::Connecting, None, &mut changes);
+        queue.push(request, move |world, request_id, result| {
+            if let Some(mut source) = world.get_mut::<EventSource>(entity) {
+                if source.request_id == Some(request_id) {
+                    source.ended = Some(result);
+                }
+            }
+        });
+    }
+}
+
+pub(crate) fn read_event_streams(
+    mut metas: EventReader<ResponseMeta>,
+    mut chunks: EventReader<HttpResponseChunk>,
+    mut sources: Query<&mut EventSource>,
+    (mut events, mut changes): (
+        EventWriter<ServerSentEvent>,
+        EventWriter<ConnectionStateChanged>,
+    ),
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    for meta in metas.read() {
+        let Ok(mut source) = sources.get_mut(meta.entity) else {
+            continue;
+        };
+        if source.request_id == Some(meta.request_id)
+            && meta.status == 200
+            && is_event_stream(&meta.headers)
+        {
+            source.attempts = 0;
+            source.received_at = Some(now);
+            source.set_state(meta.entity, ConnectionState::Open, None, &mut changes);
+        }
+    }
+
+    for chunk in chunks.read() {
+        let Ok(mut source) = sources.get_mut(chunk.entity) else {
+            continue;
+        };
+        if source.request_id != Some(chunk.request_id) || source.state != ConnectionState::Open {
+            continue;
+        }
+        let source = &mut *source;
+        source.received_at = Some(now);
+        let mut parser = std::mem::take(&mut source.parser);
+        parser.feed(&chunk.bytes, |parser, line| match line {
+            Line::Dispatch(event) => {
+                source.last_event_id.clone_from(&parser.id);
+                if let Some((event, data)) = event {
+                    events.send(ServerSentEvent {
+                        entity: chunk.entity,
+                        event,
+                        data,
+                        id: source.last_event_id.clone(),
+                    });
+                }
+            }
+            Line::Retry(retry) => source.retry = retry,
+        });
+        source.parser = parser;
+    }
+}
+
+/// Closes or schedules the reconnect of sources whose connection ended or went idle.
+pub(crate) fn end_event_streams(
+    mut sources: Query<(Entity, &mut EventSource)>,
+    mut streams: ResMut<EventStreams>,
+    (mut changes, mut aborts): (
+        EventWriter<ConnectionStateChanged>,
+        EventWriter<AbortRequestId>,
+    ),
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    for (entity, mut source) in sources.iter_mut() {
+        let idle = source
+            .idle_timeout
+            .zip(source.received_at)
+            .filter(|(idle_timeout, received_at)| now >= *received_at + *idle_timeout);
+        if let (Some((idle_timeout, _)), None) = (idle, &source.ended) {
+            // The result of the aborted request is ignored, the stream reconnects right here.
+            if let Some(request_id) = source.request_id {
+                aborts.send(AbortRequestId(request_id));
+            }
+            source.ended = Some(Err(format!("Nothing received for {idle_timeout:?}")));
+        }
+        let Some(result) = source.ended.take() else {
+            continue;
+        };
+        source.request_id = None;
+        source.received_at = None;
+        streams.0.remove(&entity);
+        // An event cut off by the end of the stream is dropped.
+        let parser = Parser {
+            id: source.parser.id.take(),
+            ..default()
+        };
+        source.parser = parser;
+        let error = match result {
+            Err(error) if error == REQUEST_ABORTED => {
+                source.set_state(entity, ConnectionState::Closed, Some(error), &mut changes);
+                continue;
+            }
+            Err(error) => error,
+            Ok(res) if res.status == 200 && is_event_stream(&res.headers) => {
+                "Stream ended".to_string()
+            }
+            Ok(res) if res.status >= 500 || res.status == 408 || res.status == 429 => {
+                format!("{} {}", res.status, res.status_text)
+            }
+            Ok(res) => {
+                let error = if res.status == 200 {
+                    "Not an event stream".to_string()
+                } else {
+                    format!("{} {}", res.status, res.status_text)
+                };
+                source.set_state(entity, ConnectionState::Closed, Some(error), &mut changes);
+                continue;
+            }
+        };
+        source.attempts += 1;
+        let backoff = source
+            .retry
+            .saturating_mul(2u32.saturating_pow(source.attempts - 1))
+            .min(source.max_backoff);
+        source.reconnect_at = Some(clock.now() + backoff);
+        source.set_state(
+            entity,
+            ConnectionState::Reconnecting,
+            Some(error),
+            &mut changes,
+        );
+    }
+}
+
+/// Aborts the stream of entities that lost their `EventSource`, other requests of the entity go on.
+pub(crate) fn close_removed_event_sources(
+    mut removed: RemovedComponents<EventSource>,
+    mut streams: ResMut<EventStreams>,
+    mut aborts: EventWriter<AbortRequestId>,
+) {
+    for entity in removed.read() {
+        if let Some(request_id) = streams.0.remove(&entity) {
+            aborts.send(AbortRequestId(request_id));
+        }
+    }
+}
+
+fn is_event_stream(headers: &ehttp::Headers) -> bool {
+    headers
+        .get("content-type")
+        .is_some_and(|content_type| content_type.trim_start().starts_with("text/event-stream"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What the parser made of the stream: the dispatched events with the id at the time, and the
+    /// retry delays.
+    #[derive(Debug, Default, PartialEq)]
+    struct Parsed {
+        events: Vec<(String, String, Option<String>)>,
+        retries: Vec<Duration>,
+    }
+
+    fn parse(parser: &mut Parser, chunks: &[&[u8]]) -> Parsed {
+        let mut parsed = Parsed::default();
+        for chunk in chunks {
+            parser.feed(chunk, |parser, line| match line {
+                Line::Dispatch(Some((event, data))) => {
+                    parsed.events.push((event, data, parser.id.clone()))
+                }
+                Line::Dispatch(None) => {}
+                Line::Retry(retry) => parsed.retries.push(retry),
+            });
+        }
+        parsed
+    }
+
+    fn events(chunks: &[&[u8]]) -> Vec<(String, String, Option<String>)> {
+        parse(&mut Parser::default(), chunks).events
+    }
+
+    fn message(data: &str) -> (String, String, Option<String>) {
+        ("message".to_string(), data.to_string(), None)
+    }
+
+    #[test]
+    fn ends_lines_with_cr_lf_and_crlf() {
+        let expected = vec![message("a"), message("b")];
+        assert_eq!(events(&[b"data: a\n\ndata: b\n\n"]), expected);
+        assert_eq!(events(&[b"data: a\r\rdata: b\r\r"]), expected);
+        assert_eq!(events(&[b"data: a\r\n\r\ndata: b\r\n\r\n"]), expected);
+        assert_eq!(
+            events(&[b"data: a\r\ndata: b\n\r\n"]),
+            vec![message("a\nb")]
+        );
+    }
+
+    #[test]
+    fn joins_lines_split_across_chunks() {
+        // A CRLF split between two chunks ends one line, not two.
+        assert_eq!(
+            events(&[b"data: a\r", b"\ndata: b\r", b"\n\r", b"\n"]),
+            vec![message("a\nb")]
+        );
+        assert_eq!(
+            events(&[b"da", b"ta: he", b"llo\n", b"\n"]),
+            vec![message("hello")]
+        );
+        // A lone CR followed by a new chunk still ends the line.
+        assert_eq!(events(&[b"data: a\r", b"\r"]), vec![message("a")]);
+    }
+
+    #[test]
+    fn skips_a_leading_byte_order_mark() {
+        assert_eq!(
+            events(&[b"\xef\xbb\xbfdata: a\n\n\xef\xbb\xbfdata: b\n\n"]),
+            vec![message("a")]
+        );
+        assert_eq!(
+            events(&[b"\xef\xbb", b"\xbfevent: ping\ndata\n\n"]),
+            vec![("ping".to_string(), String::new(), None)]
+        );
+    }
+
+    #[test]
+    fn reads_retry_delays() {
+        let parsed = parse(
+            &mut Parser::default(),
+            &[
+                b"retry: 2500\nretry: 1.5\nretry:\nretry: x1\n",
+                b"retry: 10\n",
+            ],
+        );
+        assert_eq!(
+            parsed.retries,
+            vec![Duration::from_millis(2500), Duration::from_millis(10)]
+        );
+        assert!(parsed.events.is_empty());
+    }
+
+    #[test]
+    fn keeps_the_last_event_id() {
+        let mut parser = Parser::default();
+        let parsed = parse(
+            &mut parser,
+            &[
+                b"id: 1\ndata: a\n\n",
+                b"data: b\n\n",
+                b"id: 2\n\n",
+                b"id: bad\0\ndata: c\n\n",
+                b"id\ndata: d\n\n",
+            ],
+        );
+        let with_id = |data: &str, id: Option<&str>| {
+            (
+                "message".to_string(),
+                data.to_string(),
+                id.map(str::to_string),
+            )
+        };
+        assert_eq!(
+            parsed.events,
+            vec![
+                with_id("a", Some("1")),
+                with_id("b", Some("1")),
+                with_id("c", Some("2")),
+                with_id("d", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_comments_and_unknown_fields() {
+        assert_eq!(
+            events(&[b": keep-alive\n\nfoo: bar\ndata:no space\n\n"]),
+            vec![message("no space")]
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bevy::prelude::Resource;
+use bevy::utils::HashMap;
+
+/// Tags a request with the feature it belongs to, so [`HttpStats`] can break traffic down by label.
+///
+/// # Examples
+///
+/// ```
+/// let request = HttpClient::new().get("http://example.com/scores").label("leaderboard").build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestLabel(pub Cow<'static, str>);
+
+impl RequestLabel {
+    pub fn new(label: impl Into<Cow<'static, str>>) -> Self {
+        Self(label.into())
+    }
+}
+
+impl std::fmt::Display for RequestLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Counters for a group of requests.
+#[derive(Debug, Clone, Default)]
+pub struct RequestStats {
+    /// Requests dispatched.
+    pub sent: u64,
+    /// Requests that got a 2xx response.
+    pub succeeded: u64,
+    /// Requests that failed, timed out, were aborted or got a non-2xx response.
+    pub failed: u64,
+    /// Response body bytes received.
+    pub bytes_received: u64,
+    /// Summed time from dispatch to result, of every finished request.
+    pub total_latency: Duration,
+    /// Longest time from dispatch to result.
+    pub max_latency: Duration,
+}
+
+impl RequestStats {
+    /// Requests that finished one way or another.
+    pub fn finished(&self) -> u64 {
+        self.succeeded + self.failed
+    }
+
+    /// Average time from dispatch to result.
+    pub fn average_latency(&self) -> Option<Duration> {
+        let finished = u32::try_from(self.finished()).ok().filter(|n| *n > 0)?;
+        Some(self.total_latency / finished)
+    }
+
+    fn record(&mut self, ok: bool, bytes: u64, latency: Duration) {
+        if ok {
+            self.succeeded += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.bytes_received += bytes;
+        self.total_latency += latency;
+        self.max_latency = self.max_latency.max(latency);
+    }
+}
+
+/// How often responses from a host came over a kept-alive connection.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionStats {
+    /// Responses that needed a new connection.
+    pub new: u64,
+    /// Responses that came over a connection opened by an earlier request.
+    pub reused: u64,
+}
+
+impl ConnectionStats {
+    /// Fraction of responses that reused a connection, from `0.0` to `1.0`.
+    pub fn reuse_rate(&self) -> Option<f32> {
+        let total = self.new + self.reused;
+        (total > 0).then(|| self.reused as f32 / total as f32)
+    }
+}
+
+/// Upper bounds of the latency histogram buckets in seconds, the defaults of the Prometheus
+/// client libraries.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The labels of a metric series.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct SeriesKey {
+    host: String,
+    label: String,
+    status_class: &'static str,
+}
+
+/// The counters and latency histogram of finished requests with the same labels.
+#[derive(Debug, Clone, Default)]
+struct Series {
+    count: u64,
+    bytes_received: u64,
+    latency_sum: Duration,
+    /// Requests per bucket of `LATENCY_BUCKETS`, not cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+/// Traffic counters of every request, and per [`RequestLabel`].
+///
+/// Native builds also count connection reuse per host, to check that keep-alive works. Only the
+/// connection of the response is counted, not those of redirects it followed, and none for
+/// `unix://` urls.
+///
+/// The counters are also kept by host, label and status class, with a latency histogram, for
+/// `to_prometheus`.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct HttpStats {
+    pub total: RequestStats,
+    pub labels: HashMap<RequestLabel, RequestStats>,
+    /// Connection reuse by host and port, e.g. `api.example.com:443`. Not available on wasm builds,
+    /// where the browser manages the connections.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub hosts: HashMap<String, ConnectionStats>,
+    /// Dispatched requests by host and label.
+    sent: HashMap<(String, String), u64>,
+    series: HashMap<SeriesKey, Series>,
+}
+
+impl HttpStats {
+    /// The counters of a label, if a request with it was sent.
+    pub fn label(&self, label: &str) -> Option<&RequestStats> {
+        self.labels.get(&RequestLabel::new(label.to_string()))
+    }
+
+    /// The connection reuse of `host`, e.g. `api.example.com:443`, if a response came from it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn host(&self, host: &str) -> Option<&ConnectionStats> {
+        self.hosts.get(&host.to_ascii_lowercase())
+    }
+
+    /// Counts the connection a response from `url` came over.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn record_connection(&mut self, url: &str, reused: bool) {
+        let Some(host) = url::Url::parse(url).ok().and_then(|url| {
+            Some(format!(
+                "{}:{}",
+                url.host_str()?,
+                url.port_or_known_default()?
+            ))
+        }) else {
+            return;
+        };
+        let stats = self.hosts.entry(host).or_default();
+        if reused {
+            stats.reused += 1;
+        } else {
+            stats.new += 1;
+        }
+    }
+
+    pub(crate) fn record_sent(&mut self, label: Option<&RequestLabel>, host: &str) {
+        self.total.sent += 1;
+        if let Some(label) = label {
+            self.labels.entry(label.clone()).or_default().sent += 1;
+        }
+        let label = label.map_or_else(String::new, |label| label.0.to_string());
+        *self.sent.entry((host.to_string(), label)).or_default() += 1;
+    }
+
+    /// Counts a finished request, `status` is `None` if it got no response.
+    pub(crate) fn record_finished(
+        &mut self,
+        label: Option<&RequestLabel>,
+        host: &str,
+        status: Option<u16>,
+        bytes: u64,
+        latency: Duration,
+    ) {
+        let ok = status.is_some_and(|status| (200..300).contains(&status));
+        self.total.record(ok, bytes, latency);
+        if let Some(label) = label {
+            self.labels
+                .entry(label.clone())
+                .or_default()
+                .record(ok, bytes, latency);
+        }
+        let key = SeriesKey {
+            host: host.to_string(),
+            label: label.map_or_else(String::new, |label| label.0.to_string()),
+            status_class: status_class(status),
+        };
+        let series = self.series.entry(key).or_default();
+        series.count += 1;
+        series.bytes_received += bytes;
+        series.latency_sum += latency;
+        if let Some(bucket) = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency.as_secs_f64() <= *bound)
+        {
+            series.buckets[bucket] += 1;
+        }
+    }
+
+    /// Renders the counters in the Prometheus text exposition format, e.g. to serve them from a
+    /// dedicated server build for scraping.
+    ///
+    /// Series are labeled with the `host` of the request url, with its port if it is not the
+    /// default one, the request `label`, empty for requests without one, and the `status_class`
+    /// of the response, `2xx` to `5xx`, or `error` for requests that got none, e.g. timeouts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fn serve_metrics(stats: Res<HttpStats>, mut server: ResMut<MetricsServer>) {
+    ///     server.set_body(stats.to_prometheus());
+    /// }
+    /// ```
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut sent: Vec<_> = self.sent.iter().collect();
+        sent.sort();
+        out.push_str("# HELP http_client_requests_sent_total Requests dispatched.\n");
+        out.push_str("# TYPE http_client_requests_sent_total counter\n");
+        for ((host, label), count) in sent {
+            let labels = format!("host=\"{}\",label=\"{}\"", escape(host), escape(label));
+            out.push_str(&format!(
+                "http_client_requests_sent_total{{{labels}}} {count}\n"
+            ));
+        }
+
+        let mut series: Vec<_> = self.series.iter().collect();
+        series.sort_by_key(|(key, _)| *key);
+        let labels = |key: &SeriesKey| {
+            format!(
+                "host=\"{}\",label=\"{}\",status_class=\"{}\"",
+                escape(&key.host),
+                escape(&key.label),
+                key.status_class
+            )
+        };
+        out.push_str("# HELP http_client_requests_total Requests finished.\n");
+        out.push_str("# TYPE http_client_requests_total counter\n");
+        for (key, series) in &series {
+            let labels = labels(key);
+            out.push_str(&format!(
+                "http_client_requests_total{{{labels}}} {}\n",
+                series.count
+            ));
+        }
+        out.push_str("# HELP http_client_response_bytes_total Response body bytes received.\n");
+        out.push_str("# TYPE http_client_response_bytes_total counter\n");
+        for (key, series) in &series {
+            let labels = labels(key);
+            out.push_str(&format!(
+                "http_client_response_bytes_total{{{labels}}} {}\n",
+                series.bytes_received
+            ));
+        }
+        out.push_str("# HELP http_client_request_duration_seconds Time from dispatch to result.\n");
+        out.push_str("# TYPE http_client_request_duration_seconds histogram\n");
+        for (key, series) in &series {
+            let labels = labels(key);
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(series.buckets) {
+                cumulative += count;
+                out.push_str(&format!(
+                    "http_client_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}\n"
+                ));
+            }
+            out.push_str(&format!(
+                "http_client_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}\n",
+                series.count
+            ));
+            out.push_str(&format!(
+                "http_client_request_duration_seconds_sum{{{labels}}} {}\n",
+                series.latency_sum.as_secs_f64()
+            ));
+            out.push_str(&format!(
+                "http_client_request_duration_seconds_count{{{labels}}} {}\n",
+                series.count
+            ));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut hosts: Vec<_> = self.hosts.iter().collect();
+            hosts.sort_by_key(|(host, _)| *host);
+            out.push_str("# HELP http_client_connections_total Responses by connection reuse.\n");
+            out.push_str("# TYPE http_client_connections_total counter\n");
+            for (host, stats) in hosts {
+                let host = escape(host);
+                out.push_str(&format!(
+                    "http_client_connections_total{{host=\"{host}\",reused=\"false\"}} {}\n",
+                    stats.new
+                ));
+                out.push_str(&format!(
+                    "http_client_connections_total{{host=\"{host}\",reused=\"true\"}} {}\n",
+                    stats.reused
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// The host of `url` for the metrics, with the port if it is not the default one.
+pub(crate) fn metric_host(url: &str) -> String {
+    let Ok(url) = url::Url::parse(url) else {
+        return String::new();
+    };
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_string(),
+        (None, _) => String::new(),
+    }
+}
+
+fn status_class(status: Option<u16>) -> &'static str {
+    match status {
+        None => "error",
+        Some(100..=199) => "1xx",
+        Some(200..=299) => "2xx",
+        Some(300..=399) => "3xx",
+        Some(400..=499) => "4xx",
+        Some(500..=599) => "5xx",
+        Some(_) => "unknown",
+    }
+}
+
+/// Escapes a label value of the exposition format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
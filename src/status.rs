@@ -0,0 +1,151 @@
+/// The class of an HTTP status code, carrying the code itself, so handlers can match on categories
+/// instead of comparing numbers.
+///
+/// # Examples
+///
+/// ```
+/// for response in ev_response.read() {
+///     match response.status_class() {
+///         HttpStatusClass::Success(_) => println!("{:?}", response.text()),
+///         HttpStatusClass::ClientError(404) => println!("not found"),
+///         HttpStatusClass::ServerError(code) => println!("server failed with {code}"),
+///         class => println!("unexpected {class}"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HttpStatusClass {
+    /// `1xx`
+    Informational(u16),
+    /// `2xx`
+    Success(u16),
+    /// `3xx`, only seen when redirects are not followed.
+    Redirection(u16),
+    /// `4xx`, the request was rejected.
+    ClientError(u16),
+    /// `5xx`, the server failed to answer the request.
+    ServerError(u16),
+    /// Codes outside of `100..600`.
+    Unknown(u16),
+}
+
+impl HttpStatusClass {
+    pub fn code(self) -> u16 {
+        match self {
+            Self::Informational(code)
+            | Self::Success(code)
+            | Self::Redirection(code)
+            | Self::ClientError(code)
+            | Self::ServerError(code)
+            | Self::Unknown(code) => code,
+        }
+    }
+
+    pub fn is_success(self) -> bool {
+        matches!(self, Self::Success(_))
+    }
+
+    /// Whether the status is a client or server error.
+    pub fn is_error(self) -> bool {
+        matches!(self, Self::ClientError(_) | Self::ServerError(_))
+    }
+
+    /// Whether sending the request again may succeed: a server error, `408 Request Timeout` or
+    /// `429 Too Many Requests`.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::ServerError(_) | Self::ClientError(408 | 429))
+    }
+}
+
+impl From<u16> for HttpStatusClass {
+    fn from(code: u16) -> Self {
+        match code {
+            100..=199 => Self::Informational(code),
+            200..=299 => Self::Success(code),
+            300..=399 => Self::Redirection(code),
+            400..=499 => Self::ClientError(code),
+            500..=599 => Self::ServerError(code),
+            _ => Self::Unknown(code),
+        }
+    }
+}
+
+impl std::fmt::Display for HttpStatusClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let class = match self {
+            Self::Informational(_) => "informational",
+            Self::Success(_) => "success",
+            Self::Redirection(_) => "redirection",
+            Self::ClientError(_) => "client error",
+            Self::ServerError(_) => "server error",
+            Self::Unknown(_) => "unknown status",
+        };
+        write!(f, "{} ({class})", self.code())
+    }
+}
+
+/// An HTTP status code, see `HttpResponse::status`.
+///
+/// # Examples
+///
+/// ```
+/// if response.status() == StatusCode::NOT_MODIFIED {
+///     return;
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StatusCode(pub u16);
+
+impl StatusCode {
+    pub const OK: Self = Self(200);
+    pub const CREATED: Self = Self(201);
+    pub const ACCEPTED: Self = Self(202);
+    pub const NO_CONTENT: Self = Self(204);
+    pub const PARTIAL_CONTENT: Self = Self(206);
+    pub const MOVED_PERMANENTLY: Self = Self(301);
+    pub const FOUND: Self = Self(302);
+    pub const NOT_MODIFIED: Self = Self(304);
+    pub const BAD_REQUEST: Self = Self(400);
+    pub const UNAUTHORIZED: Self = Self(401);
+    pub const FORBIDDEN: Self = Self(403);
+    pub const NOT_FOUND: Self = Self(404);
+    pub const REQUEST_TIMEOUT: Self = Self(408);
+    pub const CONFLICT: Self = Self(409);
+    pub const GONE: Self = Self(410);
+    pub const PRECONDITION_FAILED: Self = Self(412);
+    pub const TOO_MANY_REQUESTS: Self = Self(429);
+    pub const INTERNAL_SERVER_ERROR: Self = Self(500);
+    pub const BAD_GATEWAY: Self = Self(502);
+    pub const SERVICE_UNAVAILABLE: Self = Self(503);
+    pub const GATEWAY_TIMEOUT: Self = Self(504);
+
+    pub fn as_u16(self) -> u16 {
+        self.0
+    }
+
+    pub fn class(self) -> HttpStatusClass {
+        HttpStatusClass::from(self.0)
+    }
+
+    pub fn is_success(self) -> bool {
+        self.class().is_success()
+    }
+}
+
+impl From<u16> for StatusCode {
+    fn from(code: u16) -> Self {
+        Self(code)
+    }
+}
+
+impl PartialEq<u16> for StatusCode {
+    fn eq(&self, other: &u16) -> bool {
+        self.0 == *other
+    }
+}
+
+impl std::fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -0,0 +1,192 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+
+use bevy::log::warn;
+use ehttp::{Headers, Request};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Where state is kept across sessions, a file on native builds and a `localStorage` entry on wasm
+/// builds.
+#[derive(Debug, Clone)]
+pub(crate) enum Store {
+    #[cfg(not(target_arch = "wasm32"))]
+    File(PathBuf),
+    #[cfg(target_arch = "wasm32")]
+    LocalStorage(String),
+}
+
+impl Store {
+    /// The file at `location` on native builds, the `localStorage` key `location` on wasm builds.
+    pub(crate) fn new(location: impl ToString) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Self::File(PathBuf::from(location.to_string()));
+
+        #[cfg(target_arch = "wasm32")]
+        return Self::LocalStorage(location.to_string());
+    }
+
+    /// Reads the stored value, `None` if nothing is stored. A value that can not be read is logged
+    /// and treated as missing.
+    pub(crate) fn load<T: DeserializeOwned>(&self) -> Option<T> {
+        let parsed = match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(path) => match std::fs::read(path) {
+                Ok(bytes) => serde_json::from_slice(&bytes),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+                Err(e) => {
+                    warn!("Failed to read {}: {e}", path.display());
+                    return None;
+                }
+            },
+            #[cfg(target_arch = "wasm32")]
+            Self::LocalStorage(key) => serde_json::from_str(&local_storage()?.get_item(key).ok()??),
+        };
+        parsed
+            .map_err(|e| warn!("Failed to parse {self}: {e}"))
+            .ok()
+    }
+
+    /// Writes `value`, best effort. Files are replaced at once, so a crash while saving leaves the
+    /// previous value in place.
+    pub(crate) fn save<T: Serialize>(&self, value: &T) {
+        let json = match serde_json::to_string(value) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!("Failed to serialize {self}: {e}");
+                return;
+            }
+        };
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(path) => {
+                if let Err(e) = write_atomically(path, json.as_bytes()) {
+                    warn!("Failed to write {self}: {e}");
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            Self::LocalStorage(key) => {
+                if let Some(storage) = local_storage() {
+                    if let Err(e) = storage.set_item(key, &json) {
+                        warn!("Failed to write {self}: {e:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(path) => {
+                let _ = std::fs::remove_file(path);
+            }
+            #[cfg(target_arch = "wasm32")]
+            Self::LocalStorage(key) => {
+                if let Some(storage) = local_storage() {
+                    let _ = storage.remove_item(key);
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Store {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(path) => write!(f, "{}", path.display()),
+            #[cfg(target_arch = "wasm32")]
+            Self::LocalStorage(key) => write!(f, "localStorage entry {key:?}"),
+        }
+    }
+}
+
+/// Writes `bytes` to a temporary file next to `path` and renames it into place, so readers see
+/// either the old or the new contents but never a partly written file.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temporary = path.with_file_name(name);
+    std::fs::write(&temporary, bytes)
+        .and_then(|()| std::fs::rename(&temporary, path))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&temporary);
+        })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// A request as it is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StoredRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl From<&Request> for StoredRequest {
+    fn from(request: &Request) -> Self {
+        Self {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            headers: request.headers.headers.clone(),
+            body: request.body.clone(),
+        }
+    }
+}
+
+impl From<StoredRequest> for Request {
+    fn from(stored: StoredRequest) -> Self {
+        Request {
+            method: stored.method,
+            url: stored.url,
+            body: stored.body,
+            headers: Headers {
+                headers: stored.headers,
+            },
+            #[cfg(target_arch = "wasm32")]
+            mode: ehttp::Mode::default(),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn store(name: &str) -> (Store, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("bevy_http_client_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("store.json");
+        let _ = std::fs::remove_file(&path);
+        (Store::File(path), dir)
+    }
+
+    #[test]
+    fn saves_and_loads_values() {
+        let (store, dir) = store("roundtrip");
+        assert_eq!(store.load::<Vec<u32>>(), None);
+        store.save(&vec![1, 2]);
+        store.save(&vec![3]);
+        assert_eq!(store.load::<Vec<u32>>(), Some(vec![3]));
+        // Only the file itself is left, the temporary file was renamed into place.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unparsable_values_load_as_missing() {
+        let (store, dir) = store("corrupt");
+        let Store::File(path) = &store;
+        std::fs::write(path, "[1, 2").unwrap();
+        assert_eq!(store.load::<Vec<u32>>(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+use async_channel::Sender;
+use bevy::prelude::{Entity, Event};
+use bytes::Bytes;
+
+use crate::RequestId;
+
+/// How the response body is read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyMode {
+    /// The whole body is read before the response is delivered.
+    #[default]
+    Buffered,
+    /// The body is still delivered with the response, `HttpProgress` events are sent while it downloads.
+    Progress,
+    /// Every chunk is sent as an `HttpResponseChunk` event as soon as it arrives,
+    /// the final response carries the status and headers with an empty body.
+    Streaming,
+}
+
+/// Download progress of a request with `BodyMode::Progress` or `BodyMode::Streaming`.
+///
+/// At most one progress event is sent per request and frame.
+#[derive(Event, Debug, Clone)]
+pub struct HttpProgress {
+    /// The entity carrying the request task.
+    pub entity: Entity,
+    pub request_id: RequestId,
+    /// Body bytes received so far.
+    pub received: u64,
+    /// The expected body size, from the `Content-Length` header.
+    pub total: Option<u64>,
+}
+
+impl HttpProgress {
+    /// Fraction of the body received so far, if the total size is known.
+    pub fn fraction(&self) -> Option<f32> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| (self.received as f64 / total as f64).min(1.0) as f32)
+    }
+}
+
+/// A chunk of the response body of a request with `BodyMode::Streaming`.
+///
+/// Chunks of the same request arrive in order, before its `HttpResponse`.
+#[derive(Event, Debug, Clone)]
+pub struct HttpResponseChunk {
+    /// The entity carrying the request task.
+    pub entity: Entity,
+    pub request_id: RequestId,
+    pub bytes: Bytes,
+}
+
+/// What the transport reports back while the body downloads.
+pub(crate) struct BodyPart {
+    pub(crate) chunk: Option<Bytes>,
+    pub(crate) received: u64,
+    pub(crate) total: Option<u64>,
+}
+
+/// Collects the body as it is read, reporting progress and chunks according to the body mode.
+pub(crate) struct BodySink {
+    mode: BodyMode,
+    parts: Option<Sender<BodyPart>>,
+    total: Option<u64>,
+    received: u64,
+    bytes: Vec<u8>,
+}
+
+impl BodySink {
+    pub(crate) fn new(mode: BodyMode, parts: Option<Sender<BodyPart>>) -> Self {
+        Self {
+            mode,
+            parts,
+            total: None,
+            received: 0,
+            bytes: vec![],
+        }
+    }
+
+    /// Whether the transport has to read the body incrementally.
+    pub(crate) fn is_incremental(&self) -> bool {
+        self.mode != BodyMode::Buffered
+    }
+
+    pub(crate) fn set_total(&mut self, total: Option<u64>) {
+        self.total = total;
+    }
+
+    pub(crate) fn push(&mut self, chunk: Vec<u8>) {
+        self.received += chunk.len() as u64;
+        let chunk = match self.mode {
+            BodyMode::Streaming => Some(Bytes::from(chunk)),
+            _ => {
+                self.bytes.extend_from_slice(&chunk);
+                None
+            }
+        };
+        if let Some(parts) = &self.parts {
+            let _ = parts.try_send(BodyPart {
+                chunk,
+                received: self.received,
+                total: self.total,
+            });
+        }
+    }
+
+    /// The buffered body, empty in streaming mode.
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::VecDeque;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use serde::Serialize;
+use serde_json::Value;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::storage::Store;
+use crate::{HttpClient, HttpClock, HttpRequest, HttpSchedule, HttpSet, HttpShutdown, OnComplete};
+
+/// Uploads the events pushed to the [`Telemetry`] resource in batches, each as a single POST with a
+/// JSON array body.
+///
+/// A batch is sent every `flush_interval`, or as soon as `max_batch` events are waiting. Failed
+/// uploads are retried with exponential backoff, keeping the events in order. Batches rejected with
+/// a 4xx status other than 408 and 429 are dropped, since resending them would not help. Add it
+/// after `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(
+///     TelemetryPlugin::new("https://telemetry.example.com/events")
+///         .with_flush_interval(Duration::from_secs(30))
+///         .with_spill_file("telemetry.json"),
+/// );
+///
+/// fn on_level_complete(mut telemetry: ResMut<Telemetry>) {
+///     telemetry.push(&LevelComplete { level: 3, seconds: 92.5 }).unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TelemetryPlugin {
+    pub url: String,
+    pub flush_interval: Duration,
+    /// Most events in one upload, a full batch is sent right away.
+    pub max_batch: usize,
+    /// Most events kept while offline, the oldest are dropped beyond it.
+    pub max_buffered: usize,
+    /// Backoff after the first failed upload, doubled for every further failure.
+    pub retry_backoff: Duration,
+    pub max_retry_backoff: Duration,
+    /// File the waiting events are written to while uploads fail, and read back at startup. Not
+    /// available on wasm builds.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub spill_file: Option<PathBuf>,
+}
+
+impl TelemetryPlugin {
+    /// create the plugin uploading to `url`
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            flush_interval: Duration::from_secs(60),
+            max_batch: 100,
+            max_buffered: 10_000,
+            retry_backoff: Duration::from_secs(1),
+            max_retry_backoff: Duration::from_secs(300),
+            #[cfg(not(target_arch = "wasm32"))]
+            spill_file: None,
+        }
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+
+    pub fn with_max_buffered(mut self, max_buffered: usize) -> Self {
+        self.max_buffered = max_buffered;
+        self
+    }
+
+    pub fn with_retry_backoff(
+        mut self,
+        retry_backoff: Duration,
+        max_retry_backoff: Duration,
+    ) -> Self {
+        self.retry_backoff = retry_backoff;
+        self.max_retry_backoff = max_retry_backoff;
+        self
+    }
+
+    /// spill waiting events to `path` while offline, see `spill_file`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_spill_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.spill_file = Some(path.into());
+        self
+    }
+}
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        #[cfg(not(target_arch = "wasm32"))]
+        let events = self
+            .spill_file
+            .as_deref()
+            .and_then(read_spill_file)
+            .unwrap_or_default();
+        #[cfg(target_arch = "wasm32")]
+        let events = VecDeque::new();
+        app.insert_resource(Telemetry { events });
+        app.insert_resource(TelemetryUploader {
+            config: self.clone(),
+            timer: Timer::new(self.flush_interval, TimerMode::Repeating),
+            uploading: false,
+            failures: 0,
+            retry_at: None,
+        });
+        app.add_systems(schedule, flush_telemetry.before(HttpSet::Queue));
+    }
+}
+
+/// Events waiting to be uploaded by the [`TelemetryPlugin`].
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Telemetry {
+    events: VecDeque<Value>,
+}
+
+impl Telemetry {
+    /// Queues `event` for the next upload, failing if it cannot be serialized to JSON.
+    pub fn push(&mut self, event: &impl Serialize) -> serde_json::Result<()> {
+        self.events.push_back(serde_json::to_value(event)?);
+        Ok(())
+    }
+
+    /// number of events waiting to be uploaded
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// check if no event is waiting to be uploaded
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[derive(Resource)]
+struct TelemetryUploader {
+    config: TelemetryPlugin,
+    timer: Timer,
+    /// Whether a batch is being uploaded, only one is in flight at a time.
+    uploading: bool,
+    /// Failed uploads in a row.
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+fn flush_telemetry(
+    time: Res<Time>,
+    mut uploader: ResMut<TelemetryUploader>,
+    mut telemetry: ResMut<Telemetry>,
+    mut requests: EventWriter<HttpRequest>,
+    (shutdown, clock): (Option<Res<HttpShutdown>>, Res<HttpClock>),
+) {
+    let flushing = shutdown.is_some_and(|shutdown| shutdown.is_flushing());
+    let interval_elapsed = uploader.timer.tick(time.delta()).just_finished();
+    let max_buffered = uploader.config.max_buffered;
+    if telemetry.events.len() > max_buffered {
+        let dropped = telemetry.events.len() - max_buffered;
+        telemetry.events.drain(..dropped);
+    }
+    if uploader.uploading || telemetry.events.is_empty() {
+        return;
+    }
+    let batch_full = telemetry.events.len() >= uploader.config.max_batch;
+    let due = match uploader.retry_at {
+        Some(retry_at) => clock.now() >= retry_at,
+        None => interval_elapsed || batch_full || flushing,
+    };
+    if !due {
+        return;
+    }
+
+    let count = telemetry.events.len().min(uploader.config.max_batch);
+    let batch: Vec<Value> = telemetry.events.drain(..count).collect();
+    // The app may exit before the upload finished, so the batch is spilled until it did.
+    #[cfg(not(target_arch = "wasm32"))]
+    if let (true, Some(path)) = (flushing, &uploader.config.spill_file) {
+        let spilled = batch.iter().chain(&telemetry.events).cloned().collect();
+        write_spill_file(path, &spilled, true);
+    }
+    let request = HttpClient::new()
+        .post(&uploader.config.url)
+        .json(&batch)
+        .label("telemetry")
+        .build();
+    uploader.uploading = true;
+
+    let on_complete = OnComplete::new(move |world, _, response| {
+        let retry = match &response {
+            Ok(res) if res.ok => false,
+            Ok(res) => !(400..500).contains(&res.status) || matches!(res.status, 408 | 429),
+            Err(_) => true,
+        };
+        let now = world.resource::<HttpClock>().now();
+        world.resource_scope(|world, mut uploader: Mut<TelemetryUploader>| {
+            #[cfg(not(target_arch = "wasm32"))]
+            let flushing = world
+                .get_resource::<HttpShutdown>()
+                .is_some_and(|shutdown| shutdown.is_flushing());
+            let mut telemetry = world.resource_mut::<Telemetry>();
+            uploader.uploading = false;
+            if retry {
+                for event in batch.into_iter().rev() {
+                    telemetry.events.push_front(event);
+                }
+                let backoff = uploader
+                    .config
+                    .retry_backoff
+                    .saturating_mul(2u32.saturating_pow(uploader.failures))
+                    .min(uploader.config.max_retry_backoff);
+                uploader.failures += 1;
+                uploader.retry_at = Some(now + backoff);
+            } else {
+                uploader.failures = 0;
+                uploader.retry_at = None;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(path) = &uploader.config.spill_file {
+                let events = &telemetry.events;
+                write_spill_file(path, events, retry || (flushing && !events.is_empty()));
+            }
+        });
+    });
+    requests.send(HttpRequest {
+        on_complete: Some(on_complete),
+        ..request
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_spill_file(path: &Path) -> Option<VecDeque<Value>> {
+    Store::File(path.to_path_buf()).load()
+}
+
+/// Writes the waiting events while uploads fail, and cleans up once they succeed again.
+///
+/// Spilling is best effort, the events stay in memory either way.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_spill_file(path: &PathBuf, events: &VecDeque<Value>, offline: bool) {
+    if offline {
+        Store::File(path.clone()).save(events);
+    } else if path.exists() {
+        let _ = std::fs::remove_file(path);
+    }
+}
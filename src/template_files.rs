@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use bevy::asset::io::Reader;
+use bevy::asset::ron::extensions::Extensions;
+use bevy::asset::ron::Options;
+use bevy::asset::{Asset, AssetApp, AssetEvent, AssetId, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+
+use crate::{HttpSet, RequestTemplate, RequestTemplates, RetryPolicy};
+
+/// Loads [`RequestTemplates`] from RON files, so new endpoint calls can be added in data, e.g. by
+/// designers for a live-ops event, and sent with `SendTemplate` without a code change. Only
+/// available with the `asset` feature.
+///
+/// The files are loaded by the asset server, add the plugin after `AssetPlugin` or
+/// `DefaultPlugins`. Their templates are added once they loaded and replaced when a file changes
+/// with asset hot reloading, replacing templates of the same name defined in code. A file of type
+/// `.requests.ron` holds a `base_url`, which sets `RequestTemplates::base_url`, and the templates
+/// by name. A template has a `url` with `{name}` placeholders, and optionally a `method`, `GET`
+/// by default, `headers`, a `timeout` in seconds, a number of `retries`, a `label` and the
+/// `response_type` its requests are sent as.
+///
+/// ```ron
+/// (
+///     base_url: "https://api.example.com/v1",
+///     templates: {
+///         "daily_reward": (
+///             method: "POST",
+///             url: "players/{player}/rewards/daily",
+///             headers: { "Authorization": "Bearer {token}" },
+///             timeout: 5.0,
+///             retries: 2,
+///             response_type: "Reward",
+///         ),
+///     },
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(DefaultPlugins)
+///     .add_plugins(HttpClientPlugin::default())
+///     .add_plugins(RequestTemplateFilesPlugin::new("api/live_ops.requests.ron"))
+///     .register_request_type::<Reward>();
+///
+/// fn claim_reward(mut ev_template: EventWriter<SendTemplate>, session: Res<Session>) {
+///     ev_template.send(
+///         SendTemplate::new("daily_reward")
+///             .param("player", &session.player_id)
+///             .param("token", &session.token),
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RequestTemplateFilesPlugin {
+    /// The asset paths of the files.
+    pub paths: Vec<String>,
+}
+
+impl RequestTemplateFilesPlugin {
+    /// load the templates of the file at `path`
+    pub fn new(path: impl ToString) -> Self {
+        Self {
+            paths: vec![path.to_string()],
+        }
+    }
+
+    /// load the templates of the file at `path` as well, see `paths`
+    pub fn with_file(mut self, path: impl ToString) -> Self {
+        self.paths.push(path.to_string());
+        self
+    }
+}
+
+impl Plugin for RequestTemplateFilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<RequestTemplateFile>();
+        app.register_asset_loader(RequestTemplateLoader);
+        app.init_resource::<RequestTemplates>();
+        let handles = self
+            .paths
+            .iter()
+            .map(|path| app.world.resource::<AssetServer>().load(path.clone()))
+            .collect();
+        app.insert_resource(TemplateFiles {
+            handles,
+            names: HashMap::new(),
+        });
+        // Added after `HttpClientPlugin`, the templates are there before requests are queued.
+        app.add_systems(Update, apply_template_files.before(HttpSet::Queue));
+    }
+}
+
+/// The templates of a `.requests.ron` file, see [`RequestTemplateFilesPlugin`].
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct RequestTemplateFile {
+    pub base_url: Option<String>,
+    pub templates: Vec<(String, RequestTemplate)>,
+}
+
+/// The loaded files, and the templates each of them added.
+#[derive(Resource)]
+struct TemplateFiles {
+    handles: Vec<Handle<RequestTemplateFile>>,
+    names: HashMap<AssetId<RequestTemplateFile>, Vec<String>>,
+}
+
+/// Reads `.requests.ron` files as [`RequestTemplateFile`]s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestTemplateLoader;
+
+impl AssetLoader for RequestTemplateLoader {
+    type Asset = RequestTemplateFile;
+    type Settings = ();
+    type Error = String;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<RequestTemplateFile, String>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(|e| e.to_string())?;
+            let file: FileDef = Options::default()
+                .with_default_extension(Extensions::IMPLICIT_SOME)
+                .from_bytes(&bytes)
+                .map_err(|e| format!("Invalid request templates: {e}"))?;
+            file.into_file()
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["requests.ron"]
+    }
+}
+
+#[derive(Deserialize)]
+struct FileDef {
+    #[serde(default)]
+    base_url: Option<String>,
+    templates: BTreeMap<String, TemplateDef>,
+}
+
+#[derive(Deserialize)]
+struct TemplateDef {
+    #[serde(default)]
+    method: Option<String>,
+    url: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    /// In seconds.
+    #[serde(default)]
+    timeout: Option<f32>,
+    #[serde(default)]
+    retries: Option<u32>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    response_type: Option<String>,
+}
+
+impl FileDef {
+    fn into_file(self) -> Result<RequestTemplateFile, String> {
+        let mut templates = Vec::with_capacity(self.templates.len());
+        for (name, def) in self.templates {
+            let mut template =
+                RequestTemplate::new(def.method.as_deref().unwrap_or("GET"), def.url);
+            for (header, value) in def.headers {
+                template = template.header(header, value);
+            }
+            if let Some(timeout) = def.timeout {
+                let timeout = Duration::try_from_secs_f32(timeout)
+                    .map_err(|e| format!("Invalid timeout of request template {name:?}: {e}"))?;
+                template = template.timeout(timeout);
+            }
+            if let Some(retries) = def.retries {
+                template = template.retry(RetryPolicy::new(retries));
+            }
+            if let Some(label) = def.label {
+                template = template.label(label);
+            }
+            template.response_type = def.response_type;
+            templates.push((name, template));
+        }
+        Ok(RequestTemplateFile {
+            base_url: self.base_url,
+            templates,
+        })
+    }
+}
+
+/// Adds the templates of files that loaded, replacing those of files that changed.
+fn apply_template_files(
+    mut ev_asset: EventReader<AssetEvent<RequestTemplateFile>>,
+    files: Res<Assets<RequestTemplateFile>>,
+    mut loaded: ResMut<TemplateFiles>,
+    mut templates: ResMut<RequestTemplates>,
+) {
+    for event in ev_asset.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = *event
+        else {
+            continue;
+        };
+        if !loaded.handles.iter().any(|handle| handle.id() == id) {
+            continue;
+        }
+        let Some(file) = files.get(id) else {
+            continue;
+        };
+        for name in loaded.names.remove(&id).unwrap_or_default() {
+            templates.remove(&name);
+        }
+        if let Some(base_url) = &file.base_url {
+            templates.base_url = Some(base_url.clone());
+        }
+        for (name, template) in &file.templates {
+            templates.insert(name, template.clone());
+        }
+        info!("Loaded {} request templates", file.templates.len());
+        let names = file
+            .templates
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect();
+        loaded.names.insert(id, names);
+    }
+}
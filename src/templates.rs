@@ -0,0 +1,330 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{urls, HttpClient, HttpRequest, RetryPolicy, TypedRequestRegistry};
+
+/// Named request presets, so every api call is defined once instead of repeating its builder chain
+/// wherever it is sent.
+///
+/// A template holds the method, a url with `{name}` placeholders, headers, timeout and
+/// [`RetryPolicy`]. `instantiate` fills the placeholders from the parameters and returns the
+/// `HttpClient`, ready for a body or an entity before it is built. Values in the url are
+/// percent-encoded as path segments, values in headers are inserted as they are. Requests are
+/// labeled with the template name unless the template sets a label. Relative template urls are
+/// joined onto the base url of the resource, see [`join_url`](crate::join_url).
+///
+/// Send a [`SendTemplate`] event to instantiate and send a template by name, e.g. from a list of
+/// calls defined in data. Templates can be loaded from RON files with the
+/// `RequestTemplateFilesPlugin` of the `asset` feature.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(
+///     RequestTemplates::default()
+///         .with_base_url("https://api.example.com/v1")
+///         .with(
+///             "player",
+///             RequestTemplate::new("GET", "players/{id}")
+///                 .header("Authorization", "Bearer {token}")
+///                 .timeout(Duration::from_secs(5))
+///                 .retry(RetryPolicy::new(2)),
+///         ),
+/// );
+///
+/// fn load_player(templates: Res<RequestTemplates>, mut ev_request: EventWriter<HttpRequest>) {
+///     match templates.instantiate("player", [("id", "42"), ("token", "abc")]) {
+///         Ok(client) => {
+///             ev_request.send(client.build());
+///         }
+///         Err(e) => error!("{e}"),
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct RequestTemplates {
+    /// The url relative template urls are joined onto.
+    pub base_url: Option<String>,
+    templates: HashMap<String, RequestTemplate>,
+}
+
+impl RequestTemplates {
+    /// join relative template urls onto `base_url`, see `base_url`
+    pub fn with_base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// register `template` as `name`, see `insert`
+    pub fn with(mut self, name: impl ToString, template: RequestTemplate) -> Self {
+        self.insert(name, template);
+        self
+    }
+
+    /// Registers `template` as `name`, replacing the template registered under that name before.
+    pub fn insert(&mut self, name: impl ToString, template: RequestTemplate) {
+        self.templates.insert(name.to_string(), template);
+    }
+
+    /// Removes the template `name`, returning it if it was registered.
+    pub fn remove(&mut self, name: &str) -> Option<RequestTemplate> {
+        self.templates.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RequestTemplate> {
+        self.templates.get(name)
+    }
+
+    /// Builds the client of template `name`, with its placeholders filled from `params`.
+    /// Parameters no placeholder asks for are ignored.
+    pub fn instantiate<K: AsRef<str>, V: ToString>(
+        &self,
+        name: &str,
+        params: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<HttpClient, TemplateError> {
+        let template = self
+            .get(name)
+            .ok_or_else(|| TemplateError::UnknownTemplate(name.to_string()))?;
+        let params: HashMap<String, String> = params
+            .into_iter()
+            .map(|(key, value)| (key.as_ref().to_string(), value.to_string()))
+            .collect();
+        let fill_in = |pattern: &str, encode: fn(&str) -> String| {
+            fill(pattern, |parameter| {
+                params
+                    .get(parameter)
+                    .map(|value| encode(value))
+                    .ok_or_else(|| TemplateError::MissingParameter {
+                        template: name.to_string(),
+                        parameter: parameter.to_string(),
+                    })
+            })
+        };
+
+        let mut client = HttpClient::new().method(
+            &template.method,
+            fill_in(&template.url, urls::encode_path_segment)?,
+        );
+        if let Some(base_url) = &self.base_url {
+            client = client.base_url(base_url);
+        }
+        for (header, value) in &template.headers {
+            client = client.header(header, fill_in(value, str::to_string)?);
+        }
+        if let Some(timeout) = template.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(retry) = template.retry {
+            client = client.retry(retry);
+        }
+        let label = template
+            .label
+            .clone()
+            .unwrap_or_else(|| Cow::Owned(name.to_string()));
+        Ok(client.label(label))
+    }
+}
+
+/// A preset registered in [`RequestTemplates`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestTemplate {
+    pub method: String,
+    /// The url, with `{name}` placeholders.
+    pub url: String,
+    /// The headers, their values with `{name}` placeholders.
+    pub headers: Vec<(String, String)>,
+    pub timeout: Option<Duration>,
+    pub retry: Option<RetryPolicy>,
+    /// Groups the requests in `HttpStats`, the template name if `None`.
+    pub label: Option<Cow<'static, str>>,
+    /// The registered response type a [`SendTemplate`] is sent as, its full path or just its
+    /// name, e.g. `Leaderboard`. Sent as a plain `HttpRequest` if `None`.
+    pub response_type: Option<String>,
+}
+
+impl RequestTemplate {
+    /// a `method` request to `url`, its placeholders filled when the template is instantiated
+    pub fn new(method: impl ToString, url: impl ToString) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: vec![],
+            timeout: None,
+            retry: None,
+            label: None,
+            response_type: None,
+        }
+    }
+
+    /// send the header `name` with `value`, see `headers`
+    pub fn header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// fail requests that take longer than `timeout`, see `HttpClient::timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// retry failed requests with `policy`, see `HttpClient::retry`
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// label the requests with `label` instead of the template name, see `label`
+    pub fn label(mut self, label: impl Into<Cow<'static, str>>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// send the requests of a [`SendTemplate`] as typed requests of `type_name`, see
+    /// `response_type`
+    pub fn response_type(mut self, type_name: impl ToString) -> Self {
+        self.response_type = Some(type_name.to_string());
+        self
+    }
+}
+
+/// Instantiates the template `name` of the [`RequestTemplates`] with `params` and sends it, as a
+/// `TypedRequest` of its `response_type` if it has one. Templates that fail to instantiate, or
+/// name a response type that was not registered with `register_request_type`, are logged and not
+/// sent.
+///
+/// # Examples
+///
+/// ```
+/// fn claim_reward(mut ev_template: EventWriter<SendTemplate>, player: Res<Player>) {
+///     ev_template.send(SendTemplate::new("daily_reward").param("player", &player.id));
+/// }
+/// ```
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct SendTemplate {
+    pub name: String,
+    /// The values of the placeholders of the template.
+    pub params: Vec<(String, String)>,
+}
+
+impl SendTemplate {
+    /// send the template `name`, without parameters yet
+    pub fn new(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            params: vec![],
+        }
+    }
+
+    /// fill the placeholder `name` with `value`, see `params`
+    pub fn param(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.params.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Why a template could not be instantiated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// No template is registered under the name.
+    UnknownTemplate(String),
+    /// The template has a placeholder the parameters have no value for.
+    MissingParameter { template: String, parameter: String },
+    /// The response type of the template was not registered with `register_request_type`.
+    UnknownResponseType { template: String, type_name: String },
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownTemplate(name) => write!(f, "unknown request template {name:?}"),
+            Self::MissingParameter {
+                template,
+                parameter,
+            } => write!(
+                f,
+                "request template {template:?} is missing the parameter {parameter:?}"
+            ),
+            Self::UnknownResponseType {
+                template,
+                type_name,
+            } => write!(
+                f,
+                "request template {template:?} has the unregistered response type {type_name:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+pub(crate) fn send_templates(
+    mut commands: Commands,
+    mut ev_template: EventReader<SendTemplate>,
+    mut ev_request: EventWriter<HttpRequest>,
+    templates: Option<Res<RequestTemplates>>,
+    registry: Res<TypedRequestRegistry>,
+) {
+    for send in ev_template.read() {
+        let sent = templates
+            .as_deref()
+            .ok_or_else(|| TemplateError::UnknownTemplate(send.name.clone()))
+            .and_then(|templates| {
+                let client = templates.instantiate(&send.name, send.params.iter().cloned())?;
+                let response_type = templates
+                    .get(&send.name)
+                    .and_then(|template| template.response_type.as_deref());
+                let Some(type_name) = response_type else {
+                    ev_request.send(client.build());
+                    return Ok(());
+                };
+                let send_typed = registry.sender(type_name).ok_or_else(|| {
+                    TemplateError::UnknownResponseType {
+                        template: send.name.clone(),
+                        type_name: type_name.to_string(),
+                    }
+                })?;
+                let request = client.build();
+                commands.add(move |world: &mut World| send_typed(world, request));
+                Ok(())
+            });
+        if let Err(e) = sent {
+            error!("Failed to send request template: {e}");
+        }
+    }
+}
+
+/// Replaces the `{name}` placeholders of `pattern` with `value(name)`, braces around anything but
+/// a name are kept.
+fn fill(
+    pattern: &str,
+    mut value: impl FnMut(&str) -> Result<String, TemplateError>,
+) -> Result<String, TemplateError> {
+    let mut filled = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name = after.find('}').map(|end| &after[..end]).filter(|name| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+        match name {
+            Some(name) => {
+                filled.push_str(&value(name)?);
+                rest = &after[name.len() + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::RequestId;
+
+/// Where the time of a request went, sent as an event and inserted on the request entity once the
+/// response arrived.
+///
+/// The phases are measured by the transport and `None` where it cannot tell, which is every phase
+/// on wasm builds and for `unix://` urls. Native builds measure all of them for https urls, for
+/// plain http the connection setup is part of `ttfb`. Requests that reuse a kept-alive connection
+/// have no `dns`, `connect` or `tls` phase. Phases of followed redirects are left out, see
+/// `ResponseMeta::redirects` for those.
+///
+/// # Examples
+///
+/// ```
+/// fn triage(mut ev_timing: EventReader<RequestTiming>) {
+///     for timing in ev_timing.read() {
+///         if timing.dns.is_some_and(|dns| dns > Duration::from_secs(1)) {
+///             warn!("slow DNS, took {:?} of {:?}", timing.dns, timing.total);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Event, Component, Debug, Clone)]
+pub struct RequestTiming {
+    pub request_id: RequestId,
+    /// The entity carrying the request task.
+    pub entity: Entity,
+    /// Resolving the host name.
+    pub dns: Option<Duration>,
+    /// Opening the TCP connection.
+    pub connect: Option<Duration>,
+    /// The TLS handshake.
+    pub tls: Option<Duration>,
+    /// From sending the request to receiving the response headers.
+    pub ttfb: Option<Duration>,
+    /// Reading the response body.
+    pub download: Option<Duration>,
+    /// From sending the request until the client noticed the response, so up to a frame late.
+    pub total: Duration,
+}
+
+/// The phases as the transport measured them.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Phases {
+    pub(crate) dns: Option<Duration>,
+    pub(crate) connect: Option<Duration>,
+    pub(crate) tls: Option<Duration>,
+    pub(crate) ttfb: Option<Duration>,
+    pub(crate) download: Option<Duration>,
+    /// Whether the response came over a kept-alive connection, `None` where it cannot tell.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) reused: Option<bool>,
+}
+
+impl RequestTiming {
+    pub(crate) fn new(
+        request_id: RequestId,
+        entity: Entity,
+        phases: Phases,
+        total: Duration,
+    ) -> Self {
+        Self {
+            request_id,
+            entity,
+            dns: phases.dns,
+            connect: phases.connect,
+            tls: phases.tls,
+            ttfb: phases.ttfb,
+            download: phases.download,
+            total,
+        }
+    }
+}
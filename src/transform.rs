@@ -0,0 +1,138 @@
+use std::any::TypeId;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use ehttp::Response;
+
+use crate::HttpRequest;
+
+/// Rewrites a successful response in the request task, before it is delivered or deserialized,
+/// e.g. to unwrap an envelope, decrypt or decompress the body. An error fails the request.
+///
+/// # Examples
+///
+/// ```
+/// let unwrap_data = ResponseTransform::json_pointer("/data");
+/// let decrypt = ResponseTransform::new(|mut res| {
+///     res.bytes = decrypt(&res.bytes).map_err(|e| e.to_string())?;
+///     Ok(res)
+/// });
+/// ```
+#[derive(Clone)]
+pub struct ResponseTransform(Arc<dyn Fn(Response) -> Result<Response, String> + Send + Sync>);
+
+impl ResponseTransform {
+    pub fn new(
+        transform: impl Fn(Response) -> Result<Response, String> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(transform))
+    }
+
+    /// Replaces a JSON body with the value at `pointer`, e.g. `/data` for `{ "data": … }`.
+    pub fn json_pointer(pointer: impl ToString) -> Self {
+        let pointer = pointer.to_string();
+        Self::new(move |mut res| {
+            let mut body: serde_json::Value =
+                serde_json::from_slice(&res.bytes).map_err(|e| e.to_string())?;
+            let value = body
+                .pointer_mut(&pointer)
+                .ok_or_else(|| format!("Response has no {pointer:?}"))?
+                .take();
+            res.bytes = serde_json::to_vec(&value).map_err(|e| e.to_string())?;
+            Ok(res)
+        })
+    }
+
+    fn apply(&self, res: Response) -> Result<Response, String> {
+        (self.0)(res)
+    }
+}
+
+impl std::fmt::Debug for ResponseTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ResponseTransform")
+    }
+}
+
+/// The [`ResponseTransform`]s of every request with a label, or of every typed request of a type,
+/// so an envelope is unwrapped in one place instead of in every response type.
+///
+/// The transforms of the label run first, then those of the type, then those added with
+/// `HttpClient::transform_response`. Only 2xx responses are transformed.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(
+///     ResponseTransforms::default()
+///         .with_label("api", ResponseTransform::json_pointer("/data"))
+///         .with_type::<Leaderboard>(ResponseTransform::new(decompress)),
+/// );
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ResponseTransforms {
+    labels: HashMap<String, Vec<ResponseTransform>>,
+    types: HashMap<TypeId, Vec<ResponseTransform>>,
+}
+
+impl ResponseTransforms {
+    /// transform the responses of requests labeled `label`, see `insert_label`
+    pub fn with_label(mut self, label: impl ToString, transform: ResponseTransform) -> Self {
+        self.insert_label(label, transform);
+        self
+    }
+
+    /// transform the responses of typed requests of `T`, see `insert_type`
+    pub fn with_type<T: 'static>(mut self, transform: ResponseTransform) -> Self {
+        self.insert_type::<T>(transform);
+        self
+    }
+
+    /// Adds a transform of the responses of requests labeled `label`, after the ones added before.
+    pub fn insert_label(&mut self, label: impl ToString, transform: ResponseTransform) {
+        self.labels
+            .entry(label.to_string())
+            .or_default()
+            .push(transform);
+    }
+
+    /// Adds a transform of the responses of `TypedRequest<T>`s, after the ones added before.
+    pub fn insert_type<T: 'static>(&mut self, transform: ResponseTransform) {
+        self.types
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .push(transform);
+    }
+
+    /// Puts the transforms of `T` ahead of those of `request`.
+    pub(crate) fn resolve_type<T: 'static>(&self, request: &mut HttpRequest) {
+        if let Some(transforms) = self.types.get(&TypeId::of::<T>()) {
+            request.transforms.splice(0..0, transforms.iter().cloned());
+        }
+    }
+
+    /// Puts the transforms of the label of `request` ahead of the others.
+    pub(crate) fn resolve_label(&self, request: &mut HttpRequest) {
+        let transforms = request
+            .label
+            .as_ref()
+            .and_then(|label| self.labels.get(label.0.as_ref()));
+        if let Some(transforms) = transforms {
+            request.transforms.splice(0..0, transforms.iter().cloned());
+        }
+    }
+}
+
+/// Runs `transforms` in order on a 2xx response.
+pub(crate) fn apply(
+    transforms: &[ResponseTransform],
+    result: ehttp::Result<Response>,
+) -> ehttp::Result<Response> {
+    match result {
+        Ok(res) if res.ok => transforms
+            .iter()
+            .try_fold(res, |res, transform| transform.apply(res)),
+        result => result,
+    }
+}
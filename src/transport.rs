@@ -0,0 +1,93 @@
+use ehttp::Response;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+
+use async_channel::Sender;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::bandwidth::Throttle;
+use crate::response_meta::ResponseHead;
+use crate::streaming::BodySink;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::timing::Phases;
+use crate::HttpRequest;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::UrlPolicy;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+#[cfg(unix)]
+mod unix;
+#[cfg(target_arch = "wasm32")]
+mod web;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "websocket"))]
+pub(crate) use native::tls_config;
+#[cfg(unix)]
+pub(crate) use unix::is_unix_url;
+#[cfg(unix)]
+pub use unix::unix_socket_url;
+#[cfg(target_arch = "wasm32")]
+pub use web::{CacheMode, Credentials, FetchOptions, ReferrerPolicy};
+
+/// Per-request state shared between the `RequestTask` and the transport.
+pub(crate) struct FetchContext {
+    pub(crate) body: BodySink,
+    /// Gets the response metadata as soon as the headers arrive.
+    pub(crate) head: Sender<ResponseHead>,
+    /// Gets the phases of the request once the body was read.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) phases: Arc<Mutex<Phases>>,
+    /// The download caps the body is read under.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) throttle: Throttle,
+    /// Checked for every redirect the transport follows.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) url_policy: Option<UrlPolicy>,
+    /// The fetch is aborted through an `AbortController` as soon as this is closed.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) abort: async_channel::Receiver<()>,
+}
+
+/// Sends the request with the transport matching its URL scheme and target.
+///
+/// `unix://` urls are sent over a Unix domain socket on native unix targets,
+/// wasm builds go through the browser fetch API directly and everything else
+/// goes through ureq.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn fetch(request: HttpRequest, context: FetchContext) -> ehttp::Result<Response> {
+    #[cfg(unix)]
+    if is_unix_url(&request.request.url) {
+        if request.streaming_body.is_some() {
+            return Err("Streaming bodies can not be sent over unix sockets".to_string());
+        }
+        // The socket transport always buffers, the body is reported as a single chunk.
+        let mut response = unix::fetch_async(request.request).await?;
+        let _ = context
+            .head
+            .try_send(ResponseHead::from_response(&response));
+        let mut body = context.body;
+        if body.is_incremental() {
+            body.set_total(Some(response.bytes.len() as u64));
+            body.push(std::mem::take(&mut response.bytes));
+            response.bytes = body.finish();
+        }
+        return Ok(response);
+    }
+
+    let options = native::SendOptions {
+        backend: request.backend.unwrap_or_default(),
+        streaming_body: request.streaming_body,
+        max_redirects: request.max_redirects.unwrap_or(crate::MAX_REDIRECTS),
+        private_headers: request.private_headers,
+        timeout: request.timeout,
+    };
+    native::fetch(request.request, options, context).await
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn fetch(request: HttpRequest, context: FetchContext) -> ehttp::Result<Response> {
+    let backend = request.backend.unwrap_or_default();
+    web::fetch(request.request, request.fetch_options, backend, context).await
+}
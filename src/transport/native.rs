@@ -0,0 +1,448 @@
+use std::cell::Cell;
+use std::io::{ErrorKind, Read};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_channel::Sender;
+use ehttp::{Headers, Request, Response};
+use ureq::rustls;
+
+use crate::bandwidth::Throttle;
+use crate::response_meta::{RedirectHop, ResponseHead};
+use crate::timing::Phases;
+use crate::transport::FetchContext;
+use crate::{BackendOptions, HttpErrorKind, StreamingBody, UrlPolicy, REQUEST_BLOCKED};
+
+/// Size of the chunks an incremental body is read in.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// What the request thread reports back, the head carries the whole body unless it is read
+/// incrementally.
+enum Part {
+    Head(Response, Box<ResponseHead>),
+    Chunk(Vec<u8>),
+}
+
+/// Headers that are dropped when a redirect leads to another origin.
+const CREDENTIALS: [&str; 3] = ["authorization", "proxy-authorization", "cookie"];
+
+/// What the request is sent with besides the ehttp request.
+pub(crate) struct SendOptions {
+    pub(crate) backend: BackendOptions,
+    /// Sent in place of the body of the request.
+    pub(crate) streaming_body: Option<StreamingBody>,
+    /// How many redirects are followed before the request fails.
+    pub(crate) max_redirects: usize,
+    /// Dropped along with the credentials when a redirect leads to another origin.
+    pub(crate) private_headers: Vec<String>,
+    /// How long ureq may take for the whole request, redirects included.
+    pub(crate) timeout: Option<Duration>,
+}
+
+/// Sends the request with ureq on its own thread.
+///
+/// Unlike ehttp every value of a repeated response header is kept, e.g. each `Set-Cookie`, and
+/// repeated request headers are folded into one comma separated header, since ureq only sends the
+/// last of them. Redirects are followed here rather than by ureq so every hop is recorded for
+/// `ResponseMeta::redirects`, and the phases of the request are written to the context for
+/// `RequestTiming`. A `StreamingBody` is read while it is sent, the response body is read no faster
+/// than `DownloadLimits` allow. All requests share one
+/// agent, which keeps connections alive for the next request to the same host, unless the
+/// `BackendOptions` bring their own. Dropping the returned future stops an incremental download on
+/// the next chunk.
+pub(crate) async fn fetch(
+    request: Request,
+    options: SendOptions,
+    context: FetchContext,
+) -> ehttp::Result<Response> {
+    let FetchContext {
+        mut body,
+        head,
+        phases,
+        throttle,
+        url_policy,
+    } = context;
+    let incremental = body.is_incremental();
+    let (tx, rx) = async_channel::unbounded();
+    std::thread::Builder::new()
+        .name("bevy_http_client".to_owned())
+        .spawn(move || {
+            let policy = url_policy.as_ref();
+            let sent = fetch_blocking(
+                &request,
+                &options,
+                policy,
+                incremental,
+                &tx,
+                &phases,
+                &throttle,
+            );
+            if let Err(error) = sent {
+                let _ = tx.send_blocking(Err(error));
+            }
+        })
+        .map_err(|err| format!("Failed to spawn request thread: {err}"))?;
+
+    let closed = |_| "Response stream closed unexpectedly".to_string();
+    let mut response = match rx.recv().await.map_err(closed)?? {
+        Part::Head(response, meta) => {
+            let _ = head.try_send(*meta);
+            response
+        }
+        Part::Chunk(_) => return Err("Response body arrived before its headers".to_string()),
+    };
+    if !incremental {
+        return Ok(response);
+    }
+    body.set_total(
+        response
+            .headers
+            .get("content-length")
+            .and_then(|length| length.parse().ok()),
+    );
+    loop {
+        match rx.recv().await.map_err(closed)?? {
+            Part::Chunk(chunk) if chunk.is_empty() => break,
+            Part::Chunk(chunk) => body.push(chunk),
+            Part::Head(..) => {}
+        }
+    }
+    response.bytes = body.finish();
+    Ok(response)
+}
+
+fn fetch_blocking(
+    request: &Request,
+    options: &SendOptions,
+    url_policy: Option<&UrlPolicy>,
+    incremental: bool,
+    tx: &Sender<ehttp::Result<Part>>,
+    phases: &Mutex<Phases>,
+    throttle: &Throttle,
+) -> ehttp::Result<()> {
+    let backend = &options.backend;
+    let agent = backend.agent().cloned().unwrap_or_else(agent);
+    let mut method = request.method.clone();
+    let mut url = request.url.clone();
+    let mut headers = fold_headers(&request.headers);
+    // Set below from the body that is sent, a length set earlier may be stale.
+    headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-length"));
+    let mut body = request.body.as_slice();
+    let mut streaming_body = options.streaming_body.as_ref();
+    let mut redirects = vec![];
+    // The request thread stops at the timeout too, instead of waiting on a server that went quiet.
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let (ok, resp, started) = loop {
+        let started = Instant::now();
+        HOP_CLOCK.set(HopClock::default());
+        let mut req = agent.request(&method, &url);
+        for (key, value) in &headers {
+            req = req.set(key, value);
+        }
+        if let Some(deadline) = deadline {
+            req = req.timeout(deadline.saturating_duration_since(started));
+        }
+        let mut req = backend.configure_request(req);
+        let resp = match streaming_body {
+            Some(streaming_body) => {
+                let (reader, len) = streaming_body
+                    .open()
+                    .map_err(|err| format!("Failed to open request body: {err}"))?;
+                if let Some(len) = len {
+                    req = req.set("Content-Length", &len.to_string());
+                }
+                req.send(reader)
+            }
+            None if body.is_empty() => req.call(),
+            None => req
+                .set("Content-Length", &body.len().to_string())
+                .send_bytes(body),
+        };
+        let (ok, resp) = match resp {
+            Ok(resp) => (true, resp),
+            // The body of e.g. a 404 is still read.
+            Err(ureq::Error::Status(_, resp)) => (false, resp),
+            Err(ureq::Error::Transport(err)) => return Err(transport_error(&err)),
+        };
+        let status = resp.status();
+        let Some(location) = resp
+            .header("location")
+            .filter(|_| (300..400).contains(&status))
+        else {
+            break (ok, resp, started);
+        };
+        // Like curl, other methods become GET on 301 to 303, and 307 and 308 are only followed for
+        // requests without a body.
+        let next_method = match status {
+            301..=303 if !["GET", "HEAD"].contains(&method.as_str()) => "GET".to_owned(),
+            301..=303 => method.clone(),
+            307 | 308 if ["GET", "HEAD", "OPTIONS", "TRACE"].contains(&method.as_str()) => {
+                method.clone()
+            }
+            _ => break (ok, resp, started),
+        };
+        if redirects.len() == options.max_redirects {
+            return Err(format!(
+                "Too many redirects, gave up after {}",
+                options.max_redirects
+            ));
+        }
+        let bad_url = |err| format!("Bad url in redirect to {location}: {err}");
+        let current = url::Url::parse(&url).map_err(bad_url)?;
+        let next = current.join(location).map_err(bad_url)?;
+        let cross_origin = current.origin() != next.origin();
+        let next_url = next.to_string();
+        if let Some(Err(reason)) = url_policy.map(|policy| policy.check(&next_url)) {
+            return Err(format!(
+                "{REQUEST_BLOCKED}, redirect to {next_url}: {reason}"
+            ));
+        }
+        redirects.push(RedirectHop {
+            status,
+            url: std::mem::replace(&mut url, next_url.clone()),
+            location: next_url,
+            elapsed: started.elapsed(),
+        });
+        method = next_method;
+        body = &[];
+        streaming_body = None;
+        // Credentials and the keys of the environment are only passed on while the redirects stay
+        // on the origin they were set for.
+        headers.retain(|(key, _)| {
+            let is_one_of =
+                |names: &[&str]| names.iter().any(|name| key.eq_ignore_ascii_case(name));
+            let dropped = is_one_of(&["content-length", "content-type"])
+                || cross_origin && is_one_of(&CREDENTIALS)
+                || cross_origin
+                    && options
+                        .private_headers
+                        .iter()
+                        .any(|name| key.eq_ignore_ascii_case(name));
+            !dropped
+        });
+    };
+
+    let head_at = Instant::now();
+    let hop = HOP_CLOCK.get();
+    let setup_done = hop.tls.or(hop.dns).map_or(started, |(_, end)| end);
+    let mut measured = Phases {
+        dns: hop.dns.map(|(start, end)| end - start),
+        connect: hop.dns.zip(hop.tls).map(|((_, dns), (tls, _))| tls - dns),
+        tls: hop.tls.map(|(start, end)| end - start),
+        ttfb: Some(head_at - setup_done),
+        download: None,
+        // The shared agent only resolves the host when it opens a new connection, the agents of the
+        // `BackendOptions` are not watched.
+        reused: backend.agent().is_none().then_some(hop.dns.is_none()),
+    };
+    let record = |measured: &mut Phases| {
+        measured.download = Some(head_at.elapsed());
+        if let Ok(mut phases) = phases.lock() {
+            *phases = *measured;
+        }
+    };
+
+    let mut names = resp.headers_names();
+    names.sort();
+    names.dedup();
+    let mut headers = Headers::default();
+    for name in names {
+        for value in resp.all(&name) {
+            headers.insert(&name, value);
+        }
+    }
+    let meta = Box::new(ResponseHead {
+        url: resp.get_url().to_owned(),
+        status: resp.status(),
+        version: Some(resp.http_version().to_owned()),
+        remote_addr: Some(resp.remote_addr()),
+        headers: headers.clone(),
+        redirects,
+    });
+    let head = Response {
+        url: resp.get_url().to_owned(),
+        ok,
+        status: resp.status(),
+        status_text: resp.status_text().to_owned(),
+        headers,
+        bytes: vec![],
+    };
+
+    // Servers may announce a body for HEAD requests without sending it.
+    let is_head = request.method.eq_ignore_ascii_case("HEAD");
+    let body_error = |err: std::io::Error| format!("Failed to read response body: {err}");
+    let mut reader = resp.into_reader();
+    let mut buffered = if incremental {
+        if tx.send_blocking(Ok(Part::Head(head, meta))).is_err() {
+            return Ok(());
+        }
+        None
+    } else {
+        Some((head, meta))
+    };
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        let size = throttle.chunk_size(CHUNK_SIZE);
+        let chunk: &[u8] = match reader.read(&mut buffer[..size]) {
+            Ok(read) => &buffer[..read],
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) if is_head && err.kind() == ErrorKind::UnexpectedEof => &[],
+            Err(err) => return Err(body_error(err)),
+        };
+        throttle.wait(chunk.len());
+        let done = chunk.is_empty();
+        if done {
+            record(&mut measured);
+        }
+        let sent = match &mut buffered {
+            Some((head, _)) => {
+                head.bytes.extend_from_slice(chunk);
+                true
+            }
+            None => tx.send_blocking(Ok(Part::Chunk(chunk.to_vec()))).is_ok(),
+        };
+        // The receiver is gone once the request was dropped.
+        if !sent || done {
+            break;
+        }
+    }
+    if let Some((head, meta)) = buffered {
+        let _ = tx.send_blocking(Ok(Part::Head(head, meta)));
+    }
+    Ok(())
+}
+
+/// Joins the values of repeated headers, with `; ` for `Cookie` and `, ` for everything else.
+fn fold_headers(headers: &Headers) -> Vec<(String, String)> {
+    let mut folded: Vec<(String, String)> = vec![];
+    for (key, value) in headers {
+        match folded
+            .iter_mut()
+            .find(|(folded_key, _)| folded_key.eq_ignore_ascii_case(key))
+        {
+            Some((_, folded_value)) => {
+                let separator = if key.eq_ignore_ascii_case("cookie") {
+                    "; "
+                } else {
+                    ", "
+                };
+                folded_value.push_str(separator);
+                folded_value.push_str(value);
+            }
+            None => folded.push((key.clone(), value.clone())),
+        }
+    }
+    folded
+}
+
+/// The message of a ureq error, labelled with the `HttpErrorKind` its error kind maps to.
+fn transport_error(err: &ureq::Transport) -> String {
+    use ureq::ErrorKind as Ureq;
+
+    let io =
+        std::error::Error::source(err).and_then(|source| source.downcast_ref::<std::io::Error>());
+    let timed_out =
+        io.is_some_and(|io| matches!(io.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock));
+    // The rustls connector of ureq passes the handshake error on inside an io error.
+    let tls = io
+        .and_then(|io| io.get_ref())
+        .is_some_and(|inner| inner.is::<rustls::Error>());
+    let kind = match err.kind() {
+        Ureq::Dns => HttpErrorKind::Dns,
+        Ureq::ConnectionFailed | Ureq::ProxyConnect if tls => HttpErrorKind::Tls,
+        Ureq::ConnectionFailed | Ureq::ProxyConnect if timed_out => HttpErrorKind::ConnectTimeout,
+        Ureq::ConnectionFailed | Ureq::ProxyConnect => HttpErrorKind::Connect,
+        Ureq::InvalidUrl
+        | Ureq::UnknownScheme
+        | Ureq::InsecureRequestHttpsOnly
+        | Ureq::InvalidProxyUrl => HttpErrorKind::InvalidRequest,
+        Ureq::TooManyRedirects => HttpErrorKind::TooManyRedirects,
+        Ureq::Io | Ureq::BadStatus | Ureq::BadHeader if timed_out => HttpErrorKind::Timeout,
+        Ureq::Io | Ureq::BadStatus | Ureq::BadHeader => HttpErrorKind::Io,
+        Ureq::ProxyUnauthorized | Ureq::HTTP => HttpErrorKind::Backend,
+    };
+    format!("{}: {err}", kind.label())
+}
+
+/// When the phases of the current hop started and ended, both stay `None` when the hop reused a
+/// pooled connection.
+#[derive(Debug, Clone, Copy, Default)]
+struct HopClock {
+    dns: Option<(Instant, Instant)>,
+    tls: Option<(Instant, Instant)>,
+}
+
+thread_local! {
+    /// The clock of the hop this request thread is sending, the agent resolves and connects on the
+    /// thread that sends the request.
+    static HOP_CLOCK: Cell<HopClock> = const { Cell::new(HopClock { dns: None, tls: None }) };
+}
+
+/// The agent shared by all requests, so connections are kept alive and reused.
+fn agent() -> ureq::Agent {
+    static AGENT: OnceLock<ureq::Agent> = OnceLock::new();
+    AGENT
+        .get_or_init(|| {
+            ureq::AgentBuilder::new()
+                .redirects(0)
+                .resolver(|netloc: &str| {
+                    let started = Instant::now();
+                    let addrs = netloc
+                        .to_socket_addrs()
+                        .map(Iterator::collect::<Vec<SocketAddr>>);
+                    HOP_CLOCK.set(HopClock {
+                        dns: Some((started, Instant::now())),
+                        ..HOP_CLOCK.get()
+                    });
+                    addrs
+                })
+                .tls_connector(Arc::new(TimedTls {
+                    config: tls_config(),
+                }))
+                .build()
+        })
+        .clone()
+}
+
+/// The rustls connector of ureq, timing the handshake.
+struct TimedTls {
+    config: Arc<rustls::ClientConfig>,
+}
+
+impl ureq::TlsConnector for TimedTls {
+    fn connect(
+        &self,
+        dns_name: &str,
+        io: Box<dyn ureq::ReadWrite>,
+    ) -> Result<Box<dyn ureq::ReadWrite>, ureq::Error> {
+        let started = Instant::now();
+        // Completes the handshake before returning.
+        let stream = self.config.connect(dns_name, io)?;
+        HOP_CLOCK.set(HopClock {
+            tls: Some((started, Instant::now())),
+            ..HOP_CLOCK.get()
+        });
+        Ok(stream)
+    }
+}
+
+/// The TLS config ureq uses by default, ring with the webpki roots.
+pub(crate) fn tls_config() -> Arc<rustls::ClientConfig> {
+    static CONFIG: OnceLock<Arc<rustls::ClientConfig>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let roots = rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            let config = rustls::ClientConfig::builder_with_provider(
+                rustls::crypto::ring::default_provider().into(),
+            )
+            .with_protocol_versions(&[&rustls::version::TLS12, &rustls::version::TLS13])
+            .expect("ring supports TLS 1.2 and 1.3")
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+            Arc::new(config)
+        })
+        .clone()
+}
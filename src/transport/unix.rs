@@ -0,0 +1,350 @@
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+
+use async_channel::{Receiver, Sender};
+use ehttp::{Headers, Request, Response};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+
+const SCHEME: &str = "unix://";
+
+/// Builds a `unix://` url addressing `path` on the server listening at `socket_path`.
+///
+/// The socket path is percent-encoded into the host part of the url, so
+/// `unix_socket_url("/tmp/editor.sock", "/api/status")` gives
+/// `unix://%2Ftmp%2Feditor%2Esock/api/status`.
+///
+/// # Examples
+///
+/// ```
+/// let request = HttpClient::new()
+///     .get(unix_socket_url("/tmp/editor.sock", "/api/status"))
+///     .build();
+/// ```
+pub fn unix_socket_url(socket_path: impl AsRef<str>, path: impl AsRef<str>) -> String {
+    let path = path.as_ref();
+    format!(
+        "{SCHEME}{}{}{}",
+        utf8_percent_encode(socket_path.as_ref(), NON_ALPHANUMERIC),
+        if path.starts_with('/') { "" } else { "/" },
+        path
+    )
+}
+
+pub(crate) fn is_unix_url(url: &str) -> bool {
+    url.starts_with(SCHEME)
+}
+
+/// Splits a `unix://` url into the socket path and the request target.
+fn parse_url(url: &str) -> ehttp::Result<(String, String)> {
+    let rest = url
+        .strip_prefix(SCHEME)
+        .ok_or_else(|| format!("Not a unix socket url: {url}"))?;
+    let (socket, target) = match rest.find(['/', '?']) {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    if socket.is_empty() {
+        return Err(format!("Missing socket path in url: {url}"));
+    }
+    let socket = percent_decode_str(socket)
+        .decode_utf8()
+        .map_err(|err| format!("Invalid socket path in url {url}: {err}"))?;
+    let target = if target.starts_with('?') {
+        format!("/{target}")
+    } else {
+        target.to_string()
+    };
+
+    Ok((socket.into_owned(), target))
+}
+
+fn fetch_blocking(request: &Request) -> ehttp::Result<Response> {
+    let (socket, target) = parse_url(&request.url)?;
+    let mut stream = UnixStream::connect(&socket)
+        .map_err(|err| format!("Failed to connect to {socket}: {err}"))?;
+
+    let head = request_head(request, &target)?;
+
+    stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(&request.body))
+        .map_err(|err| format!("Failed to send request to {socket}: {err}"))?;
+
+    let mut raw = vec![];
+    stream
+        .read_to_end(&mut raw)
+        .map_err(|err| format!("Failed to read response from {socket}: {err}"))?;
+
+    parse_response(&request.url, &request.method, &raw)
+}
+
+/// The request line and headers, rejecting those that could smuggle in more headers or requests.
+fn request_head(request: &Request, target: &str) -> ehttp::Result<String> {
+    if !is_token(&request.method) {
+        return Err(format!("Invalid method {:?}", request.method));
+    }
+    if target
+        .bytes()
+        .any(|byte| byte.is_ascii_control() || byte == b' ')
+    {
+        return Err(format!("Invalid request target {target:?}"));
+    }
+    let mut head = format!("{} {target} HTTP/1.1\r\n", request.method);
+    head.push_str("Host: localhost\r\nConnection: close\r\n");
+    for (key, value) in &request.headers {
+        // Written below from the body that is sent.
+        if key.eq_ignore_ascii_case("Content-Length") {
+            continue;
+        }
+        if !is_token(key) {
+            return Err(format!("Invalid header name {key:?}"));
+        }
+        // Like ureq, only tabs are allowed of the control characters.
+        if value
+            .bytes()
+            .any(|byte| byte.is_ascii_control() && byte != b'\t')
+        {
+            return Err(format!("Invalid value of header {key}"));
+        }
+        head.push_str(&format!("{key}: {value}\r\n"));
+    }
+    if !request.body.is_empty() {
+        head.push_str(&format!("Content-Length: {}\r\n", request.body.len()));
+    }
+    head.push_str("\r\n");
+    Ok(head)
+}
+
+/// Whether `value` is a token of RFC 9110, as methods and header names are.
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
+fn parse_response(url: &str, method: &str, raw: &[u8]) -> ehttp::Result<Response> {
+    let head_end = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| "Malformed response: missing header terminator".to_string())?;
+    let head = std::str::from_utf8(&raw[..head_end])
+        .map_err(|err| format!("Malformed response headers: {err}"))?;
+    let body = &raw[head_end + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or_default();
+    let mut parts = status_line.splitn(3, ' ');
+    let status = parts
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| format!("Malformed status line: {status_line}"))?;
+    let status_text = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Headers::default();
+    for line in lines {
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_lowercase(), value.trim());
+        }
+    }
+    headers.sort();
+
+    let bytes = if method == "HEAD" || status == 204 || status == 304 {
+        vec![]
+    } else if headers
+        .get("transfer-encoding")
+        .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"))
+    {
+        decode_chunked(body)?
+    } else if let Some(length) = headers
+        .get("content-length")
+        .and_then(|length| length.parse::<usize>().ok())
+    {
+        if body.len() < length {
+            return Err(format!(
+                "Response body ended after {} of {length} bytes",
+                body.len()
+            ));
+        }
+        body[..length].to_vec()
+    } else {
+        body.to_vec()
+    };
+
+    Ok(Response {
+        url: url.to_string(),
+        ok: (200..300).contains(&status),
+        status,
+        status_text,
+        headers,
+        bytes,
+    })
+}
+
+fn decode_chunked(mut body: &[u8]) -> ehttp::Result<Vec<u8>> {
+    let mut decoded = vec![];
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(|| "Malformed chunked body".to_string())?;
+        let size_line = std::str::from_utf8(&body[..line_end])
+            .map_err(|err| format!("Malformed chunk size: {err}"))?;
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|err| format!("Malformed chunk size {size_hex:?}: {err}"))?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        if body.len() < size {
+            return Err("Truncated chunked body".to_string());
+        }
+        decoded.extend_from_slice(&body[..size]);
+        body = body.get(size + 2..).unwrap_or_default();
+    }
+}
+
+/// Same threading model as ehttp's native backend: the blocking socket IO runs on
+/// its own thread so it never stalls the task pool.
+pub(crate) async fn fetch_async(request: Request) -> ehttp::Result<Response> {
+    let (tx, rx): (
+        Sender<ehttp::Result<Response>>,
+        Receiver<ehttp::Result<Response>>,
+    ) = async_channel::bounded(1);
+
+    std::thread::Builder::new()
+        .name("bevy_http_client_unix".to_owned())
+        .spawn(move || tx.send_blocking(fetch_blocking(&request)))
+        .map_err(|err| format!("Failed to spawn unix socket thread: {err}"))?;
+
+    rx.recv().await.map_err(|err| err.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "unix://%2Ftmp%2Feditor%2Esock/api/status";
+
+    fn parse(raw: &str) -> ehttp::Result<Response> {
+        parse_response(URL, "GET", raw.as_bytes())
+    }
+
+    #[test]
+    fn urls() {
+        assert_eq!(unix_socket_url("/tmp/editor.sock", "/api/status"), URL);
+        assert_eq!(unix_socket_url("/tmp/editor.sock", "api/status"), URL);
+        assert_eq!(
+            parse_url(URL),
+            Ok(("/tmp/editor.sock".to_string(), "/api/status".to_string()))
+        );
+        assert_eq!(
+            parse_url("unix://%2Frun%2Fd.sock?x=1"),
+            Ok(("/run/d.sock".to_string(), "/?x=1".to_string()))
+        );
+        assert_eq!(
+            parse_url("unix://%2Frun%2Fd.sock"),
+            Ok(("/run/d.sock".to_string(), "/".to_string()))
+        );
+        assert!(parse_url("unix:///api").is_err());
+        assert!(parse_url("http://localhost/api").is_err());
+    }
+
+    #[test]
+    fn content_length_body() {
+        let res =
+            parse("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello")
+                .unwrap();
+        assert!(res.ok);
+        assert_eq!(res.status, 200);
+        assert_eq!(res.status_text, "OK");
+        assert_eq!(res.url, URL);
+        assert_eq!(res.headers.get("content-type"), Some("text/plain"));
+        assert_eq!(res.bytes, b"hello");
+    }
+
+    #[test]
+    fn body_until_close() {
+        let res = parse("HTTP/1.1 404 Not Found\r\n\r\nmissing").unwrap();
+        assert!(!res.ok);
+        assert_eq!(res.status, 404);
+        assert_eq!(res.status_text, "Not Found");
+        assert_eq!(res.bytes, b"missing");
+    }
+
+    #[test]
+    fn chunked_body() {
+        let res = parse(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(res.bytes, b"hello, world");
+        assert!(parse("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel").is_err());
+        assert!(parse("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n").is_err());
+    }
+
+    #[test]
+    fn responses_without_a_body() {
+        let res = parse("HTTP/1.1 204 No Content\r\n\r\nignored").unwrap();
+        assert!(res.bytes.is_empty());
+        let res =
+            parse_response(URL, "HEAD", b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n").unwrap();
+        assert!(res.bytes.is_empty());
+    }
+
+    #[test]
+    fn short_body() {
+        let res = parse("HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello");
+        assert_eq!(res.unwrap_err(), "Response body ended after 5 of 10 bytes");
+        let res = parse("HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nhello").unwrap();
+        assert_eq!(res.bytes, b"hel");
+    }
+
+    fn head(method: &str, headers: &[(&str, &str)], body: &[u8]) -> ehttp::Result<String> {
+        let request = Request {
+            method: method.to_string(),
+            url: URL.to_string(),
+            body: body.to_vec(),
+            headers: Headers::new(headers),
+        };
+        request_head(&request, "/api/status")
+    }
+
+    #[test]
+    fn request_heads() {
+        assert_eq!(
+            head(
+                "POST",
+                &[("Content-Type", "text/plain"), ("Content-Length", "99")],
+                b"hi"
+            ),
+            Ok(
+                "POST /api/status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+                Content-Type: text/plain\r\nContent-Length: 2\r\n\r\n"
+                    .to_string()
+            )
+        );
+        assert!(head("GET", &[("X-Trace", "a\tb")], b"").is_ok());
+    }
+
+    #[test]
+    fn rejects_header_injection() {
+        assert!(head("GET", &[("X-Name", "a\r\nX-Admin: true")], b"").is_err());
+        assert!(head("GET", &[("X-Name", "a\nb")], b"").is_err());
+        assert!(head("GET", &[("X-Name\r\nX-Admin", "true")], b"").is_err());
+        assert!(head("GET", &[("X Name", "a")], b"").is_err());
+        assert!(head("GET", &[("", "a")], b"").is_err());
+        assert!(head("GET /admin HTTP/1.1\r\n", &[], b"").is_err());
+        let request = Request::get(URL);
+        assert!(request_head(&request, "/api status").is_err());
+        assert!(request_head(&request, "/api\r\nX-Admin: true").is_err());
+    }
+
+    #[test]
+    fn malformed_responses() {
+        assert!(parse("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n").is_err());
+        assert!(parse("HTTP/1.1 abc OK\r\n\r\n").is_err());
+        assert!(parse("\r\n\r\n").is_err());
+    }
+}
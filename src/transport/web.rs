@@ -0,0 +1,252 @@
+use async_channel::Sender;
+use bevy::tasks::futures_lite;
+use ehttp::{Headers, Request, Response};
+
+use crate::method::is_forbidden_fetch_method;
+use crate::response_meta::ResponseHead;
+use crate::streaming::BodySink;
+use crate::transport::FetchContext;
+use crate::BackendOptions;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+/// Controls whether the browser sends cookies and HTTP auth with the request.
+/// Based on <https://developer.mozilla.org/en-US/docs/Web/API/Request/credentials>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Credentials {
+    /// Never send credentials.
+    Omit,
+    /// Only send credentials to the same origin. This is the browser default.
+    SameOrigin,
+    /// Always send credentials, even cross-origin.
+    Include,
+}
+
+impl From<Credentials> for web_sys::RequestCredentials {
+    fn from(credentials: Credentials) -> Self {
+        match credentials {
+            Credentials::Omit => web_sys::RequestCredentials::Omit,
+            Credentials::SameOrigin => web_sys::RequestCredentials::SameOrigin,
+            Credentials::Include => web_sys::RequestCredentials::Include,
+        }
+    }
+}
+
+/// How the request interacts with the browser HTTP cache.
+/// Based on <https://developer.mozilla.org/en-US/docs/Web/API/Request/cache>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    Default,
+    NoStore,
+    Reload,
+    NoCache,
+    ForceCache,
+    OnlyIfCached,
+}
+
+impl From<CacheMode> for web_sys::RequestCache {
+    fn from(cache: CacheMode) -> Self {
+        match cache {
+            CacheMode::Default => web_sys::RequestCache::Default,
+            CacheMode::NoStore => web_sys::RequestCache::NoStore,
+            CacheMode::Reload => web_sys::RequestCache::Reload,
+            CacheMode::NoCache => web_sys::RequestCache::NoCache,
+            CacheMode::ForceCache => web_sys::RequestCache::ForceCache,
+            CacheMode::OnlyIfCached => web_sys::RequestCache::OnlyIfCached,
+        }
+    }
+}
+
+/// Which referrer information is sent with the request.
+/// Based on <https://developer.mozilla.org/en-US/docs/Web/API/Request/referrerPolicy>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferrerPolicy {
+    NoReferrer,
+    NoReferrerWhenDowngrade,
+    Origin,
+    OriginWhenCrossOrigin,
+    UnsafeUrl,
+    SameOrigin,
+    StrictOrigin,
+    StrictOriginWhenCrossOrigin,
+}
+
+impl From<ReferrerPolicy> for web_sys::ReferrerPolicy {
+    fn from(policy: ReferrerPolicy) -> Self {
+        match policy {
+            ReferrerPolicy::NoReferrer => web_sys::ReferrerPolicy::NoReferrer,
+            ReferrerPolicy::NoReferrerWhenDowngrade => {
+                web_sys::ReferrerPolicy::NoReferrerWhenDowngrade
+            }
+            ReferrerPolicy::Origin => web_sys::ReferrerPolicy::Origin,
+            ReferrerPolicy::OriginWhenCrossOrigin => web_sys::ReferrerPolicy::OriginWhenCrossOrigin,
+            ReferrerPolicy::UnsafeUrl => web_sys::ReferrerPolicy::UnsafeUrl,
+            ReferrerPolicy::SameOrigin => web_sys::ReferrerPolicy::SameOrigin,
+            ReferrerPolicy::StrictOrigin => web_sys::ReferrerPolicy::StrictOrigin,
+            ReferrerPolicy::StrictOriginWhenCrossOrigin => {
+                web_sys::ReferrerPolicy::StrictOriginWhenCrossOrigin
+            }
+        }
+    }
+}
+
+/// Fetch options that ehttp's `Request` has no field for.
+/// `None` leaves the browser default in place. Only available on wasm builds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchOptions {
+    pub credentials: Option<Credentials>,
+    pub cache: Option<CacheMode>,
+    pub referrer_policy: Option<ReferrerPolicy>,
+}
+
+/// Mirrors ehttp's web backend, with the extra [`FetchOptions`] applied.
+///
+/// Closing the context's `abort` channel aborts the browser fetch, including reading the body.
+pub(crate) async fn fetch(
+    request: Request,
+    options: FetchOptions,
+    backend: BackendOptions,
+    context: FetchContext,
+) -> ehttp::Result<Response> {
+    if is_forbidden_fetch_method(&request.method) {
+        return Err(format!(
+            "{} requests are not supported by browsers",
+            request.method
+        ));
+    }
+    let FetchContext { body, head, abort } = context;
+    let controller = web_sys::AbortController::new().map_err(string_from_fetch_error)?;
+    let signal = controller.signal();
+
+    let fetch = async {
+        fetch_jsvalue(&request, &options, &backend, &signal, body, &head)
+            .await
+            .map_err(string_from_fetch_error)
+    };
+    let aborted = async {
+        let _ = abort.recv().await;
+        controller.abort();
+        Err("Request aborted".to_string())
+    };
+
+    futures_lite::future::or(fetch, aborted).await
+}
+
+fn string_from_fetch_error(value: JsValue) -> String {
+    value.as_string().unwrap_or_else(|| {
+        // TypeError means that this is an opaque `network error`, as defined by the spec:
+        // https://fetch.spec.whatwg.org/
+        if value.has_type::<js_sys::TypeError>() {
+            web_sys::console::error_1(&value);
+            "Failed to fetch, check the developer console for details".to_owned()
+        } else {
+            format!("{:#?}", value)
+        }
+    })
+}
+
+async fn fetch_jsvalue(
+    request: &Request,
+    options: &FetchOptions,
+    backend: &BackendOptions,
+    signal: &web_sys::AbortSignal,
+    mut body: BodySink,
+    head: &Sender<ResponseHead>,
+) -> Result<Response, JsValue> {
+    let init = web_sys::RequestInit::new();
+    init.set_signal(Some(signal));
+    init.set_method(&request.method);
+    init.set_mode(request.mode.into());
+    if let Some(credentials) = options.credentials {
+        init.set_credentials(credentials.into());
+    }
+    if let Some(cache) = options.cache {
+        init.set_cache(cache.into());
+    }
+    if let Some(referrer_policy) = options.referrer_policy {
+        init.set_referrer_policy(referrer_policy.into());
+    }
+    if !request.body.is_empty() {
+        let body: js_sys::Uint8Array = request.body.as_slice().into();
+        init.set_body(&body);
+    }
+    backend.configure_fetch(&init);
+
+    let js_request = web_sys::Request::new_with_str_and_init(&request.url, &init)?;
+    for (key, value) in &request.headers {
+        js_request.headers().set(key, value)?;
+    }
+
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window"))?;
+    let response = JsFuture::from(window.fetch_with_request(&js_request)).await?;
+    let response: web_sys::Response = response.dyn_into()?;
+    let headers = response_headers(&response)?;
+    let _ = head.try_send(ResponseHead {
+        url: response.url(),
+        status: response.status(),
+        version: None,
+        remote_addr: None,
+        headers: headers.clone(),
+        redirects: vec![],
+    });
+
+    let bytes = match response.body() {
+        Some(stream) if body.is_incremental() => {
+            body.set_total(
+                response
+                    .headers()
+                    .get("content-length")?
+                    .and_then(|length| length.parse().ok()),
+            );
+            read_stream(&stream, &mut body).await?;
+            body.finish()
+        }
+        _ => {
+            let array_buffer = JsFuture::from(response.array_buffer()?).await?;
+            js_sys::Uint8Array::new(&array_buffer).to_vec()
+        }
+    };
+
+    Ok(Response {
+        url: response.url(),
+        ok: response.ok(),
+        status: response.status(),
+        status_text: response.status_text(),
+        headers,
+        bytes,
+    })
+}
+
+/// Reads a `ReadableStream` body chunk by chunk into the sink.
+async fn read_stream(stream: &web_sys::ReadableStream, body: &mut BodySink) -> Result<(), JsValue> {
+    let reader: web_sys::ReadableStreamDefaultReader = stream.get_reader().dyn_into()?;
+    loop {
+        let result = JsFuture::from(reader.read()).await?;
+        if js_sys::Reflect::get(&result, &JsValue::from_str("done"))?.is_truthy() {
+            return Ok(());
+        }
+        let value = js_sys::Reflect::get(&result, &JsValue::from_str("value"))?;
+        let chunk: js_sys::Uint8Array = value.dyn_into()?;
+        body.push(chunk.to_vec());
+    }
+}
+
+fn response_headers(response: &web_sys::Response) -> Result<Headers, JsValue> {
+    let mut headers = Headers::default();
+    let iter = js_sys::try_iter(&response.headers())?
+        .ok_or_else(|| JsValue::from_str("headers are not iterable"))?;
+    for item in iter {
+        let pair: js_sys::Array = item?.into();
+        let key = pair
+            .get(0)
+            .as_string()
+            .ok_or_else(|| JsValue::from_str("headers name"))?;
+        let value = pair
+            .get(1)
+            .as_string()
+            .ok_or_else(|| JsValue::from_str("headers value"))?;
+        headers.insert(key, value);
+    }
+
+    Ok(headers)
+}
@@ -1,10 +1,18 @@
-use crate::{HttpClientSetting, HttpResponseError, RequestTask};
-use bevy::app::{App, PreUpdate};
-use bevy::ecs::system::CommandQueue;
-use bevy::hierarchy::DespawnRecursiveExt;
-use bevy::prelude::{Commands, Deref, Entity, Event, EventReader, Events, ResMut, World};
-use bevy::tasks::IoTaskPool;
-use ehttp::Request;
+use crate::formats::{self, ResponseFormats};
+use crate::{
+    cache, deliver, BodyFormat, Environments, HttpClientSetting, HttpClock, HttpRequest,
+    HttpResponseError, HttpSchedule, HttpSet, OnComplete, RequestHandle, RequestId, RequestQueue,
+    ResponseCache, ResponseTransforms, StatusCode, TypedHeader, TypedRequestRegistry,
+};
+use async_channel::{Receiver, TryRecvError};
+use bevy::app::{App, Update};
+use bevy::ecs::schedule::{IntoSystemConfigs, ScheduleLabel};
+use bevy::ecs::system::{IntoSystem, RunSystemOnce};
+use bevy::prelude::{
+    Commands, Component, Deref, DerefMut, Entity, Event, EventReader, Res, ResMut, Resource, World,
+};
+use bevy::tasks::AsyncComputeTaskPool;
+use ehttp::{Headers, Request, Response};
 use serde::Deserialize;
 use std::marker::PhantomData;
 
@@ -29,15 +37,64 @@ pub trait HttpTypedRequestTrait {
     fn register_request_type<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
         &mut self,
     ) -> &mut Self;
+
+    /// Lets responses of `T` be sent in `format` as well as JSON, so the backend can move a type to
+    /// another format without a client update.
+    ///
+    /// Typed requests of `T` that accept any body ask for the registered formats in the order they
+    /// were registered with the `Accept` header, JSON last unless it was registered. The body is
+    /// deserialized by the format matching the `Content-Type` of the response, as JSON if none
+    /// matches. `with_schema` only validates JSON bodies, others fail the request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.register_request_type::<Inventory>()
+    ///     .register_response_format::<Inventory>(MessagePack)
+    ///     .register_response_format::<Inventory>(Protobuf);
+    /// ```
+    fn register_response_format<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+        format: impl BodyFormat,
+    ) -> &mut Self;
 }
 
 impl HttpTypedRequestTrait for App {
     fn register_request_type<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
         &mut self,
     ) -> &mut Self {
+        // Registering twice would queue every request twice.
+        let registered = self
+            .world
+            .get_resource_or_insert_with(TypedRequestRegistry::default)
+            .register::<T>();
+        if !registered {
+            return self;
+        }
+        let schedule = self
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
         self.add_event::<TypedRequest<T>>();
         self.add_event::<TypedResponse<T>>();
-        self.add_systems(PreUpdate, handle_typed_request::<T>);
+        self.init_resource::<BackgroundParses<T>>();
+        self.add_systems(
+            schedule,
+            (
+                handle_typed_request::<T>.in_set(HttpSet::Dispatch),
+                finish_background_parses::<T>.in_set(HttpSet::HandleResponses),
+            ),
+        );
+        self
+    }
+
+    fn register_response_format<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+        format: impl BodyFormat,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(ResponseFormats::<T>::default)
+            .push(format);
         self
     }
 }
@@ -52,7 +109,7 @@ impl HttpTypedRequestTrait for App {
 ///
 /// # Fields
 ///
-/// * `request`: The actual HTTP request that will be sent.
+/// * `request`: The actual HTTP request that will be sent, dereferenced to from the typed request.
 /// * `inner`: A marker field that uses `PhantomData` to express that it may hold data of type `T`.
 ///
 /// # Examples
@@ -61,21 +118,156 @@ impl HttpTypedRequestTrait for App {
 /// let request = Request::new();
 /// let typed_request = TypedRequest::new(request);
 /// ```
-#[derive(Debug, Event)]
+#[derive(Debug, Event, Deref, DerefMut)]
 pub struct TypedRequest<T>
 where
     T: for<'a> Deserialize<'a>,
 {
-    pub from_entity: Option<Entity>,
-    pub request: Request,
+    #[deref]
+    request: HttpRequest,
+    /// The `JsonSchemas` entry the response body is validated against before it is deserialized.
+    #[cfg(feature = "json-schema")]
+    schema: Option<String>,
+    /// Whether the body is deserialized on the `AsyncComputeTaskPool` whatever its size.
+    background: bool,
     inner: PhantomData<T>,
 }
 
 impl<T: for<'a> serde::Deserialize<'a>> TypedRequest<T> {
     pub fn new(request: Request, from_entity: Option<Entity>) -> Self {
-        TypedRequest {
+        HttpRequest {
             from_entity,
+            ..HttpRequest::new(request)
+        }
+        .into()
+    }
+}
+
+impl<T: for<'a> Deserialize<'a> + Send + Sync + 'static> TypedRequest<T> {
+    /// Validates the response body against the schema `name` of the `JsonSchemas` before it is
+    /// deserialized, failing the request with every place the body does not match. Call it before
+    /// `on_complete` or `with_handle`. Only available with the `json-schema` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .get("https://api.example.com/players/42")
+    ///     .with_type::<Player>()
+    ///     .with_schema("player");
+    /// ```
+    #[cfg(feature = "json-schema")]
+    pub fn with_schema(mut self, name: impl ToString) -> Self {
+        self.schema = Some(name.to_string());
+        self
+    }
+
+    /// Deserializes the response body on the `AsyncComputeTaskPool` however small it is, so the
+    /// schedule never waits for serde, see `HttpClientSettings::background_parse_bytes`. The result
+    /// is delivered in a later frame. Call it before `on_complete` or `with_handle`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .get("https://cdn.example.com/world/chunks.json")
+    ///     .with_type::<WorldChunks>()
+    ///     .parse_in_background();
+    /// ```
+    pub fn parse_in_background(mut self) -> Self {
+        self.background = true;
+        self
+    }
+
+    /// Runs `system` once with the deserialized result, instead of sending a `TypedResponse<T>` event.
+    ///
+    /// Aborts, timeouts and bodies that fail to deserialize are passed to the system as an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .get("https://api.ipify.org?format=json")
+    ///     .with_type::<IpInfo>()
+    ///     .on_complete(|In(result): In<Result<IpInfo, HttpResponseError>>| {
+    ///         println!("ip: {:?}", result.map(|info| info.ip));
+    ///     });
+    /// ```
+    pub fn on_complete<M>(
+        mut self,
+        system: impl IntoSystem<Result<T, HttpResponseError>, (), M>,
+    ) -> Self {
+        let system = IntoSystem::into_system(system);
+        let background = self.background;
+        #[cfg(feature = "json-schema")]
+        let schema = self.schema.clone();
+        self.request.on_complete = Some(OnComplete::new(move |world, request_id, response| {
+            #[cfg(feature = "json-schema")]
+            let response =
+                crate::json_schema::validate_response(world, schema.as_deref(), response);
+            match response {
+                Ok(res) => parse_body(
+                    world,
+                    background,
+                    res,
+                    move |world, _, parsed: Result<T, String>| {
+                        let result = parsed.map_err(|e| HttpResponseError::new(request_id, e));
+                        world.run_system_once_with(result, system);
+                    },
+                ),
+                Err(e) => {
+                    world.run_system_once_with(Err(HttpResponseError::new(request_id, e)), system);
+                }
+            }
+        }));
+        self
+    }
+
+    /// Returns the request together with a handle resolving to the deserialized result, see [`RequestHandle`].
+    ///
+    /// Replaces any `on_complete` callback.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (request, handle) = HttpClient::new()
+    ///     .get("https://api.ipify.org?format=json")
+    ///     .with_type::<IpInfo>()
+    ///     .with_handle();
+    /// ```
+    pub fn with_handle(mut self) -> (Self, RequestHandle<T>) {
+        let (sender, handle) = RequestHandle::new(self.request.id);
+        let on_dispatch = sender.on_dispatch();
+        let background = self.background;
+        #[cfg(feature = "json-schema")]
+        let schema = self.schema.clone();
+        self.request.on_complete = Some(
+            OnComplete::new(move |world, request_id, response| {
+                #[cfg(feature = "json-schema")]
+                let response =
+                    crate::json_schema::validate_response(world, schema.as_deref(), response);
+                match response {
+                    Ok(res) => parse_body(world, background, res, move |_, res, parsed| {
+                        let result = parsed.map_err(|e| HttpResponseError::new(request_id, e));
+                        sender.finish(Some(res.status), result);
+                    }),
+                    Err(e) => sender.finish(None, Err(HttpResponseError::new(request_id, e))),
+                }
+            })
+            .with_on_dispatch(on_dispatch),
+        );
+        (self, handle)
+    }
+}
+
+impl<T: for<'a> serde::Deserialize<'a>> From<HttpRequest> for TypedRequest<T> {
+    fn from(request: HttpRequest) -> Self {
+        crate::typed_registry::warn_if_unregistered::<T>();
+        TypedRequest {
             request,
+            #[cfg(feature = "json-schema")]
+            schema: None,
+            background: false,
             inner: PhantomData,
         }
     }
@@ -91,74 +283,251 @@ impl<T: for<'a> serde::Deserialize<'a>> TypedRequest<T> {
 ///
 /// # Fields
 ///
+/// * `request_id`: The request this is the response to.
 /// * `inner`: The actual data contained in the HTTP response.
 ///
 /// # Examples
 ///
 /// ```
-/// let response = TypedResponse { inner: MyResponseType };
+/// for response in ev_response.read() {
+///     println!("{}: {:?}", response.request_id, *response);
+/// }
 /// ```
-#[derive(Debug, Deref, Event)]
+#[derive(Debug, Deref, Event, Component)]
 pub struct TypedResponse<T>
 where
     T: for<'a> Deserialize<'a>,
 {
+    pub request_id: RequestId,
     #[deref]
     inner: T,
+    status_code: u16,
+    response_headers: Headers,
+    stale: bool,
+}
+
+impl<T: for<'a> Deserialize<'a>> TypedResponse<T> {
+    fn new(request_id: RequestId, inner: T, res: Response, stale: bool) -> Self {
+        Self {
+            request_id,
+            inner,
+            status_code: res.status,
+            response_headers: res.headers,
+            stale,
+        }
+    }
+
+    /// Did we get a 2xx response code?
+    pub fn is_success(&self) -> bool {
+        self.status().is_success()
+    }
+
+    /// The status code of the response the value was deserialized from.
+    pub fn status(&self) -> StatusCode {
+        StatusCode(self.status_code)
+    }
+
+    /// The first value of the response header, looked up case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.response_headers.get(name)
+    }
+
+    /// Every value of the response header in order, looked up case-insensitively, e.g. each
+    /// `Link` header of a paginated listing. Browsers join repeated headers into one comma
+    /// separated value.
+    pub fn header_values(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.response_headers.get_all(name)
+    }
+
+    /// The headers of the response the value was deserialized from, see `Headers::get_all`.
+    pub fn headers(&self) -> &Headers {
+        &self.response_headers
+    }
+
+    /// The response header parsed as `H`, `None` if it is missing or malformed.
+    pub fn typed_header<H: TypedHeader>(&self) -> Option<H> {
+        self.header(H::NAME).and_then(H::parse)
+    }
+
+    /// The deserialized value, without the response it was read from.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Whether the value is from a stale cached response, the fresh one follows, see
+    /// `HttpClient::stale_while_revalidate`.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
 }
 
-/// A system that handles typed HTTP requests.
+/// A system that queues typed HTTP requests.
+///
+/// The handler owns the response, the body is deserialized in place without copying the response.
 fn handle_typed_request<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
     mut commands: Commands,
-    mut req_res: ResMut<HttpClientSetting>,
+    mut queue: ResMut<RequestQueue>,
     mut requests: EventReader<TypedRequest<T>>,
+    (mut cache, environments): (Option<ResMut<ResponseCache>>, Option<Res<Environments>>),
+    (transforms, mut registry): (
+        Option<Res<ResponseTransforms>>,
+        ResMut<TypedRequestRegistry>,
+    ),
+    (formats, clock): (Option<Res<ResponseFormats<T>>>, Res<HttpClock>),
 ) {
-    let thread_pool = IoTaskPool::get();
     for request in requests.read() {
-        if req_res.is_available() {
-            let (entity, has_from_entity) = if let Some(entity) = request.from_entity {
-                (entity, true)
-            } else {
-                (commands.spawn_empty().id(), false)
-            };
-            let req = request.request.clone();
-
-            let task = thread_pool.spawn(async move {
-                let mut command_queue = CommandQueue::default();
-
-                let response = ehttp::fetch_async(req).await;
-                command_queue.push(move |world: &mut World| {
-                    match response {
-                        Ok(res) => {
-                            serde_json::from_slice(res.bytes.as_slice())
-                                .map(|inner| {
-                                    world
-                                        .get_resource_mut::<Events<TypedResponse<T>>>()
-                                        .unwrap()
-                                        .send(TypedResponse { inner });
-                                })
-                                .expect("Failed to deserialize response");
-                        }
-                        Err(e) => {
-                            world
-                                .get_resource_mut::<Events<HttpResponseError>>()
-                                .unwrap()
-                                .send(HttpResponseError(e.to_string()));
+        let respond_to = request.respond_to;
+        let background = request.background;
+        #[cfg(feature = "json-schema")]
+        let schema = request.schema.clone();
+        if request.on_complete.is_none() {
+            if let Some(res) = cache::stale_response(
+                cache.as_deref_mut(),
+                environments.as_deref(),
+                request,
+                clock.now(),
+            ) {
+                let request_id = request.id;
+                #[cfg(feature = "json-schema")]
+                let schema = schema.clone();
+                // A stale response that does not parse is skipped, the fresh one follows.
+                commands.add(move |world: &mut World| {
+                    #[cfg(feature = "json-schema")]
+                    let Ok(res) =
+                        crate::json_schema::validate_response(world, schema.as_deref(), Ok(res))
+                    else {
+                        return;
+                    };
+                    parse_body(world, background, res, move |world, res, parsed| {
+                        if let Ok(inner) = parsed {
+                            let response = TypedResponse::<T>::new(request_id, inner, res, true);
+                            deliver(world, respond_to, response);
                         }
+                    });
+                });
+            }
+        }
+        let mut http_request = request.request.clone();
+        if let Some(transforms) = &transforms {
+            transforms.resolve_type::<T>(&mut http_request);
+        }
+        if let Some(formats) = &formats {
+            formats.set_accept(&mut http_request.request.headers);
+        }
+        registry.sent::<T>();
+        let observe = |world: &mut World, response: &ehttp::Result<Response>| {
+            let ok = response.as_ref().is_ok_and(|res| res.ok);
+            world
+                .resource_mut::<TypedRequestRegistry>()
+                .finished::<T>(ok);
+        };
+        queue.push_observed(
+            http_request,
+            move |world, request_id, response| {
+                #[cfg(feature = "json-schema")]
+                let response =
+                    crate::json_schema::validate_response(world, schema.as_deref(), response);
+                match response {
+                    Ok(res) => {
+                        parse_body(
+                            world,
+                            background,
+                            res,
+                            move |world, res, parsed| match parsed {
+                                Ok(inner) => deliver(
+                                    world,
+                                    respond_to,
+                                    TypedResponse::<T>::new(request_id, inner, res, false),
+                                ),
+                                Err(e) => deliver(
+                                    world,
+                                    respond_to,
+                                    HttpResponseError::new(request_id, e),
+                                ),
+                            },
+                        )
                     }
+                    Err(e) => deliver(world, respond_to, HttpResponseError::new(request_id, e)),
+                }
+            },
+            observe,
+        );
+    }
+}
 
-                    if has_from_entity {
-                        world.entity_mut(entity).remove::<RequestTask>();
-                    } else {
-                        world.entity_mut(entity).despawn_recursive();
-                    }
-                });
+/// Called with a response and its deserialized body.
+type ParsedFn<T> = Box<dyn FnOnce(&mut World, Response, Result<T, String>) + Send + Sync + 'static>;
+
+/// Bodies of `T` being deserialized on the `AsyncComputeTaskPool`.
+#[derive(Resource)]
+struct BackgroundParses<T>(Vec<BackgroundParse<T>>);
+
+struct BackgroundParse<T> {
+    parsed: Receiver<(Response, Result<T, String>)>,
+    then: ParsedFn<T>,
+}
+
+impl<T> Default for BackgroundParses<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
 
-                command_queue
-            });
+/// Deserializes the body of `res` and calls `then` with it, right away, or once it was deserialized
+/// on the `AsyncComputeTaskPool` if `background` is set or the body is at least
+/// `HttpClientSettings::background_parse_bytes` long. The body is read in the registered format
+/// matching its `Content-Type`, as JSON otherwise.
+fn parse_body<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+    world: &mut World,
+    background: bool,
+    mut res: Response,
+    then: impl FnOnce(&mut World, Response, Result<T, String>) + Send + Sync + 'static,
+) {
+    let decoder = world
+        .get_resource::<ResponseFormats<T>>()
+        .and_then(|formats| formats.decoder(res.headers.get("Content-Type")));
+    let threshold = world
+        .get_resource::<HttpClientSetting>()
+        .and_then(|settings| settings.background_parse_bytes);
+    let background = background || threshold.is_some_and(|threshold| res.bytes.len() >= threshold);
+    if !background || !world.contains_resource::<BackgroundParses<T>>() {
+        let parsed = formats::decode(decoder.as_ref(), &res.bytes);
+        then(world, res, parsed);
+        return;
+    }
+    // Detached since wasm builds cannot poll tasks, like the requests themselves.
+    let (tx, parsed) = async_channel::bounded(1);
+    AsyncComputeTaskPool::get()
+        .spawn(async move {
+            let result = formats::decode(decoder.as_ref(), &res.bytes);
+            // The body is freed off the main thread too.
+            res.bytes = Vec::new();
+            let _ = tx.send((res, result)).await;
+        })
+        .detach();
+    world
+        .resource_mut::<BackgroundParses<T>>()
+        .0
+        .push(BackgroundParse {
+            parsed,
+            then: Box::new(then),
+        });
+}
 
-            commands.entity(entity).insert(RequestTask(task));
-            req_res.current_clients += 1;
+fn finish_background_parses<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+    mut commands: Commands,
+    mut parses: ResMut<BackgroundParses<T>>,
+) {
+    let mut index = 0;
+    while index < parses.0.len() {
+        match parses.0[index].parsed.try_recv() {
+            Ok((res, parsed)) => {
+                let then = parses.0.remove(index).then;
+                commands.add(move |world: &mut World| then(world, res, parsed));
+            }
+            Err(TryRecvError::Empty) => index += 1,
+            // The deserializer panicked, like it would have taken down the schedule.
+            Err(TryRecvError::Closed) => drop(parses.0.remove(index)),
         }
     }
 }
@@ -0,0 +1,171 @@
+use std::any::{type_name, TypeId};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use crate::{HttpRequest, TypedRequest};
+
+/// Sends a request as the `TypedRequest` of a registered type.
+pub(crate) type SendTyped = fn(&mut World, HttpRequest);
+
+/// The typed requests of one response type, see [`TypedRequestRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypedRequestStats {
+    /// The full path of the type, e.g. `my_game::api::Leaderboard`.
+    pub type_name: &'static str,
+    /// Sent and not finished yet, including requests waiting for a client or a retry.
+    pub pending: usize,
+    /// Finished with a 2xx response.
+    pub completed: usize,
+    /// Finished with an error or a response that is not 2xx.
+    pub failed: usize,
+}
+
+/// Every response type registered with `register_request_type`, with counts of its requests, to
+/// find out why a `TypedResponse` never arrives.
+///
+/// A type missing here was never registered, its `TypedRequest`s are not read. Requests with a
+/// `pending` count that does not go down are still waiting for a client or a response.
+///
+/// # Examples
+///
+/// ```
+/// fn debug_typed_requests(registry: Res<TypedRequestRegistry>) {
+///     if !registry.is_registered::<Leaderboard>() {
+///         warn!("Leaderboard is not registered");
+///     }
+///     for stats in registry.iter() {
+///         info!("{}: {} pending, {} failed", stats.type_name, stats.pending, stats.failed);
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TypedRequestRegistry {
+    types: HashMap<TypeId, TypedRequestStats>,
+    senders: HashMap<TypeId, SendTyped>,
+}
+
+impl TypedRequestRegistry {
+    /// Whether `T` was registered with `register_request_type`.
+    pub fn is_registered<T: 'static>(&self) -> bool {
+        self.types.contains_key(&TypeId::of::<T>())
+    }
+
+    /// The requests of `T`, `None` if it was not registered.
+    pub fn get<T: 'static>(&self) -> Option<&TypedRequestStats> {
+        self.types.get(&TypeId::of::<T>())
+    }
+
+    /// The requests of the type named `name`, its full path or just its name, e.g. `Leaderboard`.
+    pub fn find(&self, name: &str) -> Option<&TypedRequestStats> {
+        self.types
+            .values()
+            .find(|stats| is_named(stats.type_name, name))
+    }
+
+    /// Sends requests as the `TypedRequest` of the type named `name`, like `find`.
+    pub(crate) fn sender(&self, name: &str) -> Option<SendTyped> {
+        self.types
+            .iter()
+            .find(|(_, stats)| is_named(stats.type_name, name))
+            .and_then(|(type_id, _)| self.senders.get(type_id).copied())
+    }
+
+    /// Every registered type, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &TypedRequestStats> {
+        self.types.values()
+    }
+
+    /// Adds `T`, returns whether it was not registered before.
+    pub(crate) fn register<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(&mut self) -> bool {
+        if self.is_registered::<T>() {
+            return false;
+        }
+        self.senders.insert(TypeId::of::<T>(), send_typed::<T>);
+        if cfg!(debug_assertions) {
+            if let Ok(mut registered) = REGISTERED.lock() {
+                registered.push(type_name::<T>());
+            }
+        }
+        self.types.insert(
+            TypeId::of::<T>(),
+            TypedRequestStats {
+                type_name: type_name::<T>(),
+                pending: 0,
+                completed: 0,
+                failed: 0,
+            },
+        );
+        true
+    }
+
+    pub(crate) fn sent<T: 'static>(&mut self) {
+        if let Some(stats) = self.types.get_mut(&TypeId::of::<T>()) {
+            stats.pending += 1;
+        }
+    }
+
+    pub(crate) fn finished<T: 'static>(&mut self, ok: bool) {
+        if let Some(stats) = self.types.get_mut(&TypeId::of::<T>()) {
+            stats.pending = stats.pending.saturating_sub(1);
+            if ok {
+                stats.completed += 1;
+            } else {
+                stats.failed += 1;
+            }
+        }
+    }
+}
+
+fn is_named(type_name: &str, name: &str) -> bool {
+    type_name == name
+        || type_name
+            .rsplit_once("::")
+            .is_some_and(|(_, short)| short == name)
+}
+
+fn send_typed<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+    world: &mut World,
+    request: HttpRequest,
+) {
+    world.send_event(TypedRequest::<T>::from(request));
+}
+
+/// The types registered in any app, for the warning of `warn_if_unregistered`.
+static REGISTERED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+/// The unregistered types that were warned about, to warn once per type.
+static WARNED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+/// Warns in debug builds when a `TypedRequest<T>` is built for a `T` that was never registered,
+/// the request would never be read and its `TypedResponse<T>` would never arrive.
+pub(crate) fn warn_if_unregistered<T>() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let name = type_name::<T>();
+    if REGISTERED
+        .lock()
+        .map_or(true, |registered| registered.contains(&name))
+    {
+        return;
+    }
+    let Ok(mut warned) = WARNED.lock() else {
+        return;
+    };
+    if warned.contains(&name) {
+        return;
+    }
+    warned.push(name);
+    // Generic types keep their full path, `Vec<a::B>` would shorten to `B>`.
+    let short = match name.rsplit_once("::") {
+        Some((_, short)) if !name.contains('<') => short,
+        _ => name,
+    };
+    warn!(
+        "TypedRequest<{name}> was built but {name} is not registered, its requests are never sent \
+         and no TypedResponse<{short}> arrives. Add app.register_request_type::<{short}>()"
+    );
+}
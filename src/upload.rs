@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Opens the source for a new attempt, with the body length if it is known.
+type OpenFn = Arc<dyn Fn() -> io::Result<(Box<dyn Read + Send>, Option<u64>)> + Send + Sync>;
+
+/// A request body read from its source while it is sent, so uploading a replay of hundreds of MB
+/// does not hold the whole file in memory. Set it with `HttpClient::streaming_body`. Only available
+/// on native builds.
+///
+/// The source is opened again for every attempt, so retries and fallback urls send the whole body
+/// again. Bodies of known length are sent with a `Content-Length` header, others with chunked
+/// transfer encoding, which some servers do not accept. Streamed bodies are not encrypted by the
+/// `PayloadEncryption`, kept by the `QueuePersistencePlugin` or sent over `unix://` urls.
+///
+/// # Examples
+///
+/// ```
+/// let request = HttpClient::new()
+///     .put("https://replays.example.com/upload/42")
+///     .streaming_body(StreamingBody::file("replays/42.replay"))
+///     .build();
+///
+/// let frames = StreamingBody::chunks(|| recorder.encoded_frames().map(Ok));
+/// ```
+#[derive(Clone)]
+pub struct StreamingBody {
+    open: OpenFn,
+    /// Sent as the `Content-Length`, the body is sent chunked if `None`.
+    pub len: Option<u64>,
+}
+
+impl StreamingBody {
+    /// Streams the file at `path`, with its size at the time of the attempt as the length.
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            open: Arc::new(move || {
+                let file = File::open(&path)?;
+                let len = file.metadata()?.len();
+                Ok((Box::new(file), Some(len)))
+            }),
+            len: None,
+        }
+    }
+
+    /// Streams what the reader returned by `open` reads, `open` is called for every attempt.
+    pub fn reader<R: Read + Send + 'static>(
+        open: impl Fn() -> io::Result<R> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            open: Arc::new(move || Ok((Box::new(open()?), None))),
+            len: None,
+        }
+    }
+
+    /// Streams the chunks of the iterator returned by `produce`, `produce` is called for every
+    /// attempt. An error stops the upload and fails the request.
+    pub fn chunks<I>(produce: impl Fn() -> I + Send + Sync + 'static) -> Self
+    where
+        I: Iterator<Item = io::Result<Vec<u8>>> + Send + 'static,
+    {
+        Self {
+            open: Arc::new(move || {
+                let reader = ChunkReader {
+                    chunks: produce(),
+                    chunk: Vec::new(),
+                    read: 0,
+                };
+                Ok((Box::new(reader), None))
+            }),
+            len: None,
+        }
+    }
+
+    /// send the body as `len` bytes instead of chunked, see `len`
+    pub fn with_len(mut self, len: u64) -> Self {
+        self.len = Some(len);
+        self
+    }
+
+    /// Opens the source for an attempt, with the length the body is sent with.
+    pub(crate) fn open(&self) -> io::Result<(Box<dyn Read + Send>, Option<u64>)> {
+        let (reader, len) = (self.open)()?;
+        Ok((reader, self.len.or(len)))
+    }
+}
+
+impl std::fmt::Debug for StreamingBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamingBody")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads the chunks of an iterator one after the other.
+struct ChunkReader<I> {
+    chunks: I,
+    chunk: Vec<u8>,
+    /// Bytes of `chunk` read so far.
+    read: usize,
+}
+
+impl<I: Iterator<Item = io::Result<Vec<u8>>>> Read for ChunkReader<I> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.read == self.chunk.len() {
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.chunk = chunk?;
+                    self.read = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.read);
+        buf[..n].copy_from_slice(&self.chunk[self.read..self.read + n]);
+        self.read += n;
+        Ok(n)
+    }
+}
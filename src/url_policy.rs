@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::RequestId;
+
+/// Limits the schemes and hosts requests may be sent to, so user generated content or mod scripts
+/// cannot make the client call arbitrary urls.
+///
+/// The policy is checked when a request is dispatched, after the active `Environment` resolved its
+/// url, and when a WebSocket connects. Native builds check every redirect as well, browsers follow
+/// redirects on their own, so pair it with a `connect-src` content security policy on wasm builds.
+/// A blocked request fails with `HttpErrorKind::Blocked` without being sent, and a
+/// [`RequestBlocked`] event is sent. A blocked redirect fails the request with the same error kind,
+/// its message names the url.
+///
+/// Hosts are matched without their port and case-insensitively, `*.example.com` matches every
+/// subdomain of `example.com` but not `example.com` itself. Denied hosts are blocked even if they
+/// are also allowed.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpClientPlugin::new(HttpClientSettings {
+///     url_policy: Some(
+///         UrlPolicy::new()
+///             .with_scheme("https")
+///             .with_scheme("wss")
+///             .allow_host("api.example.com")
+///             .allow_host("*.cdn.example.com"),
+///     ),
+///     ..default()
+/// }));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UrlPolicy {
+    /// The schemes requests may use, e.g. `https`, any scheme if empty.
+    pub schemes: Vec<String>,
+    /// The only hosts requests may be sent to, any host if `None`.
+    pub allowed_hosts: Option<Vec<String>>,
+    /// Hosts requests are never sent to.
+    pub denied_hosts: Vec<String>,
+}
+
+impl UrlPolicy {
+    /// create a policy allowing every url
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// allow urls with `scheme`, see `schemes`
+    pub fn with_scheme(mut self, scheme: impl ToString) -> Self {
+        self.schemes.push(scheme.to_string());
+        self
+    }
+
+    /// allow requests to `host`, see `allowed_hosts`
+    pub fn allow_host(mut self, host: impl ToString) -> Self {
+        self.allowed_hosts
+            .get_or_insert_with(Vec::new)
+            .push(host.to_string());
+        self
+    }
+
+    /// block requests to `host`, see `denied_hosts`
+    pub fn deny_host(mut self, host: impl ToString) -> Self {
+        self.denied_hosts.push(host.to_string());
+        self
+    }
+
+    /// Whether `url` may be called, the reason it may not otherwise.
+    pub fn check(&self, url: &str) -> Result<(), String> {
+        let url = url::Url::parse(url).map_err(|e| format!("Invalid url: {e}"))?;
+        if !self.schemes.is_empty()
+            && !self
+                .schemes
+                .iter()
+                .any(|scheme| scheme.eq_ignore_ascii_case(url.scheme()))
+        {
+            return Err(format!("Scheme {} is not allowed", url.scheme()));
+        }
+        let host = url.host_str().unwrap_or_default();
+        if self
+            .denied_hosts
+            .iter()
+            .any(|pattern| host_matches(pattern, host))
+        {
+            return Err(format!("Host {host} is denied"));
+        }
+        if let Some(allowed) = &self.allowed_hosts {
+            if !allowed.iter().any(|pattern| host_matches(pattern, host)) {
+                return Err(format!("Host {host} is not allowed"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sent when the [`UrlPolicy`] blocked a request or a WebSocket connection.
+#[derive(Event, Debug, Clone)]
+pub struct RequestBlocked {
+    /// The blocked request, `None` for a WebSocket connection.
+    pub request_id: Option<RequestId>,
+    pub url: String,
+    /// Why the url is not allowed, e.g. `Host ads.example.com is denied`.
+    pub reason: String,
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .len()
+            .checked_sub(domain.len() + 1)
+            .and_then(|dot| host.get(dot..))
+            .is_some_and(|suffix| {
+                suffix.starts_with('.') && suffix[1..].eq_ignore_ascii_case(domain)
+            }),
+        None => pattern.eq_ignore_ascii_case(host),
+    }
+}
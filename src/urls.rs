@@ -0,0 +1,500 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::Value;
+use url::Url;
+
+use crate::HttpBuildError;
+
+/// Checks that `url` is an absolute http or https url with a host, or a `unix://` url, and
+/// normalizes it.
+///
+/// Normalizing lowercases the scheme and host, resolves `.` and `..` segments and percent-encodes
+/// characters that are not allowed in urls, e.g. `https://Example.com/a b` becomes
+/// `https://example.com/a%20b`. On wasm builds urls without a scheme are left to the browser, which
+/// resolves them against the page.
+pub(crate) fn normalize(url: &str) -> Result<String, HttpBuildError> {
+    // Socket paths are percent-encoded into the host, the socket transport checks them.
+    #[cfg(unix)]
+    if crate::transport::is_unix_url(url) {
+        return Ok(url.to_string());
+    }
+    let parsed = match Url::parse(url.trim()) {
+        Ok(parsed) => parsed,
+        #[cfg(target_arch = "wasm32")]
+        Err(url::ParseError::RelativeUrlWithoutBase) => return Ok(url.to_string()),
+        Err(e) => {
+            return Err(HttpBuildError::InvalidUrl {
+                url: url.to_string(),
+                reason: e.to_string(),
+            })
+        }
+    };
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(HttpBuildError::UnsupportedScheme {
+            url: url.to_string(),
+            scheme: parsed.scheme().to_string(),
+        });
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(HttpBuildError::InvalidUrl {
+            url: url.to_string(),
+            reason: "missing host".to_string(),
+        });
+    }
+    Ok(parsed.into())
+}
+
+/// Joins `path` onto the `base` url, keeping the result below the base path.
+///
+/// Unlike plain concatenation or the rules of RFC 3986, the semantics are meant for api base urls:
+/// - the base path is a directory, whether it ends with a slash or not
+/// - leading, trailing and repeated slashes of `path` do not produce empty segments, and the
+///   result ends with a slash only if `path` does
+/// - `.` segments are dropped and `..` removes the previous segment, but never one of the base
+/// - an absolute url replaces the base, a query or fragment of `path` is kept and those of the base
+///   are dropped
+///
+/// # Examples
+///
+/// ```
+/// let base = "https://api.example.com/v1/";
+/// assert_eq!(join_url(base, "players/42")?, "https://api.example.com/v1/players/42");
+/// assert_eq!(join_url("https://api.example.com/v1", "/players")?, "https://api.example.com/v1/players");
+/// assert_eq!(join_url(base, "a/../../b?x=1")?, "https://api.example.com/v1/b?x=1");
+/// assert_eq!(join_url(base, "https://cdn.example.com/x")?, "https://cdn.example.com/x");
+/// ```
+pub fn join_url(base: &str, path: &str) -> Result<String, HttpBuildError> {
+    if Url::parse(path).is_ok_and(|url| url.has_host()) {
+        return normalize(path);
+    }
+    let mut url = Url::parse(&normalize(base)?).map_err(|e| HttpBuildError::InvalidUrl {
+        url: base.to_string(),
+        reason: e.to_string(),
+    })?;
+    let (path, suffix) = match path.find(['?', '#']) {
+        Some(index) => path.split_at(index),
+        None => (path, ""),
+    };
+
+    let mut segments: Vec<&str> = url
+        .path_segments()
+        .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+        .unwrap_or_default();
+    let base_len = segments.len();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.len() > base_len {
+                    segments.pop();
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let trailing_slash = if path.ends_with('/') && segments.len() > base_len {
+        "/"
+    } else {
+        ""
+    };
+    let joined_path = format!("/{}{trailing_slash}", segments.join("/"));
+    url.set_path(&joined_path);
+    url.set_query(None);
+    url.set_fragment(None);
+    normalize(&format!("{url}{suffix}"))
+}
+
+/// Everything but the unreserved characters of RFC 3986, so `/`, `?`, `#` and `%` stay inside the
+/// segment.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Appends `value` as a single percent-encoded segment to the path of `url`, before its query.
+pub(crate) fn push_path_segment(url: &str, value: &str) -> String {
+    let (url, suffix) = match url.find(['?', '#']) {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+    format!(
+        "{}/{}{suffix}",
+        url.trim_end_matches('/'),
+        encode_path_segment(value)
+    )
+}
+
+/// Percent-encodes `value` for use as a single path segment.
+pub(crate) fn encode_path_segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+/// How [`HttpClient::query_struct_with`](crate::HttpClient::query_struct_with) encodes fields
+/// holding sequences, e.g. a `Vec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryArrays {
+    /// One parameter per item, `tag=a&tag=b`.
+    #[default]
+    Repeat,
+    /// One parameter with the items separated by commas, `tag=a,b`.
+    Comma,
+}
+
+/// The url-encoded query parameters of the fields of `query`, sorted by name, e.g. `tag=pvp`.
+///
+/// `None` fields are left out, strings, numbers and booleans become their text, unit enum variants
+/// their name, and sequences of them are encoded as `arrays` says. Anything else, e.g. a nested
+/// struct, is an error.
+///
+/// The fields go through a `serde_json::Value` rather than `serde_urlencoded`, which can't encode
+/// sequences, so they follow the rules of JSON where the two differ:
+/// - non-finite floats, `NaN` and the infinities, become `null` and are left out like `None`
+///   instead of being sent as `NaN` or `inf`
+/// - map keys must be strings or numbers, a key such as a tuple or struct is an error
+/// - unit structs and `()` are left out like `None`
+pub(crate) fn encode_query(
+    query: &impl serde::Serialize,
+    arrays: QueryArrays,
+) -> Result<Vec<String>, String> {
+    let fields = match serde_json::to_value(query).map_err(|e| e.to_string())? {
+        Value::Object(fields) => fields,
+        Value::Null => return Ok(vec![]),
+        value => return Err(format!("expected a struct or map, got {value}")),
+    };
+    // Sorted here, as `serde_json/preserve_order` keeps the fields in order if another crate
+    // enables it.
+    let mut fields: Vec<_> = fields.into_iter().collect();
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut params = vec![];
+    for (name, value) in fields {
+        let encoded_name = encode_query_component(&name);
+        match value {
+            Value::Array(items) => {
+                let items = items
+                    .into_iter()
+                    .filter(|item| !item.is_null())
+                    .map(|item| query_value(&name, item).map(|item| encode_query_component(&item)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match arrays {
+                    QueryArrays::Repeat => params.extend(
+                        items
+                            .into_iter()
+                            .map(|item| format!("{encoded_name}={item}")),
+                    ),
+                    // Commas inside the items stay encoded, only the separators are literal.
+                    QueryArrays::Comma if items.is_empty() => {}
+                    QueryArrays::Comma => {
+                        params.push(format!("{encoded_name}={}", items.join(",")))
+                    }
+                }
+            }
+            Value::Null => {}
+            value => {
+                let value = encode_query_component(&query_value(&name, value)?);
+                params.push(format!("{encoded_name}={value}"));
+            }
+        }
+    }
+    Ok(params)
+}
+
+fn query_value(name: &str, value: Value) -> Result<String, String> {
+    match value {
+        Value::String(value) => Ok(value),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        value => Err(format!("query parameter {name:?} cannot be {value}")),
+    }
+}
+
+fn encode_query_component(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// Appends the encoded `params` to the query of `url`, before its fragment.
+pub(crate) fn append_query(url: &str, params: &[String]) -> String {
+    if params.is_empty() {
+        return url.to_string();
+    }
+    let (url, fragment) = match url.find('#') {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+    let separator = match url.find('?') {
+        Some(index) if index + 1 < url.len() && !url.ends_with('&') => "&",
+        Some(_) => "",
+        None => "?",
+    };
+    format!("{url}{separator}{}{fragment}", params.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn join_url_cases() {
+        let cases = [
+            // The base path is a directory, with or without its trailing slash.
+            (
+                "https://api.example.com/v1",
+                "players",
+                "https://api.example.com/v1/players",
+            ),
+            (
+                "https://api.example.com/v1/",
+                "players",
+                "https://api.example.com/v1/players",
+            ),
+            (
+                "https://api.example.com",
+                "players",
+                "https://api.example.com/players",
+            ),
+            (
+                "https://api.example.com/",
+                "/players",
+                "https://api.example.com/players",
+            ),
+            // Leading, trailing and repeated slashes.
+            (
+                "https://api.example.com/v1",
+                "/players",
+                "https://api.example.com/v1/players",
+            ),
+            (
+                "https://api.example.com/v1//",
+                "//players//42",
+                "https://api.example.com/v1/players/42",
+            ),
+            (
+                "https://api.example.com/v1",
+                "players/",
+                "https://api.example.com/v1/players/",
+            ),
+            (
+                "https://api.example.com/v1/",
+                "",
+                "https://api.example.com/v1",
+            ),
+            (
+                "https://api.example.com/v1/",
+                "/",
+                "https://api.example.com/v1",
+            ),
+            // Dot segments never leave the base.
+            (
+                "https://api.example.com/v1",
+                "./players",
+                "https://api.example.com/v1/players",
+            ),
+            (
+                "https://api.example.com/v1",
+                "a/../b",
+                "https://api.example.com/v1/b",
+            ),
+            (
+                "https://api.example.com/v1",
+                "../admin",
+                "https://api.example.com/v1/admin",
+            ),
+            (
+                "https://api.example.com/v1",
+                "a/../../../b",
+                "https://api.example.com/v1/b",
+            ),
+            // The query and fragment of the path are kept, those of the base dropped.
+            (
+                "https://api.example.com/v1",
+                "players?page=2",
+                "https://api.example.com/v1/players?page=2",
+            ),
+            (
+                "https://api.example.com/v1?key=1",
+                "players",
+                "https://api.example.com/v1/players",
+            ),
+            (
+                "https://api.example.com/v1",
+                "players#top",
+                "https://api.example.com/v1/players#top",
+            ),
+            (
+                "https://api.example.com/v1",
+                "?page=2",
+                "https://api.example.com/v1?page=2",
+            ),
+            (
+                "https://api.example.com/v1",
+                "a/?x=../y",
+                "https://api.example.com/v1/a/?x=../y",
+            ),
+            // Absolute urls replace the base.
+            (
+                "https://api.example.com/v1",
+                "https://cdn.example.com/x",
+                "https://cdn.example.com/x",
+            ),
+            (
+                "https://api.example.com/v1",
+                "HTTP://CDN.example.com/a/./b",
+                "http://cdn.example.com/a/b",
+            ),
+            // Characters not allowed in urls are percent-encoded.
+            (
+                "https://api.example.com/v1",
+                "players/a b",
+                "https://api.example.com/v1/players/a%20b",
+            ),
+            ("https://Example.COM/v1", "x", "https://example.com/v1/x"),
+        ];
+        for (base, path, expected) in cases {
+            assert_eq!(
+                join_url(base, path).as_deref(),
+                Ok(expected),
+                "{base} + {path}"
+            );
+        }
+    }
+
+    #[derive(serde::Serialize)]
+    struct Search {
+        name: &'static str,
+        region: Option<&'static str>,
+        tag: Vec<Option<&'static str>>,
+        page: u32,
+        ranked: bool,
+    }
+
+    fn search(tag: Vec<Option<&'static str>>) -> Search {
+        Search {
+            name: "my server",
+            region: None,
+            tag,
+            page: 2,
+            ranked: true,
+        }
+    }
+
+    #[test]
+    fn encode_query_repeats_items() {
+        assert_eq!(
+            encode_query(
+                &search(vec![Some("pvp"), None, Some("a&b")]),
+                QueryArrays::Repeat
+            ),
+            Ok(vec![
+                "name=my+server".to_string(),
+                "page=2".to_string(),
+                "ranked=true".to_string(),
+                "tag=pvp".to_string(),
+                "tag=a%26b".to_string(),
+            ])
+        );
+        assert_eq!(
+            encode_query(&search(vec![]), QueryArrays::Repeat)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn encode_query_joins_items_with_commas() {
+        assert_eq!(
+            encode_query(
+                &search(vec![Some("pvp"), Some("a,b"), None]),
+                QueryArrays::Comma
+            )
+            .unwrap()
+            .last()
+            .map(String::as_str),
+            Some("tag=pvp,a%2Cb")
+        );
+        assert!(!encode_query(&search(vec![None]), QueryArrays::Comma)
+            .unwrap()
+            .iter()
+            .any(|param| param.starts_with("tag")));
+    }
+
+    #[test]
+    fn encode_query_follows_json() {
+        #[derive(serde::Serialize)]
+        enum Sort {
+            Newest,
+        }
+        let mut map = std::collections::HashMap::new();
+        map.insert(2, f64::NAN);
+        map.insert(1, 0.5);
+        assert_eq!(
+            encode_query(&map, QueryArrays::Repeat),
+            Ok(vec!["1=0.5".to_string()])
+        );
+        assert_eq!(
+            encode_query(
+                &[("sort", Sort::Newest)]
+                    .into_iter()
+                    .collect::<std::collections::HashMap<_, _>>(),
+                QueryArrays::Repeat
+            ),
+            Ok(vec!["sort=Newest".to_string()])
+        );
+        assert_eq!(
+            encode_query(&None::<Search>, QueryArrays::Repeat),
+            Ok(vec![])
+        );
+        let mut tuple_keys = std::collections::HashMap::new();
+        tuple_keys.insert((1, 2), "x");
+        assert!(encode_query(&tuple_keys, QueryArrays::Repeat).is_err());
+        assert!(encode_query(&"text", QueryArrays::Repeat).is_err());
+        assert!(encode_query(&[("nested", [("a", 1)])], QueryArrays::Repeat).is_err());
+    }
+
+    #[test]
+    fn append_query_keeps_the_query_and_fragment() {
+        let params = ["a=1".to_string(), "b=2".to_string()];
+        let cases = [
+            ("https://example.com/x", "https://example.com/x?a=1&b=2"),
+            ("https://example.com/x?", "https://example.com/x?a=1&b=2"),
+            (
+                "https://example.com/x?k=v",
+                "https://example.com/x?k=v&a=1&b=2",
+            ),
+            (
+                "https://example.com/x?k=v&",
+                "https://example.com/x?k=v&a=1&b=2",
+            ),
+            (
+                "https://example.com/x#top",
+                "https://example.com/x?a=1&b=2#top",
+            ),
+            (
+                "https://example.com/x?k=v#top?q",
+                "https://example.com/x?k=v&a=1&b=2#top?q",
+            ),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(append_query(url, &params), expected, "{url}");
+        }
+        assert_eq!(
+            append_query("https://example.com/x#top", &[]),
+            "https://example.com/x#top"
+        );
+    }
+
+    #[test]
+    fn join_url_errors() {
+        assert!(matches!(
+            join_url("api.example.com", "players"),
+            Err(HttpBuildError::InvalidUrl { .. })
+        ));
+        assert!(matches!(
+            join_url("ftp://files.example.com", "players"),
+            Err(HttpBuildError::UnsupportedScheme { .. })
+        ));
+        assert!(matches!(
+            join_url("https://api.example.com", "ftp://files.example.com/x"),
+            Err(HttpBuildError::UnsupportedScheme { .. })
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+
+use bevy::app::{App, Plugin, Startup};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{HttpClient, HttpRequest, OnComplete};
+
+/// Fetches a [`VersionManifest`] at startup and sends [`UpdateAvailable`] when the running build is
+/// older than its `latest` version.
+///
+/// Versions are compared like semver, `1.10.0` is newer than `1.9.2` and `2.0.0-beta.1` is older
+/// than `2.0.0`. A missing patch or minor number counts as zero. A build older than `minimum` gets
+/// an event with `required` set, to turn away clients the backend no longer supports. Add it after
+/// `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(VersionCheckPlugin::new(
+///     "https://cdn.example.com/version.json",
+///     env!("CARGO_PKG_VERSION"),
+/// ));
+///
+/// fn on_update(mut updates: EventReader<UpdateAvailable>) {
+///     for update in updates.read() {
+///         if update.required {
+///             println!("please update to {} to keep playing", update.latest);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VersionCheckPlugin {
+    pub url: String,
+    /// The version of the running build.
+    pub current: String,
+    /// Ignores manifests without a valid signature, see [`ResponseSignatures`].
+    ///
+    /// [`ResponseSignatures`]: crate::ResponseSignatures
+    pub signed: bool,
+}
+
+impl VersionCheckPlugin {
+    /// create the plugin checking `url` against the `current` version
+    pub fn new(url: impl ToString, current: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            current: current.to_string(),
+            signed: false,
+        }
+    }
+
+    /// ignore manifests whose signature does not verify, see `signed`
+    pub fn with_signature(mut self) -> Self {
+        self.signed = true;
+        self
+    }
+}
+
+impl Plugin for VersionCheckPlugin {
+    fn build(&self, app: &mut App) {
+        let plugin = self.clone();
+        app.add_event::<UpdateAvailable>();
+        app.add_systems(Startup, move |mut requests: EventWriter<HttpRequest>| {
+            let current = plugin.current.clone();
+            let on_complete = OnComplete::new(move |world, _, response| {
+                let Some(manifest) = response
+                    .ok()
+                    .filter(|res| res.ok)
+                    .and_then(|res| serde_json::from_slice::<VersionManifest>(&res.bytes).ok())
+                else {
+                    return;
+                };
+                if let Some(update) = manifest.update_for(&current) {
+                    world.send_event(update);
+                }
+            });
+            requests.send(HttpRequest {
+                on_complete: Some(on_complete),
+                verify_signature: plugin.signed,
+                ..HttpClient::new().get(&plugin.url).build()
+            });
+        });
+    }
+}
+
+/// The body served by the [`VersionCheckPlugin`] url.
+///
+/// ```json
+/// { "latest": "1.4.0", "minimum": "1.2.0", "download_url": "https://example.com/download" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionManifest {
+    pub latest: String,
+    /// Builds older than this must update.
+    #[serde(default)]
+    pub minimum: Option<String>,
+    #[serde(default)]
+    pub download_url: Option<String>,
+}
+
+impl VersionManifest {
+    /// The update `current` should get, if it is older than `latest`.
+    pub fn update_for(&self, current: &str) -> Option<UpdateAvailable> {
+        let is_older = |version: &str| compare_versions(current, version) == Some(Ordering::Less);
+        let required = self.minimum.as_deref().is_some_and(is_older);
+        (required || is_older(&self.latest)).then(|| UpdateAvailable {
+            latest: self.latest.clone(),
+            required,
+            download_url: self.download_url.clone(),
+        })
+    }
+}
+
+/// Sent when the running build is older than the latest version.
+#[derive(Event, Debug, Clone)]
+pub struct UpdateAvailable {
+    pub latest: String,
+    /// Whether the build is older than the minimum version and must update.
+    pub required: bool,
+    pub download_url: Option<String>,
+}
+
+/// Compares two versions like semver, `None` if either cannot be parsed.
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let (a_core, a_pre) = parse_version(a)?;
+    let (b_core, b_pre) = parse_version(b)?;
+    Some(a_core.cmp(&b_core).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        // A pre-release comes before its release.
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(a), Some(b)) => compare_pre_release(a, b),
+    }))
+}
+
+fn parse_version(version: &str) -> Option<([u64; 3], Option<&str>)> {
+    let version = version.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let version = version.split('+').next()?;
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+    let mut numbers = [0; 3];
+    for (index, part) in core.split('.').enumerate() {
+        *numbers.get_mut(index)? = part.parse().ok()?;
+    }
+    Some((numbers, pre))
+}
+
+fn compare_pre_release(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        let ordering = match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                // Numeric identifiers come before alphanumeric ones.
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
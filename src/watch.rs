@@ -0,0 +1,212 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "asset")]
+use bevy::asset::{Asset, Assets, Handle};
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+
+use crate::{ETag, HttpClient, HttpClock, RequestQueue, TypedHeader};
+
+/// Updates the watched data from the new file.
+type ApplyFn = Arc<dyn Fn(&mut World, &[u8]) -> Result<(), String> + Send + Sync>;
+
+/// Polls `url` on an interval and hands the file over whenever it changed, e.g. to tweak hosted
+/// balance files and see the changes in a running dev build.
+///
+/// Polls are conditional requests, with `If-None-Match` once the server sent an `ETag`, or else
+/// `If-Modified-Since` once it sent a `Last-Modified` header, so unchanged files cost a
+/// `304 Not Modified`. Bodies equal to the previous one are ignored too. A changed file is parsed
+/// into the resource or asset the watch is for, stored in the [`RemoteContent`] of the entity and
+/// announced with a [`RemoteChanged`] event, the first download included. Failed polls and files
+/// that do not parse send a [`RemoteWatchFailed`] and keep the current data. Removing the
+/// component or despawning the entity stops the polling.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Resource, Deserialize)]
+/// struct Balance {
+///     enemy_health: f32,
+///     gold_per_kill: u32,
+/// }
+///
+/// fn watch_balance(mut commands: Commands) {
+///     commands.spawn(
+///         WatchRemote::new("https://dev.example.com/balance.json")
+///             .every(Duration::from_secs(1))
+///             .into_resource::<Balance>(),
+///     );
+/// }
+///
+/// fn balance_changed(mut ev_changed: EventReader<RemoteChanged>) {
+///     for changed in ev_changed.read() {
+///         info!("reloaded {}", changed.url);
+///     }
+/// }
+/// ```
+#[derive(Component, Clone)]
+pub struct WatchRemote {
+    pub url: String,
+    /// Time between the end of a poll and the next one.
+    pub interval: Duration,
+    apply: Option<ApplyFn>,
+    etag: Option<ETag>,
+    last_modified: Option<String>,
+    /// Hash of the last body, for servers without validators.
+    body_hash: Option<u64>,
+    next_poll: Option<Instant>,
+    polling: bool,
+}
+
+impl WatchRemote {
+    /// poll `url` every 2 seconds, only storing the file in [`RemoteContent`]
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            interval: Duration::from_secs(2),
+            apply: None,
+            etag: None,
+            last_modified: None,
+            body_hash: None,
+            next_poll: None,
+            polling: false,
+        }
+    }
+
+    /// poll every `interval`, see `interval`
+    pub fn every(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Calls `apply` with every changed file, e.g. to parse RON or TOML into a resource. An error
+    /// is reported with a [`RemoteWatchFailed`].
+    pub fn on_change(
+        mut self,
+        apply: impl Fn(&mut World, &[u8]) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.apply = Some(Arc::new(apply));
+        self
+    }
+
+    /// Parses every changed file as the JSON of the resource `T` and inserts it.
+    pub fn into_resource<T: Resource + DeserializeOwned>(self) -> Self {
+        self.on_change(|world, bytes| {
+            let resource: T = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+            world.insert_resource(resource);
+            Ok(())
+        })
+    }
+
+    /// Parses every changed file as the JSON of the asset `A` and inserts it at `handle`. Only
+    /// available with the `asset` feature.
+    #[cfg(feature = "asset")]
+    pub fn into_asset<A: Asset + DeserializeOwned>(self, handle: Handle<A>) -> Self {
+        self.on_change(move |world, bytes| {
+            let asset: A = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+            world.resource_mut::<Assets<A>>().insert(handle.id(), asset);
+            Ok(())
+        })
+    }
+}
+
+/// The latest file of the [`WatchRemote`] on the same entity.
+#[derive(Component, Debug, Clone)]
+pub struct RemoteContent {
+    pub bytes: Bytes,
+    pub etag: Option<ETag>,
+    pub last_modified: Option<String>,
+}
+
+/// Sent when the file of a [`WatchRemote`] changed and was applied.
+#[derive(Event, Debug, Clone)]
+pub struct RemoteChanged {
+    /// The entity of the `WatchRemote`.
+    pub entity: Entity,
+    pub url: String,
+}
+
+/// Sent when polling a [`WatchRemote`] failed, or its changed file could not be applied.
+#[derive(Event, Debug, Clone)]
+pub struct RemoteWatchFailed {
+    /// The entity of the `WatchRemote`.
+    pub entity: Entity,
+    pub url: String,
+    pub error: String,
+}
+
+pub(crate) fn poll_watched(
+    mut queue: ResMut<RequestQueue>,
+    mut watches: Query<(Entity, &mut WatchRemote)>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    for (entity, mut watch) in watches.iter_mut() {
+        if watch.polling || watch.next_poll.is_some_and(|next_poll| now < next_poll) {
+            continue;
+        }
+        watch.polling = true;
+        let mut request = HttpClient::new().get(&watch.url).label("watch");
+        if let Some(etag) = &watch.etag {
+            request = request.if_none_match(etag);
+        } else if let Some(last_modified) = &watch.last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+        queue.push(request.build(), move |world, _, result| {
+            on_polled(world, entity, result);
+        });
+    }
+}
+
+fn on_polled(world: &mut World, entity: Entity, result: ehttp::Result<ehttp::Response>) {
+    let now = world.resource::<HttpClock>().now();
+    let Some(mut watch) = world.get_mut::<WatchRemote>(entity) else {
+        return;
+    };
+    watch.polling = false;
+    watch.next_poll = Some(now + watch.interval);
+    let url = watch.url.clone();
+    let res = match result {
+        Ok(res) if res.status == 304 => return,
+        Ok(res) if res.ok => res,
+        Ok(res) => {
+            let error = format!("{} {}", res.status, res.status_text);
+            world.send_event(RemoteWatchFailed { entity, url, error });
+            return;
+        }
+        Err(error) => {
+            world.send_event(RemoteWatchFailed { entity, url, error });
+            return;
+        }
+    };
+
+    let etag = res.headers.get(ETag::NAME).and_then(ETag::parse);
+    let last_modified = res.headers.get("Last-Modified").map(str::to_owned);
+    let mut hasher = DefaultHasher::new();
+    res.bytes.hash(&mut hasher);
+    let body_hash = hasher.finish();
+    watch.etag.clone_from(&etag);
+    watch.last_modified.clone_from(&last_modified);
+    if watch.body_hash.replace(body_hash) == Some(body_hash) {
+        return;
+    }
+    // A file that does not apply is not retried until it changes again.
+    if let Some(apply) = watch.apply.clone() {
+        if let Err(error) = apply(world, &res.bytes) {
+            world.send_event(RemoteWatchFailed { entity, url, error });
+            return;
+        }
+    }
+    if let Some(mut entity) = world.get_entity_mut(entity) {
+        entity.insert(RemoteContent {
+            bytes: res.bytes.into(),
+            etag,
+            last_modified,
+        });
+    }
+    world.send_event(RemoteChanged { entity, url });
+}
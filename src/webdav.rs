@@ -0,0 +1,305 @@
+use bevy::utils::SystemTime;
+
+use crate::xml::{self, XmlTag};
+use crate::{parse_http_date, Authorization, ETag, HttpClient, TypedHeader};
+
+/// The properties asked for by `WebDav::list`.
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength/>
+    <d:getcontenttype/>
+    <d:getetag/>
+    <d:getlastmodified/>
+  </d:prop>
+</d:propfind>"#;
+
+/// Builds requests to a WebDAV server, e.g. a Nextcloud folder used to share mods. Only available
+/// with the `webdav` feature.
+///
+/// Every method returns an [`HttpClient`] with the method, url, credentials and body set, so it
+/// can be sent like any other request. Paths are joined onto the root url like
+/// `HttpClient::base_url` joins them, read a listing with [`WebDav::parse_listing`].
+///
+/// # Examples
+///
+/// ```
+/// let dav = WebDav::new("https://cloud.example.com/remote.php/dav/files/modder")
+///     .with_basic_auth("modder", app_password);
+///
+/// ev_request.send(dav.list("mods").build());
+/// ev_request.send(dav.mkcol("mods/castle").build());
+/// ev_request.send(dav.put("mods/castle/castle.pak", pak_bytes).build());
+///
+/// for response in ev_response.read() {
+///     for entry in WebDav::parse_listing(&response.bytes)? {
+///         println!("{} {:?}", entry.href, entry.content_length);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebDav {
+    /// The url paths are joined onto.
+    pub root: String,
+    pub authorization: Option<Authorization>,
+}
+
+impl WebDav {
+    /// talk to the server under `root`, without credentials
+    pub fn new(root: impl ToString) -> Self {
+        Self {
+            root: root.to_string(),
+            authorization: None,
+        }
+    }
+
+    /// send `user` and `password` with every request, see `authorization`
+    pub fn with_basic_auth(mut self, user: impl ToString, password: impl ToString) -> Self {
+        self.authorization = Some(Authorization::Basic {
+            user: user.to_string(),
+            password: password.to_string(),
+        });
+        self
+    }
+
+    /// send `token` as a bearer token with every request, see `authorization`
+    pub fn with_bearer_auth(mut self, token: impl ToString) -> Self {
+        self.authorization = Some(Authorization::Bearer(token.to_string()));
+        self
+    }
+
+    /// A request for `path`, without a body.
+    pub fn request(&self, method: &str, path: &str) -> HttpClient {
+        let client = HttpClient::new().base_url(&self.root).method(method, path);
+        match &self.authorization {
+            Some(authorization) => client.typed_header(authorization),
+            None => client,
+        }
+    }
+
+    /// A `PROPFIND` request listing the collection at `path` and its members, one level deep.
+    pub fn list(&self, path: &str) -> HttpClient {
+        self.request("PROPFIND", path)
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .bytes(PROPFIND_BODY)
+    }
+
+    /// A `MKCOL` request creating the collection at `path`, its parent must exist.
+    pub fn mkcol(&self, path: &str) -> HttpClient {
+        self.request("MKCOL", path)
+    }
+
+    /// A `PUT` request uploading `body` to `path`.
+    pub fn put(&self, path: &str, body: impl Into<Vec<u8>>) -> HttpClient {
+        self.request("PUT", path).bytes(body)
+    }
+
+    /// A `PUT` request uploading `body` to `path` while it is read, see [`StreamingBody`]. Only
+    /// available on native builds.
+    ///
+    /// [`StreamingBody`]: crate::StreamingBody
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn put_streaming(&self, path: &str, body: crate::StreamingBody) -> HttpClient {
+        self.request("PUT", path).streaming_body(body)
+    }
+
+    /// A `GET` request downloading `path`.
+    pub fn get(&self, path: &str) -> HttpClient {
+        self.request("GET", path)
+    }
+
+    /// A `DELETE` request removing `path`, collections with everything in them.
+    pub fn delete(&self, path: &str) -> HttpClient {
+        self.request("DELETE", path)
+    }
+
+    /// A `MOVE` request renaming `from` to `to`, replacing `to` if `overwrite` is set.
+    pub fn move_to(&self, from: &str, to: &str, overwrite: bool) -> HttpClient {
+        let destination = crate::join_url(&self.root, to).unwrap_or_else(|_| to.to_string());
+        self.request("MOVE", from)
+            .header("Destination", destination)
+            .header("Overwrite", if overwrite { "T" } else { "F" })
+    }
+
+    /// Reads the entries of the `207 Multi-Status` body of a `list` response. The listed collection
+    /// itself is the first entry.
+    pub fn parse_listing(body: &[u8]) -> Result<Vec<DavEntry>, String> {
+        let body = std::str::from_utf8(body).map_err(|e| format!("Invalid listing: {e}"))?;
+        let mut entries = Vec::new();
+        let mut entry: Option<DavEntry> = None;
+        let mut props = DavProps::default();
+        for tag in xml::tags(body) {
+            match tag? {
+                XmlTag::Open(name) | XmlTag::Empty(name) => match name {
+                    "response" => entry = Some(DavEntry::default()),
+                    "propstat" => props = DavProps::default(),
+                    "collection" => props.is_collection = true,
+                    _ => {}
+                },
+                XmlTag::Close(name, value) => match name {
+                    "href" => {
+                        if let Some(entry) = &mut entry {
+                            entry.href = percent_encoding::percent_decode_str(&value)
+                                .decode_utf8_lossy()
+                                .into_owned();
+                        }
+                    }
+                    "status" => props.ok = value.split_ascii_whitespace().nth(1) == Some("200"),
+                    "getcontentlength" => props.content_length = value.parse().ok(),
+                    "getcontenttype" => props.content_type = Some(value),
+                    "getetag" => props.etag = ETag::parse(&value),
+                    "getlastmodified" => props.last_modified = parse_http_date(&value),
+                    "propstat" => {
+                        if let Some(entry) = entry.as_mut().filter(|_| props.ok) {
+                            std::mem::take(&mut props).apply(entry);
+                        }
+                    }
+                    "response" => entries.extend(entry.take()),
+                    _ => {}
+                },
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// A file or collection in a WebDAV listing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DavEntry {
+    /// The decoded path of the entry on the server, e.g. `/remote.php/dav/files/modder/mods/`.
+    pub href: String,
+    /// Whether the entry is a collection, a folder, rather than a file.
+    pub is_collection: bool,
+    /// The size of a file in bytes.
+    pub content_length: Option<u64>,
+    pub content_type: Option<String>,
+    pub etag: Option<ETag>,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl DavEntry {
+    /// The last segment of the path, e.g. `castle.pak`.
+    pub fn name(&self) -> &str {
+        self.href
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+    }
+}
+
+/// The properties of one `propstat`, only kept if its status is `200`.
+#[derive(Default)]
+struct DavProps {
+    ok: bool,
+    is_collection: bool,
+    content_length: Option<u64>,
+    content_type: Option<String>,
+    etag: Option<ETag>,
+    last_modified: Option<SystemTime>,
+}
+
+impl DavProps {
+    fn apply(self, entry: &mut DavEntry) {
+        entry.is_collection |= self.is_collection;
+        entry.content_length = entry.content_length.or(self.content_length);
+        entry.content_type = entry.content_type.take().or(self.content_type);
+        entry.etag = entry.etag.take().or(self.etag);
+        entry.last_modified = entry.last_modified.or(self.last_modified);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `PROPFIND` answer of Nextcloud, listing a folder with one file.
+    const NEXTCLOUD_LISTING: &str = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:s="http://sabredav.org/ns" xmlns:oc="http://owncloud.org/ns" xmlns:nc="http://nextcloud.org/ns">
+ <d:response>
+  <d:href>/remote.php/dav/files/modder/mods/</d:href>
+  <d:propstat>
+   <d:prop>
+    <d:resourcetype><d:collection/></d:resourcetype>
+    <d:getetag>&quot;6523f9c2a1b7e&quot;</d:getetag>
+    <d:getlastmodified>Mon, 09 Oct 2023 12:36:50 GMT</d:getlastmodified>
+   </d:prop>
+   <d:status>HTTP/1.1 200 OK</d:status>
+  </d:propstat>
+  <d:propstat>
+   <d:prop>
+    <d:getcontentlength/>
+    <d:getcontenttype/>
+   </d:prop>
+   <d:status>HTTP/1.1 404 Not Found</d:status>
+  </d:propstat>
+ </d:response>
+ <d:response>
+  <d:href>/remote.php/dav/files/modder/mods/Castle%20%26%20Keep.pak</d:href>
+  <d:propstat>
+   <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength>1048576</d:getcontentlength>
+    <d:getcontenttype>application/octet-stream</d:getcontenttype>
+    <d:getetag>&quot;0f1d8c4b7e2a5f3c9d6b8a1e4c7f2d5a&quot;</d:getetag>
+    <d:getlastmodified>Tue, 10 Oct 2023 08:15:02 GMT</d:getlastmodified>
+   </d:prop>
+   <d:status>HTTP/1.1 200 OK</d:status>
+  </d:propstat>
+ </d:response>
+</d:multistatus>
+"#;
+
+    #[test]
+    fn parses_a_nextcloud_listing() {
+        let entries = WebDav::parse_listing(NEXTCLOUD_LISTING.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 2);
+        let (folder, file) = (&entries[0], &entries[1]);
+        assert_eq!(folder.href, "/remote.php/dav/files/modder/mods/");
+        assert!(folder.is_collection);
+        assert_eq!(folder.content_length, None);
+        assert_eq!(folder.etag, Some(ETag::strong("6523f9c2a1b7e")));
+        assert!(folder.last_modified.is_some());
+
+        assert_eq!(file.name(), "Castle & Keep.pak");
+        assert!(!file.is_collection);
+        assert_eq!(file.content_length, Some(1048576));
+        assert_eq!(
+            file.content_type.as_deref(),
+            Some("application/octet-stream")
+        );
+        assert_eq!(
+            file.etag,
+            Some(ETag::strong("0f1d8c4b7e2a5f3c9d6b8a1e4c7f2d5a"))
+        );
+        assert_eq!(
+            file.last_modified,
+            parse_http_date("Tue, 10 Oct 2023 08:15:02 GMT")
+        );
+    }
+
+    #[test]
+    fn parses_comments_and_cdata() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- generated by mod_dav -->
+<D:multistatus xmlns:D="DAV:">
+<D:response xmlns:lp1="DAV:">
+<D:href><![CDATA[/dav/notes<1>.txt]]></D:href>
+<D:propstat>
+<D:prop><!-- <D:collection/> -->
+<lp1:resourcetype/>
+<lp1:getcontentlength>12</lp1:getcontentlength>
+</D:prop>
+<D:status>HTTP/1.1 200 OK</D:status>
+</D:propstat>
+</D:response>
+</D:multistatus>"#;
+        let entries = WebDav::parse_listing(body.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].href, "/dav/notes<1>.txt");
+        assert!(!entries[0].is_collection);
+        assert_eq!(entries[0].content_length, Some(12));
+    }
+}
@@ -0,0 +1,485 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_channel::{Receiver, Sender};
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use ehttp::Headers;
+use serde::Deserialize;
+
+use crate::{HttpSchedule, HttpSet};
+
+/// Larger request heads are refused.
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+/// Larger bodies are refused.
+const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+/// Listens on localhost for HTTP callbacks and sends them as [`WebhookReceived`] events, so flows
+/// that need a public webhook, e.g. a payment sandbox or an OAuth redirect, can be tested inside
+/// the running game. Only available on native builds with the `webhook-listener` feature, meant
+/// for development and not for shipping builds.
+///
+/// Every callback is answered with `200 OK` and `response_body` right away, whatever the game does
+/// with it. Point the service at [`WebhookListener::url`], through a tunnel if it cannot reach
+/// localhost, and use `register_webhook` to get typed [`Webhook`] events for a path.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(WebhookListenerPlugin::new(8787))
+///     .register_webhook::<PaymentCompleted>("/payments")
+///     .register_webhook::<OAuthRedirect>("/oauth/callback");
+///
+/// fn redirect_url(listener: Res<WebhookListener>) -> String {
+///     listener.url("/oauth/callback")
+/// }
+///
+/// fn on_payment(mut ev_payment: EventReader<Webhook<PaymentCompleted>>) {
+///     for payment in ev_payment.read() {
+///         println!("paid {}", payment.amount);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebhookListenerPlugin {
+    /// The localhost port to listen on, any free port if `0`.
+    pub port: u16,
+    /// Sent as the body of every answer, e.g. shown in the browser after an OAuth redirect.
+    pub response_body: String,
+}
+
+impl WebhookListenerPlugin {
+    /// listen on `port` of localhost, any free port if `0`
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            response_body: "Received, you can return to the game.".to_string(),
+        }
+    }
+
+    /// answer every callback with `body`, see `response_body`
+    pub fn with_response_body(mut self, body: impl ToString) -> Self {
+        self.response_body = body.to_string();
+        self
+    }
+}
+
+impl Plugin for WebhookListenerPlugin {
+    fn build(&self, app: &mut App) {
+        let schedule = app
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        app.add_event::<WebhookReceived>();
+
+        let listener = match TcpListener::bind(("127.0.0.1", self.port)) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Webhook listener failed to bind port {}: {e}", self.port);
+                return;
+            }
+        };
+        let addr = match listener.local_addr() {
+            Ok(addr) => addr,
+            Err(e) => {
+                error!("Webhook listener has no address: {e}");
+                return;
+            }
+        };
+        let (sender, receiver) = async_channel::unbounded();
+        let response_body = self.response_body.clone();
+        let spawned = std::thread::Builder::new()
+            .name("webhook listener".to_string())
+            .spawn(move || accept_webhooks(listener, sender, response_body));
+        if let Err(e) = spawned {
+            error!("Webhook listener thread failed to start: {e}");
+            return;
+        }
+
+        info!("Listening for webhooks on http://{addr}");
+        app.insert_resource(WebhookListener { addr, receiver });
+        app.add_systems(schedule, receive_webhooks.in_set(HttpSet::HandleResponses));
+    }
+}
+
+/// The running listener of the [`WebhookListenerPlugin`], missing if it failed to start.
+#[derive(Resource, Debug)]
+pub struct WebhookListener {
+    addr: SocketAddr,
+    receiver: Receiver<WebhookReceived>,
+}
+
+impl WebhookListener {
+    /// The address listened on, with the port picked if the plugin was given `0`.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The url a service should call for `path`, e.g. `http://127.0.0.1:8787/payments`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}/{}", self.addr, path.trim_start_matches('/'))
+    }
+}
+
+/// An HTTP callback received by the [`WebhookListenerPlugin`].
+#[derive(Event, Debug, Clone)]
+pub struct WebhookReceived {
+    pub method: String,
+    /// The decoded path, without the query, e.g. `/oauth/callback`.
+    pub path: String,
+    /// The decoded query parameters, in order.
+    pub query: Vec<(String, String)>,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+}
+
+impl WebhookReceived {
+    /// The value of the first query parameter called `name`, e.g. the `code` of an OAuth redirect.
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.body).ok()
+    }
+
+    /// Deserializes the body from JSON.
+    pub fn json<T: for<'a> Deserialize<'a>>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+
+    /// Deserializes the JSON body, or the query parameters as an object of strings when there is no
+    /// body, as with redirects.
+    fn deserialize<T: for<'a> Deserialize<'a>>(&self) -> Result<T, serde_json::Error> {
+        if !self.body.is_empty() {
+            return self.json();
+        }
+        let query: serde_json::Map<String, serde_json::Value> = self
+            .query
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone().into()))
+            .collect();
+        serde_json::from_value(query.into())
+    }
+}
+
+/// A callback to a path registered with `register_webhook::<T>`, deserialized into `T`.
+#[derive(Event, Debug, Clone, Deref, DerefMut)]
+pub struct Webhook<T> {
+    #[deref]
+    pub value: T,
+    /// The callback `value` was read from.
+    pub received: WebhookReceived,
+}
+
+pub trait HttpWebhookTrait {
+    /// Sends a [`Webhook<T>`] event for every callback to `path`, deserialized from its JSON body,
+    /// or from its query parameters if it has no body. Callbacks that do not deserialize are logged
+    /// and only sent as [`WebhookReceived`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.register_webhook::<PaymentCompleted>("/payments");
+    /// ```
+    fn register_webhook<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+        path: &str,
+    ) -> &mut Self;
+}
+
+impl HttpWebhookTrait for App {
+    fn register_webhook<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+        path: &str,
+    ) -> &mut Self {
+        let schedule = self
+            .world
+            .get_resource::<HttpSchedule>()
+            .map_or(Update.intern(), |schedule| schedule.0);
+        let path = format!("/{}", path.trim_start_matches('/'));
+        self.add_event::<WebhookReceived>();
+        self.add_event::<Webhook<T>>();
+        self.add_systems(
+            schedule,
+            send_typed_webhooks::<T>(path)
+                .in_set(HttpSet::HandleResponses)
+                .after(receive_webhooks),
+        );
+        self
+    }
+}
+
+fn receive_webhooks(listener: Res<WebhookListener>, mut ev_received: EventWriter<WebhookReceived>) {
+    while let Ok(webhook) = listener.receiver.try_recv() {
+        ev_received.send(webhook);
+    }
+}
+
+fn send_typed_webhooks<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+    path: String,
+) -> impl FnMut(EventReader<WebhookReceived>, EventWriter<Webhook<T>>) {
+    move |mut ev_received, mut ev_webhook| {
+        for received in ev_received.read().filter(|received| received.path == path) {
+            match received.deserialize::<T>() {
+                Ok(value) => {
+                    ev_webhook.send(Webhook {
+                        value,
+                        received: received.clone(),
+                    });
+                }
+                Err(e) => warn!("Webhook to {path} did not deserialize: {e}"),
+            }
+        }
+    }
+}
+
+/// Answers every connection on a thread of its own until the app is gone, so a client that
+/// connects and sends nothing doesn't hold up the others.
+fn accept_webhooks(listener: TcpListener, sender: Sender<WebhookReceived>, response_body: String) {
+    let response_body = Arc::<str>::from(response_body);
+    for stream in listener.incoming() {
+        if sender.is_closed() {
+            return;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let (sender, response_body) = (sender.clone(), response_body.clone());
+        let spawned = std::thread::Builder::new()
+            .name("webhook connection".to_string())
+            .spawn(move || answer_webhook(stream, &sender, &response_body));
+        if let Err(e) = spawned {
+            warn!("Webhook connection thread failed to start: {e}");
+        }
+    }
+}
+
+/// Reads one callback from `stream`, answers it and sends it to the app.
+fn answer_webhook(mut stream: TcpStream, sender: &Sender<WebhookReceived>, response_body: &str) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let (status, webhook) = match read_webhook(&stream) {
+        Ok(webhook) => ("200 OK", Some(webhook)),
+        Err(status) => (status, None),
+    };
+    let body = if webhook.is_some() {
+        response_body
+    } else {
+        status
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.flush();
+    if let Some(webhook) = webhook {
+        // The app is gone if this fails, the listener stops at the next connection.
+        let _ = sender.send_blocking(webhook);
+    }
+}
+
+/// Reads one HTTP/1.1 request, the status to answer with if it is not supported.
+fn read_webhook(stream: impl Read) -> Result<WebhookReceived, &'static str> {
+    let mut reader = BufReader::new(stream).take(MAX_HEAD_BYTES as u64);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|_| "400 Bad Request")?;
+    if !line.ends_with('\n') && reader.limit() == 0 {
+        return Err("414 URI Too Long");
+    }
+    let mut parts = line.split_ascii_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("400 Bad Request");
+    };
+    let method = method.to_string();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = percent_encoding::percent_decode_str(path)
+        .decode_utf8_lossy()
+        .into_owned();
+    let query = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+
+    let mut headers = Headers::default();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|_| "400 Bad Request")?;
+        // The line is cut short by the end of the stream, or by reaching `MAX_HEAD_BYTES`.
+        if !line.ends_with('\n') {
+            return Err(if reader.limit() == 0 {
+                "431 Request Header Fields Too Large"
+            } else {
+                "400 Bad Request"
+            });
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err("400 Bad Request");
+        };
+        headers.insert(name.trim(), value.trim());
+    }
+
+    if headers
+        .get("transfer-encoding")
+        .is_some_and(|encoding| !encoding.eq_ignore_ascii_case("identity"))
+    {
+        return Err("411 Length Required");
+    }
+    let length = match headers.get("content-length") {
+        Some(length) => length.parse().map_err(|_| "400 Bad Request")?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err("413 Content Too Large");
+    }
+    let mut reader = reader.into_inner();
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| "400 Bad Request")?;
+
+    Ok(WebhookReceived {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(request: &[u8]) -> Result<WebhookReceived, &'static str> {
+        read_webhook(request)
+    }
+
+    #[test]
+    fn reads_a_callback() {
+        let webhook = read(
+            b"POST /pay%20ments?code=a%2Bb&state=1 HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: 11\r\n\r\n{\"ok\":true}trailing",
+        )
+        .unwrap();
+        assert_eq!(webhook.method, "POST");
+        assert_eq!(webhook.path, "/pay ments");
+        assert_eq!(webhook.query("code"), Some("a+b"));
+        assert_eq!(webhook.query("state"), Some("1"));
+        assert_eq!(
+            webhook.headers.get("content-type"),
+            Some("application/json")
+        );
+        assert_eq!(webhook.text(), Some("{\"ok\":true}"));
+
+        let redirect = read(b"GET /oauth/callback?code=x HTTP/1.1\n\n").unwrap();
+        assert!(redirect.body.is_empty());
+        assert!(redirect.headers.headers.is_empty());
+    }
+
+    #[test]
+    fn checks_the_content_length() {
+        assert_eq!(
+            read(b"POST / HTTP/1.1\r\nContent-Length: ten\r\n\r\n").err(),
+            Some("400 Bad Request")
+        );
+        assert_eq!(
+            read(
+                format!(
+                    "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                    MAX_BODY_BYTES + 1
+                )
+                .as_bytes()
+            )
+            .err(),
+            Some("413 Content Too Large")
+        );
+        assert_eq!(
+            read(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n")
+                .err(),
+            Some("411 Length Required")
+        );
+        assert_eq!(
+            read(b"POST / HTTP/1.1\r\nTransfer-Encoding: identity\r\nContent-Length: 1\r\n\r\nx")
+                .unwrap()
+                .body,
+            b"x"
+        );
+    }
+
+    #[test]
+    fn fails_on_early_eof() {
+        assert_eq!(read(b"").err(), Some("400 Bad Request"));
+        assert_eq!(read(b"GET").err(), Some("400 Bad Request"));
+        assert_eq!(
+            read(b"GET / HTTP/1.1\r\nHost: localhost\r\n").err(),
+            Some("400 Bad Request")
+        );
+        assert_eq!(
+            read(b"GET / HTTP/1.1\r\nHost: local").err(),
+            Some("400 Bad Request")
+        );
+        assert_eq!(
+            read(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort").err(),
+            Some("400 Bad Request")
+        );
+    }
+
+    #[test]
+    fn limits_the_head() {
+        let long_target = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_HEAD_BYTES));
+        assert_eq!(read(long_target.as_bytes()).err(), Some("414 URI Too Long"));
+
+        let long_header = format!(
+            "GET / HTTP/1.1\r\nX-Big: {}\r\n\r\n",
+            "b".repeat(MAX_HEAD_BYTES)
+        );
+        assert_eq!(
+            read(long_header.as_bytes()).err(),
+            Some("431 Request Header Fields Too Large")
+        );
+
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-A: 1\r\n".repeat(MAX_HEAD_BYTES / 8)
+        );
+        assert_eq!(
+            read(many_headers.as_bytes()).err(),
+            Some("431 Request Header Fields Too Large")
+        );
+        assert_eq!(
+            read(b"GET / HTTP/1.1\r\nno colon\r\n\r\n").err(),
+            Some("400 Bad Request")
+        );
+    }
+
+    #[test]
+    fn an_idle_client_does_not_hold_up_others() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, WebhookListenerPlugin::new(0)));
+        let addr = app.world.resource::<WebhookListener>().addr();
+        let _idle = TcpStream::connect(addr).unwrap();
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET /ping HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut answer = String::new();
+        client.read_to_string(&mut answer).unwrap();
+        assert!(answer.starts_with("HTTP/1.1 200 OK"), "{answer}");
+
+        app.update();
+        let received = app.world.resource::<Events<WebhookReceived>>();
+        let paths: Vec<_> = received
+            .iter_current_update_events()
+            .map(|webhook| webhook.path.as_str())
+            .collect();
+        assert_eq!(paths, ["/ping"]);
+    }
+}
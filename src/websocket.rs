@@ -0,0 +1,298 @@
+use async_channel::{Receiver, Sender, TryRecvError};
+use bevy::prelude::*;
+
+use crate::{HttpClientSetting, RequestBlocked, REQUEST_BLOCKED};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+#[cfg(target_arch = "wasm32")]
+mod web;
+
+/// A WebSocket connection, opened once the component is added to an entity. Only available with
+/// the `websocket` feature.
+///
+/// Messages arrive as [`WebSocketMessage`] events carrying the entity, like the responses of
+/// requests sent with `HttpClient::entity`. [`WebSocketOpened`] is sent once the handshake
+/// succeeded, then [`WebSocketClosed`] when either side closed the connection, or
+/// [`WebSocketError`] when it could not be opened or broke. Messages sent before the connection
+/// is open are sent once it is. Removing the component or despawning the entity closes the
+/// connection. Native builds use `tungstenite` over their own TCP or TLS connection, wasm builds
+/// use the browser `WebSocket` API. The events are delivered in `HttpSet::HandleResponses`.
+///
+/// # Examples
+///
+/// ```
+/// fn join_lobby(mut commands: Commands) {
+///     commands.spawn(WebSocket::connect("wss://lobby.example.com/ws").protocol("lobby.v1"));
+/// }
+///
+/// fn on_open(mut ev_opened: EventReader<WebSocketOpened>, sockets: Query<&WebSocket>) {
+///     for opened in ev_opened.read() {
+///         if let Ok(socket) = sockets.get(opened.entity) {
+///             socket.send(r#"{"type":"hello"}"#);
+///         }
+///     }
+/// }
+///
+/// fn on_message(mut ev_message: EventReader<WebSocketMessage>) {
+///     for message in ev_message.read() {
+///         if let WsMessage::Text(text) = &message.message {
+///             println!("lobby: {text}");
+///         }
+///     }
+/// }
+/// ```
+#[derive(Component, Debug)]
+pub struct WebSocket {
+    pub url: String,
+    protocols: Vec<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    headers: Vec<(String, String)>,
+    state: WebSocketState,
+    protocol: Option<String>,
+    commands: Sender<WsCommand>,
+    events: Receiver<WsEvent>,
+    /// The ends handed to the connection when it is opened.
+    connection: Option<(Receiver<WsCommand>, Sender<WsEvent>)>,
+}
+
+impl WebSocket {
+    /// Connects to a `ws://` or `wss://` url once the component is added to an entity.
+    pub fn connect(url: impl ToString) -> Self {
+        let (commands, command_rx) = async_channel::unbounded();
+        let (event_tx, events) = async_channel::unbounded();
+        Self {
+            url: url.to_string(),
+            protocols: vec![],
+            #[cfg(not(target_arch = "wasm32"))]
+            headers: vec![],
+            state: WebSocketState::Connecting,
+            protocol: None,
+            commands,
+            events,
+            connection: Some((command_rx, event_tx)),
+        }
+    }
+
+    /// Offers the subprotocol `protocol`, the one the server picked is `selected_protocol`.
+    pub fn protocol(mut self, protocol: impl ToString) -> Self {
+        self.protocols.push(protocol.to_string());
+        self
+    }
+
+    /// Sends a header with the handshake, e.g. `Authorization`. Only available on native builds,
+    /// browsers do not allow headers on WebSocket handshakes.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn header(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn state(&self) -> WebSocketState {
+        self.state
+    }
+
+    /// The subprotocol the server picked, `None` before the connection is open or if the server
+    /// picked none.
+    pub fn selected_protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Sends `message`, once the connection is open. Does nothing once it is closed.
+    pub fn send(&self, message: impl Into<WsMessage>) {
+        let _ = self.commands.try_send(WsCommand::Send(message.into()));
+    }
+
+    /// Closes the connection with the `1000` normal closure code.
+    pub fn close(&self) {
+        self.close_with(1000, "");
+    }
+
+    /// Closes the connection with `code` and `reason`, sent to the server.
+    pub fn close_with(&self, code: u16, reason: impl ToString) {
+        let _ = self
+            .commands
+            .try_send(WsCommand::Close(code, reason.to_string()));
+    }
+}
+
+/// The state of a [`WebSocket`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebSocketState {
+    /// The handshake is in progress.
+    Connecting,
+    Open,
+    /// Closed by either side, or failed.
+    Closed,
+}
+
+/// A WebSocket message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsMessage {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl From<String> for WsMessage {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for WsMessage {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<Vec<u8>> for WsMessage {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Binary(bytes)
+    }
+}
+
+impl From<&[u8]> for WsMessage {
+    fn from(bytes: &[u8]) -> Self {
+        Self::Binary(bytes.to_vec())
+    }
+}
+
+/// Sent once the connection of the [`WebSocket`] on `entity` is open.
+#[derive(Event, Debug, Clone)]
+pub struct WebSocketOpened {
+    pub entity: Entity,
+    /// The subprotocol the server picked.
+    pub protocol: Option<String>,
+}
+
+/// A message received by the [`WebSocket`] on `entity`.
+#[derive(Event, Debug, Clone)]
+pub struct WebSocketMessage {
+    pub entity: Entity,
+    pub message: WsMessage,
+}
+
+/// Sent when the connection of the [`WebSocket`] on `entity` was closed by either side.
+#[derive(Event, Debug, Clone)]
+pub struct WebSocketClosed {
+    pub entity: Entity,
+    /// The close code, `1005` if none was given.
+    pub code: u16,
+    pub reason: String,
+}
+
+/// Sent when the connection of the [`WebSocket`] on `entity` could not be opened or broke.
+#[derive(Event, Debug, Clone)]
+pub struct WebSocketError {
+    pub entity: Entity,
+    pub error: String,
+}
+
+/// What the game asks of a connection, it is closed once the `WebSocket` is gone.
+#[derive(Debug)]
+enum WsCommand {
+    Send(WsMessage),
+    Close(u16, String),
+}
+
+/// What a connection reports to the game.
+#[derive(Debug)]
+enum WsEvent {
+    Opened(Option<String>),
+    Message(WsMessage),
+    Closed(u16, String),
+    Error(String),
+}
+
+pub(crate) fn open_websockets(
+    mut sockets: Query<&mut WebSocket, Added<WebSocket>>,
+    settings: Res<HttpClientSetting>,
+    mut ev_blocked: EventWriter<RequestBlocked>,
+) {
+    for mut socket in sockets.iter_mut() {
+        let Some((commands, events)) = socket.connection.take() else {
+            continue;
+        };
+        if !(socket.url.starts_with("ws://") || socket.url.starts_with("wss://")) {
+            let _ = events.try_send(WsEvent::Error(format!(
+                "Not a WebSocket url: {}",
+                socket.url
+            )));
+            continue;
+        }
+        if let Some(Err(reason)) = settings
+            .url_policy
+            .as_ref()
+            .map(|policy| policy.check(&socket.url))
+        {
+            warn!("Blocked WebSocket to {}: {reason}", socket.url);
+            let _ = events.try_send(WsEvent::Error(format!("{REQUEST_BLOCKED}: {reason}")));
+            ev_blocked.send(RequestBlocked {
+                request_id: None,
+                url: socket.url.clone(),
+                reason,
+            });
+            continue;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        native::connect(
+            socket.url.clone(),
+            socket.protocols.clone(),
+            socket.headers.clone(),
+            commands,
+            events,
+        );
+        #[cfg(target_arch = "wasm32")]
+        web::connect(
+            socket.url.clone(),
+            socket.protocols.clone(),
+            commands,
+            events,
+        );
+    }
+}
+
+pub(crate) fn poll_websockets(
+    mut sockets: Query<(Entity, &mut WebSocket)>,
+    mut opened: EventWriter<WebSocketOpened>,
+    mut messages: EventWriter<WebSocketMessage>,
+    mut closed: EventWriter<WebSocketClosed>,
+    mut errors: EventWriter<WebSocketError>,
+) {
+    for (entity, mut socket) in sockets.iter_mut() {
+        if socket.state == WebSocketState::Closed {
+            continue;
+        }
+        loop {
+            let event = match socket.events.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Closed) => WsEvent::Error("Connection lost".to_string()),
+            };
+            match event {
+                WsEvent::Opened(protocol) => {
+                    socket.state = WebSocketState::Open;
+                    socket.protocol.clone_from(&protocol);
+                    opened.send(WebSocketOpened { entity, protocol });
+                }
+                WsEvent::Message(message) => {
+                    messages.send(WebSocketMessage { entity, message });
+                }
+                WsEvent::Closed(code, reason) => {
+                    socket.state = WebSocketState::Closed;
+                    closed.send(WebSocketClosed {
+                        entity,
+                        code,
+                        reason,
+                    });
+                    break;
+                }
+                WsEvent::Error(error) => {
+                    socket.state = WebSocketState::Closed;
+                    errors.send(WebSocketError { entity, error });
+                    break;
+                }
+            }
+        }
+    }
+}
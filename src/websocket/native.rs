@@ -0,0 +1,336 @@
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_channel::{Receiver, Sender};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::handshake::client::Request;
+use tungstenite::handshake::HandshakeError;
+use tungstenite::http::{HeaderName, HeaderValue};
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::Message;
+
+use super::{WsCommand, WsEvent, WsMessage};
+use crate::transport::tls_config;
+
+/// How long the server has to answer a close frame.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Socket = tungstenite::WebSocket<Box<dyn ureq::ReadWrite>>;
+
+/// Same threading model as the unix socket transport: the blocking socket IO runs on its own
+/// threads, which end with the connection. One reads, the other sends the commands of the game as
+/// they arrive.
+pub(super) fn connect(
+    url: String,
+    protocols: Vec<String>,
+    headers: Vec<(String, String)>,
+    commands: Receiver<WsCommand>,
+    events: Sender<WsEvent>,
+) {
+    let thread_events = events.clone();
+    let spawned = std::thread::Builder::new()
+        .name("bevy_http_client_websocket".to_owned())
+        .spawn(move || {
+            let result = handshake_request(&url, &protocols, &headers)
+                .and_then(|request| open(&url, request))
+                .and_then(|(socket, tcp, protocol)| {
+                    let _ = thread_events.send_blocking(WsEvent::Opened(protocol));
+                    run(socket, tcp, commands, &thread_events)
+                });
+            if let Err(error) = result {
+                let _ = thread_events.send_blocking(WsEvent::Error(error));
+            }
+        });
+    if let Err(err) = spawned {
+        let _ = events.try_send(WsEvent::Error(format!(
+            "Failed to spawn WebSocket thread: {err}"
+        )));
+    }
+}
+
+/// The handshake request, rejecting headers that are not valid HTTP headers, e.g. with a CR or LF.
+fn handshake_request(
+    url: &str,
+    protocols: &[String],
+    headers: &[(String, String)],
+) -> Result<Request, String> {
+    let mut request = url
+        .into_client_request()
+        .map_err(|err| format!("Invalid url {url}: {err}"))?;
+    if !protocols.is_empty() {
+        let protocols = protocols.join(", ");
+        let value = HeaderValue::from_str(&protocols)
+            .map_err(|_| format!("Invalid subprotocols {protocols:?}"))?;
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", value);
+    }
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("Invalid header name {name:?}"))?;
+        let value =
+            HeaderValue::from_str(value).map_err(|_| format!("Invalid value of header {name}"))?;
+        request.headers_mut().append(name, value);
+    }
+    Ok(request)
+}
+
+/// Connects and shakes hands, returning the socket, the TCP stream under it and the subprotocol.
+fn open(url: &str, request: Request) -> Result<(Socket, TcpStream, Option<String>), String> {
+    let parsed = url::Url::parse(url).map_err(|err| format!("Invalid url {url}: {err}"))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("Missing host in url: {url}"))?;
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let tcp = TcpStream::connect((host, port))
+        .map_err(|err| format!("Failed to connect to {host}:{port}: {err}"))?;
+    let _ = tcp.set_nodelay(true);
+    // Kept to wait for data and switch the socket to non-blocking reads.
+    let raw = tcp
+        .try_clone()
+        .map_err(|err| format!("Failed to connect to {host}:{port}: {err}"))?;
+    let stream: Box<dyn ureq::ReadWrite> = if parsed.scheme() == "wss" {
+        ureq::TlsConnector::connect(&tls_config(), host, Box::new(tcp))
+            .map_err(|err| format!("TLS handshake with {host} failed: {err}"))?
+    } else {
+        Box::new(tcp)
+    };
+
+    let (socket, response) = tungstenite::client(request, stream).map_err(|err| match err {
+        HandshakeError::Failure(tungstenite::Error::Http(response)) => {
+            format!("Handshake rejected: {}", response.status())
+        }
+        HandshakeError::Failure(err) => format!("Handshake with {host} failed: {err}"),
+        HandshakeError::Interrupted(_) => format!("Handshake with {host} was interrupted"),
+    })?;
+    let protocol = response
+        .headers()
+        .get("Sec-WebSocket-Protocol")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    Ok((socket, raw, protocol))
+}
+
+/// The socket, shared by the reading and the sending thread.
+struct Connection {
+    socket: Socket,
+    /// The code of the close frame sent, once it was.
+    closing: Option<u16>,
+}
+
+impl Connection {
+    fn close(&mut self, tcp: &TcpStream, code: u16, reason: String) -> Result<(), String> {
+        if self.closing.is_some() {
+            return Ok(());
+        }
+        self.closing = Some(code);
+        // Stops waiting for the answer of the server after a while.
+        let _ = tcp.set_read_timeout(Some(CLOSE_TIMEOUT));
+        self.socket
+            .close(Some(CloseFrame {
+                code: CloseCode::from(code),
+                reason: reason.into(),
+            }))
+            .map_err(|err| err.to_string())
+    }
+}
+
+fn lock(connection: &Mutex<Connection>) -> MutexGuard<'_, Connection> {
+    connection
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn run(
+    socket: Socket,
+    tcp: TcpStream,
+    commands: Receiver<WsCommand>,
+    events: &Sender<WsEvent>,
+) -> Result<(), String> {
+    let connection = Arc::new(Mutex::new(Connection {
+        socket,
+        closing: None,
+    }));
+    let writer_tcp = tcp.try_clone().map_err(|err| err.to_string())?;
+    let writer = {
+        let connection = connection.clone();
+        let commands = commands.clone();
+        let events = events.clone();
+        std::thread::Builder::new()
+            .name("bevy_http_client_websocket_writer".to_owned())
+            .spawn(move || {
+                if let Err(error) = send_commands(&connection, &writer_tcp, &commands) {
+                    let _ = events.send_blocking(WsEvent::Error(error));
+                    let _ = writer_tcp.shutdown(std::net::Shutdown::Both);
+                }
+            })
+            .map_err(|err| format!("Failed to spawn WebSocket thread: {err}"))?
+    };
+    let result = read_messages(&connection, &tcp, events);
+    // Ends the sending thread if the game still holds the `WebSocket`.
+    commands.close();
+    let _ = writer.join();
+    result
+}
+
+/// Sends the commands of the game until the `WebSocket` is gone.
+fn send_commands(
+    connection: &Mutex<Connection>,
+    tcp: &TcpStream,
+    commands: &Receiver<WsCommand>,
+) -> Result<(), String> {
+    while let Ok(command) = commands.recv_blocking() {
+        let mut connection = lock(connection);
+        let result = match command {
+            WsCommand::Send(message) => {
+                let message = match message {
+                    WsMessage::Text(text) => Message::Text(text),
+                    WsMessage::Binary(bytes) => Message::Binary(bytes),
+                };
+                connection
+                    .socket
+                    .send(message)
+                    .map_err(|err| err.to_string())
+            }
+            WsCommand::Close(code, reason) => connection.close(tcp, code, reason),
+        };
+        match result {
+            Ok(()) => {}
+            // Messages sent once the connection is closing are dropped.
+            Err(_) if connection.closing.is_some() => {}
+            Err(err) => return Err(format!("Connection lost: {err}")),
+        }
+    }
+    // The `WebSocket` is gone, nobody listens for the answer.
+    let _ = lock(connection).close(tcp, 1001, String::new());
+    Ok(())
+}
+
+/// Reads messages until the connection is closed.
+///
+/// Reads everything that arrived without blocking, then waits for more with `peek` without holding
+/// the socket, so the sending thread can write in the meantime. The first pass reads what arrived
+/// with the handshake response.
+fn read_messages(
+    connection: &Mutex<Connection>,
+    tcp: &TcpStream,
+    events: &Sender<WsEvent>,
+) -> Result<(), String> {
+    let mut byte = [0; 1];
+    loop {
+        {
+            let mut connection = lock(connection);
+            tcp.set_nonblocking(true).map_err(|err| err.to_string())?;
+            let closed = read_available(&mut connection, events);
+            tcp.set_nonblocking(false).map_err(|err| err.to_string())?;
+            if let Some((code, reason)) = closed? {
+                // Sends the echo of the close frame of the server.
+                let _ = connection.socket.flush();
+                let _ = events.send_blocking(WsEvent::Closed(code, reason));
+                return Ok(());
+            }
+        }
+
+        match tcp.peek(&mut byte) {
+            // `0` is the end of the stream, reading reports it.
+            Ok(_) => {}
+            // Only times out once a close frame was sent, the server never answered it.
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                let code = lock(connection).closing.unwrap_or(1006);
+                let _ = events.send_blocking(WsEvent::Closed(code, String::new()));
+                return Ok(());
+            }
+            Err(err) => return Err(format!("Connection lost: {err}")),
+        }
+    }
+}
+
+/// Hands the messages that arrived to the game, returns the close code and reason once the server
+/// closed the connection.
+fn read_available(
+    connection: &mut Connection,
+    events: &Sender<WsEvent>,
+) -> Result<Option<(u16, String)>, String> {
+    loop {
+        let message = match connection.socket.read() {
+            Ok(Message::Text(text)) => WsMessage::Text(text),
+            Ok(Message::Binary(bytes)) => WsMessage::Binary(bytes),
+            // Pings are answered by the socket.
+            Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_)) => continue,
+            Ok(Message::Close(frame)) => {
+                return Ok(Some(frame.map_or((1005, String::new()), |frame| {
+                    (frame.code.into(), frame.reason.into_owned())
+                })));
+            }
+            Err(tungstenite::Error::Io(err)) if err.kind() == ErrorKind::WouldBlock => {
+                return Ok(None);
+            }
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                return match connection.closing {
+                    Some(code) => Ok(Some((code, String::new()))),
+                    None => Err("Connection closed without a close frame".to_string()),
+                };
+            }
+            Err(err) => return Err(format!("Connection lost: {err}")),
+        };
+        let _ = events.send_blocking(WsEvent::Message(message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header<'a>(request: &'a Request, name: &str) -> Vec<&'a str> {
+        request
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|value| value.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn handshake_requests() {
+        let protocols = ["lobby.v2".to_string(), "lobby.v1".to_string()];
+        let headers = [
+            ("Authorization".to_string(), "Bearer abc".to_string()),
+            ("X-Region".to_string(), "eu\twest".to_string()),
+        ];
+        let request =
+            handshake_request("wss://lobby.example.com/ws?room=4", &protocols, &headers).unwrap();
+        assert_eq!(request.uri(), "wss://lobby.example.com/ws?room=4");
+        assert_eq!(
+            header(&request, "Sec-WebSocket-Protocol"),
+            ["lobby.v2, lobby.v1"]
+        );
+        assert_eq!(header(&request, "Authorization"), ["Bearer abc"]);
+        assert_eq!(header(&request, "X-Region"), ["eu\twest"]);
+        assert_eq!(header(&request, "Sec-WebSocket-Version"), ["13"]);
+        // 16 random bytes.
+        let key = header(&request, "Sec-WebSocket-Key")[0];
+        assert_eq!(key.len(), 24);
+        let other = handshake_request("ws://lobby.example.com/ws", &[], &[]).unwrap();
+        assert_ne!(header(&other, "Sec-WebSocket-Key")[0], key);
+    }
+
+    #[test]
+    fn rejects_header_injection() {
+        let url = "ws://lobby.example.com/ws";
+        for (name, value) in [
+            ("X-Name", "a\r\nX-Admin: true"),
+            ("X-Name", "a\nb"),
+            ("X-Name\r\nX-Admin", "true"),
+            ("X Name", "a"),
+            ("", "a"),
+        ] {
+            let headers = [(name.to_string(), value.to_string())];
+            assert!(handshake_request(url, &[], &headers).is_err(), "{name:?}");
+        }
+        let protocols = ["lobby\r\nX-Admin: true".to_string()];
+        assert!(handshake_request(url, &protocols, &[]).is_err());
+    }
+}
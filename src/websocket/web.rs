@@ -0,0 +1,105 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use async_channel::{Receiver, Sender};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use super::{WsCommand, WsEvent, WsMessage};
+
+/// Opens the browser `WebSocket`, the commands are forwarded to it once it is open.
+pub(super) fn connect(
+    url: String,
+    protocols: Vec<String>,
+    commands: Receiver<WsCommand>,
+    events: Sender<WsEvent>,
+) {
+    let socket = if protocols.is_empty() {
+        web_sys::WebSocket::new(&url)
+    } else {
+        let array = js_sys::Array::new();
+        for protocol in &protocols {
+            array.push(&JsValue::from_str(protocol));
+        }
+        web_sys::WebSocket::new_with_str_sequence(&url, &array)
+    };
+    let socket = match socket {
+        Ok(socket) => socket,
+        Err(err) => {
+            let _ = events.try_send(WsEvent::Error(format!("Failed to open WebSocket: {err:?}")));
+            return;
+        }
+    };
+    socket.set_binary_type(web_sys::BinaryType::Arraybuffer);
+
+    let (opened_tx, opened) = async_channel::bounded::<()>(1);
+    let (closed_tx, closed) = async_channel::bounded::<()>(1);
+    // Browsers report failures with an error event followed by a close event.
+    let failed = Rc::new(Cell::new(false));
+    let on_open = {
+        let events = events.clone();
+        let socket = socket.clone();
+        let opened_tx = opened_tx.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            let protocol = Some(socket.protocol()).filter(|protocol| !protocol.is_empty());
+            let _ = events.try_send(WsEvent::Opened(protocol));
+            let _ = opened_tx.try_send(());
+        })
+    };
+    let on_message = {
+        let events = events.clone();
+        Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |event: web_sys::MessageEvent| {
+            let data = event.data();
+            let message = match data.as_string() {
+                Some(text) => WsMessage::Text(text),
+                None => WsMessage::Binary(js_sys::Uint8Array::new(&data).to_vec()),
+            };
+            let _ = events.try_send(WsEvent::Message(message));
+        })
+    };
+    let on_error = {
+        let events = events.clone();
+        let failed = failed.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            failed.set(true);
+            let _ = events.try_send(WsEvent::Error("WebSocket connection failed".to_string()));
+        })
+    };
+    let on_close =
+        Closure::<dyn FnMut(web_sys::CloseEvent)>::new(move |event: web_sys::CloseEvent| {
+            // Lets the forwarding task end if the socket never opened.
+            opened_tx.close();
+            let _ = closed_tx.try_send(());
+            if !failed.get() {
+                let _ = events.try_send(WsEvent::Closed(event.code(), event.reason()));
+            }
+        });
+    socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+    socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    socket.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+    wasm_bindgen_futures::spawn_local(async move {
+        // The callbacks live as long as the `WebSocket` component, and until the browser fired
+        // `onclose`, which comes after `close()` returned.
+        let callbacks = (on_open, on_message, on_error, on_close);
+        if opened.recv().await.is_ok() {
+            while let Ok(command) = commands.recv().await {
+                let _ = match command {
+                    WsCommand::Send(WsMessage::Text(text)) => socket.send_with_str(&text),
+                    WsCommand::Send(WsMessage::Binary(bytes)) => socket.send_with_u8_array(&bytes),
+                    WsCommand::Close(code, reason) => {
+                        socket.close_with_code_and_reason(code, &reason)
+                    }
+                };
+            }
+        }
+        let _ = socket.close();
+        let _ = closed.recv().await;
+        socket.set_onopen(None);
+        socket.set_onmessage(None);
+        socket.set_onerror(None);
+        socket.set_onclose(None);
+        drop(callbacks);
+    });
+}
@@ -0,0 +1,219 @@
+/// A tag of an XML document, with namespace prefixes removed.
+pub(crate) enum XmlTag<'a> {
+    Open(&'a str),
+    /// A self-closing tag, e.g. `<d:collection/>`.
+    Empty(&'a str),
+    /// A closing tag, with the unescaped text between it and the tag before it.
+    Close(&'a str, String),
+}
+
+/// Reads the tags of the small XML documents of WebDAV and S3 responses in order, skipping the
+/// declaration, processing instructions, comments and doctype. The text of `CDATA` sections is
+/// kept as is, and a `>` in a quoted attribute value doesn't end its tag. Not a validating parser.
+pub(crate) fn tags(body: &str) -> XmlTags<'_> {
+    XmlTags { rest: body }
+}
+
+pub(crate) struct XmlTags<'a> {
+    rest: &'a str,
+}
+
+/// A run of text between two tags, either escaped or the content of a `CDATA` section.
+enum Text<'a> {
+    Escaped(&'a str),
+    CData(&'a str),
+}
+
+impl<'a> Iterator for XmlTags<'a> {
+    type Item = Result<XmlTag<'a>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut text = Vec::new();
+        loop {
+            let start = self.rest.find('<')?;
+            text.push(Text::Escaped(&self.rest[..start]));
+            let markup = &self.rest[start..];
+            let skipped = if let Some(comment) = markup.strip_prefix("<!--") {
+                comment.find("-->").map(|end| 4 + end + 3)
+            } else if let Some(cdata) = markup.strip_prefix("<![CDATA[") {
+                cdata.find("]]>").map(|end| {
+                    text.push(Text::CData(&cdata[..end]));
+                    9 + end + 3
+                })
+            } else if markup.starts_with("<?") {
+                markup.find("?>").map(|end| end + 2)
+            } else if markup.starts_with("<!") {
+                // A doctype may hold an internal subset in brackets.
+                tag_end(markup, true).map(|end| end + 1)
+            } else {
+                let Some(end) = tag_end(markup, false) else {
+                    self.rest = "";
+                    return Some(Err("Invalid XML: unclosed tag".to_string()));
+                };
+                self.rest = &markup[end + 1..];
+                return Some(Ok(tag(&markup[1..end], &text)));
+            };
+            let Some(skipped) = skipped else {
+                self.rest = "";
+                return Some(Err("Invalid XML: unclosed markup".to_string()));
+            };
+            self.rest = &markup[skipped..];
+        }
+    }
+}
+
+/// The position of the `>` ending the markup at the start of `markup`, outside of quoted
+/// attribute values and, in a doctype, brackets.
+fn tag_end(markup: &str, doctype: bool) -> Option<usize> {
+    let mut quote = None;
+    let mut brackets = 0usize;
+    for (i, c) in markup.char_indices() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') if doctype => brackets += 1,
+            (None, ']') if doctype => brackets = brackets.saturating_sub(1),
+            (None, '>') if brackets == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reads the tag between `<` and `>`, with the text before it for a closing tag.
+fn tag<'a>(tag: &'a str, text: &[Text]) -> XmlTag<'a> {
+    let name = tag
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .split_ascii_whitespace()
+        .next()
+        .unwrap_or_default();
+    // Servers pick their own namespace prefixes, e.g. `d:`, `D:` or none.
+    let name = name.rsplit(':').next().unwrap_or_default();
+    if tag.starts_with('/') {
+        XmlTag::Close(name, join_text(text))
+    } else if tag.ends_with('/') {
+        XmlTag::Empty(name)
+    } else {
+        XmlTag::Open(name)
+    }
+}
+
+/// Joins the runs of text before a closing tag, trimming the whitespace around escaped text at
+/// either end but never the content of a `CDATA` section.
+fn join_text(text: &[Text]) -> String {
+    let last = text.len().saturating_sub(1);
+    let mut joined = String::new();
+    for (i, run) in text.iter().enumerate() {
+        match run {
+            Text::CData(cdata) => joined.push_str(cdata),
+            Text::Escaped(escaped) => {
+                let mut escaped = *escaped;
+                if i == 0 {
+                    escaped = escaped.trim_start();
+                }
+                if i == last {
+                    escaped = escaped.trim_end();
+                }
+                joined.push_str(&unescape(escaped));
+            }
+        }
+    }
+    joined
+}
+
+/// Replaces the predefined and numeric XML entities.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The tags as `<name`, `<name/` and `name=text`.
+    fn read(body: &str) -> Vec<String> {
+        tags(body)
+            .map(|tag| match tag.unwrap() {
+                XmlTag::Open(name) => format!("<{name}"),
+                XmlTag::Empty(name) => format!("<{name}/"),
+                XmlTag::Close(name, text) => format!("{name}={text}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_tags_and_text() {
+        assert_eq!(
+            read("<?xml version=\"1.0\"?>\n<a:list>\n  <a:item/>\n  <key> a &amp; b &#x3C; </key>\n</a:list>"),
+            ["<list", "<item/", "<key", "key=a & b <", "list="]
+        );
+    }
+
+    #[test]
+    fn skips_comments() {
+        assert_eq!(
+            read("<a><!-- <b> --></a><!--> not a tag <c> -> --><d>x<!-- y -->z</d>"),
+            ["<a", "a=", "<d", "d=xz"]
+        );
+    }
+
+    #[test]
+    fn keeps_cdata_text() {
+        assert_eq!(
+            read("<key><![CDATA[ <b>&amp;</b> ]]></key><key>a<![CDATA[]]>]]></key>"),
+            ["<key", "key= <b>&amp;</b> ", "<key", "key=a]]>"]
+        );
+    }
+
+    #[test]
+    fn ends_tags_outside_of_quoted_attributes() {
+        assert_eq!(
+            read("<a title=\"1 > 0\" alt='<b>'><c x=\"/>\"/></a>"),
+            ["<a", "<c/", "a="]
+        );
+        assert_eq!(read("<!DOCTYPE a [<!ENTITY b \"c\">]><a/>"), ["<a/"]);
+    }
+
+    #[test]
+    fn fails_on_unclosed_markup() {
+        assert!(tags("<a>text<!-- no end").any(|tag| tag.is_err()));
+        assert!(tags("<a><![CDATA[ no end").any(|tag| tag.is_err()));
+        assert!(tags("<a title=\"x>").any(|tag| tag.is_err()));
+    }
+}
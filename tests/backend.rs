@@ -0,0 +1,75 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+/// Answers every request with a short body on a kept-alive connection.
+fn serve() -> (String, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut line = String::new();
+                loop {
+                    line.clear();
+                    match reader.read_line(&mut line) {
+                        Ok(0) | Err(_) => return,
+                        Ok(_) if line == "\r\n" => {
+                            let _ = reader
+                                .get_mut()
+                                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+                        }
+                        Ok(_) => {}
+                    }
+                }
+            });
+        }
+    });
+    (format!("http://{host}/"), host)
+}
+
+fn fetch_twice(options: Option<BackendOptions>) -> Option<ConnectionStats> {
+    let (url, host) = serve();
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HttpClientPlugin::default()));
+    app.finish();
+    app.cleanup();
+    for _ in 0..2 {
+        let entity = app.world.spawn_empty().id();
+        let mut client = HttpClient::new().get(&url).respond_to(entity);
+        if let Some(options) = options.clone() {
+            client = client.backend_options(options);
+        }
+        app.world.send_event(client.build());
+        let mut answered = false;
+        for _ in 0..500 {
+            app.update();
+            if app.world.get::<HttpResponse>(entity).is_some() {
+                answered = true;
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(answered, "no response");
+    }
+    app.world.resource::<HttpStats>().host(&host).cloned()
+}
+
+#[test]
+fn the_shared_agent_reports_reused_connections() {
+    let stats = fetch_twice(None).unwrap();
+    assert_eq!((stats.new, stats.reused), (1, 1));
+}
+
+#[test]
+fn custom_agents_do_not_report_connections() {
+    let agent = ureq::AgentBuilder::new().redirects(0).build();
+    let stats = fetch_twice(Some(BackendOptions::new().with_ureq_agent(agent)));
+    assert!(stats.is_none());
+}
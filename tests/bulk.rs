@@ -0,0 +1,85 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+#[derive(Resource, Default)]
+struct Delivered(usize);
+
+fn count(mut delivered: ResMut<Delivered>, mut ev_response: EventReader<HttpResponse>) {
+    delivered.0 += ev_response.read().count();
+}
+
+/// Answers every request with `204 No Content` by fault injection.
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HttpClientPlugin::new(HttpClientSettings {
+            max_concurrent: 8,
+            ..default()
+        }),
+        FaultInjectionPlugin::new(1.0).with_fault(Fault::Status(204)),
+    ))
+    .init_resource::<Delivered>()
+    .add_systems(Update, count);
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Adds `batch` as a command in the next frame.
+fn send(app: &mut App, batch: SendBatch) {
+    let mut batch = Some(batch);
+    app.add_systems(Update, move |mut commands: Commands| {
+        if let Some(batch) = batch.take() {
+            commands.add(batch);
+        }
+    });
+}
+
+#[test]
+fn every_request_of_a_batch_is_answered() {
+    let mut app = app();
+    let panel = app.world.spawn_empty().id();
+    let pings = (0..100).map(|seq| {
+        HttpClient::new()
+            .get(format!("http://localhost/ping/{seq}"))
+            .build()
+    });
+    let panel_request = HttpClient::new()
+        .get("http://localhost/panel")
+        .respond_to(panel)
+        .build();
+    let batch = HttpClient::send_batch(pings.chain([panel_request]));
+    assert_eq!(batch.len(), 101);
+    send(&mut app, batch);
+
+    for _ in 0..500 {
+        app.update();
+        let delivered = app.world.resource::<Delivered>().0;
+        if delivered == 100 && app.world.get::<HttpResponse>(panel).is_some() {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    panic!(
+        "{} of 100 responses arrived",
+        app.world.resource::<Delivered>().0
+    );
+}
+
+#[test]
+fn empty_batches_send_nothing() {
+    let batch = HttpClient::send_batch(Vec::new());
+    assert!(batch.is_empty());
+    let mut app = app();
+    send(&mut app, batch);
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(app.world.resource::<Delivered>().0, 0);
+    assert_eq!(app.world.resource::<HttpStats>().total.sent, 0);
+}
@@ -0,0 +1,86 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+/// Answers every request with a short body.
+fn serve() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/profile", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+            let _ = reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+        }
+    });
+    url
+}
+
+fn app(cleanup: ResponseCleanup) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HttpClientPlugin::new(HttpClientSettings {
+            response_cleanup: Some(cleanup),
+            ..default()
+        }),
+    ));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Updates until the entity got its response.
+fn wait_for_response(app: &mut App, entity: Entity) {
+    for _ in 0..500 {
+        app.update();
+        if app.world.get::<HttpResponse>(entity).is_some() {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("no response");
+}
+
+#[test]
+fn responses_are_removed_after_frames() {
+    let url = serve();
+    let mut app = app(ResponseCleanup::after_frames(1));
+    let panel = app.world.spawn_empty().id();
+    app.world
+        .send_event(HttpClient::new().get(&url).respond_to(panel).build());
+
+    wait_for_response(&mut app, panel);
+    app.update();
+    assert!(app.world.get::<HttpResponse>(panel).is_none());
+    assert!(app.world.get_entity(panel).is_some());
+}
+
+#[test]
+fn request_entities_are_despawned() {
+    let url = serve();
+    let mut app = app(ResponseCleanup::after_frames(1).despawning());
+    let entity = app.world.spawn_empty().id();
+    app.world.send_event(
+        HttpClient::new()
+            .get(&url)
+            .entity(entity)
+            .respond_to(entity)
+            .build(),
+    );
+
+    wait_for_response(&mut app, entity);
+    assert!(app.world.get::<ResponseMeta>(entity).is_some());
+    app.update();
+    assert!(app.world.get_entity(entity).is_none());
+}
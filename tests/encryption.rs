@@ -0,0 +1,91 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+/// Wraps the body in a 4 byte header and a 12 byte trailer, so the ciphertext is longer than the
+/// plaintext.
+struct Framing;
+
+impl PayloadCipher for Framing {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        Ok([b"ENC:", plaintext, b"____trailer_"].concat())
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(ciphertext.to_vec())
+    }
+}
+
+/// The `Content-Length` header of a received request, and its body.
+type Received = (Option<usize>, Vec<u8>);
+
+/// Answers one request with an empty response, sending its `Content-Length` header and the body
+/// it read back.
+fn serve_once() -> (String, mpsc::Receiver<Received>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/saves", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut lengths = vec![];
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    lengths.push(value.trim().parse::<usize>().unwrap());
+                }
+            }
+        }
+        assert!(
+            lengths.len() <= 1,
+            "Content-Length sent {} times",
+            lengths.len()
+        );
+        let length = lengths.first().copied();
+        let mut body = vec![0; length.unwrap_or_default()];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        tx.send((length, body)).unwrap();
+    });
+    (url, rx)
+}
+
+#[test]
+fn encrypted_body_is_sent_with_its_length() {
+    let (url, rx) = serve_once();
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HttpClientPlugin::default()));
+    app.insert_resource(PayloadEncryption::new(Framing));
+    app.finish();
+    app.cleanup();
+
+    let plaintext = serde_json::json!({ "slot": 1, "level": 12 });
+    app.world
+        .send_event(HttpClient::new().post(&url).json(&plaintext).build());
+
+    let (length, body) = loop {
+        app.update();
+        if let Ok(received) = rx.recv_timeout(Duration::from_millis(10)) {
+            break received;
+        }
+    };
+    let expected = Framing
+        .encrypt(&serde_json::to_vec(&plaintext).unwrap())
+        .unwrap();
+    assert_eq!(length, Some(expected.len()));
+    assert_eq!(body, expected);
+}
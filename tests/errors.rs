@@ -0,0 +1,36 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+#[test]
+fn refused_connections_are_classified_by_the_error_kind() {
+    // A port nothing listens on once the listener is dropped.
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HttpClientPlugin::default()));
+    app.finish();
+    app.cleanup();
+    let entity = app.world.spawn_empty().id();
+    // The url mentions other failures, which must not change the kind.
+    let url = format!("http://127.0.0.1:{port}/tls/certificate/timeout");
+    app.world
+        .send_event(HttpClient::new().get(&url).respond_to(entity).build());
+
+    for _ in 0..500 {
+        app.update();
+        if let Some(error) = app.world.get::<HttpResponseError>(entity) {
+            assert_eq!(error.kind, HttpErrorKind::Connect, "{error}");
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("no error");
+}
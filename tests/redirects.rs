@@ -0,0 +1,130 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+/// Answers `/start` with a redirect to `target` and everything else with a short body, sending
+/// the request headers of every request it gets.
+fn serve(target: Option<String>) -> (String, Receiver<(String, Vec<String>)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let origin = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = channel();
+    let location = target.unwrap_or_else(|| format!("{origin}/done"));
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let path = line.split(' ').nth(1).unwrap_or_default().to_owned();
+            let mut headers = vec![];
+            line.clear();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                headers.push(line.trim_end().to_ascii_lowercase());
+                line.clear();
+            }
+            let answer = if path == "/start" {
+                format!(
+                    "HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+            } else {
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_owned()
+            };
+            let _ = reader.get_mut().write_all(answer.as_bytes());
+            let _ = tx.send((path, headers));
+        }
+    });
+    (origin, rx)
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HttpClientPlugin::default()));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Updates until the entity got its response.
+fn wait_for_response(app: &mut App, entity: Entity) {
+    for _ in 0..500 {
+        app.update();
+        if let Some(response) = app.world.get::<HttpResponse>(entity) {
+            assert_eq!(response.status, 200);
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("no response");
+}
+
+fn fetch(app: &mut App, url: &str) {
+    let entity = app.world.spawn_empty().id();
+    app.world.send_event(
+        HttpClient::new()
+            .get(url)
+            .header("Authorization", "Bearer secret")
+            .header("Cookie", "session=1")
+            .respond_to(entity)
+            .build(),
+    );
+    wait_for_response(app, entity);
+}
+
+fn has_header(headers: &[String], name: &str) -> bool {
+    headers.iter().any(|header| header.starts_with(name))
+}
+
+#[test]
+fn credentials_are_kept_on_the_same_origin() {
+    let (origin, requests) = serve(None);
+    let mut app = app();
+    fetch(&mut app, &format!("{origin}/start"));
+
+    for _ in 0..2 {
+        let (path, headers) = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(has_header(&headers, "authorization:"), "{path}");
+        assert!(has_header(&headers, "cookie:"), "{path}");
+    }
+}
+
+#[test]
+fn credentials_are_dropped_on_another_origin() {
+    let (other, other_requests) = serve(None);
+    let (origin, requests) = serve(Some(format!("{other}/done")));
+    let mut app = app();
+    fetch(&mut app, &format!("{origin}/start"));
+
+    let (_, headers) = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(has_header(&headers, "authorization:"));
+    let (path, headers) = other_requests.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(path, "/done");
+    assert!(!has_header(&headers, "authorization:"));
+    assert!(!has_header(&headers, "cookie:"));
+}
+
+#[test]
+fn environment_keys_are_dropped_on_another_origin() {
+    let (other, other_requests) = serve(None);
+    let (origin, requests) = serve(Some(format!("{other}/done")));
+    let mut app = app();
+    app.insert_resource(Environments::new(
+        "staging",
+        Environment::new()
+            .base_url(&origin)
+            .api_key("X-Api-Key", "staging-key")
+            .header("X-Env", "staging"),
+    ));
+    fetch(&mut app, "start");
+
+    let (_, headers) = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(has_header(&headers, "x-api-key: staging-key"));
+    assert!(has_header(&headers, "x-env: staging"));
+    let (_, headers) = other_requests.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(!has_header(&headers, "x-api-key:"));
+    assert!(!has_header(&headers, "x-env:"));
+}
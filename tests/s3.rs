@@ -0,0 +1,108 @@
+#![cfg(all(feature = "s3", not(target_arch = "wasm32")))]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+use sha2::{Digest, Sha256};
+
+/// Prefixes the body, so the signed body differs from the one passed to `put`.
+struct Prefix;
+
+impl PayloadCipher for Prefix {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        Ok([b"ENC:", plaintext].concat())
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(ciphertext.to_vec())
+    }
+}
+
+/// The headers of a received request, lowercased, and its body.
+type Received = (Vec<(String, String)>, Vec<u8>);
+
+/// Answers one request with an empty response, sending back what it received.
+fn serve_once() -> (String, mpsc::Receiver<Received>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut headers = vec![];
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+        let length = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .map_or(0, |(_, value)| value.parse().unwrap());
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        tx.send((headers, body)).unwrap();
+    });
+    (endpoint, rx)
+}
+
+#[test]
+fn requests_are_signed_as_they_are_sent() {
+    let (endpoint, rx) = serve_once();
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HttpClientPlugin::new(HttpClientSettings {
+            default_headers: vec![("Authorization".to_string(), "Bearer player".to_string())],
+            ..default()
+        }),
+    ));
+    app.insert_resource(PayloadEncryption::new(Prefix));
+    app.finish();
+    app.cleanup();
+
+    let bucket = S3Bucket::new(
+        &endpoint,
+        "us-east-1",
+        "saves",
+        S3Credentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"),
+    );
+    app.world
+        .send_event(bucket.put("slot1.json", b"{}".to_vec(), "application/json"));
+
+    let (headers, body) = loop {
+        app.update();
+        if let Ok(received) = rx.recv_timeout(Duration::from_millis(10)) {
+            break received;
+        }
+    };
+    let values = |name: &str| -> Vec<&str> {
+        headers
+            .iter()
+            .filter(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    };
+    assert_eq!(body, b"ENC:{}");
+    let body_hash: String = Sha256::digest(&body)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    assert_eq!(values("x-amz-content-sha256"), [body_hash.as_str()]);
+    let authorization = values("authorization");
+    assert_eq!(authorization.len(), 1);
+    assert!(authorization[0].starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+}
@@ -0,0 +1,75 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+enum AppState {
+    #[default]
+    InGame,
+    Menu,
+}
+
+#[derive(Resource, Default)]
+struct Errors(Vec<HttpResponseError>);
+
+fn collect_errors(mut ev_error: EventReader<HttpResponseError>, mut errors: ResMut<Errors>) {
+    errors.0.extend(ev_error.read().cloned());
+}
+
+#[test]
+fn exiting_the_state_aborts_requests_through_their_handlers() {
+    // Accepts connections without ever answering, so the first request stays in flight.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/match", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let _streams: Vec<_> = listener.incoming().collect();
+    });
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HttpClientPlugin::new(HttpClientSettings {
+            max_concurrent: 1,
+            ..default()
+        }),
+    ));
+    app.init_state::<AppState>();
+    app.init_resource::<Errors>();
+    app.add_systems(
+        OnExit(AppState::InGame),
+        abort_requests_on_exit(AppState::InGame),
+    );
+    app.add_systems(Update, collect_errors.after(HttpSet::HandleResponses));
+    app.finish();
+    app.cleanup();
+
+    let entity = app.world.spawn(RequestStateScope(AppState::InGame)).id();
+    for _ in 0..2 {
+        app.world
+            .send_event(HttpClient::new().get(&url).entity(entity).build());
+    }
+    // One request in flight, the other queued behind the client limit.
+    for _ in 0..5 {
+        app.update();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(app.world.resource::<RequestQueue>().len(), 1);
+
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Menu);
+    app.update();
+    app.update();
+
+    let errors = &app.world.resource::<Errors>().0;
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|error| error.kind == HttpErrorKind::Aborted));
+    assert!(app.world.get_entity(entity).is_none());
+    assert!(app.world.resource::<RequestQueue>().is_empty());
+}
@@ -0,0 +1,48 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+#[test]
+fn the_grace_period_runs_out_while_virtual_time_is_paused() {
+    // Accepts connections without ever answering, so the request stays pending.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/session-end", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let _streams: Vec<_> = listener.incoming().collect();
+    });
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HttpClientPlugin::new(HttpClientSettings {
+            clock: ClockSource::Virtual,
+            ..default()
+        }),
+        GracefulShutdownPlugin::new(Duration::from_millis(100)),
+    ));
+    app.finish();
+    app.cleanup();
+    app.world.send_event(HttpClient::new().post(&url).build());
+    app.update();
+    // Quitting from the pause menu.
+    app.world.resource_mut::<Time<Virtual>>().pause();
+    app.world.send_event(AppExit);
+
+    let started = Instant::now();
+    let mut flushing = false;
+    while started.elapsed() < Duration::from_secs(5) {
+        app.update();
+        flushing |= app.world.resource::<HttpShutdown>().is_flushing();
+        if !app.world.resource::<Events<AppExit>>().is_empty() && flushing {
+            assert!(started.elapsed() >= Duration::from_millis(100));
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("the app never exited");
+}
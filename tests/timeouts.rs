@@ -0,0 +1,50 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::io::Read;
+use std::net::TcpListener;
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+
+#[test]
+fn timed_out_requests_close_their_connection() {
+    // Reads the request and never answers, reporting when the client hangs up.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/slow", listener.local_addr().unwrap());
+    let (closed_tx, closed) = channel();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 1024];
+        while stream.read(&mut buf).is_ok_and(|read| read > 0) {}
+        let _ = closed_tx.send(());
+    });
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HttpClientPlugin::default()));
+    app.finish();
+    app.cleanup();
+    let entity = app.world.spawn_empty().id();
+    app.world.send_event(
+        HttpClient::new()
+            .get(&url)
+            .timeout(Duration::from_millis(200))
+            .respond_to(entity)
+            .build(),
+    );
+
+    let mut error = None;
+    for _ in 0..500 {
+        app.update();
+        error = app.world.get::<HttpResponseError>(entity).cloned();
+        if error.is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(error.expect("no error").kind, HttpErrorKind::Timeout);
+    closed
+        .recv_timeout(Duration::from_secs(5))
+        .expect("the connection was left open");
+}
@@ -0,0 +1,204 @@
+#![cfg(all(feature = "websocket", not(target_arch = "wasm32")))]
+
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_http_client::prelude::*;
+use bevy_http_client::{WebSocket, WebSocketClosed, WebSocketMessage, WebSocketOpened, WsMessage};
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::Message;
+
+/// Accepts one connection, sends the handshake headers it got, then echoes text and binary
+/// messages, pinging before each echo, until the client closes.
+// The error of the handshake callback is tungstenite's.
+#[allow(clippy::result_large_err)]
+fn echo_server() -> (String, mpsc::Receiver<Vec<(String, String)>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}/lobby", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket =
+            tungstenite::accept_hdr(stream, |request: &Request, mut response: Response| {
+                let headers = request
+                    .headers()
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_string()))
+                    .collect();
+                tx.send(headers).unwrap();
+                response
+                    .headers_mut()
+                    .insert("Sec-WebSocket-Protocol", "lobby.v1".parse().unwrap());
+                Ok(response)
+            })
+            .unwrap();
+        loop {
+            match socket.read() {
+                Ok(message @ (Message::Text(_) | Message::Binary(_))) => {
+                    socket
+                        .send(Message::Ping(b"are you there".to_vec()))
+                        .unwrap();
+                    socket.send(message).unwrap();
+                }
+                Ok(Message::Close(_)) => {
+                    let _ = socket.flush();
+                }
+                Ok(_) => {}
+                Err(_) => return,
+            }
+        }
+    });
+    (url, rx)
+}
+
+/// Accepts one connection and closes it with `4000` right away.
+fn closing_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}/lobby", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        socket
+            .close(Some(CloseFrame {
+                code: CloseCode::from(4000),
+                reason: "lobby full".into(),
+            }))
+            .unwrap();
+        while socket.read().is_ok() {}
+    });
+    url
+}
+
+#[derive(Resource, Default)]
+struct Received {
+    opened: Vec<Option<String>>,
+    messages: Vec<WsMessage>,
+    closed: Vec<(u16, String)>,
+    errors: Vec<String>,
+}
+
+fn collect(
+    mut received: ResMut<Received>,
+    mut ev_opened: EventReader<WebSocketOpened>,
+    mut ev_message: EventReader<WebSocketMessage>,
+    mut ev_closed: EventReader<WebSocketClosed>,
+    mut ev_error: EventReader<bevy_http_client::WebSocketError>,
+) {
+    received
+        .opened
+        .extend(ev_opened.read().map(|opened| opened.protocol.clone()));
+    received
+        .messages
+        .extend(ev_message.read().map(|message| message.message.clone()));
+    received.closed.extend(
+        ev_closed
+            .read()
+            .map(|closed| (closed.code, closed.reason.clone())),
+    );
+    received
+        .errors
+        .extend(ev_error.read().map(|error| error.error.clone()));
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HttpClientPlugin::default()));
+    app.init_resource::<Received>();
+    app.add_systems(Update, collect.after(HttpSet::HandleResponses));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+fn update_until(app: &mut App, done: impl Fn(&Received) -> bool) {
+    for _ in 0..500 {
+        app.update();
+        if done(app.world.resource::<Received>()) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    panic!(
+        "timed out, errors: {:?}",
+        app.world.resource::<Received>().errors
+    );
+}
+
+#[test]
+fn echoes_messages_and_closes() {
+    let (url, handshake) = echo_server();
+    let mut app = app();
+    let entity = app
+        .world
+        .spawn(
+            WebSocket::connect(&url)
+                .protocol("lobby.v1")
+                .header("Authorization", "Bearer abc"),
+        )
+        .id();
+    // Sent before the connection is open.
+    app.world.get::<WebSocket>(entity).unwrap().send("hello");
+    update_until(&mut app, |received| !received.messages.is_empty());
+
+    let headers = handshake.recv().unwrap();
+    assert!(headers.contains(&("authorization".to_string(), "Bearer abc".to_string())));
+    assert!(headers.contains(&("sec-websocket-protocol".to_string(), "lobby.v1".to_string())));
+    assert_eq!(
+        app.world.resource::<Received>().opened,
+        [Some("lobby.v1".to_string())]
+    );
+    let socket = app.world.get::<WebSocket>(entity).unwrap();
+    assert_eq!(socket.selected_protocol(), Some("lobby.v1"));
+
+    // Large enough for a 64 bit length.
+    let binary: Vec<u8> = (0..70_000).map(|index| index as u8).collect();
+    socket.send(binary.clone());
+    update_until(&mut app, |received| received.messages.len() == 2);
+    assert_eq!(
+        app.world.resource::<Received>().messages,
+        [
+            WsMessage::Text("hello".to_string()),
+            WsMessage::Binary(binary)
+        ]
+    );
+
+    app.world
+        .get::<WebSocket>(entity)
+        .unwrap()
+        .close_with(4001, "bye");
+    update_until(&mut app, |received| !received.closed.is_empty());
+    assert_eq!(
+        app.world.resource::<Received>().closed,
+        [(4001, "bye".to_string())]
+    );
+    assert!(app.world.resource::<Received>().errors.is_empty());
+}
+
+#[test]
+fn reports_the_close_frame_of_the_server() {
+    let url = closing_server();
+    let mut app = app();
+    app.world.spawn(WebSocket::connect(&url));
+    update_until(&mut app, |received| !received.closed.is_empty());
+    assert_eq!(
+        app.world.resource::<Received>().closed,
+        [(4000, "lobby full".to_string())]
+    );
+}
+
+#[test]
+fn rejects_headers_with_line_breaks() {
+    let (url, _) = echo_server();
+    let mut app = app();
+    app.world
+        .spawn(WebSocket::connect(&url).header("X-Name", "a\r\nX-Admin: true"));
+    update_until(&mut app, |received| !received.errors.is_empty());
+    assert_eq!(
+        app.world.resource::<Received>().errors,
+        ["Invalid value of header x-name"]
+    );
+}